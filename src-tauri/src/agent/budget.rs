@@ -0,0 +1,565 @@
+//! 用量预算与限流
+//!
+//! - 按提供商的令牌桶限流（每分钟请求数）
+//! - 单个任务与当日的 token / 费用预算，费用按内置价格表估算
+//! - 超出预算时拒绝后续请求并返回 [`BudgetError`]，由调用方中止任务
+//!
+//! 当日用量按日期保存在应用数据目录（见 [`load_daily_ledger`]），应用重启后继续累计
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::langgraph::metrics::TokenUsage;
+
+/// 预算与限流配置，未设置的项不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// 每个提供商每分钟最多请求数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// 单个任务最多使用的 tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_task_tokens: Option<u32>,
+    /// 单个任务最多花费（美元）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_task_cost_usd: Option<f64>,
+    /// 当日最多使用的 tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_daily_tokens: Option<u32>,
+    /// 当日最多花费（美元）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_daily_cost_usd: Option<f64>,
+}
+
+impl BudgetConfig {
+    /// 是否设置了费用上限
+    pub fn limits_cost(&self) -> bool {
+        self.max_task_cost_usd.is_some() || self.max_daily_cost_usd.is_some()
+    }
+
+    /// 计费价格：没有内置价格的模型在设置了费用上限时按 [`FALLBACK_PRICE`] 计，否则不计费
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        model_price(model).or_else(|| self.limits_cost().then_some(FALLBACK_PRICE))
+    }
+
+    /// 估算一次调用的费用（美元）
+    pub fn estimate_cost(&self, model: &str, prompt_tokens: usize, cached_tokens: usize, completion_tokens: usize) -> f64 {
+        self.price(model)
+            .map(|price| price.cost(prompt_tokens, cached_tokens, completion_tokens))
+            .unwrap_or(0.0)
+    }
+
+    /// 模型没有内置价格、将按保守价格计入费用预算时的提示
+    pub fn unpriced_warning(&self, model: &str) -> Option<String> {
+        if !self.limits_cost() || model_price(model).is_some() {
+            return None;
+        }
+        Some(format!(
+            "模型 {} 没有内置价格，按每百万 tokens 输入 ${:.2} / 输出 ${:.2} 计入费用预算",
+            model, FALLBACK_PRICE.input, FALLBACK_PRICE.output,
+        ))
+    }
+}
+
+/// 超出预算
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BudgetError {
+    #[error("已超出任务 token 预算：{used} / {limit}")]
+    TaskTokens { used: u32, limit: u32 },
+    #[error("已超出任务费用预算：${used:.4} / ${limit:.4}")]
+    TaskCost { used: f64, limit: f64 },
+    #[error("已超出今日 token 预算：{used} / {limit}")]
+    DailyTokens { used: u32, limit: u32 },
+    #[error("已超出今日费用预算：${used:.4} / ${limit:.4}")]
+    DailyCost { used: f64, limit: f64 },
+}
+
+// ============ 价格表 ============
+
+/// 模型价格（美元 / 百万 tokens）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    /// 命中提示缓存的输入
+    pub cached_input: f64,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    /// 一次调用的费用（美元）
+    pub fn cost(&self, prompt_tokens: usize, cached_tokens: usize, completion_tokens: usize) -> f64 {
+        let cached = cached_tokens.min(prompt_tokens);
+        ((prompt_tokens - cached) as f64 * self.input
+            + cached as f64 * self.cached_input
+            + completion_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// 没有内置价格的模型在设置了费用上限时使用的保守价格（价格表中最贵的一档）
+pub const FALLBACK_PRICE: ModelPrice = ModelPrice { input: 15.00, cached_input: 7.50, output: 75.00 };
+
+/// 按模型名查找价格，按前缀匹配（更具体的前缀在前），本地或未知模型返回 None
+pub fn model_price(model: &str) -> Option<ModelPrice> {
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+
+    const PRICES: &[(&str, f64, f64, f64)] = &[
+        ("gpt-4.1-nano", 0.10, 0.025, 0.40),
+        ("gpt-4.1-mini", 0.40, 0.10, 1.60),
+        ("gpt-4.1", 2.00, 0.50, 8.00),
+        ("gpt-4o-mini", 0.15, 0.075, 0.60),
+        ("gpt-4o", 2.50, 1.25, 10.00),
+        ("gpt-5-nano", 0.05, 0.005, 0.40),
+        ("gpt-5-mini", 0.25, 0.025, 2.00),
+        ("gpt-5", 1.25, 0.125, 10.00),
+        ("o4-mini", 1.10, 0.275, 4.40),
+        ("o3-mini", 1.10, 0.55, 4.40),
+        ("o3", 2.00, 0.50, 8.00),
+        ("o1", 15.00, 7.50, 60.00),
+        ("claude-opus-4-5", 5.00, 0.50, 25.00),
+        ("claude-opus", 15.00, 1.50, 75.00),
+        ("claude-sonnet", 3.00, 0.30, 15.00),
+        ("claude-3-7-sonnet", 3.00, 0.30, 15.00),
+        ("claude-3-5-sonnet", 3.00, 0.30, 15.00),
+        ("claude-haiku", 1.00, 0.10, 5.00),
+        ("claude-3-5-haiku", 0.80, 0.08, 4.00),
+        ("gemini-2.5-pro", 1.25, 0.31, 10.00),
+        ("gemini-2.5-flash-lite", 0.10, 0.025, 0.40),
+        ("gemini-2.5-flash", 0.30, 0.075, 2.50),
+        ("gemini-2.0-flash", 0.10, 0.025, 0.40),
+        ("deepseek-chat", 0.27, 0.07, 1.10),
+        ("deepseek-reasoner", 0.55, 0.14, 2.19),
+    ];
+    PRICES.iter()
+        .find(|(prefix, ..)| name.starts_with(prefix))
+        .map(|&(_, input, cached_input, output)| ModelPrice { input, cached_input, output })
+}
+
+/// 从各提供商的响应 JSON（或流式事件）中读取 token 用量
+///
+/// 兼容 OpenAI `usage`、Anthropic `usage` / `message.usage`、Gemini `usageMetadata`
+/// 与 Ollama `prompt_eval_count`
+pub fn parse_usage(json: &Value) -> Option<TokenUsage> {
+    let count = |value: &Value| value.as_u64().map(|n| n as u32);
+    let usage = json.get("usage")
+        .or_else(|| json.pointer("/message/usage"))
+        .filter(|u| u.is_object());
+    let (prompt, completion) = if let Some(usage) = usage {
+        let prompt = count(&usage["prompt_tokens"]).or_else(|| {
+            // Anthropic 的 input_tokens 不含缓存读写部分
+            let input = count(&usage["input_tokens"])?;
+            Some(input
+                + count(&usage["cache_creation_input_tokens"]).unwrap_or(0)
+                + count(&usage["cache_read_input_tokens"]).unwrap_or(0))
+        });
+        let completion = count(&usage["completion_tokens"]).or_else(|| count(&usage["output_tokens"]));
+        (prompt, completion)
+    } else if let Some(meta) = json.get("usageMetadata") {
+        (count(&meta["promptTokenCount"]), count(&meta["candidatesTokenCount"]))
+    } else {
+        (count(&json["prompt_eval_count"]), count(&json["eval_count"]))
+    };
+    if prompt.is_none() && completion.is_none() {
+        return None;
+    }
+    Some(TokenUsage::new(prompt.unwrap_or(0), completion.unwrap_or(0)))
+}
+
+/// 命中提示缓存的 prompt tokens
+pub fn parse_cached_tokens(json: &Value) -> u32 {
+    [
+        "/usage/prompt_tokens_details/cached_tokens",
+        "/usage/prompt_cache_hit_tokens",
+        "/usage/cache_read_input_tokens",
+        "/message/usage/cache_read_input_tokens",
+        "/usageMetadata/cachedContentTokenCount",
+    ]
+    .iter()
+    .find_map(|pointer| json.pointer(pointer)?.as_u64())
+    .unwrap_or(0) as u32
+}
+
+// ============ 限流 ============
+
+/// 令牌桶：容量为每分钟请求数，按固定速率补充
+#[derive(Debug)]
+struct TokenBucket {
+    per_minute: u32,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        Self { per_minute, tokens: per_minute as f64, updated: now }
+    }
+
+    /// 取一个令牌，不足时返回需要等待的时长
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let capacity = self.per_minute as f64;
+        let per_sec = capacity / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_sec).min(capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / per_sec))
+        }
+    }
+}
+
+/// 提供商（或主机）→ 令牌桶
+static RATE_LIMITERS: Lazy<Mutex<HashMap<String, TokenBucket>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 等待 `key` 的令牌桶中有可用令牌；`per_minute` 为 0 时不限制
+pub async fn acquire_rate_limit(key: &str, per_minute: u32) {
+    if per_minute == 0 {
+        return;
+    }
+    loop {
+        let wait = {
+            let Ok(mut limiters) = RATE_LIMITERS.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            let bucket = limiters.entry(key.to_string())
+                .or_insert_with(|| TokenBucket::new(per_minute, now));
+            // 配置变更后按新的速率重新计数
+            if bucket.per_minute != per_minute {
+                *bucket = TokenBucket::new(per_minute, now);
+            }
+            match bucket.try_take(now) {
+                Ok(()) => return,
+                Err(wait) => wait,
+            }
+        };
+        println!("[Budget] ⏳ {} 触发限流，等待 {:?}", key, wait);
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// ============ 预算 ============
+
+/// 按日期记录的用量，设置了文件路径时每次记录后写回
+#[derive(Debug, Default)]
+struct DailyLedger {
+    days: BTreeMap<String, TokenUsage>,
+    path: Option<PathBuf>,
+}
+
+impl DailyLedger {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从文件读取，文件不存在或无法解析时从空账本开始
+    fn open(path: PathBuf) -> Self {
+        let days = std::fs::read_to_string(&path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { days, path: Some(path) }
+    }
+
+    /// 当日用量
+    fn today(&self) -> TokenUsage {
+        self.days.get(&today()).copied().unwrap_or_default()
+    }
+
+    /// 计入当日用量
+    fn add(&mut self, usage: &TokenUsage) {
+        self.days.entry(today()).or_default().add(usage);
+        if let Err(e) = self.save() {
+            eprintln!("[Budget] 保存当日用量失败: {}", e);
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(&self.days)?)
+    }
+}
+
+/// 账本的日期键（本地日期，如 `2026-10-19`）
+fn today() -> String {
+    Local::now().date_naive().to_string()
+}
+
+/// 当日用量，所有任务与前端直连请求共享
+static DAILY_LEDGER: Lazy<Arc<Mutex<DailyLedger>>> = Lazy::new(|| Arc::new(Mutex::new(DailyLedger::new())));
+
+/// 从文件加载按日期记录的用量，之后的用量写回该文件（应用启动时调用）
+pub fn load_daily_ledger(path: PathBuf) {
+    if let Ok(mut ledger) = DAILY_LEDGER.lock() {
+        *ledger = DailyLedger::open(path);
+    }
+}
+
+/// 当日已用量
+pub fn daily_usage() -> TokenUsage {
+    DAILY_LEDGER.lock().map(|ledger| ledger.today()).unwrap_or_default()
+}
+
+/// 检查当日预算
+pub fn check_daily(config: &BudgetConfig) -> Result<(), BudgetError> {
+    check_daily_usage(config, &daily_usage())
+}
+
+/// 将一次调用计入当日用量
+pub fn record_daily(usage: &TokenUsage) {
+    if let Ok(mut ledger) = DAILY_LEDGER.lock() {
+        ledger.add(usage);
+    }
+}
+
+fn check_daily_usage(config: &BudgetConfig, usage: &TokenUsage) -> Result<(), BudgetError> {
+    if let Some(limit) = config.max_daily_tokens.filter(|&limit| usage.total_tokens >= limit) {
+        return Err(BudgetError::DailyTokens { used: usage.total_tokens, limit });
+    }
+    if let Some(limit) = config.max_daily_cost_usd.filter(|&limit| usage.cost_usd >= limit) {
+        return Err(BudgetError::DailyCost { used: usage.cost_usd, limit });
+    }
+    Ok(())
+}
+
+/// 单个任务的用量预算，任务内的所有 LlmClient（含各节点与回退模型）共享
+///
+/// 每次请求前检查，已超出时拒绝请求；超出预算的那次调用本身已经完成，其用量照常计入
+#[derive(Debug)]
+pub struct UsageBudget {
+    config: BudgetConfig,
+    task: Mutex<TokenUsage>,
+    exceeded: Mutex<Option<BudgetError>>,
+    /// 已提示过没有内置价格的模型
+    unpriced: Mutex<HashSet<String>>,
+    daily: Arc<Mutex<DailyLedger>>,
+}
+
+impl UsageBudget {
+    pub fn new(config: BudgetConfig) -> Self {
+        Self::with_ledger(config, DAILY_LEDGER.clone())
+    }
+
+    fn with_ledger(config: BudgetConfig, daily: Arc<Mutex<DailyLedger>>) -> Self {
+        Self {
+            config,
+            task: Mutex::new(TokenUsage::default()),
+            exceeded: Mutex::new(None),
+            unpriced: Mutex::new(HashSet::new()),
+            daily,
+        }
+    }
+
+    pub fn config(&self) -> &BudgetConfig {
+        &self.config
+    }
+
+    /// 请求前检查任务与当日预算
+    pub fn check(&self) -> Result<(), BudgetError> {
+        let task = self.task_usage();
+        let result = if let Some(limit) = self.config.max_task_tokens.filter(|&limit| task.total_tokens >= limit) {
+            Err(BudgetError::TaskTokens { used: task.total_tokens, limit })
+        } else if let Some(limit) = self.config.max_task_cost_usd.filter(|&limit| task.cost_usd >= limit) {
+            Err(BudgetError::TaskCost { used: task.cost_usd, limit })
+        } else {
+            let daily = self.daily.lock().map(|ledger| ledger.today()).unwrap_or_default();
+            check_daily_usage(&self.config, &daily)
+        };
+        if let (Err(error), Ok(mut exceeded)) = (&result, self.exceeded.lock()) {
+            exceeded.get_or_insert_with(|| error.clone());
+        }
+        result
+    }
+
+    /// 记录一次调用的用量
+    pub fn record(&self, usage: &TokenUsage) {
+        if let Ok(mut task) = self.task.lock() {
+            task.add(usage);
+        }
+        if let Ok(mut ledger) = self.daily.lock() {
+            ledger.add(usage);
+        }
+    }
+
+    /// 模型没有内置价格、将按保守价格计费时的提示，每个模型只返回一次
+    pub fn unpriced_warning(&self, model: &str) -> Option<String> {
+        let warning = self.config.unpriced_warning(model)?;
+        let mut unpriced = self.unpriced.lock().ok()?;
+        unpriced.insert(model.to_string()).then_some(warning)
+    }
+
+    /// 任务已用量
+    pub fn task_usage(&self) -> TokenUsage {
+        self.task.lock().map(|usage| *usage).unwrap_or_default()
+    }
+
+    /// 第一次超出预算时的错误
+    pub fn exceeded(&self) -> Option<BudgetError> {
+        self.exceeded.lock().ok().and_then(|exceeded| exceeded.clone())
+    }
+}
+
+impl Default for UsageBudget {
+    fn default() -> Self {
+        Self::new(BudgetConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn budget(config: BudgetConfig) -> UsageBudget {
+        UsageBudget::with_ledger(config, Arc::new(Mutex::new(DailyLedger::new())))
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        assert_eq!(model_price("gpt-4o-mini").unwrap().input, 0.15);
+        assert_eq!(model_price("openai/gpt-4o").unwrap().output, 10.0);
+        assert_eq!(model_price("claude-sonnet-4-5").unwrap().cached_input, 0.30);
+        assert!(model_price("qwen3:8b").is_none());
+
+        // 1M 输入（其中一半命中缓存）+ 100k 输出
+        let unlimited = BudgetConfig::default();
+        let cost = unlimited.estimate_cost("gpt-4o", 1_000_000, 500_000, 100_000);
+        assert!((cost - (1.25 + 0.625 + 1.0)).abs() < 1e-9);
+        assert_eq!(unlimited.estimate_cost("llama3.1:8b", 1_000, 0, 1_000), 0.0);
+        assert_eq!(unlimited.unpriced_warning("llama3.1:8b"), None);
+    }
+
+    #[test]
+    fn test_unpriced_model_with_cost_limit() {
+        let budget = budget(BudgetConfig { max_task_cost_usd: Some(0.01), ..Default::default() });
+        // 未知模型按保守价格计费，费用上限仍然生效
+        let cost = budget.config().estimate_cost("qwen3:8b", 100_000, 0, 10_000);
+        assert!((cost - (1.5 + 0.75)).abs() < 1e-9);
+        budget.record(&TokenUsage::new(100_000, 10_000).with_cost(cost));
+        assert!(matches!(budget.check(), Err(BudgetError::TaskCost { .. })));
+
+        assert!(budget.unpriced_warning("qwen3:8b").unwrap().contains("qwen3:8b"));
+        assert_eq!(budget.unpriced_warning("qwen3:8b"), None);
+        assert_eq!(budget.unpriced_warning("gpt-4o"), None);
+    }
+
+    #[test]
+    fn test_parse_usage() {
+        let openai = json!({ "usage": { "prompt_tokens": 100, "completion_tokens": 20,
+            "prompt_tokens_details": { "cached_tokens": 64 } } });
+        assert_eq!(parse_usage(&openai), Some(TokenUsage::new(100, 20)));
+        assert_eq!(parse_cached_tokens(&openai), 64);
+
+        let anthropic = json!({ "type": "message_start", "message": { "usage": {
+            "input_tokens": 10, "cache_read_input_tokens": 90, "output_tokens": 1 } } });
+        assert_eq!(parse_usage(&anthropic), Some(TokenUsage::new(100, 1)));
+        assert_eq!(parse_cached_tokens(&anthropic), 90);
+
+        let gemini = json!({ "usageMetadata": { "promptTokenCount": 7, "candidatesTokenCount": 3 } });
+        assert_eq!(parse_usage(&gemini), Some(TokenUsage::new(7, 3)));
+        assert_eq!(parse_usage(&json!({ "choices": [] })), None);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, start);
+        assert!(bucket.try_take(start).is_ok());
+        assert!(bucket.try_take(start).is_ok());
+        let wait = bucket.try_take(start).unwrap_err();
+        assert_eq!(wait.as_secs(), 30);
+        assert!(bucket.try_take(start + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn test_task_budget() {
+        let budget = budget(BudgetConfig { max_task_tokens: Some(1_000), ..Default::default() });
+        budget.record(&TokenUsage::new(600, 100));
+        assert!(budget.check().is_ok());
+        budget.record(&TokenUsage::new(300, 50));
+        assert_eq!(budget.check(), Err(BudgetError::TaskTokens { used: 1_050, limit: 1_000 }));
+        assert_eq!(budget.exceeded(), Some(BudgetError::TaskTokens { used: 1_050, limit: 1_000 }));
+
+        let serialized = serde_json::to_value(budget.exceeded().unwrap()).unwrap();
+        assert_eq!(serialized, json!({ "kind": "task_tokens", "used": 1_050, "limit": 1_000 }));
+    }
+
+    #[test]
+    fn test_daily_budget_shared_across_tasks() {
+        let daily = Arc::new(Mutex::new(DailyLedger::new()));
+        let config = BudgetConfig { max_daily_cost_usd: Some(0.01), ..Default::default() };
+        let first = UsageBudget::with_ledger(config.clone(), daily.clone());
+        first.record(&TokenUsage::new(1_000, 1_000).with_cost(0.012));
+
+        let second = UsageBudget::with_ledger(config, daily);
+        assert!(matches!(second.check(), Err(BudgetError::DailyCost { .. })));
+        assert_eq!(second.task_usage(), TokenUsage::default());
+    }
+
+    #[test]
+    fn test_daily_ledger_persisted_by_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("llm_usage.json");
+        let mut ledger = DailyLedger::open(path.clone());
+        ledger.add(&TokenUsage::new(100, 20).with_cost(0.5));
+
+        // 重启后继续累计当日用量
+        let reopened = DailyLedger::open(path.clone());
+        assert_eq!(reopened.today(), TokenUsage::new(100, 20).with_cost(0.5));
+        let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved[today()]["total_tokens"], 120);
+
+        // 其他日期的记录不计入当日
+        std::fs::write(&path, r#"{ "2000-01-01": { "prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2 } }"#).unwrap();
+        assert_eq!(DailyLedger::open(path).today(), TokenUsage::default());
+    }
+
+    #[tokio::test]
+    async fn test_client_stops_after_task_budget() {
+        use crate::agent::llm_client::LlmClient;
+        use crate::agent::types::{AgentConfig, Message};
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "好的" } }],
+                "usage": { "prompt_tokens": 900, "completion_tokens": 200, "total_tokens": 1_100 },
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = LlmClient::new(AgentConfig {
+            model: "gpt-4o".to_string(),
+            base_url: Some(server.uri()),
+            budget: BudgetConfig { max_task_tokens: Some(1_000), ..Default::default() },
+            ..AgentConfig::default()
+        });
+        client.call(&[Message::user("hi")], None).await.unwrap();
+        let usage = client.budget().task_usage();
+        assert_eq!(usage.total_tokens, 1_100);
+        assert!((usage.cost_usd - 0.00425).abs() < 1e-9);
+
+        // 超出预算后不再发送请求
+        let error = client.call(&[Message::user("hi")], None).await.unwrap_err();
+        assert_eq!(error.to_string(), "已超出任务 token 预算：1100 / 1000");
+        assert!(client.budget().exceeded().is_some());
+    }
+}
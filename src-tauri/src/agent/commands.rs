@@ -5,15 +5,18 @@
 //! 使用 langgraph-rust 框架构建和执行 Agent 图
 
 use crate::agent::types::*;
+use crate::agent::budget::UsageBudget;
+use crate::agent::events::forward_events;
 use crate::agent::graph::{GraphExecutor, AgentContext, build_agent_graph};
 use crate::agent::deep_research::{
     DeepResearchConfig, DeepResearchRequest, DeepResearchState,
     DeepResearchContext, DeepResearchEvent, ResearchPhase,
     build_deep_research_graph,
 };
-use crate::langgraph::executor::{Checkpoint, ExecutionResult};
-use crate::langgraph::error::ResumeCommand;
-use tauri::{AppHandle, Emitter, State};
+use crate::langgraph::checkpoint::{Checkpointer, CheckpointRecord, SqliteCheckpointer, ThreadSummary};
+use crate::langgraph::executor::{CompiledGraph, ExecutionResult};
+use crate::langgraph::error::{GraphResult, ResumeCommand};
+use tauri::{AppHandle, Emitter, Manager, State};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -115,6 +118,65 @@ impl Default for AgentState {
     }
 }
 
+/// Agent 图在检查点库中的名称
+const AGENT_GRAPH: &str = "agent";
+
+/// Deep Research 图在检查点库中的名称
+const DEEP_RESEARCH_GRAPH: &str = "deep_research";
+
+/// 打开检查点数据库（应用数据目录下）
+fn open_checkpointer(app: &AppHandle) -> Result<Arc<dyn Checkpointer>, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app dir: {}", e))?;
+    let checkpointer = SqliteCheckpointer::open(dir.join("langgraph").join("checkpoints.db"))
+        .map_err(|e| e.to_string())?;
+    Ok(Arc::new(checkpointer))
+}
+
+/// 保存检查点的同时写入调试日志，使日志中的每一步都能对应到快照
+struct DebugLogCheckpointer(Arc<dyn Checkpointer>);
+
+impl Checkpointer for DebugLogCheckpointer {
+    fn put(&self, record: &CheckpointRecord) -> GraphResult<()> {
+        self.0.put(record)?;
+        // 分叉时复制的历史步骤不重复记录
+        if record.parent_step.is_none_or(|parent_step| record.step >= parent_step) {
+            crate::agent::debug_log::log_checkpoint(&record.thread_id, record.step, &record.node, &record.next_node);
+        }
+        Ok(())
+    }
+
+    fn get(&self, thread_id: &str, step: Option<usize>) -> GraphResult<Option<CheckpointRecord>> {
+        self.0.get(thread_id, step)
+    }
+
+    fn list(&self, thread_id: &str) -> GraphResult<Vec<CheckpointRecord>> {
+        self.0.list(thread_id)
+    }
+
+    fn list_threads(&self, graph: Option<&str>) -> GraphResult<Vec<ThreadSummary>> {
+        self.0.list_threads(graph)
+    }
+
+    fn delete_thread(&self, thread_id: &str) -> GraphResult<()> {
+        self.0.delete_thread(thread_id)
+    }
+}
+
+/// 为图挂载检查点，数据库不可用时仅记录日志，不影响执行
+fn attach_checkpointer<S>(app: &AppHandle, graph: CompiledGraph<S>, thread_id: &str) -> CompiledGraph<S>
+where
+    S: crate::langgraph::state::GraphState + serde::Serialize,
+{
+    match open_checkpointer(app) {
+        Ok(checkpointer) => graph.with_checkpointer(Arc::new(DebugLogCheckpointer(checkpointer)), thread_id),
+        Err(e) => {
+            eprintln!("[Agent] Checkpoints disabled: {}", e);
+            graph
+        }
+    }
+}
+
 /// 是否使用 langgraph-rust 框架执行
 /// 设为 true 使用新的 langgraph-rust 框架，false 使用旧的直接实现
 const USE_LANGGRAPH: bool = true;
@@ -174,7 +236,11 @@ pub async fn agent_start_task(
     // 根据配置选择执行方式
     let result = if USE_LANGGRAPH {
         // 使用 langgraph-rust 框架
-        run_with_langgraph(app.clone(), config, initial_state).await
+        let thread_id = uuid::Uuid::new_v4().to_string();
+        let _ = app.emit("agent-event", AgentEvent::ThreadStarted {
+            thread_id: thread_id.clone(),
+        });
+        run_with_langgraph(app.clone(), config, initial_state, &thread_id).await
     } else {
         // 使用旧的直接实现
         run_with_legacy(app.clone(), config, initial_state).await
@@ -210,22 +276,49 @@ async fn run_with_langgraph(
     app: AppHandle,
     config: AgentConfig,
     initial_state: GraphState,
+    thread_id: &str,
 ) -> Result<GraphState, String> {
+    let (graph, budget) = build_agent_runner(&app, &config, thread_id)?;
+    
+    // 执行图，节点事件经事件流转发给前端
+    forward_events(&app, graph.stream_events(initial_state)).await
+        .map_err(|e| {
+            emit_budget_exceeded(&app, &budget);
+            format!("Graph execution error: {}", e)
+        })
+}
+
+/// 任务因超出用量预算中止时通知前端
+fn emit_budget_exceeded(app: &AppHandle, budget: &UsageBudget) {
+    if let Some(error) = budget.exceeded() {
+        let _ = app.emit("agent-event", AgentEvent::BudgetExceeded {
+            message: error.to_string(),
+            error,
+        });
+    }
+}
+
+/// 构建带检查点的 Agent 图，同时返回任务的用量预算
+fn build_agent_runner(
+    app: &AppHandle,
+    config: &AgentConfig,
+    thread_id: &str,
+) -> Result<(CompiledGraph<GraphState>, Arc<UsageBudget>), String> {
     // 创建执行上下文
-    let ctx = AgentContext::new(app, config.clone());
+    let ctx = AgentContext::new(app.clone(), config.clone());
+    let budget = ctx.router.budget().clone();
     
     // 构建图
     let graph = build_agent_graph(ctx)
         .map_err(|e| format!("Failed to build graph: {}", e))?;
     
-    // 配置并执行
+    // 配置
     let graph = graph
+        .with_name(AGENT_GRAPH)
         .with_max_iterations(config.max_steps * 2)
         .with_debug(false);
     
-    // 执行图
-    graph.invoke(initial_state).await
-        .map_err(|e| format!("Graph execution error: {}", e))
+    Ok((attach_checkpointer(app, graph, thread_id), budget))
 }
 
 /// 使用旧的直接实现执行（保留兼容性）
@@ -236,6 +329,7 @@ async fn run_with_legacy(
 ) -> Result<GraphState, String> {
     let executor = GraphExecutor::new(config);
     executor.run(&app, initial_state).await
+        .inspect_err(|_| emit_budget_exceeded(&app, executor.budget()))
 }
 
 /// 中止 Agent 任务
@@ -297,6 +391,191 @@ pub async fn agent_get_status(
     }
 }
 
+/// 从最新检查点恢复 Agent 任务（如重启后继续）
+///
+/// 从历史步骤重放会改写原线程的后续检查点，需使用 `agent_fork_thread` 写入新线程
+#[tauri::command]
+pub async fn agent_resume_thread(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    config: AgentConfig,
+    thread_id: String,
+) -> Result<(), String> {
+    {
+        let mut is_running = state.is_running.lock().await;
+        if *is_running {
+            return Err("Agent is already running".to_string());
+        }
+        *is_running = true;
+    }
+
+    let result = async {
+        let (graph, budget) = build_agent_runner(&app, &config, &thread_id)?;
+        let checkpoint = graph.load_checkpoint(None)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No checkpoint found for thread {}", thread_id))?;
+
+        let _ = app.emit("agent-event", AgentEvent::StatusChange {
+            status: AgentStatus::Running,
+        });
+
+        let result = graph.continue_from(checkpoint).await.map_err(|e| {
+            emit_budget_exceeded(&app, &budget);
+            format!("Graph execution error: {}", e)
+        })?;
+        match result {
+            ExecutionResult::Complete(final_state) => Ok(final_state),
+            ExecutionResult::Interrupted { .. } => Err("Unexpected interrupt".to_string()),
+        }
+    }.await;
+
+    match result {
+        Ok(final_state) => {
+            let mut state_lock = state.current_state.lock().await;
+            *state_lock = Some(final_state);
+        }
+        Err(e) => {
+            let _ = app.emit("agent-event", AgentEvent::Error {
+                message: e.clone(),
+            });
+            let _ = app.emit("agent-event", AgentEvent::StatusChange {
+                status: AgentStatus::Error,
+            });
+        }
+    }
+
+    {
+        let mut is_running = state.is_running.lock().await;
+        *is_running = false;
+    }
+
+    Ok(())
+}
+
+/// 从历史步骤分叉 Agent 任务（时间回溯调试）
+///
+/// `patch` 的字段覆盖该步骤快照中的状态（如新的 `current_plan`），为空时原样重放。
+/// 新的执行写入新线程，原线程保持不变，返回新线程 ID
+#[tauri::command]
+pub async fn agent_fork_thread(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    config: AgentConfig,
+    thread_id: String,
+    step: usize,
+    patch: Option<serde_json::Value>,
+) -> Result<String, String> {
+    {
+        let mut is_running = state.is_running.lock().await;
+        if *is_running {
+            return Err("Agent is already running".to_string());
+        }
+        *is_running = true;
+    }
+
+    let result = async {
+        let (graph, budget) = build_agent_runner(&app, &config, &thread_id)?;
+        let patched = match patch {
+            Some(patch) => Some(patch_checkpoint_state(&app, &thread_id, step, patch)?),
+            None => None,
+        };
+
+        crate::agent::debug_log::log_fork(&thread_id, step);
+        let _ = app.emit("agent-event", AgentEvent::StatusChange {
+            status: AgentStatus::Running,
+        });
+
+        let forked = match patched {
+            Some(patched) => graph.fork(&thread_id, step, |s| *s = patched).await,
+            None => graph.replay(&thread_id, step).await,
+        };
+        let forked = forked
+            .map_err(|e| {
+                emit_budget_exceeded(&app, &budget);
+                format!("Graph execution error: {}", e)
+            })?;
+        match forked.result {
+            ExecutionResult::Complete(final_state) => Ok((forked.thread_id, final_state)),
+            ExecutionResult::Interrupted { .. } => Err("Unexpected interrupt".to_string()),
+        }
+    }.await;
+
+    {
+        let mut is_running = state.is_running.lock().await;
+        *is_running = false;
+    }
+
+    match result {
+        Ok((fork_id, final_state)) => {
+            let mut state_lock = state.current_state.lock().await;
+            *state_lock = Some(final_state);
+            Ok(fork_id)
+        }
+        Err(e) => {
+            let _ = app.emit("agent-event", AgentEvent::Error {
+                message: e.clone(),
+            });
+            let _ = app.emit("agent-event", AgentEvent::StatusChange {
+                status: AgentStatus::Error,
+            });
+            Err(e)
+        }
+    }
+}
+
+/// 将 patch 的顶层字段合并到检查点中的状态
+fn patch_checkpoint_state(
+    app: &AppHandle,
+    thread_id: &str,
+    step: usize,
+    patch: serde_json::Value,
+) -> Result<GraphState, String> {
+    let record = open_checkpointer(app)?
+        .get(thread_id, Some(step))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No checkpoint found for thread {} at step {}", thread_id, step))?;
+
+    let mut value = record.state;
+    match (value.as_object_mut(), patch) {
+        (Some(state), serde_json::Value::Object(fields)) => state.extend(fields),
+        _ => return Err("State patch must be a JSON object".to_string()),
+    }
+    serde_json::from_value(value).map_err(|e| format!("Invalid state patch: {}", e))
+}
+
+/// 列出已保存的执行线程（Agent 与 Deep Research）
+#[tauri::command]
+pub async fn agent_list_threads(
+    app: AppHandle,
+    graph: Option<String>,
+) -> Result<Vec<ThreadSummary>, String> {
+    open_checkpointer(&app)?
+        .list_threads(graph.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 获取线程的全部检查点
+#[tauri::command]
+pub async fn agent_get_checkpoints(
+    app: AppHandle,
+    thread_id: String,
+) -> Result<Vec<CheckpointRecord>, String> {
+    open_checkpointer(&app)?
+        .list(&thread_id)
+        .map_err(|e| e.to_string())
+}
+
+/// 删除线程的检查点
+#[tauri::command]
+pub async fn agent_delete_thread(
+    app: AppHandle,
+    thread_id: String,
+) -> Result<(), String> {
+    open_checkpointer(&app)?
+        .delete_thread(&thread_id)
+        .map_err(|e| e.to_string())
+}
+
 /// 继续任务（用户回答问题后）
 #[tauri::command]
 pub async fn agent_continue_with_answer(
@@ -320,8 +599,8 @@ pub async fn agent_continue_with_answer(
 /// Deep Research 状态管理
 pub struct DeepResearchStateManager {
     is_running: Arc<Mutex<bool>>,
-    /// 保存中断时的检查点（用于恢复执行）
-    checkpoint: Arc<Mutex<Option<Checkpoint<DeepResearchState>>>>,
+    /// 当前研究的线程 ID（检查点持久化在数据库中，重启后可按 ID 恢复）
+    thread_id: Arc<Mutex<Option<String>>>,
     /// 保存当前配置（用于恢复时重建图）
     current_config: Arc<Mutex<Option<DeepResearchConfig>>>,
 }
@@ -330,7 +609,7 @@ impl DeepResearchStateManager {
     pub fn new() -> Self {
         Self {
             is_running: Arc::new(Mutex::new(false)),
-            checkpoint: Arc::new(Mutex::new(None)),
+            thread_id: Arc::new(Mutex::new(None)),
             current_config: Arc::new(Mutex::new(None)),
         }
     }
@@ -406,17 +685,23 @@ pub async fn deep_research_start(
         *config_lock = Some(final_config.clone());
     }
 
+    // 记录线程 ID（用于 resume 时加载检查点）
+    let thread_id = uuid::Uuid::new_v4().to_string();
+    {
+        let mut thread_lock = state.thread_id.lock().await;
+        *thread_lock = Some(thread_id.clone());
+    }
+
     // 异步执行研究
     let app_clone = app.clone();
     let state_is_running = state.is_running.clone();
-    let state_checkpoint = state.checkpoint.clone();
     
     tokio::spawn(async move {
         let result = run_deep_research_resumable(
             app_clone.clone(), 
             final_config, 
             initial_state,
-            state_checkpoint.clone(),
+            &thread_id,
         ).await;
         
         match result {
@@ -427,10 +712,8 @@ pub async fn deep_research_start(
                         let mut is_running = state_is_running.lock().await;
                         *is_running = false;
                     }
-                    ExecutionResult::Interrupted { checkpoint, interrupts: _ } => {
-                        // 保存检查点，等待用户输入
-                        let mut cp_lock = state_checkpoint.lock().await;
-                        *cp_lock = Some(checkpoint);
+                    ExecutionResult::Interrupted { .. } => {
+                        // 检查点已由图写入数据库，等待用户输入
                         // 中断时也设为 false，因为研究已暂停
                         // resume 时会重新设为 true
                         let mut is_running = state_is_running.lock().await;
@@ -456,65 +739,86 @@ async fn run_deep_research_resumable(
     app: AppHandle,
     config: DeepResearchConfig,
     initial_state: DeepResearchState,
-    _checkpoint_store: Arc<Mutex<Option<Checkpoint<DeepResearchState>>>>,
+    thread_id: &str,
 ) -> Result<ExecutionResult<DeepResearchState>, String> {
+    let graph = build_deep_research_runner(&app, &config, thread_id)?;
+    
+    // 使用可中断执行
+    graph.invoke_resumable(initial_state).await
+        .map_err(|e| format!("Deep research execution error: {}", e))
+}
+
+/// 构建带检查点的 Deep Research 图
+fn build_deep_research_runner(
+    app: &AppHandle,
+    config: &DeepResearchConfig,
+    thread_id: &str,
+) -> Result<CompiledGraph<DeepResearchState>, String> {
     // 创建执行上下文
-    let ctx = DeepResearchContext::new(app, config.clone());
+    let ctx = DeepResearchContext::new(app.clone(), config.clone());
     
     // 构建图
     let graph = build_deep_research_graph(ctx)
         .map_err(|e| format!("Failed to build deep research graph: {}", e))?;
     
-    // 配置并执行
+    // 配置
     let graph = graph
+        .with_name(DEEP_RESEARCH_GRAPH)
         .with_max_iterations(20)
         .with_debug(false);
     
-    // 使用可中断执行
-    graph.invoke_resumable(initial_state).await
-        .map_err(|e| format!("Deep research execution error: {}", e))
+    Ok(attach_checkpointer(app, graph, thread_id))
 }
 
 /// 恢复 Deep Research 任务（用户提供澄清后）
+///
+/// 检查点保存在数据库中：应用重启后可传入 `thread_id` 与 `config` 继续之前暂停的研究
 #[tauri::command]
 pub async fn deep_research_resume(
     app: AppHandle,
     state: State<'_, DeepResearchStateManager>,
     clarification: String,
+    thread_id: Option<String>,
+    config: Option<DeepResearchConfig>,
 ) -> Result<(), String> {
-    // 获取保存的检查点和配置
-    let checkpoint = {
-        let mut cp_lock = state.checkpoint.lock().await;
-        cp_lock.take()
+    // 获取线程 ID 和配置（参数优先，其次是当前会话保存的值）
+    let thread_id = match thread_id {
+        Some(id) => id,
+        None => state.thread_id.lock().await.clone()
+            .ok_or("No checkpoint found. Research may not be in clarification state.")?,
     };
     
-    let config = {
-        let config_lock = state.current_config.lock().await;
-        config_lock.clone()
+    let config = match config {
+        Some(config) => config,
+        None => state.current_config.lock().await.clone().ok_or("No config found.")?,
     };
     
-    let checkpoint = checkpoint.ok_or("No checkpoint found. Research may not be in clarification state.")?;
-    let config = config.ok_or("No config found.")?;
+    // 重建图并加载最新检查点
+    let graph = build_deep_research_runner(&app, &config, &thread_id)?;
+    let checkpoint = graph.load_checkpoint(None)
+        .map_err(|e| e.to_string())?
+        .filter(|cp| !cp.pending_interrupts.is_empty())
+        .ok_or("No checkpoint found. Research may not be in clarification state.")?;
     
     // 设置为运行中
     {
         let mut is_running = state.is_running.lock().await;
         *is_running = true;
     }
+    {
+        let mut thread_lock = state.thread_id.lock().await;
+        *thread_lock = Some(thread_id);
+    }
+    {
+        let mut config_lock = state.current_config.lock().await;
+        *config_lock = Some(config);
+    }
     
     // 更新状态，添加用户澄清
-    let mut resumed_state = checkpoint.state.clone();
-    resumed_state.clarification = Some(clarification.clone());
-    resumed_state.phase = ResearchPhase::AnalyzingTopic;  // 重新进入分析阶段
-    
-    // 创建新的检查点
-    let resumed_checkpoint = Checkpoint {
-        state: resumed_state,
-        next_node: checkpoint.next_node,
-        pending_interrupts: vec![],  // 清空中断
-        iterations: checkpoint.iterations,
-        resume_values: checkpoint.resume_values,
-    };
+    let mut resumed_checkpoint = checkpoint;
+    resumed_checkpoint.state.clarification = Some(clarification.clone());
+    resumed_checkpoint.state.phase = ResearchPhase::AnalyzingTopic;  // 重新进入分析阶段
+    resumed_checkpoint.pending_interrupts.clear();  // 清空中断
     
     // 发送恢复事件
     let _ = app.emit("deep-research-event", DeepResearchEvent::PhaseChange {
@@ -525,41 +829,17 @@ pub async fn deep_research_resume(
     // 异步恢复执行
     let app_clone = app.clone();
     let state_is_running = state.is_running.clone();
-    let state_checkpoint = state.checkpoint.clone();
     
     tokio::spawn(async move {
-        // 创建执行上下文
-        let ctx = DeepResearchContext::new(app_clone.clone(), config.clone());
-        
-        // 构建图
-        let graph = match build_deep_research_graph(ctx) {
-            Ok(g) => g.with_max_iterations(20).with_debug(false),
-            Err(e) => {
-                let _ = app_clone.emit("deep-research-event", DeepResearchEvent::Error {
-                    message: format!("Failed to rebuild graph: {}", e),
-                });
-                return;
-            }
-        };
-        
         // 恢复执行
         let resume_cmd = ResumeCommand::new(clarification);
         let result = graph.resume(resumed_checkpoint, resume_cmd).await;
         
         match result {
-            Ok(exec_result) => {
-                match exec_result {
-                    ExecutionResult::Complete(_final_state) => {
-                        // 成功完成
-                        let mut is_running = state_is_running.lock().await;
-                        *is_running = false;
-                    }
-                    ExecutionResult::Interrupted { checkpoint, interrupts: _ } => {
-                        // 又一次中断（可能需要更多澄清）
-                        let mut cp_lock = state_checkpoint.lock().await;
-                        *cp_lock = Some(checkpoint);
-                    }
-                }
+            Ok(_) => {
+                // 完成或再次中断（可能需要更多澄清），检查点已写入数据库
+                let mut is_running = state_is_running.lock().await;
+                *is_running = false;
             }
             Err(e) => {
                 let _ = app_clone.emit("deep-research-event", DeepResearchEvent::Error {
@@ -580,10 +860,10 @@ pub async fn deep_research_abort(
     app: AppHandle,
     state: State<'_, DeepResearchStateManager>,
 ) -> Result<(), String> {
-    // 清空检查点
+    // 清空当前线程（数据库中的检查点保留，可按线程 ID 查看或恢复）
     {
-        let mut cp_lock = state.checkpoint.lock().await;
-        *cp_lock = None;
+        let mut thread_lock = state.thread_id.lock().await;
+        *thread_lock = None;
     }
     
     {
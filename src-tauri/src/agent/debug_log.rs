@@ -176,9 +176,15 @@ pub fn log_llm_request(messages: &[crate::agent::types::Message], tools: Option<
         log_raw(&format!("#### 消息 {} - {}\n\n", i + 1, format!("{:?}", msg.role)));
         
         // 安全截断过长的内容（处理中文等多字节字符）
-        let content = safe_truncate(&msg.content, 2000);
+        let content = safe_truncate(&msg.text(), 2000);
         
         log_code("", &content);
+        for call in &msg.tool_calls {
+            log_kv("工具调用", &format!("{} ({})", call.name, call.id));
+        }
+        if let Some(ref id) = msg.tool_call_id {
+            log_kv("工具结果", id);
+        }
     }
     
     if let Some(tools) = tools {
@@ -239,6 +245,16 @@ pub fn log_iteration(n: usize) {
     log_separator(&format!("迭代 {}", n));
 }
 
+/// 记录检查点（每个节点执行后的快照），可按线程和步骤重放或分叉
+pub fn log_checkpoint(thread_id: &str, step: usize, node: &str, next_node: &str) {
+    log_raw(&format!("> 📌 检查点 `{}` #{}: `{}` → `{}`\n\n", thread_id, step, node, next_node));
+}
+
+/// 记录分叉（从历史步骤重新执行）
+pub fn log_fork(thread_id: &str, step: usize) {
+    log_separator(&format!("分叉 `{}` #{}", thread_id, step));
+}
+
 /// 记录错误
 pub fn log_error(error: &str) {
     log_separator("❌ 错误");
@@ -260,6 +276,7 @@ mod tests {
         
         log_task("测试任务");
         log_intent("Create", "writer", "用户想创建笔记");
+        log_checkpoint("thread-1", 2, "coordinator", "writer");
         
         disable_debug();
         
@@ -268,5 +285,6 @@ mod tests {
         let content = fs::read_to_string(&path).unwrap();
         assert!(content.contains("测试任务"));
         assert!(content.contains("Create"));
+        assert!(content.contains("`thread-1` #2: `coordinator` → `writer`"));
     }
 }
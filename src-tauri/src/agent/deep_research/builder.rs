@@ -1,43 +1,66 @@
 //! Deep Research 图构建器
 
 use std::sync::Arc;
-use tauri::AppHandle;
+use tauri::{AppHandle, Runtime, Wry};
 
 use crate::langgraph::prelude::{StateGraph, CompiledGraph, GraphError, END};
 use crate::langgraph::error::{GraphResult, Interrupt};
-use crate::agent::llm_client::LlmClient;
+use crate::langgraph::branch::path_map;
+use crate::langgraph::node::NodeSpec;
+use crate::agent::llm_error::NodeError;
+use crate::agent::model_router::ModelRouter;
 use crate::agent::deep_research::types::*;
 use crate::agent::deep_research::nodes::*;
 use crate::agent::deep_research::tavily::TavilyClient;
 use crate::agent::deep_research::crawler::JinaClient;
+use crate::agent::transport::HttpTransport;
+use crate::agent::graph::builder::context_node;
 
 /// Deep Research 执行上下文
-#[derive(Clone)]
-pub struct DeepResearchContext {
-    pub app: AppHandle,
-    pub llm: Arc<LlmClient>,
+///
+/// 运行时默认为 `Wry`，测试中可使用 `tauri::test::MockRuntime`
+pub struct DeepResearchContext<R: Runtime = Wry> {
+    pub app: AppHandle<R>,
+    pub router: Arc<ModelRouter>,
     pub config: DeepResearchConfig,
     pub tavily: Option<Arc<TavilyClient>>,
     pub jina: Option<Arc<JinaClient>>,
 }
 
-impl DeepResearchContext {
-    pub fn new(app: AppHandle, config: DeepResearchConfig) -> Self {
-        // 复用 AgentConfig 创建 LlmClient
+impl<R: Runtime> DeepResearchContext<R> {
+    pub fn new(app: AppHandle<R>, config: DeepResearchConfig) -> Self {
+        Self::build(app, config, None)
+    }
+
+    /// LLM、Tavily 与 Jina 的请求都经由指定的传输发送（如测试中回放固件）
+    pub fn with_transport(app: AppHandle<R>, config: DeepResearchConfig, transport: Arc<dyn HttpTransport>) -> Self {
+        Self::build(app, config, Some(transport))
+    }
+
+    fn build(app: AppHandle<R>, config: DeepResearchConfig, transport: Option<Arc<dyn HttpTransport>>) -> Self {
+        // 复用 AgentConfig 创建各节点的 LlmClient
         let agent_config = crate::agent::types::AgentConfig {
             provider: config.provider.clone(),
             model: config.model.clone(),
             api_key: config.api_key.clone(),
             base_url: config.base_url.clone(),
             temperature: config.temperature,
+            budget: config.budget.clone(),
             ..Default::default()
         };
-        let llm = Arc::new(LlmClient::new(agent_config));
+        let router = Arc::new(match &transport {
+            Some(transport) => ModelRouter::with_transport(&agent_config, &config.routing, transport.clone()),
+            None => ModelRouter::with_routing(&agent_config, &config.routing),
+        });
         
         // 创建 Tavily 客户端（如果启用且有 API Key）
         let tavily = if config.enable_web_search {
             config.tavily_api_key.as_ref().map(|key| {
-                Arc::new(TavilyClient::new(key.clone()))
+                let client = TavilyClient::new(key.clone());
+                Arc::new(match &transport {
+                    Some(transport) => client.with_transport(transport.clone()),
+                    None => client,
+                })
             })
         } else {
             None
@@ -46,148 +69,126 @@ impl DeepResearchContext {
         // 创建 Jina 客户端（如果启用网络搜索）
         // Jina Reader 免费版不需要 API Key，但有速率限制
         let jina = if config.enable_web_search {
-            Some(Arc::new(JinaClient::new(None)))
+            let client = JinaClient::new(None);
+            Some(Arc::new(match &transport {
+                Some(transport) => client.with_transport(transport.clone()),
+                None => client,
+            }))
         } else {
             None
         };
         
-        Self { app, llm, config, tavily, jina }
+        Self { app, router, config, tavily, jina }
+    }
+}
+
+impl<R: Runtime> Clone for DeepResearchContext<R> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            router: self.router.clone(),
+            config: self.config.clone(),
+            tavily: self.tavily.clone(),
+            jina: self.jina.clone(),
+        }
     }
 }
 
 /// 构建 Deep Research 图
-pub fn build_deep_research_graph(ctx: DeepResearchContext) -> GraphResult<CompiledGraph<DeepResearchState>> {
+pub fn build_deep_research_graph<R: Runtime>(ctx: DeepResearchContext<R>) -> GraphResult<CompiledGraph<DeepResearchState>> {
     let mut graph = StateGraph::<DeepResearchState>::new();
     
-    // ============ 添加节点 ============
+    add_deep_research_nodes(&mut graph, Some(&ctx));
+    add_deep_research_edges(&mut graph);
     
+    // 编译图
+    graph.compile()
+}
+
+/// 注册 Deep Research 图的节点
+///
+/// `ctx` 为 `None` 时节点为直通占位，用于导出图结构，导出的图与实际的图共用这里的节点
+fn add_deep_research_nodes<R: Runtime>(graph: &mut StateGraph<DeepResearchState>, ctx: Option<&DeepResearchContext<R>>) {
     // 1. 分析主题节点（支持 interrupt）
-    let ctx_analyze = ctx.clone();
-    graph.add_node("analyze_topic", move |state: DeepResearchState| {
-        let ctx = ctx_analyze.clone();
-        async move {
-            match analyze_topic_node(&ctx.app, &ctx.llm, state.clone()).await {
-                Ok(result) => {
-                    let mut state = result.state;
-                    state.goto = result.next_node.unwrap_or_default();
-                    Ok(state)
-                }
-                Err(e) if e.starts_with("INTERRUPT:") => {
-                    // 解析 interrupt 信息
-                    let interrupt_json = e.trim_start_matches("INTERRUPT:");
-                    if let Ok(interrupt) = serde_json::from_str::<Interrupt>(interrupt_json) {
-                        Err(GraphError::Interrupted(vec![interrupt]))
-                    } else {
-                        Err(GraphError::ExecutionError {
-                            node: "analyze_topic".to_string(),
-                            message: e,
-                        })
-                    }
+    graph.add_node("analyze_topic", context_node(ctx, |ctx: DeepResearchContext<R>, state: DeepResearchState| async move {
+        match analyze_topic_node(&ctx.app, ctx.router.for_node("analyze_topic"), state.clone()).await {
+            Ok(result) => {
+                let mut state = result.state;
+                state.goto = result.next_node.unwrap_or_default();
+                Ok(state)
+            }
+            Err(NodeError::Message(e)) if e.starts_with("INTERRUPT:") => {
+                // 解析 interrupt 信息
+                let interrupt_json = e.trim_start_matches("INTERRUPT:");
+                if let Ok(interrupt) = serde_json::from_str::<Interrupt>(interrupt_json) {
+                    Err(GraphError::Interrupted(vec![interrupt]))
+                } else {
+                    Err(GraphError::ExecutionError {
+                        node: "analyze_topic".to_string(),
+                        message: e,
+                    })
                 }
-                Err(e) => Err(GraphError::ExecutionError {
-                    node: "analyze_topic".to_string(),
-                    message: e,
-                })
             }
+            Err(e) => Err(e.into_graph_error("analyze_topic")),
         }
-    });
+    }));
     
     // 2. 搜索笔记节点
-    let ctx_search = ctx.clone();
-    graph.add_node("search_notes", move |state: DeepResearchState| {
-        let ctx = ctx_search.clone();
-        let max_results = ctx.config.max_search_results;
-        let max_web_results = ctx.config.max_web_search_results;
-        let tavily = ctx.tavily.clone();
-        async move {
-            let result = search_notes_node(
-                &ctx.app, 
-                &ctx.llm, 
-                state, 
-                max_results,
-                tavily.as_ref(),
-                max_web_results,
-            ).await
-                .map_err(|e| GraphError::ExecutionError {
-                    node: "search_notes".to_string(),
-                    message: e,
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("search_notes", context_node(ctx, |ctx: DeepResearchContext<R>, state: DeepResearchState| async move {
+        let result = search_notes_node(
+            &ctx.app, 
+            ctx.router.for_node("search_notes"), 
+            state, 
+            ctx.config.max_search_results,
+            ctx.tavily.as_ref(),
+            ctx.config.max_web_search_results,
+        ).await
+            .map_err(|e| e.into_graph_error("search_notes"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 3. 爬取网页节点
-    let ctx_crawl = ctx.clone();
-    graph.add_node("crawl_web", move |state: DeepResearchState| {
-        let ctx = ctx_crawl.clone();
-        let jina = ctx.jina.clone();
+    graph.add_node("crawl_web", context_node(ctx, |ctx: DeepResearchContext<R>, state: DeepResearchState| async move {
         let max_pages = ctx.config.max_web_search_results.min(10); // 最多爬取 10 个网页
-        async move {
-            let result = crawl_web_node(&ctx.app, state, jina.as_ref(), max_pages).await
-                .map_err(|e| GraphError::ExecutionError {
-                    node: "crawl_web".to_string(),
-                    message: e,
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+        let result = crawl_web_node(&ctx.app, state, ctx.jina.as_ref(), max_pages).await
+            .map_err(|e| e.into_graph_error("crawl_web"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
 
     // 4. 阅读笔记节点
-    let ctx_read = ctx.clone();
-    graph.add_node("read_notes", move |state: DeepResearchState| {
-        let ctx = ctx_read.clone();
-        let max_notes = ctx.config.max_notes_to_read;
-        async move {
-            let result = read_notes_node(&ctx.app, &ctx.llm, state, max_notes).await
-                .map_err(|e| GraphError::ExecutionError {
-                    node: "read_notes".to_string(),
-                    message: e,
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
-    
-    // 5. 生成大纲节点
-    let ctx_outline = ctx.clone();
-    graph.add_node("generate_outline", move |state: DeepResearchState| {
-        let ctx = ctx_outline.clone();
-        async move {
-            let result = generate_outline_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError {
-                    node: "generate_outline".to_string(),
-                    message: e,
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("read_notes", context_node(ctx, |ctx: DeepResearchContext<R>, state: DeepResearchState| async move {
+        let result = read_notes_node(&ctx.app, ctx.router.for_node("read_notes"), state, ctx.config.max_notes_to_read).await
+            .map_err(|e| e.into_graph_error("read_notes"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
-    // 5. 撰写报告节点
-    let ctx_write = ctx.clone();
-    graph.add_node("write_report", move |state: DeepResearchState| {
-        let ctx = ctx_write.clone();
-        let include_citations = ctx.config.include_citations;
-        async move {
-            let result = write_report_node(&ctx.app, &ctx.llm, state, include_citations).await
-                .map_err(|e| GraphError::ExecutionError {
-                    node: "write_report".to_string(),
-                    message: e,
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
-    
-    // ============ 定义边 ============
+    // 5. 生成大纲节点（只调用 LLM，临时错误时重试一次）
+    graph.add_node_spec(NodeSpec::new("generate_outline", context_node(ctx, |ctx: DeepResearchContext<R>, state: DeepResearchState| async move {
+        let result = generate_outline_node(&ctx.app, ctx.router.for_node("generate_outline"), state).await
+            .map_err(|e| e.into_graph_error("generate_outline"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    })).with_retry(1));
     
+    // 6. 撰写报告节点
+    graph.add_node("write_report", context_node(ctx, |ctx: DeepResearchContext<R>, state: DeepResearchState| async move {
+        let result = write_report_node(&ctx.app, ctx.router.for_node("write_report"), state, ctx.config.include_citations).await
+            .map_err(|e| e.into_graph_error("write_report"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
+}
+
+/// 定义 Deep Research 图的边
+fn add_deep_research_edges(graph: &mut StateGraph<DeepResearchState>) {
     // 入口点
     graph.set_entry_point("analyze_topic");
     
@@ -203,7 +204,7 @@ pub fn build_deep_research_graph(ctx: DeepResearchContext) -> GraphResult<Compil
                 "search_notes".to_string()
             }
         },
-        None,
+        Some(path_map(["search_notes", END])),
     );
     
     graph.add_conditional_edges_sync(
@@ -217,7 +218,7 @@ pub fn build_deep_research_graph(ctx: DeepResearchContext) -> GraphResult<Compil
                 "crawl_web".to_string()
             }
         },
-        None,
+        Some(path_map(["crawl_web", END])),
     );
     
     graph.add_conditional_edges_sync(
@@ -231,7 +232,7 @@ pub fn build_deep_research_graph(ctx: DeepResearchContext) -> GraphResult<Compil
                 "read_notes".to_string()
             }
         },
-        None,
+        Some(path_map(["read_notes", END])),
     );
     
     graph.add_conditional_edges_sync(
@@ -245,7 +246,7 @@ pub fn build_deep_research_graph(ctx: DeepResearchContext) -> GraphResult<Compil
                 "generate_outline".to_string()
             }
         },
-        None,
+        Some(path_map(["generate_outline", END])),
     );
     
     graph.add_conditional_edges_sync(
@@ -259,54 +260,80 @@ pub fn build_deep_research_graph(ctx: DeepResearchContext) -> GraphResult<Compil
                 "write_report".to_string()
             }
         },
-        None,
+        Some(path_map(["write_report", END])),
     );
     
     // 结束点
     graph.set_finish_point("write_report");
-    
-    // 编译图
-    graph.compile()
 }
 
-/// 获取 Deep Research 图的可视化描述
+/// 获取 Deep Research 图的可视化描述 (Mermaid)
+/// 
+/// 由实际的边定义生成
 pub fn describe_deep_research_graph() -> String {
-    r#"
-Deep Research Graph Structure:
-==============================
+    skeleton_graph().draw_mermaid()
+}
+
+/// 与实际图相同的节点与边，节点为直通占位
+fn skeleton_graph() -> StateGraph<DeepResearchState> {
+    let mut graph = StateGraph::<DeepResearchState>::new();
+    add_deep_research_nodes::<Wry>(&mut graph, None);
+    add_deep_research_edges(&mut graph);
+    graph
+}
 
-    ┌─────────────────┐
-    │     START       │
-    └────────┬────────┘
-             │
-             ▼
-    ┌─────────────────┐
-    │  analyze_topic  │ ─── 分析研究主题，提取关键词
-    └────────┬────────┘
-             │
-             ▼
-    ┌─────────────────┐
-    │  search_notes   │ ─── 搜索笔记库
-    └────────┬────────┘
-             │
-             ▼
-    ┌─────────────────┐
-    │   read_notes    │ ─── 批量读取笔记内容
-    └────────┬────────┘
-             │
-             ▼
-    ┌─────────────────┐
-    │generate_outline │ ─── 生成报告大纲
-    └────────┬────────┘
-             │
-             ▼
-    ┌─────────────────┐
-    │  write_report   │ ─── 撰写研究报告（流式输出）
-    └────────┬────────┘
-             │
-             ▼
-    ┌─────────────────┐
-    │      END        │
-    └─────────────────┘
-"#.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::transport::fixture::{FixtureMode, FixtureTransport};
+    use crate::agent::transport::ReqwestTransport;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_graph_structure() {
+        let graph = skeleton_graph();
+        assert!(graph.view().validate().is_empty());
+        assert!(graph.compile().is_ok());
+    }
+
+    /// 回放固件运行完整的图（LLM、Tavily 与 Jina），无需网络与 API Key
+    ///
+    /// 重新录制：`LUMINA_RECORD_FIXTURES=1 OPENAI_API_KEY=... TAVILY_API_KEY=... cargo test test_replay_web_research`
+    #[tokio::test]
+    async fn test_replay_web_research() {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/deep_research/web_research.json");
+        let transport = Arc::new(
+            FixtureTransport::from_env(&fixture, Arc::new(ReqwestTransport::default())).unwrap(),
+        );
+        let workspace = tempfile::tempdir().unwrap();
+        let config = DeepResearchConfig {
+            model: "gpt-4o-mini".to_string(),
+            api_key: std::env::var("OPENAI_API_KEY").unwrap_or_else(|_| "sk-test".to_string()),
+            enable_web_search: true,
+            tavily_api_key: Some(std::env::var("TAVILY_API_KEY").unwrap_or_else(|_| "tvly-test".to_string())),
+            max_web_search_results: 2,
+            ..Default::default()
+        };
+
+        let app = tauri::test::mock_app();
+        let ctx = DeepResearchContext::with_transport(app.handle().clone(), config, transport.clone());
+        let graph = build_deep_research_graph(ctx).unwrap().with_max_iterations(20);
+        let state = graph.invoke(DeepResearchState {
+            topic: "Rust 的所有权与借用".to_string(),
+            workspace_path: workspace.path().to_string_lossy().into_owned(),
+            search_mode: SearchMode::Keyword,
+            ..Default::default()
+        }).await.unwrap();
+
+        assert!(state.error.is_none(), "{:?}", state.error);
+        assert_eq!(state.web_search_results.len(), 2);
+        assert_eq!(state.crawled_pages.len(), 2);
+        assert_eq!(state.outline.as_ref().unwrap().sections.len(), 3);
+        let report = state.report.unwrap();
+        assert!(report.starts_with("# Rust 所有权与借用"), "{}", report);
+        assert!(state.report_chunks.len() > 1);
+        if transport.mode() == FixtureMode::Replay {
+            assert!(transport.unused().is_empty(), "unused recordings: {:?}", transport.unused());
+        }
+    }
 }
@@ -2,7 +2,9 @@
 //! 
 //! 使用 Jina Reader API 将网页转换为可读的 Markdown 格式
 
-use reqwest::Client;
+use std::sync::Arc;
+
+use crate::agent::transport::{HttpRequest, HttpTransport, ReqwestTransport};
 
 /// 爬取的网页内容
 #[derive(Debug, Clone)]
@@ -17,7 +19,7 @@ pub struct CrawledPage {
 
 /// Jina Reader 客户端
 pub struct JinaClient {
-    client: Client,
+    transport: Arc<dyn HttpTransport>,
     api_key: Option<String>,
 }
 
@@ -25,11 +27,17 @@ impl JinaClient {
     /// 创建新的 Jina 客户端
     pub fn new(api_key: Option<String>) -> Self {
         Self {
-            client: Client::new(),
+            transport: Arc::new(ReqwestTransport::default()),
             api_key,
         }
     }
 
+    /// 使用指定的传输发送请求（如测试中回放固件）
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// 爬取网页内容
     /// 
     /// 使用 Jina Reader API 将网页转换为 Markdown
@@ -37,8 +45,7 @@ impl JinaClient {
         // Jina Reader API: https://r.jina.ai/{url}
         let jina_url = format!("https://r.jina.ai/{}", url);
         
-        let mut request = self.client
-            .get(&jina_url)
+        let mut request = HttpRequest::get(&jina_url)
             .header("Accept", "text/markdown");
         
         // 如果有 API Key，添加到请求头
@@ -46,15 +53,16 @@ impl JinaClient {
             request = request.header("Authorization", format!("Bearer {}", key));
         }
         
-        let response = request
-            .send()
+        let response = self.transport
+            .send(request)
             .await
             .map_err(|e| format!("请求 Jina API 失败: {}", e))?;
         
-        if !response.status().is_success() {
+        if !response.is_success() {
+            let status = response.status;
             return Err(format!(
                 "Jina API 返回错误状态: {} - {}",
-                response.status(),
+                status,
                 response.text().await.unwrap_or_default()
             ));
         }
@@ -2,10 +2,12 @@
 
 use std::sync::Arc;
 use std::path::Path;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Runtime};
 use walkdir::WalkDir;
 
+use crate::agent::events::emit;
 use crate::agent::llm_client::LlmClient;
+use crate::agent::llm_error::NodeError;
 use crate::agent::deep_research::types::*;
 use crate::agent::deep_research::tavily::TavilyClient;
 use crate::agent::deep_research::crawler::JinaClient;
@@ -18,12 +20,12 @@ pub struct NodeResult {
 }
 
 /// 发送事件到前端
-fn emit_event(app: &AppHandle, event: DeepResearchEvent) {
-    let _ = app.emit("deep-research-event", &event);
+fn emit_event<R: Runtime>(app: &AppHandle<R>, event: DeepResearchEvent) {
+    emit(app, "deep-research-event", event);
 }
 
 /// 发送 Token 使用量事件
-fn emit_token_usage(app: &AppHandle, prompt_tokens: usize, completion_tokens: usize, total_tokens: usize) {
+fn emit_token_usage<R: Runtime>(app: &AppHandle<R>, prompt_tokens: usize, completion_tokens: usize, total_tokens: usize) {
     emit_event(app, DeepResearchEvent::TokenUsage {
         prompt_tokens,
         completion_tokens,
@@ -149,11 +151,11 @@ fn parse_json<T: serde::de::DeserializeOwned>(text: &str, context: &str) -> Resu
 /// - 如果是简单问候/闲聊，直接回复
 /// - 如果主题不够明确，触发 interrupt 请求用户澄清
 /// - 如果有 clarification，使用它来增强研究主题
-pub async fn analyze_topic_node(
-    app: &AppHandle,
+pub async fn analyze_topic_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &Arc<LlmClient>,
     mut state: DeepResearchState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     state.phase = ResearchPhase::AnalyzingTopic;
     emit_event(app, DeepResearchEvent::PhaseChange {
         phase: state.phase.clone(),
@@ -299,7 +301,7 @@ pub async fn analyze_topic_node(
         });
 
         // 返回中断错误（会被 builder 捕获并转换为 GraphError::Interrupted）
-        return Err(format!("INTERRUPT:{}", serde_json::to_string(&interrupt).unwrap_or_default()));
+        return Err(format!("INTERRUPT:{}", serde_json::to_string(&interrupt).unwrap_or_default()).into());
     }
 
     // 正常研究流程：提取关键词（使用可能包含澄清的有效主题）
@@ -352,14 +354,14 @@ React
 /// - Hybrid: 合并两者结果
 /// 
 /// 同时可选支持网络搜索（Tavily）
-pub async fn search_notes_node(
-    app: &AppHandle,
+pub async fn search_notes_node<R: Runtime>(
+    app: &AppHandle<R>,
     _llm: &Arc<LlmClient>,
     mut state: DeepResearchState,
     max_results: usize,
     tavily: Option<&Arc<TavilyClient>>,
     max_web_results: usize,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     state.phase = ResearchPhase::SearchingNotes;
     
     let search_mode_msg = match state.search_mode {
@@ -477,12 +479,12 @@ pub async fn search_notes_node(
 /// 
 /// 分批爬取：每次爬取 BATCH_SIZE 个，直到达到 max_pages 或内容总长度达到限制
 /// 这样可以更快开始生成报告，同时控制 prompt 长度
-pub async fn crawl_web_node(
-    app: &AppHandle,
+pub async fn crawl_web_node<R: Runtime>(
+    app: &AppHandle<R>,
     mut state: DeepResearchState,
     jina: Option<&Arc<JinaClient>>,
     max_pages: usize,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     const BATCH_SIZE: usize = 2;  // 每批爬取 2 个
     const MAX_TOTAL_CONTENT_CHARS: usize = 15000;  // 总内容限制 15000 字符
     const MAX_PER_PAGE_CHARS: usize = 3000;  // 每页内容限制 3000 字符
@@ -814,12 +816,12 @@ fn get_random_tags(workspace_path: &str, count: usize) -> Vec<String> {
 /// 
 /// 批量读取找到的笔记内容
 /// 如果没有本地笔记但有网络结果，跳过此阶段直接生成大纲
-pub async fn read_notes_node(
-    app: &AppHandle,
+pub async fn read_notes_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &Arc<LlmClient>,
     mut state: DeepResearchState,
     max_notes: usize,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     // 如果没有本地笔记，检查是否有网络结果
     if state.found_notes.is_empty() {
         if !state.web_search_results.is_empty() {
@@ -928,11 +930,11 @@ pub async fn read_notes_node(
 /// 生成大纲节点
 /// 
 /// 基于阅读的笔记内容和/或网络搜索结果，生成报告大纲
-pub async fn generate_outline_node(
-    app: &AppHandle,
+pub async fn generate_outline_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &Arc<LlmClient>,
     mut state: DeepResearchState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     state.phase = ResearchPhase::GeneratingOutline;
     emit_event(app, DeepResearchEvent::PhaseChange {
         phase: state.phase.clone(),
@@ -1024,12 +1026,12 @@ pub async fn generate_outline_node(
 /// 撰写报告节点
 /// 
 /// 基于大纲和笔记内容/网络搜索结果，生成完整报告
-pub async fn write_report_node(
-    app: &AppHandle,
+pub async fn write_report_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &Arc<LlmClient>,
     mut state: DeepResearchState,
     include_citations: bool,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     state.phase = ResearchPhase::WritingReport;
     emit_event(app, DeepResearchEvent::PhaseChange {
         phase: state.phase.clone(),
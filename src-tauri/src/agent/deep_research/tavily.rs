@@ -3,8 +3,10 @@
 //! 封装 Tavily API 调用，用于 Deep Research 的网络搜索功能
 
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 use super::types::WebSearchResult;
+use crate::agent::transport::{HttpRequest, HttpTransport, ReqwestTransport};
 
 /// Tavily 搜索请求
 #[derive(Debug, Serialize)]
@@ -43,7 +45,7 @@ struct TavilyResult {
 /// Tavily 客户端
 pub struct TavilyClient {
     api_key: String,
-    client: reqwest::Client,
+    transport: Arc<dyn HttpTransport>,
 }
 
 impl TavilyClient {
@@ -56,7 +58,13 @@ impl TavilyClient {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { api_key, client }
+        Self { api_key, transport: ReqwestTransport::shared(client) }
+    }
+
+    /// 使用指定的传输发送请求（如测试中回放固件）
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = transport;
+        self
     }
 
     /// 执行搜索
@@ -74,16 +82,17 @@ impl TavilyClient {
             include_raw_content: Some(false),
         };
 
-        let response = self
-            .client
-            .post(Self::API_URL)
+        let request = HttpRequest::post(Self::API_URL)
             .json(&request)
-            .send()
+            .map_err(|e| format!("Tavily request failed: {}", e))?;
+        let response = self
+            .transport
+            .send(request)
             .await
             .map_err(|e| format!("Tavily request failed: {}", e))?;
 
-        if !response.status().is_success() {
-            let status = response.status();
+        if !response.is_success() {
+            let status = response.status;
             let body = response.text().await.unwrap_or_default();
             return Err(format!("Tavily API error {}: {}", status, body));
         }
@@ -1,6 +1,8 @@
 //! Deep Research 类型定义
 
 use serde::{Deserialize, Serialize};
+use crate::agent::budget::BudgetConfig;
+use crate::agent::types::ModelRouting;
 use crate::langgraph::state::GraphState as LangGraphState;
 
 /// 研究阶段
@@ -169,6 +171,14 @@ pub struct DeepResearchConfig {
     /// 网络搜索最大结果数
     #[serde(default = "default_web_search_results")]
     pub max_web_search_results: usize,
+    
+    /// 按节点选择模型与回退链（节点名见 `NODE_NAMES`）
+    #[serde(default)]
+    pub routing: ModelRouting,
+    
+    /// 限流与用量预算
+    #[serde(default)]
+    pub budget: BudgetConfig,
 }
 
 fn default_web_search_results() -> usize { 10 }
@@ -195,6 +205,8 @@ impl Default for DeepResearchConfig {
             enable_web_search: false,
             tavily_api_key: None,
             max_web_search_results: default_web_search_results(),
+            routing: ModelRouting::default(),
+            budget: BudgetConfig::default(),
         }
     }
 }
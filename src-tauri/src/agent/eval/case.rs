@@ -0,0 +1,206 @@
+//! 评估用例
+//!
+//! 格式与 `tests/agent-eval/cases/*.yaml` 一致（camelCase 字段），
+//! 额外的 `mock` 字段为离线运行提供脚本化的 LLM 回复
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::langgraph::ablation::TestCase;
+use crate::langgraph::evaluator::{
+    CompositeEvaluator, ContainsEvaluator, LatencyEvaluator, TokenBudgetEvaluator, ToolCallEvaluator,
+};
+
+/// 评估用例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalCase {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub category: String,
+    /// 用户输入
+    pub input: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 当前打开的笔记（相对于固件笔记库）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_note: Option<String>,
+    /// 预期调用的工具
+    #[serde(default)]
+    pub expected_tools: Vec<String>,
+    /// 预期的工具调用顺序
+    #[serde(default)]
+    pub expected_tool_sequence: Vec<String>,
+    /// 不应调用的工具
+    #[serde(default)]
+    pub forbidden_tools: Vec<String>,
+    /// 最终回复中必须出现的关键词
+    #[serde(default)]
+    pub expected_contains: Vec<String>,
+    /// 预期结果描述（仅用于报告）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_outcome: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_time_ms: Option<u64>,
+    /// 脚本化的 LLM 回复，按请求顺序依次返回
+    #[serde(default)]
+    pub mock: Vec<MockReply>,
+}
+
+/// 一次 LLM 回复
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockReply {
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<MockToolCall>,
+}
+
+/// 回复中的工具调用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+impl MockReply {
+    pub fn text(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    pub fn tool(name: impl Into<String>, arguments: Value) -> Self {
+        Self {
+            content: String::new(),
+            tool_calls: vec![MockToolCall {
+                name: name.into(),
+                arguments,
+            }],
+        }
+    }
+}
+
+impl EvalCase {
+    /// 是否带有 mock 脚本（可离线运行）
+    pub fn is_offline(&self) -> bool {
+        !self.mock.is_empty()
+    }
+
+    /// 转换为 langgraph 的测试用例
+    pub fn to_test_case(&self) -> TestCase {
+        let mut case = TestCase::new(&self.id, json!(self.input));
+        case.expected = self.expected_outcome.clone().map(Value::String);
+        case.expected_nodes = self.expected_tools.clone();
+        case.max_latency_ms = self.max_time_ms;
+        case.max_tokens = self.max_tokens;
+        case
+    }
+
+    /// 根据预期构建评估器
+    pub fn evaluator(&self) -> CompositeEvaluator {
+        let mut tools = ToolCallEvaluator::new(self.expected_tools.clone())
+            .with_forbidden(self.forbidden_tools.clone());
+        if !self.expected_tool_sequence.is_empty() {
+            tools = tools.with_order(self.expected_tool_sequence.clone());
+        }
+        let mut evaluator = CompositeEvaluator::new().add(tools, 1.0);
+        if !self.expected_contains.is_empty() {
+            evaluator = evaluator.add(ContainsEvaluator::new(self.expected_contains.clone()), 1.0);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            evaluator = evaluator.add(TokenBudgetEvaluator::new(max_tokens), 0.5);
+        }
+        if let Some(max_time_ms) = self.max_time_ms {
+            evaluator = evaluator.add(LatencyEvaluator::new(max_time_ms), 0.5);
+        }
+        evaluator
+    }
+}
+
+/// 加载用例：单个 YAML/JSON 文件，或目录下所有此类文件（按文件名排序）
+pub fn load_cases(path: &Path) -> Result<Vec<EvalCase>, String> {
+    if !path.is_dir() {
+        return load_case_file(path);
+    }
+    let mut files: Vec<_> = std::fs::read_dir(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| case_format(p).is_some())
+        .collect();
+    files.sort();
+
+    let mut cases = Vec::new();
+    for file in files {
+        cases.extend(load_case_file(&file)?);
+    }
+    Ok(cases)
+}
+
+fn load_case_file(path: &Path) -> Result<Vec<EvalCase>, String> {
+    let format = case_format(path)
+        .ok_or_else(|| format!("Unsupported case file: {}", path.display()))?;
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let cases = if format == "json" {
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str(&text).map_err(|e| e.to_string())
+    };
+    cases.map_err(|e| format!("Invalid cases in {}: {}", path.display(), e))
+}
+
+fn case_format(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "json" => Some("json"),
+        "yaml" | "yml" => Some("yaml"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_cases_from_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("b.json"),
+            r#"[{"id": "b1", "name": "second", "input": "hi"}]"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("a.json"),
+            r#"[{
+                "id": "a1",
+                "name": "search",
+                "category": "basic",
+                "input": "find React",
+                "expectedTools": ["search_notes"],
+                "expectedPlanSteps": 2,
+                "mock": [
+                    {"toolCalls": [{"name": "search_notes", "arguments": {"query": "React"}}]},
+                    {"content": "done"}
+                ]
+            }]"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let cases = load_cases(dir.path()).unwrap();
+        let ids: Vec<_> = cases.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["a1", "b1"]);
+        assert!(cases[0].is_offline());
+        assert!(!cases[1].is_offline());
+        assert_eq!(cases[0].mock[0].tool_calls[0].name, "search_notes");
+        assert_eq!(cases[0].to_test_case().expected_nodes, vec!["search_notes"]);
+    }
+}
@@ -0,0 +1,231 @@
+//! 脚本化的 mock LLM 服务
+//!
+//! 在本地端口模拟 OpenAI 兼容的 `/chat/completions`，按顺序返回用例中的回复，
+//! 同时支持非流式 JSON 与 SSE 流式响应。token 用量按字符数估算（约 4 字符 / token）
+
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use super::case::MockReply;
+
+/// 脚本用尽后的回复：不含工具调用，让工作节点结束循环
+const FALLBACK_REPLY: &str = "(no scripted reply)";
+
+#[derive(Default)]
+struct MockState {
+    replies: VecDeque<MockReply>,
+    requests: Vec<Value>,
+    unscripted: usize,
+}
+
+/// mock LLM 服务，drop 时停止
+pub struct MockLlmServer {
+    base_url: String,
+    state: Arc<Mutex<MockState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockLlmServer {
+    /// 在随机端口上启动
+    pub async fn start(replies: Vec<MockReply>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}/v1", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(MockState {
+            replies: replies.into(),
+            ..Default::default()
+        }));
+
+        let server_state = state.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = server_state.clone();
+                tokio::spawn(async move {
+                    let _ = serve(stream, state).await;
+                });
+            }
+        });
+
+        Ok(Self { base_url, state, handle })
+    }
+
+    /// 作为 `AgentConfig::base_url` 使用的地址
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 收到的请求体
+    pub fn requests(&self) -> Vec<Value> {
+        self.state.lock().map(|s| s.requests.clone()).unwrap_or_default()
+    }
+
+    /// 脚本用尽后收到的请求数
+    pub fn unscripted(&self) -> usize {
+        self.state.lock().map(|s| s.unscripted).unwrap_or_default()
+    }
+
+    /// 尚未使用的回复数
+    pub fn remaining(&self) -> usize {
+        self.state.lock().map(|s| s.replies.len()).unwrap_or_default()
+    }
+}
+
+impl Drop for MockLlmServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// 处理一个连接上的请求（支持 keep-alive）
+async fn serve(stream: TcpStream, state: Arc<Mutex<MockState>>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+
+        let mut content_length = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await? == 0 {
+                return Ok(());
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await?;
+        let request: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+        let response = respond(&state, request);
+        reader.get_mut().write_all(response.as_bytes()).await?;
+    }
+}
+
+/// 取出下一条回复并编码为 HTTP 响应
+fn respond(state: &Mutex<MockState>, request: Value) -> String {
+    let stream = request["stream"].as_bool().unwrap_or(false);
+    let prompt_tokens = estimate_tokens(&request["messages"].to_string());
+
+    let reply = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.requests.push(request);
+        state.replies.pop_front().unwrap_or_else(|| {
+            state.unscripted += 1;
+            MockReply::text(FALLBACK_REPLY)
+        })
+    };
+
+    let tool_calls: Vec<Value> = reply.tool_calls.iter().enumerate()
+        .map(|(index, call)| json!({
+            "index": index,
+            "id": format!("call_{}", index),
+            "type": "function",
+            "function": {
+                "name": call.name,
+                "arguments": call.arguments.to_string(),
+            },
+        }))
+        .collect();
+
+    let (content_type, body) = if stream {
+        let mut body = String::new();
+        if !reply.content.is_empty() {
+            let chunk = json!({ "choices": [{ "index": 0, "delta": { "content": reply.content } }] });
+            body.push_str(&format!("data: {}\n\n", chunk));
+        }
+        if !tool_calls.is_empty() {
+            let chunk = json!({ "choices": [{ "index": 0, "delta": { "tool_calls": tool_calls } }] });
+            body.push_str(&format!("data: {}\n\n", chunk));
+        }
+        body.push_str("data: [DONE]\n\n");
+        ("text/event-stream", body)
+    } else {
+        let completion_tokens = estimate_tokens(&reply.content)
+            + tool_calls.iter().map(|c| estimate_tokens(&c["function"].to_string())).sum::<u32>();
+        let mut message = json!({ "role": "assistant", "content": reply.content });
+        if !tool_calls.is_empty() {
+            message["tool_calls"] = json!(tool_calls);
+        }
+        let body = json!({
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "finish_reason": if tool_calls.is_empty() { "stop" } else { "tool_calls" },
+            }],
+            "usage": {
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens,
+            },
+        });
+        ("application/json", body.to_string())
+    };
+
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn estimate_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scripted_replies() {
+        let server = MockLlmServer::start(vec![
+            MockReply::tool("read_note", json!({ "path": "note1.md" })),
+            MockReply::text("hello"),
+        ])
+        .await
+        .unwrap();
+        let url = format!("{}/chat/completions", server.base_url());
+        let client = reqwest::Client::new();
+
+        let first: Value = client.post(&url)
+            .json(&json!({ "messages": [{ "role": "user", "content": "hi" }], "stream": false }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        let call = &first["choices"][0]["message"]["tool_calls"][0]["function"];
+        assert_eq!(call["name"], "read_note");
+        assert_eq!(call["arguments"], r#"{"path":"note1.md"}"#);
+        assert!(first["usage"]["prompt_tokens"].as_u64().unwrap() > 0);
+
+        let second = client.post(&url)
+            .json(&json!({ "messages": [], "stream": true }))
+            .send().await.unwrap()
+            .text().await.unwrap();
+        assert!(second.contains(r#""content":"hello""#));
+        assert!(second.ends_with("data: [DONE]\n\n"));
+
+        let third: Value = client.post(&url)
+            .json(&json!({ "messages": [] }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(third["choices"][0]["message"]["content"], FALLBACK_REPLY);
+
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(server.remaining(), 0);
+        assert_eq!(server.unscripted(), 1);
+    }
+}
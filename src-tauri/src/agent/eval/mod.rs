@@ -0,0 +1,9 @@
+//! 离线 Agent 评估
+//!
+//! 读取 YAML/JSON 测试用例，在固件笔记库副本上针对脚本化的 mock LLM
+//! 运行 `build_agent_graph`，用 langgraph 评估器打分并生成 Markdown/JSON 报告。
+//! 是 `tests/agent-eval/runner.ts` 的离线 Rust 版本
+
+pub mod case;
+pub mod mock_llm;
+pub mod runner;
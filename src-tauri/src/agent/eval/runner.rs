@@ -0,0 +1,445 @@
+//! 评估运行器
+//!
+//! 每个用例在固件笔记库的临时副本上运行一次真实的 Agent 图，
+//! LLM 请求由 [`MockLlmServer`] 按脚本应答
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+use walkdir::WalkDir;
+
+use crate::agent::graph::{build_agent_graph, AgentContext};
+use crate::agent::types::{AgentConfig, AgentStatus, GraphState};
+use crate::langgraph::ablation::{AblationConfig, AblationReport};
+use crate::langgraph::evaluator::{EvalContext, EvalResult, Evaluator};
+use crate::langgraph::executor::{ExecutionConfig, ExecutionResult};
+use crate::langgraph::metrics::{MetricsCollector, RunMetrics};
+
+use super::case::EvalCase;
+use super::mock_llm::MockLlmServer;
+
+/// 单个用例在某个配置下的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub case_id: String,
+    pub name: String,
+    pub category: String,
+    /// 消融配置 ID
+    pub config_id: String,
+    pub passed: bool,
+    pub score: f64,
+    /// 最终回复
+    pub output: String,
+    /// 按顺序调用的工具
+    pub tool_calls: Vec<String>,
+    /// 发往 LLM 的请求数
+    pub llm_requests: usize,
+    /// 脚本用尽后的请求数
+    pub unscripted_requests: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub eval: EvalResult,
+    pub metrics: RunMetrics,
+}
+
+/// 评估报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub run_id: String,
+    pub timestamp: String,
+    pub results: Vec<CaseResult>,
+    /// 没有 mock 脚本而跳过的用例
+    pub skipped: Vec<String>,
+    /// 按消融配置汇总
+    pub ablation: AblationReport,
+}
+
+/// 一次图运行的原始结果
+struct RunOutcome {
+    state: Option<GraphState>,
+    metrics: RunMetrics,
+    llm_requests: usize,
+    unscripted_requests: usize,
+    error: Option<String>,
+}
+
+/// 离线评估运行器
+pub struct EvalRunner {
+    vault: PathBuf,
+    configs: Vec<AblationConfig>,
+    agent_config: AgentConfig,
+}
+
+impl EvalRunner {
+    /// 使用固件笔记库创建，默认只运行基线配置
+    pub fn new(vault: impl Into<PathBuf>) -> Self {
+        Self {
+            vault: vault.into(),
+            configs: vec![AblationConfig::baseline("baseline")],
+            agent_config: AgentConfig {
+                model: "mock".to_string(),
+                api_key: "mock".to_string(),
+                auto_approve: true,
+                ..AgentConfig::default()
+            },
+        }
+    }
+
+    /// 替换消融配置，每个用例在每个配置下各运行一次
+    pub fn with_configs(mut self, configs: Vec<AblationConfig>) -> Self {
+        self.configs = configs;
+        self
+    }
+
+    /// 替换 Agent 配置（`base_url` 会被指向 mock 服务）
+    pub fn with_agent_config(mut self, config: AgentConfig) -> Self {
+        self.agent_config = config;
+        self
+    }
+
+    /// 运行所有带 mock 脚本的用例
+    pub async fn run<R: Runtime>(&self, app: &AppHandle<R>, cases: &[EvalCase]) -> EvalReport {
+        let collector = MetricsCollector::new();
+        let mut results = Vec::new();
+        for config in &self.configs {
+            for case in cases.iter().filter(|c| c.is_offline()) {
+                let result = self.run_case(app, case, config).await;
+                collector.add_run(result.metrics.clone());
+                results.push(result);
+            }
+        }
+
+        let now = chrono::Local::now();
+        EvalReport {
+            run_id: now.format("%Y%m%d-%H%M%S").to_string(),
+            timestamp: now.to_rfc3339(),
+            results,
+            skipped: cases.iter().filter(|c| !c.is_offline()).map(|c| c.id.clone()).collect(),
+            ablation: AblationReport::from_metrics(&collector, &self.configs),
+        }
+    }
+
+    /// 运行单个用例
+    pub async fn run_case<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        case: &EvalCase,
+        config: &AblationConfig,
+    ) -> CaseResult {
+        let config_id = config.config_id();
+        let workspace = std::env::temp_dir().join(format!("lumina-agent-eval-{}", uuid::Uuid::new_v4()));
+        let outcome = self.execute(app, case, config, &workspace).await;
+        let _ = std::fs::remove_dir_all(&workspace);
+
+        let RunOutcome { state, mut metrics, llm_requests, unscripted_requests, error } = outcome
+            .unwrap_or_else(|e| RunOutcome {
+                state: None,
+                metrics: failed_metrics(&config_id, &e),
+                llm_requests: 0,
+                unscripted_requests: 0,
+                error: Some(e),
+            });
+
+        let output = state.as_ref()
+            .and_then(|s| s.final_result.clone())
+            .unwrap_or_default();
+        let tool_calls = state.as_ref().map(called_tools).unwrap_or_default();
+
+        let test_case = case.to_test_case();
+        let ctx = EvalContext {
+            output: Value::String(output.clone()),
+            expected: test_case.expected,
+            metrics: metrics.clone(),
+            test_name: test_case.name,
+            input: test_case.input,
+            tool_calls: tool_calls.clone(),
+        };
+        let eval = case.evaluator().evaluate(&ctx);
+
+        let passed = error.is_none() && metrics.success && eval.passed;
+        metrics.success = passed;
+        metrics.set_quality_score(eval.score);
+
+        CaseResult {
+            case_id: case.id.clone(),
+            name: case.name.clone(),
+            category: case.category.clone(),
+            config_id,
+            passed,
+            score: eval.score,
+            output,
+            tool_calls,
+            llm_requests,
+            unscripted_requests,
+            error,
+            eval,
+            metrics,
+        }
+    }
+
+    async fn execute<R: Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        case: &EvalCase,
+        config: &AblationConfig,
+        workspace: &Path,
+    ) -> Result<RunOutcome, String> {
+        copy_dir(&self.vault, workspace)?;
+        let server = MockLlmServer::start(case.mock.clone()).await
+            .map_err(|e| format!("Failed to start mock LLM: {}", e))?;
+
+        let agent_config = AgentConfig {
+            base_url: Some(server.base_url().to_string()),
+            ..self.agent_config.clone()
+        };
+        let max_iterations = agent_config.max_steps * 2;
+        let graph = build_agent_graph(AgentContext::new(app.clone(), agent_config))
+            .map_err(|e| format!("Failed to build graph: {}", e))?
+            .with_name("agent_eval")
+            .with_config(ExecutionConfig::for_ablation(config.config_id(), config.masked_nodes.clone()))
+            .with_max_iterations(max_iterations);
+
+        let active_note = case.active_note.as_ref().map(|note| workspace.join(note));
+        let active_note_content = active_note.as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        let initial_state = GraphState {
+            user_task: case.input.clone(),
+            workspace_path: workspace.to_string_lossy().to_string(),
+            active_note_path: active_note.map(|path| path.to_string_lossy().to_string()),
+            active_note_content,
+            auto_approve: self.agent_config.auto_approve,
+            status: AgentStatus::Running,
+            ..GraphState::default()
+        };
+
+        let (state, metrics, error) = match graph.invoke_with_metrics(initial_state).await {
+            Ok(run) => {
+                let metrics = run.metrics.unwrap_or_else(|| failed_metrics(&config.config_id(), ""));
+                match run.result {
+                    ExecutionResult::Complete(state) => (Some(state), metrics, None),
+                    ExecutionResult::Interrupted { .. } => {
+                        (None, metrics, Some("Unexpected interrupt".to_string()))
+                    }
+                }
+            }
+            Err(e) => {
+                let error = e.to_string();
+                (None, failed_metrics(&config.config_id(), &error), Some(error))
+            }
+        };
+
+        Ok(RunOutcome {
+            state,
+            metrics,
+            llm_requests: server.requests().len(),
+            unscripted_requests: server.unscripted(),
+            error,
+        })
+    }
+}
+
+/// 运行未产出指标时的占位指标
+fn failed_metrics(config_id: &str, error: &str) -> RunMetrics {
+    let mut metrics = RunMetrics::new(uuid::Uuid::new_v4().to_string(), config_id);
+    if !error.is_empty() {
+        metrics.error = Some(error.to_string());
+    }
+    metrics
+}
+
+/// 从观察记录（`[tool] 结果`）中提取工具名
+fn called_tools(state: &GraphState) -> Vec<String> {
+    state.observations.iter()
+        .filter_map(|obs| {
+            let name = obs.strip_prefix('[')?.split(']').next()?;
+            let is_tool = !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            is_tool.then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// 复制固件笔记库
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    for entry in WalkDir::new(from) {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let relative = entry.path().strip_prefix(from).map_err(|e| e.to_string())?;
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)
+        } else {
+            std::fs::copy(entry.path(), &target).map(|_| ())
+        }
+        .map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+    }
+    Ok(())
+}
+
+impl EvalReport {
+    /// 通过率
+    pub fn pass_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.results.iter().filter(|r| r.passed).count() as f64 / self.results.len() as f64
+    }
+
+    /// 失败的用例
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> {
+        self.results.iter().filter(|r| !r.passed)
+    }
+
+    /// Markdown 报告
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+
+        md.push_str("# Agent Evaluation Report\n\n");
+        md.push_str(&format!(
+            "Run `{}` at {}: {} passed of {} ({:.1}%), {} skipped without a mock script\n\n",
+            self.run_id,
+            self.timestamp,
+            self.results.iter().filter(|r| r.passed).count(),
+            self.results.len(),
+            self.pass_rate() * 100.0,
+            self.skipped.len()
+        ));
+
+        md.push_str("| Case | Config | Result | Score | Tools | Tokens | Latency |\n");
+        md.push_str("|------|--------|--------|-------|-------|--------|---------|\n");
+        for r in &self.results {
+            md.push_str(&format!(
+                "| {} | {} | {} | {:.2} | {} | {} | {}ms |\n",
+                r.case_id,
+                r.config_id,
+                if r.passed { "✅" } else { "❌" },
+                r.score,
+                r.tool_calls.join(" → "),
+                r.metrics.total_tokens,
+                r.metrics.total_latency_ms
+            ));
+        }
+
+        let failures: Vec<_> = self.failures().collect();
+        if !failures.is_empty() {
+            md.push_str("\n## Failures\n");
+            for r in failures {
+                md.push_str(&format!("\n### {} ({})\n\n", r.case_id, r.name));
+                if let Some(ref error) = r.error {
+                    md.push_str(&format!("- Error: {}\n", error));
+                }
+                for detail in r.eval.details.iter().filter(|d| !d.passed) {
+                    md.push_str(&format!("- {}\n", detail.message));
+                }
+                if r.unscripted_requests > 0 {
+                    md.push_str(&format!("- {} LLM requests beyond the script\n", r.unscripted_requests));
+                }
+            }
+        }
+
+        if !self.skipped.is_empty() {
+            md.push_str(&format!("\n## Skipped\n\n{}\n", self.skipped.join(", ")));
+        }
+
+        if self.ablation.configs.len() > 1 {
+            md.push('\n');
+            md.push_str(&self.ablation.to_markdown());
+        }
+
+        md
+    }
+
+    /// JSON 报告
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// 写入 `report-<run_id>.md` 与 `report-<run_id>.json`，返回两者路径
+    pub fn write(&self, dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let md_path = dir.join(format!("report-{}.md", self.run_id));
+        let json_path = dir.join(format!("report-{}.json", self.run_id));
+        let json = self.to_json().map_err(|e| e.to_string())?;
+        std::fs::write(&md_path, self.to_markdown()).map_err(|e| e.to_string())?;
+        std::fs::write(&json_path, json).map_err(|e| e.to_string())?;
+        Ok((md_path, json_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::eval::case::MockReply;
+    use serde_json::json;
+
+    fn search_case() -> EvalCase {
+        serde_json::from_value(json!({
+            "id": "search_react",
+            "name": "搜索 React 笔记",
+            "category": "basic",
+            "input": "帮我找一下包含 React 的笔记",
+            "expectedTools": ["grep_search"],
+            "expectedContains": ["React"],
+        }))
+        .unwrap()
+    }
+
+    fn vault() -> tempfile::TempDir {
+        let vault = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(vault.path().join("notes")).unwrap();
+        std::fs::write(vault.path().join("notes/react.md"), "# React\n\nHooks and state").unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_run_scripted_case() {
+        let vault = vault();
+        let mut case = search_case();
+        case.mock = vec![
+            MockReply::text(r#"{"intent": "search", "reason": "查找笔记"}"#),
+            MockReply::tool("grep_search", json!({ "pattern": "React" })),
+            MockReply::text("搜索完成"),
+            MockReply::text("找到 1 篇 React 笔记：notes/react.md"),
+        ];
+        let mut unscripted = search_case();
+        unscripted.id = "no_script".to_string();
+
+        let app = tauri::test::mock_app();
+        let report = EvalRunner::new(vault.path())
+            .run(app.handle(), &[case, unscripted])
+            .await;
+
+        assert_eq!(report.skipped, vec!["no_script"]);
+        let result = &report.results[0];
+        assert!(result.passed, "{:?}", result);
+        assert_eq!(result.tool_calls, vec!["grep_search"]);
+        assert_eq!(result.llm_requests, 4);
+        assert_eq!(result.metrics.execution_path, vec!["coordinator", "researcher", "reporter"]);
+        assert!(result.metrics.node_metrics["researcher"].total_tokens > 0);
+        assert!(report.to_markdown().contains("| search_react | baseline | ✅ |"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_expectations_are_reported() {
+        let vault = vault();
+        let mut case = search_case();
+        case.mock = vec![
+            MockReply::text(r#"{"intent": "chat"}"#),
+            MockReply::text("你好"),
+        ];
+
+        let app = tauri::test::mock_app();
+        let report = EvalRunner::new(vault.path()).run(app.handle(), &[case]).await;
+
+        assert_eq!(report.pass_rate(), 0.0);
+        let md = report.to_markdown();
+        assert!(md.contains("Tool 'grep_search' was NOT called"));
+        assert!(md.contains("Missing 'React'"));
+
+        let out = tempfile::tempdir().unwrap();
+        let (md_path, json_path) = report.write(out.path()).unwrap();
+        assert!(md_path.exists());
+        let json: Value = serde_json::from_str(&std::fs::read_to_string(json_path).unwrap()).unwrap();
+        assert_eq!(json["results"][0]["passed"], false);
+    }
+}
@@ -0,0 +1,32 @@
+//! Agent 事件发送
+//!
+//! 节点通过当前 `NodeContext` 把进度事件写入图的事件流，由命令层统一转发给前端；
+//! 没有事件流消费者时（旧执行器、可中断执行）直接发给前端。
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+use futures_util::StreamExt;
+
+use crate::langgraph::context::NodeContext;
+use crate::langgraph::error::GraphResult;
+use crate::langgraph::events::{EventStream, GraphEvent};
+
+/// 发送事件（`name` 为前端监听的事件名，如 `agent-event`）
+pub fn emit<R: Runtime, E: Serialize + Clone>(app: &AppHandle<R>, name: &str, event: E) {
+    match NodeContext::current().filter(|ctx| ctx.has_event_stream()) {
+        Some(ctx) => ctx.emit(name, event),
+        None => {
+            let _ = app.emit(name, event);
+        }
+    }
+}
+
+/// 驱动图运行，并把节点写入的自定义事件转发给前端
+pub async fn forward_events<R: Runtime, S>(app: &AppHandle<R>, mut events: EventStream<'_, S>) -> GraphResult<S> {
+    while let Some(event) = events.next().await {
+        if let GraphEvent::Custom { name, data, .. } = event {
+            let _ = app.emit(&name, data);
+        }
+    }
+    events.finish().await
+}
@@ -2,172 +2,155 @@
 //! 
 //! 使用 langgraph-rust 框架构建 Agent 执行图
 
+use futures_util::future::BoxFuture;
+use std::future::Future;
 use std::sync::Arc;
-use tauri::AppHandle;
+use tauri::{AppHandle, Runtime, Wry};
 
-use crate::langgraph::prelude::{StateGraph, CompiledGraph, GraphError};
+use crate::langgraph::prelude::{StateGraph, CompiledGraph};
 use crate::langgraph::error::GraphResult;
+use crate::langgraph::branch::path_map;
 use crate::agent::types::{
     GraphState, AgentConfig, TaskIntent,
 };
-use crate::agent::llm_client::LlmClient;
+use crate::agent::model_router::ModelRouter;
 use crate::agent::graph::nodes::*;
 
 /// Agent 执行上下文
 /// 
-/// 包含节点执行所需的共享资源
-#[derive(Clone)]
-pub struct AgentContext {
-    pub app: AppHandle,
-    pub llm: Arc<LlmClient>,
+/// 包含节点执行所需的共享资源，各节点通过 `router` 获取各自的模型。
+/// 运行时默认为 `Wry`，评估与测试中可使用 `tauri::test::MockRuntime`
+pub struct AgentContext<R: Runtime = Wry> {
+    pub app: AppHandle<R>,
+    pub router: Arc<ModelRouter>,
     pub config: AgentConfig,
 }
 
-impl AgentContext {
-    pub fn new(app: AppHandle, config: AgentConfig) -> Self {
-        let llm = Arc::new(LlmClient::new(config.clone()));
-        Self { app, llm, config }
+impl<R: Runtime> AgentContext<R> {
+    pub fn new(app: AppHandle<R>, config: AgentConfig) -> Self {
+        let router = Arc::new(ModelRouter::new(&config));
+        Self { app, router, config }
+    }
+}
+
+impl<R: Runtime> Clone for AgentContext<R> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            router: self.router.clone(),
+            config: self.config.clone(),
+        }
     }
 }
 
 /// 构建 Agent 图
 /// 
 /// 返回编译后的图，可以使用 `invoke()` 执行
-pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphState>> {
+pub fn build_agent_graph<R: Runtime>(ctx: AgentContext<R>) -> GraphResult<CompiledGraph<GraphState>> {
     let mut graph = StateGraph::<GraphState>::new();
     
-    // ============ 添加节点 ============
+    add_agent_nodes(&mut graph, Some(&ctx));
+    add_agent_edges(&mut graph);
     
+    // 编译图
+    graph.compile()
+}
+
+/// 注册 Agent 图的节点
+///
+/// `ctx` 为 `None` 时节点为直通占位，用于导出图结构，导出的图与实际的图共用这里的节点
+fn add_agent_nodes<R: Runtime>(graph: &mut StateGraph<GraphState>, ctx: Option<&AgentContext<R>>) {
     // 协调器节点 - 分析用户意图
-    let ctx_coordinator = ctx.clone();
-    graph.add_node("coordinator", move |state: GraphState| {
-        let ctx = ctx_coordinator.clone();
-        async move {
-            let result = coordinator_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "coordinator".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("coordinator", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = coordinator_node(&ctx.app, ctx.router.for_node("coordinator"), state).await
+            .map_err(|e| e.into_graph_error("coordinator"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 规划器节点 - 分解复杂任务
-    let ctx_planner = ctx.clone();
-    graph.add_node("planner", move |state: GraphState| {
-        let ctx = ctx_planner.clone();
-        async move {
-            let result = planner_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "planner".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("planner", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = planner_node(&ctx.app, ctx.router.for_node("planner"), state).await
+            .map_err(|e| e.into_graph_error("planner"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 执行器节点 - 执行计划步骤
-    let ctx_executor = ctx.clone();
-    graph.add_node("executor", move |state: GraphState| {
-        let ctx = ctx_executor.clone();
-        async move {
-            let result = executor_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "executor".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("executor", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = executor_node(&ctx.app, ctx.router.for_node("executor"), state).await
+            .map_err(|e| e.into_graph_error("executor"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 编辑器节点 - 编辑笔记
-    let ctx_editor = ctx.clone();
-    graph.add_node("editor", move |state: GraphState| {
-        let ctx = ctx_editor.clone();
-        async move {
-            let result = editor_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "editor".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("editor", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = editor_node(&ctx.app, ctx.router.for_node("editor"), state).await
+            .map_err(|e| e.into_graph_error("editor"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 研究员节点 - 搜索研究
-    let ctx_researcher = ctx.clone();
-    graph.add_node("researcher", move |state: GraphState| {
-        let ctx = ctx_researcher.clone();
-        async move {
-            let result = researcher_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "researcher".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("researcher", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = researcher_node(&ctx.app, ctx.router.for_node("researcher"), state).await
+            .map_err(|e| e.into_graph_error("researcher"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 写作者节点 - 创建内容
-    let ctx_writer = ctx.clone();
-    graph.add_node("writer", move |state: GraphState| {
-        let ctx = ctx_writer.clone();
-        async move {
-            let result = writer_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "writer".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("writer", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = writer_node(&ctx.app, ctx.router.for_node("writer"), state).await
+            .map_err(|e| e.into_graph_error("writer"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 整理者节点 - 文件组织
-    let ctx_organizer = ctx.clone();
-    graph.add_node("organizer", move |state: GraphState| {
-        let ctx = ctx_organizer.clone();
-        async move {
-            let result = organizer_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "organizer".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
+    graph.add_node("organizer", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = organizer_node(&ctx.app, ctx.router.for_node("organizer"), state).await
+            .map_err(|e| e.into_graph_error("organizer"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
     
     // 报告者节点 - 汇总结果
-    let ctx_reporter = ctx.clone();
-    graph.add_node("reporter", move |state: GraphState| {
-        let ctx = ctx_reporter.clone();
-        async move {
-            let result = reporter_node(&ctx.app, &ctx.llm, state).await
-                .map_err(|e| GraphError::ExecutionError { 
-                    node: "reporter".to_string(), 
-                    message: e 
-                })?;
-            let mut state = result.state;
-            state.goto = result.next_node.unwrap_or_default();
-            Ok(state)
-        }
-    });
-    
-    // ============ 定义边 ============
-    
+    graph.add_node("reporter", context_node(ctx, |ctx: AgentContext<R>, state: GraphState| async move {
+        let result = reporter_node(&ctx.app, ctx.router.for_node("reporter"), state).await
+            .map_err(|e| e.into_graph_error("reporter"))?;
+        let mut state = result.state;
+        state.goto = result.next_node.unwrap_or_default();
+        Ok(state)
+    }));
+}
+
+/// 节点函数：有执行上下文时运行 `run`，否则原样返回状态（仅用于导出图结构）
+pub(crate) fn context_node<C, S, F, Fut>(ctx: Option<&C>, run: F) -> impl Fn(S) -> BoxFuture<'static, GraphResult<S>> + Send + Sync + 'static
+where
+    C: Clone + Send + Sync + 'static,
+    S: Send + 'static,
+    F: Fn(C, S) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = GraphResult<S>> + Send + 'static,
+{
+    let ctx = ctx.cloned();
+    move |state| match &ctx {
+        Some(ctx) => Box::pin(run(ctx.clone(), state)),
+        None => Box::pin(async move { Ok(state) }),
+    }
+}
+
+
+/// 定义 Agent 图的边
+fn add_agent_edges(graph: &mut StateGraph<GraphState>) {
     // 入口点
     graph.set_entry_point("coordinator");
     
@@ -190,7 +173,7 @@ pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphSt
                 }
             }
         },
-        None,
+        Some(path_map(["reporter", "editor", "writer", "organizer", "researcher", "planner"])),
     );
     
     // 规划器 -> 执行器
@@ -206,7 +189,7 @@ pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphSt
                 "reporter".to_string()
             }
         },
-        None,
+        Some(path_map(["editor", "researcher", "reporter"])),
     );
     
     // 各专业节点 -> 报告者（或回到执行器继续）
@@ -219,7 +202,7 @@ pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphSt
                 "reporter".to_string()
             }
         },
-        None,
+        Some(path_map(["reporter"])),
     );
     
     graph.add_conditional_edges_sync(
@@ -231,7 +214,7 @@ pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphSt
                 "reporter".to_string()
             }
         },
-        None,
+        Some(path_map(["reporter"])),
     );
     
     graph.add_conditional_edges_sync(
@@ -243,7 +226,7 @@ pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphSt
                 "reporter".to_string()
             }
         },
-        None,
+        Some(path_map(["reporter"])),
     );
     
     graph.add_conditional_edges_sync(
@@ -255,52 +238,36 @@ pub fn build_agent_graph(ctx: AgentContext) -> GraphResult<CompiledGraph<GraphSt
                 "reporter".to_string()
             }
         },
-        None,
+        Some(path_map(["reporter"])),
     );
     
     // 报告者 -> 结束
     graph.set_finish_point("reporter");
-    
-    // 编译图
-    graph.compile()
 }
 
-/// 获取图的可视化描述
+/// 获取图的可视化描述 (Mermaid)
+/// 
+/// 由实际的边定义生成
 pub fn describe_graph() -> String {
-    r#"
-Agent Graph Structure:
-======================
+    skeleton_graph().draw_mermaid()
+}
+
+/// 与实际图相同的节点与边，节点为直通占位
+fn skeleton_graph() -> StateGraph<GraphState> {
+    let mut graph = StateGraph::<GraphState>::new();
+    add_agent_nodes::<Wry>(&mut graph, None);
+    add_agent_edges(&mut graph);
+    graph
+}
 
-    ┌─────────────┐
-    │   START     │
-    └──────┬──────┘
-           │
-           ▼
-    ┌─────────────┐
-    │ coordinator │ ─── 分析用户意图
-    └──────┬──────┘
-           │
-     ┌─────┴─────┬─────────┬─────────┬─────────┐
-     ▼           ▼         ▼         ▼         ▼
-┌────────┐ ┌────────┐ ┌────────┐ ┌─────────┐ ┌────────┐
-│ editor │ │ writer │ │research│ │organizer│ │planner │
-└────┬───┘ └────┬───┘ └────┬───┘ └────┬────┘ └────┬───┘
-     │          │          │          │           │
-     │          │          │          │           ▼
-     │          │          │          │     ┌──────────┐
-     │          │          │          │     │ executor │
-     │          │          │          │     └────┬─────┘
-     │          │          │          │          │
-     └──────────┴──────────┴──────────┴──────────┘
-                           │
-                           ▼
-                    ┌─────────────┐
-                    │  reporter   │ ─── 汇总结果
-                    └──────┬──────┘
-                           │
-                           ▼
-                    ┌─────────────┐
-                    │    END      │
-                    └─────────────┘
-"#.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_structure() {
+        let graph = skeleton_graph();
+        assert!(graph.view().validate().is_empty());
+        assert!(graph.compile().is_ok());
+    }
 }
@@ -3,20 +3,26 @@
 //! 执行多智能体图
 
 use crate::agent::types::*;
-use crate::agent::llm_client::LlmClient;
+use crate::agent::budget::UsageBudget;
+use crate::agent::model_router::ModelRouter;
 use crate::agent::graph::nodes::*;
 use tauri::{AppHandle, Emitter};
 
 /// 图执行器
 pub struct GraphExecutor {
     config: AgentConfig,
-    llm: LlmClient,
+    router: ModelRouter,
 }
 
 impl GraphExecutor {
     pub fn new(config: AgentConfig) -> Self {
-        let llm = LlmClient::new(config.clone());
-        Self { config, llm }
+        let router = ModelRouter::new(&config);
+        Self { config, router }
+    }
+
+    /// 任务的用量预算
+    pub fn budget(&self) -> &UsageBudget {
+        self.router.budget()
     }
 
     /// 执行图
@@ -72,17 +78,18 @@ impl GraphExecutor {
         node_name: &str,
         state: GraphState,
     ) -> Result<NodeResult, String> {
-        match node_name {
-            "coordinator" => coordinator_node(app, &self.llm, state).await,
-            "planner" => planner_node(app, &self.llm, state).await,
-            "executor" => executor_node(app, &self.llm, state).await,
-            "editor" => editor_node(app, &self.llm, state).await,
-            "researcher" => researcher_node(app, &self.llm, state).await,
-            "writer" => writer_node(app, &self.llm, state).await,
-            "organizer" => organizer_node(app, &self.llm, state).await,
-            "reporter" => reporter_node(app, &self.llm, state).await,
-            _ => Err(format!("Unknown node: {}", node_name)),
-        }
+        let result = match node_name {
+            "coordinator" => coordinator_node(app, self.router.for_node("coordinator"), state).await,
+            "planner" => planner_node(app, self.router.for_node("planner"), state).await,
+            "executor" => executor_node(app, self.router.for_node("executor"), state).await,
+            "editor" => editor_node(app, self.router.for_node("editor"), state).await,
+            "researcher" => researcher_node(app, self.router.for_node("researcher"), state).await,
+            "writer" => writer_node(app, self.router.for_node("writer"), state).await,
+            "organizer" => organizer_node(app, self.router.for_node("organizer"), state).await,
+            "reporter" => reporter_node(app, self.router.for_node("reporter"), state).await,
+            _ => return Err(format!("Unknown node: {}", node_name)),
+        };
+        result.map_err(|e| e.to_string())
     }
 
     /// 中止执行
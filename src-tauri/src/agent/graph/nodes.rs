@@ -3,10 +3,12 @@
 //! 每个节点代表一个智能体的处理逻辑
 
 use crate::agent::types::*;
+use crate::agent::events::emit;
 use crate::agent::llm_client::LlmClient;
+use crate::agent::llm_error::NodeError;
 use crate::agent::tools::{get_tools_for_agent_with_mcp, ToolRegistry};
 use serde_json::Value;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Runtime};
 
 /// 节点处理结果
 pub struct NodeResult {
@@ -15,17 +17,17 @@ pub struct NodeResult {
 }
 
 /// 协调器节点 - 理解用户意图
-pub async fn coordinator_node(
-    app: &AppHandle,
+pub async fn coordinator_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     mut state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     use crate::agent::debug_log as dbg;
     use crate::agent::workspace_layout::{generate_workspace_layout, WorkspaceLayoutConfig};
     
     dbg::log_separator("协调器节点 (Coordinator)");
     
-    let _ = app.emit("agent-event", AgentEvent::StatusChange {
+    emit(app, "agent-event", AgentEvent::StatusChange {
         status: AgentStatus::Running,
     });
 
@@ -81,25 +83,15 @@ pub async fn coordinator_node(
 
     // 构建消息
     let messages = vec![
-        Message {
-            role: MessageRole::System,
-            content: system_prompt,
-            name: None,
-            tool_call_id: None,
-        },
-        Message {
-            role: MessageRole::User,
-            content: state.user_task.clone(),
-            name: None,
-            tool_call_id: None,
-        },
+        Message::system(system_prompt),
+        Message::user(state.user_task.clone()),
     ];
 
     // 调用 LLM
     let response = llm.call(&messages, None).await?;
     
     // 发送 token 使用量
-    let _ = app.emit("agent-event", AgentEvent::TokenUsage {
+    emit(app, "agent-event", AgentEvent::TokenUsage {
         prompt_tokens: response.prompt_tokens,
         completion_tokens: response.completion_tokens,
         total_tokens: response.total_tokens,
@@ -135,7 +127,7 @@ pub async fn coordinator_node(
     );
     
     // 发送完整消息事件
-    let _ = app.emit("agent-event", AgentEvent::IntentAnalysis {
+    emit(app, "agent-event", AgentEvent::IntentAnalysis {
         intent: format!("{:?}", intent),
         route: match intent {
             TaskIntent::Chat => "reporter".to_string(),
@@ -168,11 +160,11 @@ pub async fn coordinator_node(
 }
 
 /// 规划器节点 - 为任务生成执行计划
-pub async fn planner_node(
-    app: &AppHandle,
+pub async fn planner_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     mut state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     let system_prompt = format!(
         r#"你是任务规划专家。为用户任务生成 1-5 个执行步骤。
 
@@ -213,24 +205,14 @@ pub async fn planner_node(
     );
 
     let messages = vec![
-        Message {
-            role: MessageRole::System,
-            content: system_prompt,
-            name: None,
-            tool_call_id: None,
-        },
-        Message {
-            role: MessageRole::User,
-            content: state.user_task.clone(),
-            name: None,
-            tool_call_id: None,
-        },
+        Message::system(system_prompt),
+        Message::user(state.user_task.clone()),
     ];
 
     let response = llm.call(&messages, None).await?;
     
     // 发送 token 使用量
-    let _ = app.emit("agent-event", AgentEvent::TokenUsage {
+    emit(app, "agent-event", AgentEvent::TokenUsage {
         prompt_tokens: response.prompt_tokens,
         completion_tokens: response.completion_tokens,
         total_tokens: response.total_tokens,
@@ -238,7 +220,7 @@ pub async fn planner_node(
 
     // 解析计划
     if let Some(plan) = parse_plan(&response.content) {
-        let _ = app.emit("agent-event", AgentEvent::PlanUpdated {
+        emit(app, "agent-event", AgentEvent::PlanUpdated {
             plan: plan.clone(),
         });
         state.current_plan = Some(plan);
@@ -258,11 +240,11 @@ pub async fn planner_node(
 }
 
 /// 执行器节点 - 根据意图路由到对应的 agent (Windsurf 风格简化)
-pub async fn executor_node(
-    _app: &AppHandle,
+pub async fn executor_node<R: Runtime>(
+    _app: &AppHandle<R>,
     _llm: &LlmClient,
     mut state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     // Windsurf 风格：计划只是展示给用户的，实际执行根据 intent 决定
     // 直接根据意图路由到合适的 agent
     let next_node = match state.intent {
@@ -280,49 +262,49 @@ pub async fn executor_node(
 }
 
 /// 编辑器节点
-pub async fn editor_node(
-    app: &AppHandle,
+pub async fn editor_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     agent_worker_node(app, llm, state, AgentType::Editor, "editor").await
 }
 
 /// 研究员节点
-pub async fn researcher_node(
-    app: &AppHandle,
+pub async fn researcher_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     agent_worker_node(app, llm, state, AgentType::Researcher, "researcher").await
 }
 
 /// 写作者节点
-pub async fn writer_node(
-    app: &AppHandle,
+pub async fn writer_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     agent_worker_node(app, llm, state, AgentType::Writer, "writer").await
 }
 
 /// 整理者节点
-pub async fn organizer_node(
-    app: &AppHandle,
+pub async fn organizer_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     agent_worker_node(app, llm, state, AgentType::Organizer, "organizer").await
 }
 
 /// 通用工作节点
-async fn agent_worker_node(
-    app: &AppHandle,
+async fn agent_worker_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     mut state: GraphState,
     _agent_type: AgentType,
     agent_name: &str,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     use crate::agent::note_map::{generate_note_map, extract_mentioned_notes, NoteMapConfig};
     use crate::agent::messages::{ChatChunks, FORMAT_REMINDER};
     use crate::agent::debug_log as dbg;
@@ -390,18 +372,8 @@ async fn agent_worker_node(
         let rag_text: Vec<String> = state.rag_results.iter()
             .map(|r| format!("文件: {}\n{}", r.file_path, r.content))
             .collect();
-        rag_messages.push(Message {
-            role: MessageRole::User,
-            content: format!("以下是通过语义搜索找到的相关笔记片段：\n\n{}", rag_text.join("\n---\n")),
-            name: None,
-            tool_call_id: None,
-        });
-        rag_messages.push(Message {
-            role: MessageRole::Assistant,
-            content: "好的，我会参考这些相关内容。".to_string(),
-            name: None,
-            tool_call_id: None,
-        });
+        rag_messages.push(Message::user(format!("以下是通过语义搜索找到的相关笔记片段：\n\n{}", rag_text.join("\n---\n"))));
+        rag_messages.push(Message::assistant("好的，我会参考这些相关内容。"));
     }
     
     // 5. 历史对话消息
@@ -417,6 +389,16 @@ async fn agent_worker_node(
         chunks.add_tool_result(obs.clone());
     }
     
+    // 超出上下文预算时压缩观察记录与较早的历史
+    let budget = llm.prompt_budget(Some(&tools));
+    if chunks.fit_to_window(budget) {
+        dbg::log_kv("上下文压缩", &format!(
+            "预算 {} tokens，历史摘要 {} 条",
+            budget,
+            chunks.history_summary.len()
+        ));
+    }
+    
     // 转换为消息列表
     let mut messages = chunks.to_messages();
 
@@ -444,7 +426,7 @@ async fn agent_worker_node(
             Ok(r) => r,
             Err(e) => {
                 dbg::log_error(&format!("LLM 调用失败: {}", e));
-                return Err(e);
+                return Err(e.into());
             }
         };
         
@@ -456,13 +438,14 @@ async fn agent_worker_node(
         );
         
         // 发送 token 使用量
-        let _ = app.emit("agent-event", AgentEvent::TokenUsage {
+        emit(app, "agent-event", AgentEvent::TokenUsage {
             prompt_tokens: response.prompt_tokens,
             completion_tokens: response.completion_tokens,
             total_tokens: response.total_tokens,
         });
 
         // 获取工具调用：优先使用 FC 模式的直接返回，否则回退到 XML 解析
+        let fc_mode = response.tool_calls.is_some();
         let tool_calls = if let Some(fc_calls) = response.tool_calls {
             // FC 模式：直接使用 LLM 返回的结构化工具调用
             if fc_calls.is_empty() {
@@ -476,9 +459,19 @@ async fn agent_worker_node(
                 _ => break, // 没有工具调用，任务完成
             }
         };
+
+        // 将助手回复加入消息历史，FC 模式下保留结构化的工具调用，使结果能对应到调用
+        messages.push(if fc_mode {
+            Message::assistant_with_tool_calls(response.content, tool_calls.clone())
+        } else {
+            Message::assistant(response.content)
+        });
+        // 工具结果之后再追加提醒，避免插在同一轮的工具结果之间
+        let mut reminders = Vec::new();
+
         for tool_call in tool_calls {
             // 发送工具调用事件
-            let _ = app.emit("agent-event", AgentEvent::ToolCall {
+            emit(app, "agent-event", AgentEvent::ToolCall {
                 tool: tool_call.clone(),
             });
 
@@ -494,7 +487,7 @@ async fn agent_worker_node(
             );
 
             // 发送工具结果事件
-            let _ = app.emit("agent-event", AgentEvent::ToolResult {
+            emit(app, "agent-event", AgentEvent::ToolResult {
                 result: result.clone(),
             });
 
@@ -524,7 +517,7 @@ async fn agent_worker_node(
                         };
                         
                         // 发送计划更新事件
-                        let _ = app.emit("agent-event", AgentEvent::PlanUpdated {
+                        emit(app, "agent-event", AgentEvent::PlanUpdated {
                             plan: plan.clone(),
                         });
                         
@@ -556,12 +549,7 @@ async fn agent_worker_node(
                         
                         // 添加反馈到消息历史
                         state.observations.push(format!("[update_plan] {}", feedback));
-                        messages.push(Message {
-                            role: MessageRole::User,
-                            content: format!("工具 update_plan 执行结果：\n{}", feedback),
-                            name: None,
-                            tool_call_id: None,
-                        });
+                        messages.push(tool_result_message(&tool_call, fc_mode, &feedback, &[]));
                         continue; // 跳过默认的消息添加
                     }
                 }
@@ -578,36 +566,30 @@ async fn agent_worker_node(
             );
             state.observations.push(observation.clone());
             
-            // 将工具结果添加到消息历史，使用 User role（更兼容）
-            messages.push(Message {
-                role: MessageRole::User,
-                content: format!("工具 {} 执行结果：\n{}", tool_call.name, 
-                    if result.success { &result.content } else { result.error.as_deref().unwrap_or("Unknown error") }
-                ),
-                name: None,
-                tool_call_id: None,
-            });
+            // 将工具结果添加到消息历史
+            messages.push(tool_result_message(
+                &tool_call,
+                fc_mode,
+                if result.success { &result.content } else { result.error.as_deref().unwrap_or("Unknown error") },
+                &result.attachments,
+            ));
             
             // 如果工具执行失败，添加动态提醒帮助 LLM 自修复
             if !result.success {
                 use crate::agent::messages::detect_reminder_needed;
                 if let Some(reminder) = detect_reminder_needed(result.error.as_deref()) {
-                    messages.push(Message {
-                        role: MessageRole::User,
-                        content: format!("[系统提醒] {}", reminder),
-                        name: None,
-                        tool_call_id: None,
-                    });
+                    reminders.push(Message::user(format!("[系统提醒] {}", reminder)));
                 }
             }
         }
+        messages.extend(reminders);
         
         // 工具调用循环继续，直到 LLM 不再返回工具调用
     }
 
     // 循环结束，发送最终计划状态
     if let Some(ref plan) = state.current_plan {
-        let _ = app.emit("agent-event", AgentEvent::PlanUpdated {
+        emit(app, "agent-event", AgentEvent::PlanUpdated {
             plan: plan.clone(),
         });
     }
@@ -621,17 +603,17 @@ async fn agent_worker_node(
 }
 
 /// 报告者节点 - 汇总结果
-pub async fn reporter_node(
-    app: &AppHandle,
+pub async fn reporter_node<R: Runtime>(
+    app: &AppHandle<R>,
     llm: &LlmClient,
     mut state: GraphState,
-) -> Result<NodeResult, String> {
+) -> Result<NodeResult, NodeError> {
     // 如果已经有最终结果，直接返回
     if let Some(ref result) = state.final_result {
-        let _ = app.emit("agent-event", AgentEvent::Complete {
+        emit(app, "agent-event", AgentEvent::Complete {
             result: result.clone(),
         });
-        let _ = app.emit("agent-event", AgentEvent::StatusChange {
+        emit(app, "agent-event", AgentEvent::StatusChange {
             status: AgentStatus::Completed,
         });
         return Ok(NodeResult {
@@ -668,18 +650,8 @@ pub async fn reporter_node(
 
     // 始终包含 System + User 消息
     let messages = vec![
-        Message {
-            role: MessageRole::System,
-            content: system_prompt,
-            name: None,
-            tool_call_id: None,
-        },
-        Message {
-            role: MessageRole::User,
-            content: user_prompt,
-            name: None,
-            tool_call_id: None,
-        },
+        Message::system(system_prompt),
+        Message::user(user_prompt),
     ];
 
     let request_id = format!("reporter-{}", chrono::Utc::now().timestamp_millis());
@@ -693,10 +665,10 @@ pub async fn reporter_node(
 
     state.final_result = Some(response.clone());
 
-    let _ = app.emit("agent-event", AgentEvent::Complete {
+    emit(app, "agent-event", AgentEvent::Complete {
         result: response,
     });
-    let _ = app.emit("agent-event", AgentEvent::StatusChange {
+    emit(app, "agent-event", AgentEvent::StatusChange {
         status: AgentStatus::Completed,
     });
 
@@ -810,6 +782,19 @@ fn parse_plan(response: &str) -> Option<Plan> {
     None
 }
 
+/// 工具结果消息：FC 模式使用 Tool 角色关联到调用 ID，XML 模式使用 User 角色（更兼容）
+///
+/// 工具返回的附件（如图片）作为内容块追加
+fn tool_result_message(tool_call: &ToolCall, fc_mode: bool, output: &str, attachments: &[ContentPart]) -> Message {
+    let mut message = if fc_mode {
+        Message::tool_result(tool_call, output)
+    } else {
+        Message::user(format!("工具 {} 执行结果：\n{}", tool_call.name, output))
+    };
+    message.parts.extend(attachments.iter().cloned());
+    message
+}
+
 fn parse_tool_calls(response: &str) -> Option<Vec<ToolCall>> {
     // 解析 XML 格式的工具调用
     let mut calls = Vec::new();
@@ -819,7 +804,7 @@ fn parse_tool_calls(response: &str) -> Option<Vec<ToolCall>> {
         // 计划工具 (Windsurf 风格)
         "update_plan",
         // 笔记操作
-        "read_note", "read_outline", "read_section",
+        "read_note", "read_outline", "read_section", "read_image",
         "edit_note", "create_note", "list_notes",
         "search_notes", "grep_search", "semantic_search",
         "move_note", "delete_note", "get_backlinks",
@@ -909,8 +894,8 @@ fn build_agent_prompt(agent_name: &str, workspace: &str, context: &str, supports
 
     // Windsurf 风格：单一 update_plan 工具，移除 attempt_completion
     let tools_info = match agent_name {
-        "editor" => "update_plan, read_note, edit_note, search_notes, grep_search, semantic_search",
-        "researcher" => "update_plan, read_note, list_notes, search_notes, grep_search, semantic_search, get_backlinks",
+        "editor" => "update_plan, read_note, read_image, edit_note, search_notes, grep_search, semantic_search",
+        "researcher" => "update_plan, read_note, read_image, list_notes, search_notes, grep_search, semantic_search, get_backlinks",
         "writer" => "update_plan, read_note, create_note, edit_note, list_notes, search_notes",
         "organizer" => "update_plan, list_notes, move_note, delete_note, create_note, read_note",
         _ => "update_plan, read_note, edit_note, create_note, list_notes, search_notes",
@@ -4,55 +4,43 @@
 //! 
 //! ## SSE 稳定性增强
 //! - 心跳机制：定期发送心跳事件，检测连接状态
-//! - 指数退避重试：网络错误时自动重试
+//! - 指数退避重试：按 [`LlmError`] 类型判断是否重试
 //! - 超时检测：检测流式响应假死
+//! - 流式响应由 [`StreamFramer`] 按 SSE 规范或 NDJSON 分帧
+//!
+//! ## 模型回退
+//! - 主模型遇到限流（429）、服务端错误（5xx）或连接失败时，依次尝试回退模型
+//!
+//! ## 上下文窗口
+//! - 按模型推断上下文窗口，接近上限时压缩较早的工具结果
+//! - 消息上的缓存断点由提供商转换为提示缓存标记
+//!
+//! ## 限流与预算
+//! - 每次请求前按提供商限流，并检查任务与当日预算，超出时拒绝请求
+//! - 每次调用的 token 与估算费用计入预算和图节点指标
+//!
+//! ## 传输
+//! - 请求经由 [`HttpTransport`] 发送，测试中可替换为录制/回放固件的传输
 
+use crate::agent::budget::{acquire_rate_limit, UsageBudget};
+use crate::agent::events::emit;
+use crate::agent::llm_error::LlmError;
+use crate::agent::messages::{compact_tool_results, context_window, estimate_messages_tokens, estimate_tools_tokens};
+use crate::agent::note_map::estimate_tokens;
+use crate::agent::providers::{self, ChatParams, LlmProvider, StreamFramer, StreamStep};
+use crate::agent::transport::{HttpRequest, HttpTransport, ReqwestTransport};
 use crate::agent::types::*;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use crate::langgraph::context::NodeContext;
+use crate::langgraph::metrics::TokenUsage;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Runtime};
 use futures_util::StreamExt;
 use tokio::time::interval;
 
-/// OpenAI 格式的请求
-#[derive(Debug, Serialize)]
-struct ChatRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    temperature: Option<f32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    max_tokens: Option<usize>,
-    stream: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tools: Option<Vec<ToolDefinition>>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    tool_call_id: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-struct ToolDefinition {
-    r#type: String,
-    function: FunctionDefinition,
-}
-
-#[derive(Debug, Serialize)]
-struct FunctionDefinition {
-    name: String,
-    description: String,
-    parameters: Value,
-}
-
 /// LLM 响应（包含 token 使用量）
 #[derive(Debug, Clone)]
 pub struct LlmResponse {
@@ -61,293 +49,340 @@ pub struct LlmResponse {
     pub prompt_tokens: usize,
     pub completion_tokens: usize,
     pub total_tokens: usize,
+    /// prompt_tokens 中命中提示缓存的部分
+    pub cached_tokens: usize,
 }
 
 /// LLM 客户端
 pub struct LlmClient {
     config: AgentConfig,
-    client: reqwest::Client,
+    provider: Arc<dyn LlmProvider>,
+    transport: Arc<dyn HttpTransport>,
+    /// 回退链，按顺序尝试
+    fallbacks: Vec<LlmClient>,
+    /// 用量预算，同一任务的客户端共享
+    budget: Arc<UsageBudget>,
+    /// 网络选项无效时的错误，每次请求都返回该错误而不是绕过代理/证书直连
+    network_error: Option<String>,
 }
 
 impl LlmClient {
     pub fn new(config: AgentConfig) -> Self {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
-            .build()
-            .expect("Failed to create HTTP client");
+        let provider = providers::resolve(&config.provider);
+        Self::with_provider(config, provider)
+    }
+
+    /// 使用指定的提供商适配（忽略 `config.provider`）
+    pub fn with_provider(config: AgentConfig, provider: Arc<dyn LlmProvider>) -> Self {
+        let builder = || reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(300));
+        // 网络选项无效 (如代理 URL 格式错误) 时不能静默直连：保留错误，请求时返回
+        let (client, network_error) = match config.network.apply(builder())
+            .and_then(|b| b.build().map_err(|e| e.to_string()))
+        {
+            Ok(client) => (client, None),
+            Err(e) => {
+                eprintln!("[LLM] Invalid network options: {}", e);
+                (builder().build().expect("Failed to create HTTP client"), Some(e))
+            }
+        };
         
-        Self { config, client }
+        let budget = Arc::new(UsageBudget::new(config.budget.clone()));
+        let transport = ReqwestTransport::shared(client);
+        Self { config, provider, transport, fallbacks: Vec::new(), budget, network_error }
+    }
+
+    /// 使用指定的传输发送请求（回退链一并设置），如测试中回放固件
+    pub fn with_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.fallbacks = self.fallbacks.into_iter()
+            .map(|f| f.with_transport(transport.clone()))
+            .collect();
+        self.transport = transport;
+        // 指定的传输不使用按网络选项构建的客户端
+        self.network_error = None;
+        self
+    }
+
+    /// 设置回退链：限流、服务端错误或连接失败时按顺序尝试
+    pub fn with_fallbacks(mut self, fallbacks: Vec<LlmClient>) -> Self {
+        self.fallbacks = fallbacks;
+        self
+    }
+
+    /// 共享用量预算（回退链一并设置）
+    pub fn with_budget(mut self, budget: Arc<UsageBudget>) -> Self {
+        self.fallbacks = self.fallbacks.into_iter()
+            .map(|f| f.with_budget(budget.clone()))
+            .collect();
+        self.budget = budget;
+        self
+    }
+
+    /// 用量预算
+    pub fn budget(&self) -> &Arc<UsageBudget> {
+        &self.budget
+    }
+
+    /// 模型名称
+    pub fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// 回退链中的模型名称
+    pub fn fallback_models(&self) -> Vec<&str> {
+        self.fallbacks.iter().map(|f| f.model()).collect()
+    }
+
+    /// 主模型与回退模型，按尝试顺序
+    fn candidates(&self) -> impl Iterator<Item = &LlmClient> {
+        std::iter::once(self).chain(self.fallbacks.iter())
     }
 
     /// 获取 API URL
-    fn get_api_url(&self) -> String {
+    fn get_api_url(&self, stream: bool) -> String {
         let base = self.config.base_url.clone()
-            .unwrap_or_else(|| self.get_default_base_url());
+            .unwrap_or_else(|| self.provider.default_base_url().to_string());
         
         // 移除尾部斜杠
         let base = base.trim_end_matches('/');
         
-        match self.config.provider.as_str() {
-            "anthropic" => format!("{}/messages", base),
-            _ => format!("{}/chat/completions", base),
-        }
-    }
-
-    fn get_default_base_url(&self) -> String {
-        match self.config.provider.as_str() {
-            "anthropic" => "https://api.anthropic.com/v1".to_string(),
-            "openai" => "https://api.openai.com/v1".to_string(),
-            "deepseek" => "https://api.deepseek.com/v1".to_string(),
-            "moonshot" => "https://api.moonshot.cn/v1".to_string(),
-            "groq" => "https://api.groq.com/openai/v1".to_string(),
-            _ => "https://api.openai.com/v1".to_string(),
-        }
+        self.provider.endpoint(base, &self.config.model, stream)
     }
 
-    /// 判断当前 provider 是否支持 Function Calling
+    /// 判断当前模型是否支持 Function Calling
     pub fn supports_fc(&self) -> bool {
-        match self.config.provider.as_str() {
-            "openai" | "anthropic" | "deepseek" | "moonshot" | "gemini" | "groq" | "openrouter" => true,
-            "ollama" => false,  // 本地模型 FC 支持不稳定，使用 XML 模式
-            _ => false,  // 未知 provider 默认不支持
-        }
+        self.provider.supports_function_calling(&self.config.model)
     }
 
     /// 构建请求头
     fn build_headers(&self) -> HashMap<String, String> {
-        let mut headers = HashMap::new();
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
-        
-        match self.config.provider.as_str() {
-            "anthropic" => {
-                headers.insert("x-api-key".to_string(), self.config.api_key.clone());
-                headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
-            }
-            _ => {
-                headers.insert(
-                    "Authorization".to_string(), 
-                    format!("Bearer {}", self.config.api_key)
-                );
+        self.provider.headers(&self.config.api_key)
+    }
+
+    /// 判断当前模型是否接受图片输入
+    pub fn supports_vision(&self) -> bool {
+        self.provider.supports_vision(&self.config.model)
+    }
+
+    /// 模型的上下文窗口（tokens），配置优先
+    pub fn context_window(&self) -> usize {
+        self.config.context_window.unwrap_or_else(|| context_window(&self.config.model))
+    }
+
+    /// 扣除输出预留与工具定义后，消息可用的 token 预算
+    pub fn prompt_budget(&self, tools: Option<&[Value]>) -> usize {
+        let tools_tokens = tools.map(estimate_tools_tokens).unwrap_or(0);
+        self.context_window()
+            .saturating_sub(self.config.max_tokens)
+            .saturating_sub(tools_tokens)
+    }
+
+    /// 构建请求体
+    ///
+    /// - 超出上下文预算时压缩较早的工具结果
+    /// - 非视觉模型的图片块替换为文本占位，避免请求被拒绝
+    async fn build_body(&self, messages: &[Message], tools: Option<&[Value]>, stream: bool) -> Value {
+        let mut prepared: Option<Vec<Message>> = None;
+
+        let budget = self.prompt_budget(tools);
+        let estimated = estimate_messages_tokens(messages);
+        if estimated > budget {
+            let mut compacted = messages.to_vec();
+            let count = compact_tool_results(&mut compacted, budget);
+            println!("[LlmClient] ⚠️ 上下文约 {} tokens，超出预算 {}，压缩了 {} 条工具结果",
+                estimated, budget, count);
+            prepared = Some(compacted);
+        }
+        // 图片只在这里读入请求，不进入消息历史
+        if messages.iter().any(Message::has_images) {
+            let current = prepared.as_deref().unwrap_or(messages);
+            prepared = Some(if self.supports_vision() {
+                load_images(current).await
+            } else {
+                current.iter().map(Message::without_images).collect()
+            });
+        }
+
+        let messages = prepared.as_deref().unwrap_or(messages);
+        self.provider.build_request(&ChatParams {
+            model: &self.config.model,
+            messages,
+            tools,
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+            stream,
+        })
+    }
+
+    /// 请求前检查预算并等待限流令牌
+    async fn before_request(&self) -> Result<(), LlmError> {
+        if let Some(e) = &self.network_error {
+            return Err(LlmError::Config(e.clone()));
+        }
+        self.budget.check()?;
+        if let Some(message) = self.budget.unpriced_warning(&self.config.model) {
+            eprintln!("[Budget] {}", message);
+            if let Some(ctx) = NodeContext::current() {
+                ctx.emit("agent-event", AgentEvent::BudgetWarning { message });
             }
         }
-        
-        headers
+        if let Some(per_minute) = self.budget.config().requests_per_minute {
+            acquire_rate_limit(self.provider.name(), per_minute).await;
+        }
+        Ok(())
     }
 
-    /// 转换消息格式
-    fn convert_messages(&self, messages: &[Message]) -> Vec<ChatMessage> {
-        messages.iter().map(|m| ChatMessage {
-            role: match m.role {
-                MessageRole::System => "system".to_string(),
-                MessageRole::User => "user".to_string(),
-                MessageRole::Assistant => "assistant".to_string(),
-                MessageRole::Tool => "tool".to_string(),
-            },
-            content: m.content.clone(),
-            name: m.name.clone(),
-            tool_call_id: m.tool_call_id.clone(),
-        }).collect()
+    /// 将一次调用的用量与估算费用计入预算，在图节点内调用时同时计入该节点的指标
+    fn record_usage(&self, response: &LlmResponse) {
+        record_usage(&self.budget, NodeContext::current().as_ref(), &self.config.model, response);
+    }
+
+    /// 流式调用的用量：提供商未返回统计时按内容估算
+    fn record_stream_usage(&self, messages: &[Message], response: LlmResponse, content: &str) {
+        let response = with_estimated_usage(response, estimate_messages_tokens(messages), content);
+        self.record_usage(&response);
     }
 
-    /// 非流式调用（带重试机制）
+    /// 非流式调用（带重试与模型回退）
     pub async fn call(
         &self,
         messages: &[Message],
         tools: Option<&[Value]>,
-    ) -> Result<LlmResponse, String> {
-        let url = self.get_api_url();
+    ) -> Result<LlmResponse, LlmError> {
+        let mut last_error = None;
+        for (i, client) in self.candidates().enumerate() {
+            if let (true, Some(e)) = (i > 0, &last_error) {
+                println!("[LlmClient] 🔀 回退到模型 {}，上次错误: {}", client.model(), e);
+            }
+            match client.call_model(messages, tools).await {
+                Err(e) if e.should_fall_back() => last_error = Some(e),
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(|| LlmError::Connect("no candidates".to_string())))
+    }
+
+    /// 单个模型的非流式调用（带重试机制）
+    async fn call_model(
+        &self,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+    ) -> Result<LlmResponse, LlmError> {
+        let url = self.get_api_url(false);
         let headers = self.build_headers();
         
-        let chat_messages = self.convert_messages(messages);
-        
-        let mut body = json!({
-            "model": self.config.model,
-            "messages": chat_messages,
-            "temperature": self.config.temperature,
-            "max_tokens": self.config.max_tokens,
-            "stream": false,
-        });
-        
-        let has_tools = tools.is_some();
-        if let Some(tools) = tools {
-            body["tools"] = json!(tools);
-        }
+        let body = self.build_body(messages, tools, false).await;
         
         println!("[LlmClient] 📤 发送请求到: {}", url);
         println!("[LlmClient] 📤 模型: {}, 消息数: {}, 工具: {}", 
-            self.config.model, chat_messages.len(), has_tools);
+            self.config.model, messages.len(), tools.is_some());
         
         // 重试机制
         let max_retries = 2;
-        let mut last_error = String::new();
+        let mut last_error: Option<LlmError> = None;
         
         for attempt in 0..=max_retries {
-            if attempt > 0 {
+            if let Some(error) = &last_error {
                 // 重试前等待，指数退避
-                let wait_secs = 1u64 << (attempt - 1); // 1s, 2s
-                println!("[LlmClient] ⏳ 重试 {} (等待 {}s)，上次错误: {}", attempt, wait_secs, last_error);
-                tokio::time::sleep(std::time::Duration::from_secs(wait_secs)).await;
+                let delay = error.retry_delay(Duration::from_secs(1 << (attempt - 1))); // 1s, 2s
+                println!("[LlmClient] ⏳ 重试 {} (等待 {:?})，上次错误: {}", attempt, delay, error);
+                tokio::time::sleep(delay).await;
             }
+            self.before_request().await?;
             
             let start_time = std::time::Instant::now();
             
             // 每次重试都重新构建请求（避免连接复用问题）
-            let mut req = self.client.post(&url);
-            for (key, value) in &headers {
-                req = req.header(key, value);
-            }
-            req = req.json(&body);
+            let req = HttpRequest::post(&url).headers(headers.clone()).json(&body)?;
             
-            match req.send().await {
+            let error = match self.transport.send(req).await {
                 Ok(response) => {
                     println!("[LlmClient] ✅ 收到响应，耗时: {:?}", start_time.elapsed());
                     
-                    if !response.status().is_success() {
-                        let status = response.status();
-                        let text = response.text().await.unwrap_or_default();
-                        last_error = format!("HTTP {}: {}", status, text);
-                        
-                        // 5xx 错误可以重试，4xx 错误不重试
-                        if status.is_server_error() {
-                            continue;
+                    if !response.is_success() {
+                        LlmError::from_response(response).await
+                    } else {
+                        match response.json::<Value>().await {
+                            Ok(json) => {
+                                // 成功，解析响应
+                                let response = self.provider.parse_response(&json).map_err(LlmError::Api)?;
+                                let response = self.adapt_tool_calls(response);
+                                if response.cached_tokens > 0 {
+                                    println!("[LlmClient] 💾 提示缓存命中 {} / {} tokens", response.cached_tokens, response.prompt_tokens);
+                                }
+                                self.record_usage(&response);
+                                return Ok(response);
+                            }
+                            Err(e) => LlmError::Decode(e),
                         }
-                        return Err(last_error);
                     }
-                    
-                    let json: Value = match response.json().await {
-                        Ok(j) => j,
-                        Err(e) => {
-                            last_error = format!("Failed to parse response: {}", e);
-                            continue;
-                        }
-                    };
-                    
-                    // 成功，解析响应
-                    return self.parse_llm_response(json);
                 }
                 Err(e) => {
                     println!("[LlmClient] ❌ 请求失败: {}", e);
-                    last_error = format!("Request failed: {}", e);
-                    // 继续重试
+                    LlmError::from(e)
                 }
+            };
+            
+            // 5xx 与网络错误可以重试，4xx 错误不重试
+            if !error.is_retryable() {
+                return Err(error);
             }
+            last_error = Some(error);
         }
         
         // 所有重试都失败
-        Err(last_error)
+        Err(last_error.unwrap_or_else(|| LlmError::Connect("no attempts".to_string())))
     }
     
-    /// 解析 LLM 响应
-    fn parse_llm_response(&self, json: Value) -> Result<LlmResponse, String> {
-        // 提取 token 使用量
-        let usage = &json["usage"];
-        let prompt_tokens = usage["prompt_tokens"].as_u64().unwrap_or(0) as usize;
-        let completion_tokens = usage["completion_tokens"].as_u64().unwrap_or(0) as usize;
-        let total_tokens = usage["total_tokens"].as_u64().map(|t| t as usize).unwrap_or(prompt_tokens + completion_tokens);
-        
-        let message = &json["choices"][0]["message"];
-        
-        // 检查是否有 tool_calls（Function Call）
-        if let Some(fc_tool_calls) = message.get("tool_calls").and_then(|v| v.as_array()) {
-            if self.supports_fc() {
-                // FC 模式：直接解析 JSON 返回结构化工具调用
-                let mut parsed_calls = Vec::new();
-                for (idx, tc) in fc_tool_calls.iter().enumerate() {
-                    let name = tc["function"]["name"].as_str().unwrap_or("").to_string();
-                    let args_str = tc["function"]["arguments"].as_str().unwrap_or("{}");
-                    
-                    // 解析参数 JSON
-                    let params: HashMap<String, Value> = serde_json::from_str(args_str)
-                        .unwrap_or_default();
-                    
-                    parsed_calls.push(ToolCall {
-                        id: format!("call_{}", idx),
-                        name,
-                        params,
-                    });
-                }
-                
-                // 文本内容（如果有）
-                let content = message["content"].as_str().unwrap_or("").to_string();
-                
-                return Ok(LlmResponse {
-                    content,
-                    tool_calls: Some(parsed_calls),
-                    prompt_tokens,
-                    completion_tokens,
-                    total_tokens,
-                });
-            } else {
-                // XML 模式（Ollama 等不支持 FC 的 provider）：转换为 XML 格式
-                let mut xml_output = String::new();
-                for tc in fc_tool_calls {
-                    let name = tc["function"]["name"].as_str().unwrap_or("");
-                    let args_str = tc["function"]["arguments"].as_str().unwrap_or("{}");
-                    
-                    if let Ok(args) = serde_json::from_str::<Value>(args_str) {
-                        xml_output.push_str(&format!("<{}>\n", name));
-                        if let Some(obj) = args.as_object() {
-                            for (key, value) in obj {
-                                let val_str = match value {
-                                    Value::String(s) => s.clone(),
-                                    _ => value.to_string(),
-                                };
-                                xml_output.push_str(&format!("<{}>{}</{}>\n", key, val_str, key));
-                            }
-                        }
-                        xml_output.push_str(&format!("</{}>\n", name));
-                    }
-                }
-                return Ok(LlmResponse {
-                    content: xml_output,
-                    tool_calls: None,
-                    prompt_tokens,
-                    completion_tokens,
-                    total_tokens,
-                });
-            }
+    /// 不支持 FC 的 provider（Ollama 等）：将工具调用转换为 XML 格式交给 Agent 解析
+    fn adapt_tool_calls(&self, mut response: LlmResponse) -> LlmResponse {
+        if self.supports_fc() {
+            return response;
         }
-        
-        // 提取文本内容
-        let content = message["content"]
-            .as_str()
-            .unwrap_or("")
-            .to_string();
-        
-        Ok(LlmResponse {
-            content,
-            tool_calls: None,
-            prompt_tokens,
-            completion_tokens,
-            total_tokens,
-        })
+        if let Some(calls) = response.tool_calls.take() {
+            let calls: Vec<(String, String)> = calls.into_iter()
+                .map(|c| (c.name, serde_json::to_string(&c.params).unwrap_or_default()))
+                .collect();
+            response.content = tool_calls_to_xml(&calls);
+        }
+        response
     }
 
     /// 流式调用（带心跳和超时检测）
-    pub async fn call_stream(
+    pub async fn call_stream<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
         request_id: &str,
         messages: &[Message],
         tools: Option<&[Value]>,
         current_agent: AgentType,
-    ) -> Result<String, String> {
+    ) -> Result<String, LlmError> {
         // 发送 LLM 请求开始事件
         let start_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
             .as_millis() as u64;
         
-        let _ = app.emit("agent-event", AgentEvent::LlmRequestStart {
+        emit(app, "agent-event", AgentEvent::LlmRequestStart {
             request_id: request_id.to_string(),
             timestamp: start_timestamp,
         });
         
-        // 使用带重试的流式调用
-        let result = self.call_stream_with_retry(app, request_id, messages, tools, current_agent).await;
+        // 使用带重试的流式调用，主模型不可用时依次回退
+        let mut result = Err(LlmError::Connect("no candidates".to_string()));
+        for (i, client) in self.candidates().enumerate() {
+            if let (true, Err(e)) = (i > 0, &result) {
+                println!("[LlmClient] 🔀 回退到模型 {}，上次错误: {}", client.model(), e);
+            }
+            result = client.call_stream_with_retry(app, request_id, messages, tools, current_agent.clone()).await;
+            match &result {
+                Err(e) if e.should_fall_back() => continue,
+                _ => break,
+            }
+        }
         
         // 发送 LLM 请求结束事件
-        let _ = app.emit("agent-event", AgentEvent::LlmRequestEnd {
+        emit(app, "agent-event", AgentEvent::LlmRequestEnd {
             request_id: request_id.to_string(),
         });
         
@@ -355,106 +390,77 @@ impl LlmClient {
     }
     
     /// 流式调用（带指数退避重试）
-    async fn call_stream_with_retry(
+    async fn call_stream_with_retry<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
         request_id: &str,
         messages: &[Message],
         tools: Option<&[Value]>,
         current_agent: AgentType,
-    ) -> Result<String, String> {
+    ) -> Result<String, LlmError> {
         let max_retries = 3;
         let base_delay = Duration::from_secs(1);
-        let mut last_error = String::new();
+        let mut last_error: Option<LlmError> = None;
         
         for attempt in 0..max_retries {
-            if attempt > 0 {
+            if let Some(error) = &last_error {
                 // 指数退避 + 随机抖动
-                let delay_secs = base_delay.as_secs() * 2u64.pow(attempt as u32);
+                let backoff = base_delay * 2u32.pow(attempt as u32);
                 let jitter_ms = rand::random::<u64>() % 500;
-                let delay = Duration::from_secs(delay_secs) + Duration::from_millis(jitter_ms);
+                let delay = error.retry_delay(backoff) + Duration::from_millis(jitter_ms);
                 
                 println!("[LlmClient] ⏳ 流式调用重试 {} (等待 {:?})，上次错误: {}", 
-                    attempt, delay, last_error);
+                    attempt, delay, error);
                 tokio::time::sleep(delay).await;
             }
             
             match self.call_stream_inner(app, request_id, messages, tools, current_agent.clone()).await {
                 Ok(content) => return Ok(content),
                 Err(e) => {
-                    last_error = e.clone();
-                    // 判断是否可重试
-                    if !Self::is_retryable_error(&e) {
+                    if !e.is_retryable() {
                         return Err(e);
                     }
                     println!("[LlmClient] ❌ 流式调用失败 (attempt {}): {}", attempt + 1, e);
+                    last_error = Some(e);
                 }
             }
         }
         
-        Err(format!("流式调用失败，已重试 {} 次: {}", max_retries, last_error))
-    }
-    
-    /// 判断错误是否可重试
-    fn is_retryable_error(error: &str) -> bool {
-        let error_lower = error.to_lowercase();
-        error_lower.contains("timeout") ||
-        error_lower.contains("connection") ||
-        error_lower.contains("reset") ||
-        error_lower.contains("broken pipe") ||
-        error_lower.contains("stream error") ||
-        error_lower.contains("no data") ||
-        error.contains("5") && error.contains("HTTP")  // 5xx 错误
+        println!("[LlmClient] ❌ 流式调用失败，已重试 {} 次", max_retries);
+        Err(last_error.unwrap_or_else(|| LlmError::Connect("no attempts".to_string())))
     }
     
     /// 流式调用内部实现（带心跳）
-    async fn call_stream_inner(
+    async fn call_stream_inner<R: Runtime>(
         &self,
-        app: &AppHandle,
+        app: &AppHandle<R>,
         _request_id: &str,
         messages: &[Message],
         tools: Option<&[Value]>,
         current_agent: AgentType,
-    ) -> Result<String, String> {
-        let url = self.get_api_url();
+    ) -> Result<String, LlmError> {
+        let url = self.get_api_url(true);
         let headers = self.build_headers();
         
-        let chat_messages = self.convert_messages(messages);
-        
-        let mut body = json!({
-            "model": self.config.model,
-            "messages": chat_messages,
-            "temperature": self.config.temperature,
-            "max_tokens": self.config.max_tokens,
-            "stream": true,
-        });
-        
-        if let Some(tools) = tools {
-            body["tools"] = json!(tools);
-        }
+        let body = self.build_body(messages, tools, true).await;
         
-        let mut req = self.client.post(&url);
-        for (key, value) in headers {
-            req = req.header(&key, &value);
-        }
-        req = req.json(&body);
+        self.before_request().await?;
+        let req = HttpRequest::post(&url).headers(headers).json(&body)?;
         
-        let response = req.send().await
-            .map_err(|e| format!("Request failed: {}", e))?;
+        let response = self.transport.send(req).await?;
         
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(format!("HTTP {}: {}", status, text));
+        if !response.is_success() {
+            return Err(LlmError::from_response(response).await);
         }
         
         // 流式读取（带心跳和超时检测）
         let mut stream = response.bytes_stream();
-        let mut buffer = String::new();
+        let mut framer = StreamFramer::new(self.provider.stream_format());
         let mut full_content = String::new();
         
-        // 用于累积 tool_calls
-        let mut tool_calls: Vec<(String, String)> = Vec::new(); // (name, arguments)
+        // 各 provider 的流式格式由其解析器处理
+        let mut decoder = self.provider.stream_decoder();
+        let node_ctx = NodeContext::current();
         
         // 心跳和超时配置
         let heartbeat_interval = Duration::from_secs(15);
@@ -466,81 +472,45 @@ impl LlmClient {
             tokio::select! {
                 // 处理流数据
                 chunk_result = stream.next() => {
-                    match chunk_result {
+                    let (frames, finished) = match chunk_result {
                         Some(Ok(bytes)) => {
                             last_data_time = Instant::now();
-                            let text = String::from_utf8_lossy(&bytes);
-                            buffer.push_str(&text);
-                            
-                            // 按行处理 SSE
-                            while let Some(newline_pos) = buffer.find('\n') {
-                                let line = buffer[..newline_pos].trim().to_string();
-                                buffer = buffer[newline_pos + 1..].to_string();
-                                
-                                if line.is_empty() || line.starts_with(": ") {
-                                    continue;
-                                }
-                                
-                                if line.starts_with("data: ") {
-                                    let data = &line[6..];
-                                    
-                                    if data == "[DONE]" {
-                                        // 流正常结束
-                                        return self.finalize_stream_result(full_content, tool_calls);
-                                    }
-                                    
-                                    if let Ok(json) = serde_json::from_str::<Value>(data) {
-                                        let delta = &json["choices"][0]["delta"];
-                                        
-                                        // 处理 tool_calls（Function Call 流式响应）
-                                        if let Some(tc_array) = delta.get("tool_calls").and_then(|v| v.as_array()) {
-                                            for tc in tc_array {
-                                                let idx = tc["index"].as_u64().unwrap_or(0) as usize;
-                                                
-                                                // 确保 tool_calls 数组足够大
-                                                while tool_calls.len() <= idx {
-                                                    tool_calls.push((String::new(), String::new()));
-                                                }
-                                                
-                                                // 累积函数名
-                                                if let Some(name) = tc["function"]["name"].as_str() {
-                                                    tool_calls[idx].0.push_str(name);
-                                                }
-                                                
-                                                // 累积参数
-                                                if let Some(args) = tc["function"]["arguments"].as_str() {
-                                                    tool_calls[idx].1.push_str(args);
-                                                }
-                                            }
-                                        }
-                                        
-                                        // 处理普通文本内容
-                                        if let Some(content) = delta["content"].as_str() {
-                                            // 跳过空内容
-                                            if content.is_empty() {
-                                                continue;
-                                            }
-                                            
-                                            full_content.push_str(content);
-                                            
-                                            // 发送事件到前端
-                                            let _ = app.emit("agent-event", AgentEvent::MessageChunk {
-                                                content: content.to_string(),
-                                                agent: current_agent.clone(),
-                                            });
-                                        }
-                                    }
-                                }
-                            }
+                            (framer.push(&bytes), false)
                         }
                         Some(Err(e)) => {
-                            return Err(format!("Stream error: {}", e));
+                            return Err(LlmError::Stream(e));
                         }
-                        None => {
-                            // 流结束
-                            return self.finalize_stream_result(full_content, tool_calls);
+                        // 流结束（Gemini 等没有结束事件的格式）
+                        None => (framer.finish(), true),
+                    };
+                    
+                    for data in frames {
+                        match decoder.push_data(&data).map_err(LlmError::Api)? {
+                            StreamStep::Text(content) => {
+                                full_content.push_str(&content);
+                                if let Some(ctx) = &node_ctx {
+                                    ctx.emit_token(content.clone());
+                                }
+                                
+                                // 发送事件到前端
+                                emit(app, "agent-event", AgentEvent::MessageChunk {
+                                    content,
+                                    agent: current_agent.clone(),
+                                });
+                            }
+                            StreamStep::Stop => {
+                                // 流正常结束
+                                self.record_stream_usage(messages, decoder.response(), &full_content);
+                                return Ok(self.finalize_stream_result(full_content, decoder.tool_calls()));
+                            }
+                            StreamStep::Continue => {}
                         }
                     }
+                    
+                    if finished {
+                        self.record_stream_usage(messages, decoder.response(), &full_content);
+                        return Ok(self.finalize_stream_result(full_content, decoder.tool_calls()));
+                    }
                 }
                 
                 // 定期发送心跳
@@ -550,11 +520,11 @@ impl LlmClient {
                         .unwrap_or_default()
                         .as_millis() as u64;
                     
-                    let _ = app.emit("agent-event", AgentEvent::Heartbeat { timestamp });
+                    emit(app, "agent-event", AgentEvent::Heartbeat { timestamp });
                     
                     // 检测假死（超时无数据）
                     if last_data_time.elapsed() > stream_timeout {
-                        return Err(format!("Stream timeout: no data for {} seconds", stream_timeout.as_secs()));
+                        return Err(LlmError::StreamTimeout(stream_timeout.as_secs()));
                     }
                 }
             }
@@ -566,31 +536,10 @@ impl LlmClient {
         &self,
         full_content: String,
         tool_calls: Vec<(String, String)>,
-    ) -> Result<String, String> {
+    ) -> String {
         // 如果有 tool_calls，转换为 XML 格式
         if !tool_calls.is_empty() {
-            let mut xml_output = String::new();
-            for (name, args_str) in &tool_calls {
-                if name.is_empty() {
-                    continue;
-                }
-                
-                // 解析参数 JSON
-                if let Ok(args) = serde_json::from_str::<Value>(args_str) {
-                    xml_output.push_str(&format!("<{}>\n", name));
-                    if let Some(obj) = args.as_object() {
-                        for (key, value) in obj {
-                            let val_str = match value {
-                                Value::String(s) => s.clone(),
-                                _ => value.to_string(),
-                            };
-                            xml_output.push_str(&format!("<{}>{}</{}>\n", key, val_str, key));
-                        }
-                    }
-                    xml_output.push_str(&format!("</{}>\n", name));
-                }
-            }
-            return Ok(xml_output);
+            return tool_calls_to_xml(&tool_calls);
         }
         
         // 调试日志 - 检查最终内容
@@ -603,37 +552,50 @@ impl LlmClient {
             }
         }
         
-        Ok(full_content)
+        full_content
     }
 
     // ============ 简化接口（用于 Deep Research 等场景）============
 
     /// 简单的非流式调用（只传入 prompt）
-    pub async fn call_simple(&self, prompt: &str) -> Result<String, String> {
+    pub async fn call_simple(&self, prompt: &str) -> Result<String, LlmError> {
         let response = self.call_simple_with_usage(prompt).await?;
         Ok(response.content)
     }
 
     /// 简单的非流式调用（返回完整响应，包含 token 统计）
-    pub async fn call_simple_with_usage(&self, prompt: &str) -> Result<LlmResponse, String> {
-        let messages = vec![Message {
-            role: MessageRole::User,
-            content: prompt.to_string(),
-            name: None,
-            tool_call_id: None,
-        }];
+    pub async fn call_simple_with_usage(&self, prompt: &str) -> Result<LlmResponse, LlmError> {
+        let messages = vec![Message::user(prompt)];
         
         self.call(&messages, None).await
     }
 
     /// 简单的流式调用（只传入 prompt，通过 channel 返回）
     /// 
-    /// 包含超时处理和错误日志
+    /// 包含超时处理和错误日志；开始接收前失败时依次回退
     pub async fn call_stream_simple(
         &self,
         prompt: &str,
-    ) -> Result<tokio::sync::mpsc::Receiver<String>, String> {
-        let url = self.get_api_url();
+    ) -> Result<tokio::sync::mpsc::Receiver<String>, LlmError> {
+        let mut last_error = None;
+        for (i, client) in self.candidates().enumerate() {
+            if let (true, Some(e)) = (i > 0, &last_error) {
+                println!("[LLM] 🔀 回退到模型 {}，上次错误: {}", client.model(), e);
+            }
+            match client.stream_simple_model(prompt).await {
+                Err(e) if e.should_fall_back() => last_error = Some(e),
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(|| LlmError::Connect("no candidates".to_string())))
+    }
+
+    /// 单个模型的简单流式调用
+    async fn stream_simple_model(
+        &self,
+        prompt: &str,
+    ) -> Result<tokio::sync::mpsc::Receiver<String>, LlmError> {
+        let url = self.get_api_url(true);
         let headers = self.build_headers();
         
         // 限制 prompt 长度，避免超过模型限制
@@ -642,38 +604,24 @@ impl LlmClient {
         #[cfg(debug_assertions)]
         println!("[LLM] 流式调用开始，prompt 长度: {} 字符", prompt_chars.chars().count());
         
-        let body = json!({
-            "model": self.config.model,
-            "messages": [{
-                "role": "user",
-                "content": prompt_chars
-            }],
-            "temperature": self.config.temperature,
-            "max_tokens": self.config.max_tokens,
-            "stream": true,
-        });
+        let messages = [Message::user(prompt_chars)];
+        let body = self.build_body(&messages, None, true).await;
         
-        let mut req = self.client.post(&url);
-        for (key, value) in headers {
-            req = req.header(&key, &value);
-        }
-        req = req.json(&body);
+        self.before_request().await?;
+        let req = HttpRequest::post(&url).headers(headers).json(&body)?;
         
         // 添加请求超时（大请求需要更长时间）
         let response = tokio::time::timeout(
             tokio::time::Duration::from_secs(120),
-            req.send()
+            self.transport.send(req)
         ).await
-            .map_err(|_| "请求超时（120秒）".to_string())?
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .map_err(|_| LlmError::Timeout(120))??;
         
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            let error_msg = format!("HTTP {}: {}", status, text);
+        if !response.is_success() {
+            let error = LlmError::from_response(response).await;
             #[cfg(debug_assertions)]
-            eprintln!("[LLM] 流式调用失败: {}", error_msg);
-            return Err(error_msg);
+            eprintln!("[LLM] 流式调用失败: {}", error);
+            return Err(error);
         }
         
         #[cfg(debug_assertions)]
@@ -684,8 +632,15 @@ impl LlmClient {
         
         // 在后台任务中处理流
         let mut stream = response.bytes_stream();
+        let mut framer = StreamFramer::new(self.provider.stream_format());
+        let mut decoder = self.provider.stream_decoder();
+        // 用量在流结束后计入，节点上下文不会传递到新任务中，需提前获取
+        let budget = self.budget.clone();
+        let node_ctx = NodeContext::current();
+        let model = self.config.model.clone();
+        let prompt_tokens = estimate_messages_tokens(&messages);
         tokio::spawn(async move {
-            let mut buffer = String::new();
+            let mut full_content = String::new();
             let mut chunk_count = 0usize;
             let mut total_chars = 0usize;
             let start_time = std::time::Instant::now();
@@ -693,76 +648,233 @@ impl LlmClient {
             // 流式读取超时：如果 60 秒没有新数据，认为流结束
             let stream_timeout = tokio::time::Duration::from_secs(60);
             
-            loop {
+            'read: loop {
                 let chunk_result = tokio::time::timeout(stream_timeout, stream.next()).await;
                 
-                let chunk = match chunk_result {
-                    Ok(Some(Ok(bytes))) => bytes,
+                let (frames, finished) = match chunk_result {
+                    Ok(Some(Ok(bytes))) => (framer.push(&bytes), false),
                     Ok(Some(Err(e))) => {
                         #[cfg(debug_assertions)]
                         eprintln!("[LLM] 流式读取错误: {}", e);
-                        break;
+                        break 'read;
                     }
                     Ok(None) => {
                         // 流正常结束
                         #[cfg(debug_assertions)]
                         println!("[LLM] 流式响应结束，共 {} 个 chunk，{} 字符，耗时 {:?}", 
                             chunk_count, total_chars, start_time.elapsed());
-                        break;
+                        (framer.finish(), true)
                     }
                     Err(_) => {
                         // 超时
                         #[cfg(debug_assertions)]
                         eprintln!("[LLM] 流式读取超时（{}秒无数据）", stream_timeout.as_secs());
-                        break;
+                        break 'read;
                     }
                 };
                 
-                let text = String::from_utf8_lossy(&chunk);
-                buffer.push_str(&text);
-                
-                // 按行处理 SSE
-                while let Some(newline_pos) = buffer.find('\n') {
-                    let line = buffer[..newline_pos].trim().to_string();
-                    buffer = buffer[newline_pos + 1..].to_string();
-                    
-                    if line.is_empty() || line.starts_with(": ") {
-                        continue;
-                    }
-                    
-                    if line.starts_with("data: ") {
-                        let data = &line[6..];
-                        
-                        if data == "[DONE]" {
+                for data in frames {
+                    let content = match decoder.push_data(&data) {
+                        Ok(StreamStep::Text(content)) => content,
+                        Ok(StreamStep::Continue) => continue,
+                        Ok(StreamStep::Stop) => {
                             #[cfg(debug_assertions)]
-                            println!("[LLM] 收到 [DONE] 信号");
-                            return;  // 使用 return 而不是 break，确保退出整个 spawn
+                            println!("[LLM] 收到结束信号");
+                            break 'read;
                         }
-                        
-                        if let Ok(json) = serde_json::from_str::<Value>(data) {
-                            // 检查是否有错误
-                            if let Some(error) = json.get("error") {
-                                #[cfg(debug_assertions)]
-                                eprintln!("[LLM] API 返回错误: {}", error);
-                                return;
-                            }
-                            
-                            if let Some(content) = json["choices"][0]["delta"]["content"].as_str() {
-                                chunk_count += 1;
-                                total_chars += content.chars().count();
-                                if tx.send(content.to_string()).await.is_err() {
-                                    // 接收端已关闭
-                                    #[cfg(debug_assertions)]
-                                    println!("[LLM] 接收端已关闭，停止发送");
-                                    return;
-                                }
-                            }
+                        Err(e) => {
+                            #[cfg(debug_assertions)]
+                            eprintln!("[LLM] API 返回错误: {}", e);
+                            break 'read;
                         }
+                    };
+                    
+                    chunk_count += 1;
+                    total_chars += content.chars().count();
+                    full_content.push_str(&content);
+                    if let Some(ctx) = &node_ctx {
+                        ctx.emit_token(content.clone());
+                    }
+                    if tx.send(content).await.is_err() {
+                        // 接收端已关闭
+                        #[cfg(debug_assertions)]
+                        println!("[LLM] 接收端已关闭，停止发送");
+                        break 'read;
                     }
                 }
+                
+                if finished {
+                    break;
+                }
             }
+            
+            let response = with_estimated_usage(decoder.response(), prompt_tokens, &full_content);
+            record_usage(&budget, node_ctx.as_ref(), &model, &response);
         });
         
         Ok(rx)
     }
 }
+
+/// 读取消息引用的图片文件并填充 base64 数据
+///
+/// 无法读取的图片替换为文本说明，不中断请求
+async fn load_images(messages: &[Message]) -> Vec<Message> {
+    let mut loaded = messages.to_vec();
+    for part in loaded.iter_mut().flat_map(|message| message.parts.iter_mut()) {
+        let ContentPart::Image { path, data, .. } = part else {
+            continue;
+        };
+        if data.is_some() {
+            continue;
+        }
+        match read_image_data(path).await {
+            Ok(encoded) => *data = Some(encoded),
+            Err(e) => {
+                let text = format!("[图片 {} 无法读取: {}]", path, e);
+                *part = ContentPart::Text { text };
+            }
+        }
+    }
+    loaded
+}
+
+async fn read_image_data(path: &str) -> Result<String, String> {
+    let size = tokio::fs::metadata(path).await.map_err(|e| e.to_string())?.len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(format!("超过 {} MB", MAX_IMAGE_BYTES / 1024 / 1024));
+    }
+    let bytes = tokio::fs::read(path).await.map_err(|e| e.to_string())?;
+    Ok(STANDARD.encode(bytes))
+}
+
+/// 将一次调用的用量与估算费用计入预算与节点指标
+fn record_usage(budget: &UsageBudget, node_ctx: Option<&NodeContext>, model: &str, response: &LlmResponse) {
+    let cost = budget.config().estimate_cost(model, response.prompt_tokens, response.cached_tokens, response.completion_tokens);
+    let usage = TokenUsage::new(response.prompt_tokens as u32, response.completion_tokens as u32)
+        .with_cost(cost);
+    budget.record(&usage);
+    if let Some(ctx) = node_ctx {
+        ctx.record_usage(usage);
+    }
+}
+
+/// 提供商未返回 token 统计时按消息与输出内容估算
+fn with_estimated_usage(mut response: LlmResponse, prompt_tokens: usize, content: &str) -> LlmResponse {
+    if response.total_tokens == 0 {
+        response.prompt_tokens = prompt_tokens;
+        response.completion_tokens = estimate_tokens(content);
+        response.total_tokens = response.prompt_tokens + response.completion_tokens;
+    }
+    response
+}
+
+/// 将工具调用 (名称, 参数 JSON) 转换为 XML 格式
+fn tool_calls_to_xml(tool_calls: &[(String, String)]) -> String {
+    let mut xml_output = String::new();
+    for (name, args_str) in tool_calls {
+        if name.is_empty() {
+            continue;
+        }
+        
+        // 解析参数 JSON
+        if let Ok(args) = serde_json::from_str::<Value>(args_str) {
+            xml_output.push_str(&format!("<{}>\n", name));
+            if let Some(obj) = args.as_object() {
+                for (key, value) in obj {
+                    let val_str = match value {
+                        Value::String(s) => s.clone(),
+                        _ => value.to_string(),
+                    };
+                    xml_output.push_str(&format!("<{}>{}</{}>\n", key, val_str, key));
+                }
+            }
+            xml_output.push_str(&format!("</{}>\n", name));
+        }
+    }
+    xml_output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::NetworkOptions;
+
+    #[tokio::test]
+    async fn test_invalid_network_options_fail_requests() {
+        let client = LlmClient::new(AgentConfig {
+            base_url: Some("http://127.0.0.1:9".to_string()),
+            network: NetworkOptions {
+                proxy_url: Some("not a url".to_string()),
+                ..Default::default()
+            },
+            ..AgentConfig::default()
+        });
+
+        // 不回退到绕过代理的默认客户端
+        let error = client.call(&[Message::user("hi")], None).await.unwrap_err().to_string();
+        assert!(error.starts_with("Invalid network options: Invalid proxy URL"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_images_loaded_at_request_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("shot.png");
+        std::fs::write(&image, b"PNG").unwrap();
+        let oversized = dir.path().join("big.png");
+        std::fs::File::create(&oversized).unwrap().set_len(MAX_IMAGE_BYTES + 1).unwrap();
+
+        let message = Message::user("看图")
+            .with_part(ContentPart::image("image/png", image.to_string_lossy()))
+            .with_part(ContentPart::image("image/png", oversized.to_string_lossy()));
+        // 消息历史只保存路径
+        assert!(!serde_json::to_string(&message).unwrap().contains("UE5H"));
+
+        let loaded = load_images(std::slice::from_ref(&message)).await;
+        match &loaded[0].parts[0] {
+            ContentPart::Image { data, .. } => assert_eq!(data.as_deref(), Some("UE5H")),
+            other => panic!("unexpected part: {:?}", other),
+        }
+        assert!(matches!(&loaded[0].parts[1], ContentPart::Text { text } if text.contains("超过 5 MB")));
+    }
+
+    #[tokio::test]
+    async fn test_stream_chunks_reach_graph_events() {
+        use crate::agent::eval::mock_llm::MockLlmServer;
+        use crate::agent::eval::case::MockReply;
+        use crate::langgraph::constants::{START, END};
+        use crate::langgraph::error::GraphError;
+        use crate::langgraph::events::GraphEvent;
+        use crate::langgraph::graph::StateGraph;
+        use crate::langgraph::state::DictState;
+
+        let server = MockLlmServer::start(vec![MockReply::text("你好")]).await.unwrap();
+        let client = Arc::new(LlmClient::new(AgentConfig {
+            base_url: Some(server.base_url().to_string()),
+            model: "mock".to_string(),
+            api_key: "mock".to_string(),
+            ..AgentConfig::default()
+        }));
+        let app = tauri::test::mock_app();
+        let handle = app.handle().clone();
+
+        let mut graph = StateGraph::<DictState>::new();
+        graph.add_node("reporter", move |s: DictState| {
+            let client = client.clone();
+            let app = handle.clone();
+            async move {
+                client.call_stream(&app, "req", &[Message::user("hi")], None, AgentType::Reporter).await
+                    .map_err(|e| GraphError::Other(e.to_string()))?;
+                Ok(s)
+            }
+        });
+        graph.add_edge(START, "reporter");
+        graph.add_edge("reporter", END);
+        let graph = graph.compile().unwrap();
+
+        let events: Vec<GraphEvent> = graph.stream_events(DictState::new()).collect().await;
+        assert!(events.contains(&GraphEvent::Token { node: "reporter".to_string(), chunk: "你好".to_string() }));
+        // 进度事件经节点上下文写入事件流，而不是直接发给前端
+        assert!(events.iter().any(|e| matches!(e, GraphEvent::Custom { name, .. } if name == "agent-event")));
+    }
+}
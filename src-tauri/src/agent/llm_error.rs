@@ -0,0 +1,192 @@
+//! LLM 调用错误
+//!
+//! 按错误类型决定是否重试同一模型、是否切换到回退模型，
+//! 对外接口仍返回 `String`（错误的 Display）
+
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::agent::budget::BudgetError;
+use crate::agent::transport::{HttpResponse, TransportError};
+use crate::langgraph::error::GraphError;
+
+/// LLM 调用错误
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LlmError {
+    /// 连接失败或请求未发送成功
+    #[error("Request failed: {0}")]
+    Connect(String),
+    /// 等待响应超时
+    #[error("请求超时（{0}秒）")]
+    Timeout(u64),
+    /// 非 2xx 响应
+    #[error("HTTP {status}: {body}")]
+    Http {
+        status: u16,
+        body: String,
+        /// `Retry-After` 响应头
+        retry_after: Option<Duration>,
+    },
+    /// 读取流式响应中断
+    #[error("Stream error: {0}")]
+    Stream(String),
+    /// 流式响应长时间没有数据
+    #[error("Stream timeout: no data for {0} seconds")]
+    StreamTimeout(u64),
+    /// 响应无法解析
+    #[error("Failed to parse response: {0}")]
+    Decode(String),
+    /// 响应中返回的 API 错误（如流式响应中的 error 事件）
+    #[error("{0}")]
+    Api(String),
+    /// 超出用量预算
+    #[error(transparent)]
+    Budget(#[from] BudgetError),
+    /// 客户端配置无效（如代理 URL 或 CA 证书错误），不发送请求
+    #[error("Invalid network options: {0}")]
+    Config(String),
+}
+
+impl LlmError {
+    /// 读取非 2xx 响应的状态码、`Retry-After` 与响应体
+    pub async fn from_response(response: HttpResponse) -> Self {
+        let status = response.status;
+        let retry_after = response.header("retry-after")
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Self::Http { status, body, retry_after }
+    }
+
+    /// HTTP 状态码
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// 同一模型重试可能成功：网络错误、流中断、服务端错误（5xx）
+    ///
+    /// 限流（429）不重试，直接交给回退链或调用方
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Connect(_) | Self::Timeout(_) | Self::Stream(_) | Self::StreamTimeout(_) | Self::Decode(_) => true,
+            Self::Http { status, .. } => *status >= 500 || *status == 408,
+            Self::Api(_) | Self::Budget(_) | Self::Config(_) => false,
+        }
+    }
+
+    /// 切换到回退模型：限流（429）、服务端错误（5xx，含 Anthropic 的 529 过载）或连接失败
+    pub fn should_fall_back(&self) -> bool {
+        match self {
+            Self::Connect(_) | Self::Timeout(_) => true,
+            Self::Http { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// 重试前的等待时间：服务端给出 `Retry-After` 时取两者中较大的，最多 30 秒
+    pub fn retry_delay(&self, backoff: Duration) -> Duration {
+        match self {
+            Self::Http { retry_after: Some(after), .. } => backoff.max(*after).min(Duration::from_secs(30)),
+            _ => backoff,
+        }
+    }
+}
+
+/// 回放固件中没有匹配的请求时不重试，也不切换回退模型
+impl From<TransportError> for LlmError {
+    fn from(error: TransportError) -> Self {
+        match error {
+            TransportError::Request(message) => Self::Connect(message),
+            error => Self::Api(error.to_string()),
+        }
+    }
+}
+
+/// 图节点错误
+///
+/// 保留 LLM 错误的类型，执行器据此判断是否重试节点；其余错误仍为文本
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum NodeError {
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+    #[error("{0}")]
+    Message(String),
+}
+
+impl From<String> for NodeError {
+    fn from(message: String) -> Self {
+        Self::Message(message)
+    }
+}
+
+impl From<&str> for NodeError {
+    fn from(message: &str) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+impl NodeError {
+    /// 转换为图错误：重试或回退后仍失败的临时 LLM 错误（网络、超时、限流、5xx）
+    /// 标记为 `GraphError::Transient`，由节点的重试配置决定是否重跑
+    pub fn into_graph_error(self, node: &str) -> GraphError {
+        match self {
+            Self::Llm(e) if e.is_retryable() || e.should_fall_back() => GraphError::transient(node, e.to_string()),
+            e => GraphError::ExecutionError { node: node.to_string(), message: e.to_string() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http(status: u16) -> LlmError {
+        LlmError::Http { status, body: String::new(), retry_after: None }
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(http(503).is_retryable() && http(503).should_fall_back());
+        assert!(http(529).should_fall_back());
+        assert!(!http(429).is_retryable() && http(429).should_fall_back());
+        assert!(!http(401).is_retryable() && !http(401).should_fall_back());
+        // 响应体中出现 5 不会被误判为服务端错误
+        let client_error = LlmError::Http { status: 400, body: "max_tokens must be <= 8192".into(), retry_after: None };
+        assert!(!client_error.is_retryable());
+
+        assert!(LlmError::Connect("dns error".into()).should_fall_back());
+        let config = LlmError::Config("Invalid proxy URL".into());
+        assert!(!config.is_retryable() && !config.should_fall_back());
+        assert!(LlmError::StreamTimeout(60).is_retryable());
+        assert!(!LlmError::StreamTimeout(60).should_fall_back());
+        assert!(!LlmError::Api("API error: quota".into()).is_retryable());
+        let budget = LlmError::from(BudgetError::TaskTokens { used: 10, limit: 5 });
+        assert!(!budget.is_retryable() && !budget.should_fall_back());
+        assert_eq!(budget.to_string(), "已超出任务 token 预算：10 / 5");
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let backoff = Duration::from_secs(2);
+        let limited = LlmError::Http { status: 503, body: String::new(), retry_after: Some(Duration::from_secs(10)) };
+        assert_eq!(limited.retry_delay(backoff), Duration::from_secs(10));
+        let long = LlmError::Http { status: 503, body: String::new(), retry_after: Some(Duration::from_secs(600)) };
+        assert_eq!(long.retry_delay(backoff), Duration::from_secs(30));
+        assert_eq!(http(500).retry_delay(backoff), backoff);
+    }
+
+    #[test]
+    fn test_node_error_marks_transient_llm_failures() {
+        assert!(NodeError::from(http(503)).into_graph_error("writer").is_retryable());
+        assert!(NodeError::from(http(429)).into_graph_error("writer").is_retryable());
+        assert!(NodeError::from(LlmError::Timeout(60)).into_graph_error("writer").is_retryable());
+        assert!(!NodeError::from(http(401)).into_graph_error("writer").is_retryable());
+
+        // 文本错误不再按内容猜测，即使包含 "timeout"
+        let message = NodeError::from("connection timeout while parsing outline");
+        assert!(matches!(message.into_graph_error("writer"), GraphError::ExecutionError { .. }));
+    }
+}
@@ -0,0 +1,32 @@
+//! 本地 LLM Tauri 命令
+
+use super::detect::{default_endpoints, detect_local_servers, LocalEndpoint, LocalServer};
+use super::runtime::{
+    managed_runtime_status, start_managed_runtime, stop_managed_runtime, ManagedRuntimeConfig,
+    ManagedRuntimeStatus,
+};
+
+/// 检测本地模型服务，未指定地址时检测各服务的默认地址
+#[tauri::command]
+pub async fn local_llm_detect(endpoints: Option<Vec<LocalEndpoint>>) -> Result<Vec<LocalServer>, String> {
+    let endpoints = endpoints.unwrap_or_else(default_endpoints);
+    Ok(detect_local_servers(&endpoints).await)
+}
+
+/// 启动内置 GGUF 运行时，模型加载完成后返回
+#[tauri::command]
+pub async fn local_runtime_start(config: ManagedRuntimeConfig) -> Result<ManagedRuntimeStatus, String> {
+    start_managed_runtime(config).await
+}
+
+/// 停止内置运行时（应用退出时也应调用）
+#[tauri::command]
+pub async fn local_runtime_stop() -> Result<(), String> {
+    stop_managed_runtime().await
+}
+
+/// 获取内置运行时状态
+#[tauri::command]
+pub async fn local_runtime_status() -> Result<Option<ManagedRuntimeStatus>, String> {
+    Ok(managed_runtime_status().await)
+}
@@ -0,0 +1,308 @@
+//! 本地模型服务检测
+//!
+//! - Ollama：`/api/version`、`/api/tags`，并通过 `/api/show` 读取模型能力与上下文长度
+//! - llama.cpp server / LM Studio：OpenAI 兼容的 `/models`
+
+use futures_util::future::join_all;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::agent::providers;
+
+/// 检测请求超时，本地服务应很快响应
+const DETECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 检测到的 Ollama 模型能力（`模型名:标签` → capabilities）
+static OLLAMA_CAPABILITIES: Lazy<RwLock<HashMap<String, Vec<String>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 本地服务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalServerKind {
+    Ollama,
+    Llamacpp,
+    Lmstudio,
+}
+
+impl LocalServerKind {
+    /// 对应的 `AgentConfig::provider`
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Self::Ollama => "ollama",
+            Self::Llamacpp => "llamacpp",
+            Self::Lmstudio => "lmstudio",
+        }
+    }
+}
+
+/// 待检测的本地服务地址
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalEndpoint {
+    pub kind: LocalServerKind,
+    /// 为空时使用提供商的默认地址
+    pub base_url: Option<String>,
+}
+
+/// 本地模型
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LocalModel {
+    /// 模型名，即 `AgentConfig::model`
+    pub name: String,
+    pub size_bytes: Option<u64>,
+    pub family: Option<String>,
+    /// 参数量，如 `8.2B`
+    pub parameter_size: Option<String>,
+    /// 量化方式，如 `Q4_K_M`
+    pub quantization: Option<String>,
+    /// 模型能力，如 `completion`、`tools`、`vision`（仅 Ollama 提供）
+    pub capabilities: Vec<String>,
+    /// 模型支持的上下文长度
+    pub context_length: Option<usize>,
+}
+
+/// 本地服务检测结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalServer {
+    pub kind: LocalServerKind,
+    pub provider: String,
+    pub base_url: String,
+    pub available: bool,
+    pub version: Option<String>,
+    pub models: Vec<LocalModel>,
+    pub error: Option<String>,
+}
+
+/// 各本地服务的默认地址
+pub fn default_endpoints() -> Vec<LocalEndpoint> {
+    [LocalServerKind::Ollama, LocalServerKind::Llamacpp, LocalServerKind::Lmstudio]
+        .into_iter()
+        .map(|kind| LocalEndpoint { kind, base_url: None })
+        .collect()
+}
+
+/// 并发检测多个本地服务
+pub async fn detect_local_servers(endpoints: &[LocalEndpoint]) -> Vec<LocalServer> {
+    // 本地地址不经过系统代理
+    let client = reqwest::Client::builder()
+        .timeout(DETECT_TIMEOUT)
+        .no_proxy()
+        .build()
+        .unwrap_or_default();
+    join_all(endpoints.iter().map(|endpoint| detect_server(&client, endpoint))).await
+}
+
+/// 检测单个本地服务，连接失败时 `available` 为 false
+pub async fn detect_server(client: &reqwest::Client, endpoint: &LocalEndpoint) -> LocalServer {
+    let provider = endpoint.kind.provider();
+    let base_url = endpoint.base_url.clone()
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| providers::resolve(provider).default_base_url().to_string());
+    let base_url = base_url.trim_end_matches('/').to_string();
+
+    let result = match endpoint.kind {
+        LocalServerKind::Ollama => detect_ollama(client, &base_url).await,
+        LocalServerKind::Llamacpp | LocalServerKind::Lmstudio => {
+            detect_openai_compatible(client, &base_url).await.map(|models| (None, models))
+        }
+    };
+    let (available, version, models, error) = match result {
+        Ok((version, models)) => (true, version, models, None),
+        Err(e) => (false, None, Vec::new(), Some(e)),
+    };
+    LocalServer {
+        kind: endpoint.kind,
+        provider: provider.to_string(),
+        base_url,
+        available,
+        version,
+        models,
+        error,
+    }
+}
+
+/// 检测过的 Ollama 模型是否具有某项能力，未检测或服务未返回能力列表时为 `None`
+pub fn ollama_capability(model: &str, capability: &str) -> Option<bool> {
+    let capabilities = OLLAMA_CAPABILITIES.read().ok()?;
+    capabilities.get(&ollama_model_key(model))
+        .map(|caps| caps.iter().any(|c| c == capability))
+}
+
+/// 未写标签的模型名等同于 `:latest`
+fn ollama_model_key(model: &str) -> String {
+    let model = model.trim().to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    if name.contains(':') {
+        model
+    } else {
+        format!("{}:latest", model)
+    }
+}
+
+/// 记录模型能力，旧版 Ollama 不返回 capabilities，此时仍按模型名判断
+fn remember_ollama_capabilities(models: &[LocalModel]) {
+    if let Ok(mut capabilities) = OLLAMA_CAPABILITIES.write() {
+        for model in models.iter().filter(|m| !m.capabilities.is_empty()) {
+            capabilities.insert(ollama_model_key(&model.name), model.capabilities.clone());
+        }
+    }
+}
+
+async fn get_json(client: &reqwest::Client, url: &str) -> Result<Value, String> {
+    let response = client.get(url).send().await
+        .map_err(|e| format!("无法连接 {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} 返回 HTTP {}", url, response.status().as_u16()));
+    }
+    response.json().await.map_err(|e| format!("{} 响应无法解析: {}", url, e))
+}
+
+async fn detect_ollama(client: &reqwest::Client, base_url: &str) -> Result<(Option<String>, Vec<LocalModel>), String> {
+    // 兼容填写了 OpenAI 兼容地址（`.../v1`）的配置
+    let root = base_url.trim_end_matches("/v1");
+    let tags = get_json(client, &format!("{}/api/tags", root)).await?;
+    let version = get_json(client, &format!("{}/api/version", root)).await.ok()
+        .and_then(|v| v["version"].as_str().map(str::to_string));
+
+    let entries = tags["models"].as_array().cloned().unwrap_or_default();
+    let models = join_all(entries.iter().map(|entry| async move {
+        let name = entry["name"].as_str().or(entry["model"].as_str()).unwrap_or("").to_string();
+        let details = &entry["details"];
+        let mut model = LocalModel {
+            size_bytes: entry["size"].as_u64(),
+            family: details["family"].as_str().map(str::to_string),
+            parameter_size: details["parameter_size"].as_str().map(str::to_string),
+            quantization: details["quantization_level"].as_str().map(str::to_string),
+            ..Default::default()
+        };
+        if let Ok(show) = show_ollama_model(client, root, &name).await {
+            model.capabilities = show["capabilities"].as_array().into_iter().flatten()
+                .filter_map(|c| c.as_str().map(str::to_string))
+                .collect();
+            model.context_length = ollama_context_length(&show);
+        }
+        model.name = name;
+        model
+    }))
+    .await;
+
+    remember_ollama_capabilities(&models);
+    Ok((version, models))
+}
+
+async fn show_ollama_model(client: &reqwest::Client, root: &str, name: &str) -> Result<Value, String> {
+    let url = format!("{}/api/show", root);
+    let response = client.post(&url).json(&json!({ "model": name })).send().await
+        .map_err(|e| format!("无法连接 {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} 返回 HTTP {}", url, response.status().as_u16()));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+/// `model_info` 中的上下文长度键以模型架构为前缀，如 `qwen3.context_length`
+fn ollama_context_length(show: &Value) -> Option<usize> {
+    show["model_info"].as_object()?
+        .iter()
+        .find(|(key, _)| key.ends_with(".context_length"))
+        .and_then(|(_, value)| value.as_u64())
+        .map(|n| n as usize)
+}
+
+async fn detect_openai_compatible(client: &reqwest::Client, base_url: &str) -> Result<Vec<LocalModel>, String> {
+    let body = get_json(client, &format!("{}/models", base_url)).await?;
+    let models = body["data"].as_array().into_iter().flatten()
+        .filter_map(|entry| {
+            let name = entry["id"].as_str()?.to_string();
+            // llama.cpp server 在 `meta` 中返回 GGUF 元数据
+            let meta = &entry["meta"];
+            Some(LocalModel {
+                name,
+                size_bytes: meta["size"].as_u64(),
+                context_length: meta["n_ctx_train"].as_u64().map(|n| n as usize),
+                ..Default::default()
+            })
+        })
+        .collect();
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn endpoint(kind: LocalServerKind, server: &MockServer) -> LocalEndpoint {
+        LocalEndpoint { kind, base_url: Some(server.uri()) }
+    }
+
+    #[tokio::test]
+    async fn test_detect_ollama() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/api/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "version": "0.9.0" })))
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": [
+                { "name": "detect-tools:8b", "size": 5_000_000_000u64,
+                  "details": { "family": "qwen3", "parameter_size": "8.2B", "quantization_level": "Q4_K_M" } },
+                { "name": "detect-plain:latest", "size": 1_000_000_000u64, "details": {} },
+            ] })))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/api/show")).and(body_partial_json(json!({ "model": "detect-tools:8b" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "capabilities": ["completion", "tools"],
+                "model_info": { "general.architecture": "qwen3", "qwen3.context_length": 40960 },
+            })))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/api/show")).and(body_partial_json(json!({ "model": "detect-plain:latest" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "capabilities": ["completion"] })))
+            .mount(&server).await;
+
+        let servers = detect_local_servers(&[endpoint(LocalServerKind::Ollama, &server)]).await;
+        let ollama = &servers[0];
+        assert!(ollama.available);
+        assert_eq!(ollama.provider, "ollama");
+        assert_eq!(ollama.version.as_deref(), Some("0.9.0"));
+        assert_eq!(ollama.models.len(), 2);
+        assert_eq!(ollama.models[0].quantization.as_deref(), Some("Q4_K_M"));
+        assert_eq!(ollama.models[0].context_length, Some(40960));
+
+        // 检测结果覆盖按模型名的判断，未写标签等同于 `:latest`
+        assert_eq!(ollama_capability("detect-tools:8b", "tools"), Some(true));
+        assert_eq!(ollama_capability("Detect-Plain", "tools"), Some(false));
+        assert_eq!(ollama_capability("never-detected", "tools"), None);
+    }
+
+    #[tokio::test]
+    async fn test_detect_openai_compatible() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "object": "list", "data": [
+                { "id": "qwen2.5-7b-instruct-q4_k_m.gguf", "object": "model",
+                  "meta": { "n_ctx_train": 32768, "size": 4_683_073_952u64 } },
+            ] })))
+            .mount(&server).await;
+
+        let base_url = format!("{}/v1/", server.uri());
+        let servers = detect_local_servers(&[
+            LocalEndpoint { kind: LocalServerKind::Llamacpp, base_url: Some(base_url) },
+            // 未启动的服务
+            LocalEndpoint { kind: LocalServerKind::Lmstudio, base_url: Some("http://127.0.0.1:9".to_string()) },
+        ]).await;
+
+        let llamacpp = &servers[0];
+        assert!(llamacpp.available);
+        assert_eq!(llamacpp.base_url, format!("{}/v1", server.uri()));
+        assert_eq!(llamacpp.models[0].name, "qwen2.5-7b-instruct-q4_k_m.gguf");
+        assert_eq!(llamacpp.models[0].context_length, Some(32768));
+
+        assert!(!servers[1].available);
+        assert!(servers[1].error.is_some());
+    }
+}
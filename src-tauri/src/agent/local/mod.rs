@@ -0,0 +1,15 @@
+//! 本地 LLM 运行时
+//!
+//! 离线环境下使用本地模型：
+//! - 检测本机的 Ollama、llama.cpp server、LM Studio 及其模型
+//! - 由应用启动并管理 llama-server 加载 GGUF 模型
+//!
+//! 检测到的 Ollama 模型能力会被缓存，Ollama 适配据此决定是否使用原生工具调用
+
+pub mod commands;
+pub mod detect;
+pub mod runtime;
+
+pub use commands::*;
+pub use detect::*;
+pub use runtime::*;
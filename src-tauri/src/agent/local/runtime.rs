@@ -0,0 +1,445 @@
+//! 内置 GGUF 运行时
+//!
+//! 由应用启动 llama.cpp 的 `llama-server` 加载 GGUF 模型，轮询 `/health` 直到模型加载完成，
+//! 之后以 `llamacpp` 提供商（OpenAI 兼容接口）访问。同一时间只运行一个实例
+//!
+//! 进程启动后立即登记为当前实例，加载期间不持有锁，可以查询状态或停止
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+/// 当前运行的实例
+static MANAGED_RUNTIME: Lazy<Mutex<Option<ManagedRuntime>>> = Lazy::new(|| Mutex::new(None));
+
+/// 实例编号，用于判断加载期间实例是否被停止或替换
+static NEXT_RUNTIME_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 健康检查轮询间隔
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 保留的 llama-server 日志行数，启动失败时附在错误信息中
+const LOG_TAIL_LINES: usize = 20;
+
+fn default_binary_path() -> String {
+    "llama-server".to_string()
+}
+
+fn default_startup_timeout() -> u64 {
+    120
+}
+
+/// llama-server 启动配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManagedRuntimeConfig {
+    /// llama-server 可执行文件，默认从 PATH 查找
+    #[serde(default = "default_binary_path")]
+    pub binary_path: String,
+    /// GGUF 模型文件
+    pub model_path: String,
+    /// 监听端口，未设置时自动选择空闲端口
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// 上下文长度（`-c`）
+    #[serde(default)]
+    pub context_size: Option<usize>,
+    /// 加载到 GPU 的层数（`-ngl`）
+    #[serde(default)]
+    pub gpu_layers: Option<i32>,
+    /// 推理线程数（`-t`）
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// 其他命令行参数
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// 等待模型加载完成的最长时间（秒）
+    #[serde(default = "default_startup_timeout")]
+    pub startup_timeout_secs: u64,
+}
+
+impl ManagedRuntimeConfig {
+    pub fn new(model_path: impl Into<String>) -> Self {
+        Self {
+            binary_path: default_binary_path(),
+            model_path: model_path.into(),
+            port: None,
+            context_size: None,
+            gpu_layers: None,
+            threads: None,
+            extra_args: Vec::new(),
+            startup_timeout_secs: default_startup_timeout(),
+        }
+    }
+
+    /// llama-server 命令行参数，只监听本机地址
+    ///
+    /// `--jinja` 使用模型自带的对话模板，工具调用依赖它
+    pub fn args(&self, port: u16) -> Vec<String> {
+        let mut args = vec![
+            "-m".to_string(), self.model_path.clone(),
+            "--host".to_string(), "127.0.0.1".to_string(),
+            "--port".to_string(), port.to_string(),
+            "--jinja".to_string(),
+        ];
+        if let Some(n) = self.context_size {
+            args.extend(["-c".to_string(), n.to_string()]);
+        }
+        if let Some(n) = self.gpu_layers {
+            args.extend(["-ngl".to_string(), n.to_string()]);
+        }
+        if let Some(n) = self.threads {
+            args.extend(["-t".to_string(), n.to_string()]);
+        }
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+}
+
+/// 运行时健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeHealth {
+    /// 模型加载中（`/health` 返回 503）
+    Loading,
+    Ready,
+    /// 进程在运行但无法连接
+    Unreachable,
+    /// 进程已退出
+    Exited,
+}
+
+/// 运行时状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManagedRuntimeStatus {
+    /// 对应的 `AgentConfig::provider`
+    pub provider: String,
+    /// 对应的 `AgentConfig::base_url`
+    pub base_url: String,
+    /// llama-server 会忽略请求中的模型名，这里取模型文件名
+    pub model: String,
+    pub model_path: String,
+    pub pid: Option<u32>,
+    pub health: RuntimeHealth,
+}
+
+/// 由应用管理的 llama-server 进程
+pub struct ManagedRuntime {
+    id: u64,
+    config: ManagedRuntimeConfig,
+    child: Child,
+    base_url: String,
+    client: reqwest::Client,
+    /// 模型是否已加载完成
+    ready: bool,
+    /// stderr 的最后几行
+    log_tail: Arc<std::sync::Mutex<VecDeque<String>>>,
+}
+
+impl ManagedRuntime {
+    /// 启动进程，不等待模型加载
+    pub fn spawn(config: ManagedRuntimeConfig) -> Result<Self, String> {
+        let model_path = Path::new(&config.model_path);
+        if !model_path.is_file() {
+            return Err(format!("模型文件不存在: {}", config.model_path));
+        }
+        let is_gguf = model_path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gguf"));
+        if !is_gguf {
+            return Err(format!("不是 GGUF 模型文件: {}", config.model_path));
+        }
+
+        let port = match config.port {
+            Some(port) => port,
+            None => free_port()?,
+        };
+        let mut cmd = Command::new(&config.binary_path);
+        cmd.args(config.args(port))
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        // Windows: 隐藏控制台窗口
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt as _;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut child = cmd.spawn()
+            .map_err(|e| format!("无法启动 llama-server '{}': {}", config.binary_path, e))?;
+
+        // llama-server 的日志输出到 stderr，其中包含每个请求的日志，只保留最后几行用于报错
+        let log_tail = Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(LOG_TAIL_LINES)));
+        if let Some(stderr) = child.stderr.take() {
+            let log_tail = log_tail.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Ok(mut tail) = log_tail.lock() {
+                        if tail.len() == LOG_TAIL_LINES {
+                            tail.pop_front();
+                        }
+                        tail.push_back(line);
+                    }
+                }
+            });
+        }
+
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            id: NEXT_RUNTIME_ID.fetch_add(1, Ordering::Relaxed),
+            config,
+            child,
+            base_url: format!("http://127.0.0.1:{}/v1", port),
+            client,
+            ready: false,
+            log_tail,
+        })
+    }
+
+    /// 进程是否已退出
+    fn exited(&mut self) -> bool {
+        !matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// 先检查进程是否退出，再查询 `/health`；加载完成前无法连接也算作加载中
+    pub async fn health(&mut self) -> RuntimeHealth {
+        if self.exited() {
+            return RuntimeHealth::Exited;
+        }
+        match check_health(&self.client, &self.base_url).await {
+            RuntimeHealth::Unreachable if !self.ready => RuntimeHealth::Loading,
+            health => health,
+        }
+    }
+
+    /// 启动失败时的错误信息，附上 llama-server 最后几行日志
+    fn startup_error(&self, message: String) -> String {
+        let tail = self.log_tail.lock()
+            .map(|tail| tail.iter().cloned().collect::<Vec<_>>().join("\n"))
+            .unwrap_or_default();
+        if tail.is_empty() {
+            message
+        } else {
+            format!("{}\n{}", message, tail)
+        }
+    }
+
+    pub async fn status(&mut self) -> ManagedRuntimeStatus {
+        let health = self.health().await;
+        self.status_with(health)
+    }
+
+    fn status_with(&self, health: RuntimeHealth) -> ManagedRuntimeStatus {
+        let model = Path::new(&self.config.model_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        ManagedRuntimeStatus {
+            provider: "llamacpp".to_string(),
+            base_url: self.base_url.clone(),
+            model,
+            model_path: self.config.model_path.clone(),
+            pid: self.child.id(),
+            health,
+        }
+    }
+
+    pub async fn stop(mut self) -> Result<(), String> {
+        self.child.kill().await
+            .map_err(|e| format!("无法结束 llama-server: {}", e))
+    }
+}
+
+/// 查询 llama.cpp server 的 `/health`：200 为就绪，503 为模型加载中
+pub async fn check_health(client: &reqwest::Client, base_url: &str) -> RuntimeHealth {
+    let root = base_url.trim_end_matches('/').trim_end_matches("/v1");
+    let response = client.get(format!("{}/health", root))
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await;
+    match response {
+        Ok(r) if r.status().is_success() => RuntimeHealth::Ready,
+        Ok(r) if r.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE => RuntimeHealth::Loading,
+        _ => RuntimeHealth::Unreachable,
+    }
+}
+
+/// 由系统分配一个本机空闲端口
+fn free_port() -> Result<u16, String> {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("无法分配端口: {}", e))?;
+    listener.local_addr()
+        .map(|addr| addr.port())
+        .map_err(|e| e.to_string())
+}
+
+/// 启动内置运行时并等待模型加载完成，已有实例时先停止
+///
+/// 超时或进程退出时结束进程并返回错误；加载期间被停止或替换时也返回错误
+pub async fn start_managed_runtime(config: ManagedRuntimeConfig) -> Result<ManagedRuntimeStatus, String> {
+    let timeout = Duration::from_secs(config.startup_timeout_secs);
+    let (id, client, base_url) = {
+        let mut current = MANAGED_RUNTIME.lock().await;
+        if let Some(runtime) = current.take() {
+            runtime.stop().await?;
+        }
+        let runtime = ManagedRuntime::spawn(config)?;
+        let handle = (runtime.id, runtime.client.clone(), runtime.base_url.clone());
+        *current = Some(runtime);
+        handle
+    };
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        // 健康检查在锁外进行，只在检查结果时短暂加锁
+        let health = check_health(&client, &base_url).await;
+        let mut current = MANAGED_RUNTIME.lock().await;
+        let Some(runtime) = current.as_mut().filter(|runtime| runtime.id == id) else {
+            return Err("llama-server 在模型加载完成前已停止".to_string());
+        };
+        if runtime.exited() {
+            let status = runtime.child.try_wait().ok().flatten()
+                .map(|s| s.to_string())
+                .unwrap_or_default();
+            let error = runtime.startup_error(format!("llama-server 已退出 {}", status));
+            *current = None;
+            return Err(error);
+        }
+        if health == RuntimeHealth::Ready {
+            runtime.ready = true;
+            return Ok(runtime.status_with(health));
+        }
+        if Instant::now() >= deadline {
+            let error = runtime.startup_error(format!("llama-server 在 {} 秒内未就绪", timeout.as_secs()));
+            if let Some(runtime) = current.take() {
+                let _ = runtime.stop().await;
+            }
+            return Err(error);
+        }
+        drop(current);
+        tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+    }
+}
+
+/// 停止内置运行时
+pub async fn stop_managed_runtime() -> Result<(), String> {
+    match MANAGED_RUNTIME.lock().await.take() {
+        Some(runtime) => runtime.stop().await,
+        None => Ok(()),
+    }
+}
+
+/// 内置运行时状态，未启动时为 `None`
+pub async fn managed_runtime_status() -> Option<ManagedRuntimeStatus> {
+    let mut current = MANAGED_RUNTIME.lock().await;
+    match current.as_mut() {
+        Some(runtime) => Some(runtime.status().await),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_args() {
+        let mut config = ManagedRuntimeConfig::new("/models/qwen.gguf");
+        config.context_size = Some(8192);
+        config.gpu_layers = Some(99);
+        config.extra_args = vec!["--flash-attn".to_string()];
+        assert_eq!(
+            config.args(8080).join(" "),
+            "-m /models/qwen.gguf --host 127.0.0.1 --port 8080 --jinja -c 8192 -ngl 99 --flash-attn",
+        );
+
+        let config: ManagedRuntimeConfig = serde_json::from_str(r#"{ "model_path": "a.gguf" }"#).unwrap();
+        assert_eq!(config, ManagedRuntimeConfig::new("a.gguf"));
+    }
+
+    #[tokio::test]
+    async fn test_check_health() {
+        let client = reqwest::Client::new();
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/health"))
+            .respond_with(ResponseTemplate::new(503).set_body_string(r#"{"error":{"message":"Loading model"}}"#))
+            .up_to_n_times(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"status":"ok"}"#))
+            .mount(&server).await;
+
+        let base_url = format!("{}/v1", server.uri());
+        assert_eq!(check_health(&client, &base_url).await, RuntimeHealth::Loading);
+        assert_eq!(check_health(&client, &base_url).await, RuntimeHealth::Ready);
+        assert_eq!(check_health(&client, "http://127.0.0.1:9").await, RuntimeHealth::Unreachable);
+    }
+
+    #[tokio::test]
+    async fn test_start_rejects_invalid_model() {
+        let missing = ManagedRuntime::spawn(ManagedRuntimeConfig::new("/nonexistent/model.gguf"));
+        assert!(missing.err().unwrap().contains("模型文件不存在"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let not_gguf = dir.path().join("model.bin");
+        std::fs::write(&not_gguf, b"").unwrap();
+        let result = ManagedRuntime::spawn(ManagedRuntimeConfig::new(not_gguf.to_string_lossy()));
+        assert!(result.err().unwrap().contains("GGUF"));
+    }
+
+    /// 用脚本代替 llama-server：输出一行日志后按 `body` 执行
+    #[cfg(unix)]
+    fn fake_server(dir: &Path, body: &str) -> ManagedRuntimeConfig {
+        use std::os::unix::fs::PermissionsExt;
+
+        let binary = dir.join("llama-server");
+        std::fs::write(&binary, format!("#!/bin/sh\necho 'load_model: failed to load' >&2\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let model = dir.join("model.gguf");
+        std::fs::write(&model, b"").unwrap();
+        let mut config = ManagedRuntimeConfig::new(model.to_string_lossy());
+        config.binary_path = binary.to_string_lossy().into_owned();
+        config.startup_timeout_secs = 30;
+        config
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_managed_runtime_not_locked_while_loading() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // 启动失败时报告 llama-server 的日志
+        let error = start_managed_runtime(fake_server(dir.path(), "sleep 0.2; exit 1")).await.unwrap_err();
+        assert!(error.contains("load_model: failed to load"), "{}", error);
+        assert!(managed_runtime_status().await.is_none());
+
+        // 加载期间可以查询状态并停止
+        let start = tokio::spawn(start_managed_runtime(fake_server(dir.path(), "exec sleep 30")));
+        let status = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Some(status) = managed_runtime_status().await {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }).await.unwrap();
+        assert_eq!(status.health, RuntimeHealth::Loading);
+        tokio::time::timeout(Duration::from_secs(5), stop_managed_runtime()).await.unwrap().unwrap();
+        assert!(start.await.unwrap().unwrap_err().contains("已停止"));
+    }
+}
@@ -7,9 +7,25 @@
 //! 4. History: 历史对话
 //! 5. Current: 当前任务 + 工具结果
 //! 6. Reminder: 动态格式提醒
+//!
+//! System、Note Map、Current Note 在多次请求间保持不变，末尾设置提示缓存断点；
+//! 超出上下文预算时由 [`ChatChunks::fit_to_window`] 压缩观察记录与历史对话
 
+use super::context::{estimate_message_tokens, estimate_messages_tokens, truncate_output, COMPACT_RESULT_CHARS};
 use crate::agent::types::{Message, MessageRole};
 
+/// 压缩时保留完整内容的最近工具结果数
+const KEEP_RECENT_RESULTS: usize = 3;
+
+/// 历史摘要最多保留的条目数
+const MAX_SUMMARY_LINES: usize = 20;
+
+/// 历史摘要中每条消息保留的字符数
+const SUMMARY_LINE_CHARS: usize = 60;
+
+/// 截断后当前笔记至少保留的行数
+const MIN_NOTE_LINES: usize = 50;
+
 /// 分层消息结构
 #[derive(Debug, Clone, Default)]
 pub struct ChatChunks {
@@ -28,6 +44,9 @@ pub struct ChatChunks {
     /// 历史对话消息
     pub history: Vec<Message>,
     
+    /// 被移出上下文的较早历史的摘要
+    pub history_summary: Vec<String>,
+    
     /// 当前用户任务
     pub current_task: String,
     
@@ -83,35 +102,73 @@ impl ChatChunks {
         self
     }
     
+    /// 压缩内容使估算 token 不超过 `budget`，返回是否做了压缩
+    ///
+    /// 依次：截断较早的工具结果 → 将最早的历史对话替换为摘要 → 截断当前笔记
+    pub fn fit_to_window(&mut self, budget: usize) -> bool {
+        let mut total = estimate_messages_tokens(&self.to_messages());
+        if total <= budget {
+            return false;
+        }
+
+        // 1. 较早的工具结果只保留开头
+        let recent = self.tool_results.len().saturating_sub(KEEP_RECENT_RESULTS);
+        for result in self.tool_results.iter_mut().take(recent) {
+            if total <= budget {
+                return true;
+            }
+            let before = estimate_message_tokens(&Message::user(result.as_str()));
+            *result = truncate_output(result, COMPACT_RESULT_CHARS);
+            total = total + estimate_message_tokens(&Message::user(result.as_str())) - before;
+        }
+
+        // 2. 从最早的历史开始移出，工具调用与其结果一起移出
+        while total > budget && !self.history.is_empty() {
+            let mut end = 1;
+            while end < self.history.len() && self.history[end].role == MessageRole::Tool {
+                end += 1;
+            }
+            for message in self.history.drain(..end) {
+                if let Some(line) = summary_line(&message) {
+                    self.history_summary.push(line);
+                }
+            }
+            if self.history_summary.len() > MAX_SUMMARY_LINES {
+                let excess = self.history_summary.len() - MAX_SUMMARY_LINES;
+                self.history_summary.drain(..excess);
+            }
+            total = estimate_messages_tokens(&self.to_messages());
+        }
+
+        // 3. 仍然超出时截断当前笔记，其余内容由 read_note 分段读取
+        if let Some(content) = self.current_note_content.clone() {
+            let mut lines: Vec<&str> = content.lines().collect();
+            while total > budget && lines.len() > MIN_NOTE_LINES {
+                lines.truncate((lines.len() / 2).max(MIN_NOTE_LINES));
+                self.current_note_content = Some(format!(
+                    "{}\n…（笔记过长，后续内容请使用 read_note 分段读取）",
+                    lines.join("\n")
+                ));
+                total = estimate_messages_tokens(&self.to_messages());
+            }
+        }
+        true
+    }
+    
     /// 转换为消息列表
     pub fn to_messages(&self) -> Vec<Message> {
         let mut messages = Vec::new();
         
         // 1. System 消息
-        messages.push(Message {
-            role: MessageRole::System,
-            content: self.system.clone(),
-            name: None,
-            tool_call_id: None,
-        });
+        messages.push(Message::system(self.system.clone()));
         
         // 2. Note Map（对话注入）
         if let Some(ref note_map) = self.note_map {
-            messages.push(Message {
-                role: MessageRole::User,
-                content: format!(
-                    "以下是笔记库的结构摘要，请先了解。如需查看具体内容，请使用工具。\n\n{}",
-                    note_map
-                ),
-                name: None,
-                tool_call_id: None,
-            });
-            messages.push(Message {
-                role: MessageRole::Assistant,
-                content: "好的，我已了解笔记库结构。需要查看或编辑具体内容时，我会使用相应工具。".to_string(),
-                name: None,
-                tool_call_id: None,
-            });
+            messages.push(Message::user(format!(
+                "以下是笔记库的结构摘要，请先了解。如需查看具体内容，请使用工具。\n\n{}",
+                note_map
+            )));
+            messages.push(Message::assistant("好的，我已了解笔记库结构。需要查看或编辑具体内容时，我会使用相应工具。"));
         }
         
         // 3. 当前笔记（对话注入）
@@ -123,64 +180,67 @@ impl ChatChunks {
                 .collect::<Vec<_>>()
                 .join("\n");
             
-            messages.push(Message {
-                role: MessageRole::User,
-                content: format!(
-                    "当前正在编辑的笔记（你可以直接使用 edit_note 编辑）：\n\n\
-                     文件：{}\n\
-                     ---\n\
-                     {}\n\
-                     ---",
-                    path, numbered
-                ),
-                name: None,
-                tool_call_id: None,
-            });
-            messages.push(Message {
-                role: MessageRole::Assistant,
-                content: "好的，我看到了当前笔记的完整内容。".to_string(),
-                name: None,
-                tool_call_id: None,
-            });
+            messages.push(Message::user(format!(
+                "当前正在编辑的笔记（你可以直接使用 edit_note 编辑）：\n\n\
+                 文件：{}\n\
+                 ---\n\
+                 {}\n\
+                 ---",
+                path, numbered
+            )));
+            messages.push(Message::assistant("好的，我看到了当前笔记的完整内容。"));
         }
         
-        // 4. 历史对话
+        // 稳定前缀到此为止
+        if let Some(last) = messages.last_mut() {
+            last.cache_breakpoint = true;
+        }
+        
+        // 4. 历史对话（较早的部分以摘要代替）
+        if !self.history_summary.is_empty() {
+            messages.push(Message::user(format!(
+                "[较早的对话已省略，以下是摘要]\n{}",
+                self.history_summary.join("\n")
+            )));
+            messages.push(Message::assistant("好的，我已了解之前的对话。"));
+        }
         for msg in &self.history {
             messages.push(msg.clone());
         }
         
         // 5. 当前任务
-        messages.push(Message {
-            role: MessageRole::User,
-            content: self.current_task.clone(),
-            name: None,
-            tool_call_id: None,
-        });
+        messages.push(Message::user(self.current_task.clone()));
         
         // 6. 工具结果（用 user 角色，兼容所有模型）
         for result in &self.tool_results {
-            messages.push(Message {
-                role: MessageRole::User,
-                content: format!("[工具执行结果]\n{}", result),
-                name: None,
-                tool_call_id: None,
-            });
+            messages.push(Message::user(format!("[工具执行结果]\n{}", result)));
         }
         
         // 7. 动态格式提醒
         if let Some(ref reminder) = self.reminder {
-            messages.push(Message {
-                role: MessageRole::User,
-                content: format!("[系统提醒] {}", reminder),
-                name: None,
-                tool_call_id: None,
-            });
+            messages.push(Message::user(format!("[系统提醒] {}", reminder)));
         }
         
         messages
     }
 }
 
+/// 历史消息的一行摘要，工具结果不计入
+fn summary_line(message: &Message) -> Option<String> {
+    let role = match message.role {
+        MessageRole::User => "用户",
+        MessageRole::Assistant => "助手",
+        MessageRole::System | MessageRole::Tool => return None,
+    };
+    let text = message.text();
+    let first_line = text.lines().find(|line| !line.trim().is_empty())?.trim();
+    let mut line: String = first_line.chars().take(SUMMARY_LINE_CHARS).collect();
+    if first_line.chars().count() > SUMMARY_LINE_CHARS {
+        line.push('…');
+    }
+    Some(format!("- {}：{}", role, line))
+}
+
 /// 基础格式提醒（放在 system prompt 末尾）
 pub const FORMAT_REMINDER: &str = r#"
 重要提醒：
@@ -225,4 +285,50 @@ mod tests {
         let messages = chunks.to_messages();
         assert_eq!(messages.len(), 4); // system + note_map(2) + task
     }
+
+    #[test]
+    fn test_cache_breakpoint_after_stable_prefix() {
+        let messages = ChatChunks::new("你是一个 AI 助手".to_string())
+            .with_current_note("a.md".to_string(), "# A".to_string())
+            .with_history(vec![Message::user("之前的问题"), Message::assistant("之前的回答")])
+            .with_task("继续".to_string())
+            .to_messages();
+        let breakpoints: Vec<usize> = messages.iter().enumerate()
+            .filter(|(_, m)| m.cache_breakpoint)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(breakpoints, vec![2]); // system + 当前笔记(2)
+    }
+
+    #[test]
+    fn test_fit_to_window() {
+        let history: Vec<Message> = (0..10)
+            .flat_map(|i| [
+                Message::user(format!("问题 {} {}", i, "细节".repeat(200))),
+                Message::assistant(format!("回答 {}", i)),
+            ])
+            .collect();
+        let mut chunks = ChatChunks::new("你是一个 AI 助手".to_string())
+            .with_history(history)
+            .with_task("总结".to_string());
+        for i in 0..5 {
+            chunks.add_tool_result(format!("结果 {} {}", i, "数据".repeat(500)));
+        }
+
+        let budget = 5_000;
+        assert!(chunks.fit_to_window(budget));
+        assert!(estimate_messages_tokens(&chunks.to_messages()) <= budget);
+        // 较早的结果被截断，最近的保持完整
+        assert!(chunks.tool_results[0].contains("已省略"));
+        assert!(!chunks.tool_results[4].contains("已省略"));
+        // 最早的历史换成摘要
+        let first: String = format!("问题 0 {}", "细节".repeat(200)).chars().take(60).collect();
+        assert_eq!(chunks.history_summary[0], format!("- 用户：{}…", first));
+        assert_eq!(chunks.history_summary[1], "- 助手：回答 0");
+        assert_eq!(chunks.history.last().unwrap().content, "回答 9");
+        let messages = chunks.to_messages();
+        assert!(messages[1].content.starts_with("[较早的对话已省略"));
+
+        assert!(!chunks.fit_to_window(budget));
+    }
 }
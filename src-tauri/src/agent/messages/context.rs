@@ -0,0 +1,181 @@
+//! 上下文窗口管理
+//!
+//! - 按模型名推断上下文窗口
+//! - 估算消息 token
+//! - 接近上限时压缩较早的工具结果
+
+use serde_json::Value;
+
+use crate::agent::note_map::estimate_tokens;
+use crate::agent::types::{ContentPart, Message, MessageRole};
+
+/// 未知模型的默认上下文窗口
+pub const DEFAULT_CONTEXT_WINDOW: usize = 32_000;
+
+/// 每条消息的角色、分隔符等格式开销
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// 单张图片的估算 token（主流视觉模型约 1000px 边长的图片）
+const IMAGE_TOKENS: usize = 1_000;
+
+/// 压缩后保留的工具结果长度（字符）
+pub const COMPACT_RESULT_CHARS: usize = 300;
+
+/// 以用户消息形式回传的工具结果前缀（XML 模式与 ChatChunks 观察记录）
+const TOOL_RESULT_PREFIXES: &[&str] = &["[工具执行结果]", "工具 "];
+
+/// 按模型名推断上下文窗口（tokens）
+pub fn context_window(model: &str) -> usize {
+    let model = model.to_lowercase();
+    // OpenRouter 等聚合服务的模型名带有厂商前缀，如 `anthropic/claude-sonnet-4`
+    let name = model.rsplit('/').next().unwrap_or(&model);
+
+    // 模型名中标注了窗口大小，如 moonshot-v1-32k
+    if let Some(k) = name.split(['-', ':', '_'])
+        .find_map(|segment| segment.strip_suffix('k')?.parse::<usize>().ok())
+    {
+        return k * 1024;
+    }
+
+    const WINDOWS: &[(&str, usize)] = &[
+        ("gpt-4.1", 1_047_576),
+        ("gpt-5", 400_000),
+        ("gpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-4", 8_192),
+        ("gpt-3.5", 16_385),
+        ("o1", 200_000),
+        ("o3", 200_000),
+        ("o4", 200_000),
+        ("claude", 200_000),
+        ("gemini", 1_048_576),
+        ("deepseek", 128_000),
+        ("kimi", 128_000),
+        ("qwen", 128_000),
+        ("glm", 128_000),
+        ("llama3", 128_000),
+        ("mistral", 32_000),
+    ];
+    WINDOWS.iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, window)| *window)
+        .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+}
+
+/// 估算单条消息的 token
+pub fn estimate_message_tokens(message: &Message) -> usize {
+    let images = message.parts.iter()
+        .filter(|part| matches!(part, ContentPart::Image { .. }))
+        .count();
+    let tool_calls: usize = message.tool_calls.iter()
+        .map(|call| estimate_tokens(&call.name) + estimate_tokens(&serde_json::to_string(&call.params).unwrap_or_default()))
+        .sum();
+    MESSAGE_OVERHEAD + estimate_tokens(&message.text()) + images * IMAGE_TOKENS + tool_calls
+}
+
+/// 估算消息列表的 token
+pub fn estimate_messages_tokens(messages: &[Message]) -> usize {
+    messages.iter().map(estimate_message_tokens).sum()
+}
+
+/// 估算工具定义的 token
+pub fn estimate_tools_tokens(tools: &[Value]) -> usize {
+    estimate_tokens(&serde_json::to_string(tools).unwrap_or_default())
+}
+
+/// 是否为工具结果消息（Tool 角色或以用户消息回传的结果）
+pub fn is_tool_output(message: &Message) -> bool {
+    match message.role {
+        MessageRole::Tool => true,
+        MessageRole::User => TOOL_RESULT_PREFIXES.iter().any(|prefix| message.content.starts_with(prefix)),
+        _ => false,
+    }
+}
+
+/// 截断标记，已截断的文本不再重复截断
+const TRUNCATED_MARKER: &str = "…（已省略";
+
+/// 截断文本，保留前 `max_chars` 个字符并注明省略的长度
+pub fn truncate_output(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars || text.contains(TRUNCATED_MARKER) {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars).collect();
+    format!("{}\n{} {} 字符，如需完整内容请重新调用工具）", kept, TRUNCATED_MARKER, total - max_chars)
+}
+
+/// 从最早的工具结果开始压缩，直到估算 token 不超过 `budget`，返回压缩的消息数
+///
+/// 最后一个缓存断点之前的稳定前缀与最近一轮（最后一条助手消息之后）的结果保持不变
+pub fn compact_tool_results(messages: &mut [Message], budget: usize) -> usize {
+    let start = messages.iter().rposition(|m| m.cache_breakpoint).map(|i| i + 1).unwrap_or(0);
+    let end = messages.iter().rposition(|m| m.role == MessageRole::Assistant).unwrap_or(messages.len());
+    let mut total = estimate_messages_tokens(messages);
+    let mut compacted = 0;
+
+    for message in messages.iter_mut().take(end).skip(start) {
+        if total <= budget {
+            break;
+        }
+        if !is_tool_output(message) {
+            continue;
+        }
+        let before = estimate_message_tokens(message);
+        let text = message.text();
+        let truncated = truncate_output(&text, COMPACT_RESULT_CHARS);
+        if truncated == text && !message.has_images() {
+            continue;
+        }
+        message.content = truncated;
+        message.parts.clear();
+        total = total + estimate_message_tokens(message) - before;
+        compacted += 1;
+    }
+    compacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::ToolCall;
+
+    #[test]
+    fn test_context_window() {
+        assert_eq!(context_window("claude-sonnet-4-5"), 200_000);
+        assert_eq!(context_window("anthropic/claude-sonnet-4"), 200_000);
+        assert_eq!(context_window("gpt-4o-mini"), 128_000);
+        assert_eq!(context_window("gpt-4"), 8_192);
+        assert_eq!(context_window("moonshot-v1-32k"), 32_768);
+        assert_eq!(context_window("gemini-2.5-flash"), 1_048_576);
+        assert_eq!(context_window("my-local-model"), DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_compact_tool_results() {
+        let call = ToolCall { id: "call_0".into(), name: "read_note".into(), params: Default::default() };
+        let long = "内容".repeat(2_000);
+        let xml_result = format!("工具 read_note 执行结果：\n{}", long);
+        let mut messages = vec![
+            Message::system("系统提示").with_cache_breakpoint(),
+            Message::user("任务"),
+            Message::assistant_with_tool_calls("", vec![call.clone()]),
+            Message::tool_result(&call, long.clone()),
+            Message::user(xml_result.clone()),
+            Message::assistant_with_tool_calls("", vec![call.clone()]),
+            Message::tool_result(&call, long.clone()),
+        ];
+        let budget = estimate_messages_tokens(&messages) - 1_000;
+
+        assert_eq!(compact_tool_results(&mut messages, budget), 1);
+        assert!(messages[3].content.contains("已省略 3700 字符"));
+        // 未超出预算后不再压缩，最近一轮结果保持完整
+        assert_eq!(messages[4].content, xml_result);
+        assert_eq!(messages[6].content, long);
+        assert!(estimate_messages_tokens(&messages) <= budget);
+
+        assert_eq!(compact_tool_results(&mut messages, 0), 1);
+        assert_eq!(messages[6].content, long);
+        assert_eq!(messages[1].content, "任务");
+    }
+}
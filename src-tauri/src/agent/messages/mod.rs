@@ -3,5 +3,7 @@
 //! 将 LLM 消息分成多个逻辑块，便于管理和组装
 
 mod chunks;
+mod context;
 
 pub use chunks::*;
+pub use context::*;
@@ -5,6 +5,13 @@
 
 pub mod types;
 pub mod llm_client;
+pub mod llm_error;
+pub mod providers;
+pub mod model_router;
+pub mod events;
+pub mod budget;
+pub mod local;
+pub mod transport;
 pub mod tools;
 pub mod graph;
 pub mod commands;
@@ -13,6 +20,7 @@ pub mod note_map;
 pub mod messages;
 pub mod debug_log;
 pub mod workspace_layout;
+pub mod eval;
 
 pub use commands::*;
 pub use commands::ApprovalManager;
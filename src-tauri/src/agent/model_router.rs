@@ -0,0 +1,191 @@
+//! 模型路由
+//!
+//! 按图节点选择模型（如廉价模型做 coordinator、强模型做 writer），
+//! 每个节点的客户端都带上 [`ModelRouting::fallbacks`] 回退链，并共享同一份任务预算
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::agent::budget::UsageBudget;
+use crate::agent::llm_client::LlmClient;
+use crate::agent::transport::HttpTransport;
+use crate::agent::types::{AgentConfig, ModelRoute, ModelRouting};
+
+/// 节点 → LLM 客户端
+pub struct ModelRouter {
+    default: Arc<LlmClient>,
+    nodes: HashMap<String, Arc<LlmClient>>,
+    budget: Arc<UsageBudget>,
+}
+
+impl ModelRouter {
+    /// 根据 `config.routing` 创建，未配置路由的节点使用 `config` 本身的模型
+    pub fn new(config: &AgentConfig) -> Self {
+        Self::with_routing(config, &config.routing)
+    }
+
+    /// 使用单独的路由配置（如 Deep Research 的配置）
+    pub fn with_routing(config: &AgentConfig, routing: &ModelRouting) -> Self {
+        Self::build(config, routing, None)
+    }
+
+    /// 所有客户端经由指定的传输发送请求（如测试中回放固件）
+    pub fn with_transport(config: &AgentConfig, routing: &ModelRouting, transport: Arc<dyn HttpTransport>) -> Self {
+        Self::build(config, routing, Some(transport))
+    }
+
+    fn build(config: &AgentConfig, routing: &ModelRouting, transport: Option<Arc<dyn HttpTransport>>) -> Self {
+        let budget = Arc::new(UsageBudget::new(config.budget.clone()));
+        let fallbacks: Vec<AgentConfig> = routing.fallbacks.iter()
+            .filter_map(|route| apply_route(route, config))
+            .collect();
+        let client = |primary: AgentConfig| {
+            // 与主模型相同的回退项没有意义
+            let chain = fallbacks.iter()
+                .filter(|f| f.provider != primary.provider || f.model != primary.model)
+                .map(|f| LlmClient::new(f.clone()))
+                .collect();
+            let client = LlmClient::new(primary).with_fallbacks(chain).with_budget(budget.clone());
+            Arc::new(match &transport {
+                Some(transport) => client.with_transport(transport.clone()),
+                None => client,
+            })
+        };
+
+        let nodes = routing.nodes.iter()
+            .filter_map(|(node, route)| Some((node.clone(), client(apply_route(route, config)?))))
+            .collect();
+        Self {
+            default: client(config.clone()),
+            nodes,
+            budget,
+        }
+    }
+
+    /// 节点使用的客户端
+    pub fn for_node(&self, node: &str) -> &Arc<LlmClient> {
+        self.nodes.get(node).unwrap_or(&self.default)
+    }
+
+    /// 默认客户端
+    pub fn default_client(&self) -> &Arc<LlmClient> {
+        &self.default
+    }
+
+    /// 任务的用量预算
+    pub fn budget(&self) -> &Arc<UsageBudget> {
+        &self.budget
+    }
+}
+
+/// 生成路由的配置，缺少 API Key 的路由被跳过
+fn apply_route(route: &ModelRoute, config: &AgentConfig) -> Option<AgentConfig> {
+    let applied = route.apply(config);
+    if applied.is_none() {
+        eprintln!(
+            "[ModelRouter] 跳过路由 {}/{}：切换提供商时未配置 API Key",
+            route.provider.as_deref().unwrap_or_default(),
+            route.model,
+        );
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::llm_error::LlmError;
+    use crate::agent::types::Message;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn route(model: &str) -> ModelRoute {
+        ModelRoute { model: model.to_string(), ..Default::default() }
+    }
+
+    fn config(base_url: &str) -> AgentConfig {
+        AgentConfig {
+            model: "gpt-4o".to_string(),
+            api_key: "sk-test".to_string(),
+            base_url: Some(base_url.to_string()),
+            routing: ModelRouting {
+                nodes: HashMap::from([("coordinator".to_string(), route("gpt-4o-mini"))]),
+                fallbacks: vec![route("gpt-4o"), route("deepseek-chat")],
+            },
+            ..AgentConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_node_routes() {
+        let router = ModelRouter::new(&config("http://localhost"));
+        assert_eq!(router.for_node("coordinator").model(), "gpt-4o-mini");
+        assert_eq!(router.for_node("coordinator").fallback_models(), vec!["gpt-4o", "deepseek-chat"]);
+        assert_eq!(router.for_node("writer").model(), "gpt-4o");
+        // 与主模型相同的回退项被跳过
+        assert_eq!(router.for_node("writer").fallback_models(), vec!["deepseek-chat"]);
+
+        let switched = ModelRoute {
+            provider: Some("anthropic".into()),
+            api_key: Some("sk-ant".into()),
+            ..route("claude-sonnet-4-5")
+        };
+        let switched = switched.apply(&config("http://localhost")).unwrap();
+        assert_eq!(switched.provider, "anthropic");
+        assert_eq!(switched.base_url, None);
+        assert_ne!(switched.api_key, "sk-test");
+    }
+
+    #[test]
+    fn test_switched_route_without_key_is_skipped() {
+        let keyless = ModelRoute { provider: Some("anthropic".into()), ..route("claude-sonnet-4-5") };
+        assert!(keyless.apply(&config("http://localhost")).is_none());
+        // 本地模型不需要 Key，也不沿用原提供商的 Key
+        let local = ModelRoute { provider: Some("ollama".into()), ..route("llama3") };
+        assert_eq!(local.apply(&config("http://localhost")).unwrap().api_key, "");
+
+        let mut config = config("http://localhost");
+        config.routing.nodes.insert("writer".to_string(), keyless.clone());
+        config.routing.fallbacks.push(keyless);
+        let router = ModelRouter::new(&config);
+        assert_eq!(router.for_node("writer").model(), "gpt-4o");
+        assert_eq!(router.for_node("writer").fallback_models(), vec!["deepseek-chat"]);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_on_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "gpt-4o" })))
+            .respond_with(ResponseTemplate::new(429).set_body_string("rate limited"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "model": "deepseek-chat" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "role": "assistant", "content": "来自回退模型" } }],
+            })))
+            .mount(&server)
+            .await;
+
+        let router = ModelRouter::new(&config(&server.uri()));
+        let response = router.for_node("writer").call(&[Message::user("hi")], None).await.unwrap();
+        assert_eq!(response.content, "来自回退模型");
+    }
+
+    #[tokio::test]
+    async fn test_no_fallback_on_client_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("invalid api key"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let router = ModelRouter::new(&config(&server.uri()));
+        let error = router.for_node("writer").call(&[Message::user("hi")], None).await.unwrap_err();
+        assert!(matches!(error, LlmError::Http { status: 401, .. }));
+    }
+}
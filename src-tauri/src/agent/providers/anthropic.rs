@@ -0,0 +1,579 @@
+//! Anthropic Messages API 适配
+//!
+//! - system 提示放在顶层 `system` 字段，不进入 `messages`
+//! - 工具定义使用 `{name, description, input_schema}`
+//! - 工具调用为 `tool_use` 内容块，工具结果以 `tool_result` 块放在 user 消息中
+//! - 流式响应按 `message_start` / `content_block_*` / `message_delta` / `message_stop` 事件解析
+//! - 消息上的缓存断点转换为 `cache_control`（最多 4 个）
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{
+    object_params, token_count, ChatParams, LlmProvider, ProviderCapabilities, StreamDecoder,
+    StreamStep,
+};
+use crate::agent::llm_client::LlmResponse;
+use crate::agent::types::{ContentPart, Message, MessageRole, ToolCall};
+
+/// Anthropic 的 temperature 取值范围为 0-1
+const MAX_TEMPERATURE: f32 = 1.0;
+
+/// 单次请求允许的 `cache_control` 断点数
+const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+/// Anthropic Messages API
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Anthropic;
+
+impl LlmProvider for Anthropic {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "https://api.anthropic.com/v1"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            function_calling: true,
+            requires_api_key: true,
+        }
+    }
+
+    fn endpoint(&self, base_url: &str, _model: &str, _stream: bool) -> String {
+        format!("{}/messages", base_url)
+    }
+
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("x-api-key".to_string(), api_key.to_string());
+        headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
+        headers
+    }
+
+    /// 构建 `/messages` 请求体
+    fn build_request(&self, params: &ChatParams) -> Value {
+        let (system, messages) = convert_messages(params.messages);
+
+        let mut body = json!({
+            "model": params.model,
+            "messages": messages,
+            "max_tokens": params.max_tokens,
+            "temperature": params.temperature.clamp(0.0, MAX_TEMPERATURE),
+            "stream": params.stream,
+        });
+        if let Some(system) = system {
+            body["system"] = json!(system);
+        }
+        if let Some(tools) = params.tools.filter(|t| !t.is_empty()) {
+            body["tools"] = json!(tools.iter().map(convert_tool).collect::<Vec<_>>());
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<LlmResponse, String> {
+        if let Some(error) = error_message(json) {
+            return Err(error);
+        }
+
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in json["content"].as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("text") => content.push_str(block["text"].as_str().unwrap_or("")),
+                Some("tool_use") => tool_calls.push(ToolCall {
+                    id: block["id"].as_str().unwrap_or("").to_string(),
+                    name: block["name"].as_str().unwrap_or("").to_string(),
+                    params: object_params(&block["input"]),
+                }),
+                _ => {}
+            }
+        }
+
+        let (prompt_tokens, cached_tokens) = prompt_usage(&json["usage"]);
+        let completion_tokens = token_count(&json["usage"]["output_tokens"]);
+        Ok(LlmResponse {
+            content,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cached_tokens,
+        })
+    }
+
+    fn stream_decoder(&self) -> Box<dyn StreamDecoder> {
+        Box::new(AnthropicStream::new())
+    }
+}
+
+/// 转换消息：返回 (system 提示, messages)
+///
+/// 连续的同角色消息合并为一条，满足 user/assistant 交替的要求；
+/// 缓存断点标记在对应消息的最后一个内容块上
+fn convert_messages(messages: &[Message]) -> (Option<Value>, Vec<Value>) {
+    let mut system: Vec<String> = Vec::new();
+    let mut system_cached = false;
+    let mut converted: Vec<(&str, Vec<Value>)> = Vec::new();
+    let mut breakpoints = 0;
+
+    for message in messages {
+        let cache = message.cache_breakpoint && breakpoints < MAX_CACHE_BREAKPOINTS;
+        let (role, mut blocks) = match message.role {
+            MessageRole::System => {
+                system.push(message.text());
+                if cache && !system_cached {
+                    system_cached = true;
+                    breakpoints += 1;
+                }
+                continue;
+            }
+            MessageRole::User => ("user", content_blocks(message)),
+            MessageRole::Assistant => {
+                let mut blocks = content_blocks(message);
+                blocks.extend(message.tool_calls.iter().map(|call| json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.name,
+                    "input": call.params,
+                })));
+                ("assistant", blocks)
+            }
+            MessageRole::Tool => match &message.tool_call_id {
+                Some(id) => ("user", vec![json!({
+                    "type": "tool_result",
+                    "tool_use_id": id,
+                    "content": if message.parts.is_empty() {
+                        json!(message.content)
+                    } else {
+                        json!(content_blocks(message))
+                    },
+                })]),
+                None => ("user", content_blocks(message)),
+            },
+        };
+        // 空文本块会被 API 拒绝
+        let Some(last_block) = blocks.last_mut() else {
+            continue;
+        };
+        if cache {
+            last_block["cache_control"] = json!({ "type": "ephemeral" });
+            breakpoints += 1;
+        }
+
+        match converted.last_mut() {
+            Some((last_role, last)) if *last_role == role => last.extend(blocks),
+            _ => converted.push((role, blocks)),
+        }
+    }
+
+    let system = (!system.is_empty()).then(|| {
+        let text = system.join("\n\n");
+        if system_cached {
+            json!([{ "type": "text", "text": text, "cache_control": { "type": "ephemeral" } }])
+        } else {
+            json!(text)
+        }
+    });
+    let messages = converted.into_iter()
+        .map(|(role, content)| json!({ "role": role, "content": content }))
+        .collect();
+    (system, messages)
+}
+
+/// content 与内容块转换为 Anthropic 内容块（跳过空文本），图片使用 base64 source
+fn content_blocks(message: &Message) -> Vec<Value> {
+    message.content_parts().into_iter()
+        .filter_map(|part| match part {
+            ContentPart::Text { text } if text.is_empty() => None,
+            ContentPart::Text { text } => Some(json!({ "type": "text", "text": text })),
+            ContentPart::Image { media_type, data, .. } => Some(json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": media_type, "data": data.unwrap_or_default() },
+            })),
+        })
+        .collect()
+}
+
+/// 将 OpenAI 格式的工具定义转换为 Anthropic 格式（已是 Anthropic 格式的原样返回）
+pub fn convert_tool(tool: &Value) -> Value {
+    let Some(function) = tool.get("function") else {
+        return tool.clone();
+    };
+    json!({
+        "name": function["name"],
+        "description": function["description"].as_str().unwrap_or(""),
+        "input_schema": function.get("parameters")
+            .cloned()
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+    })
+}
+
+/// 返回 (prompt tokens, 其中命中缓存的部分)
+///
+/// `input_tokens` 不含缓存写入与读取的部分，三者之和才是完整的输入
+fn prompt_usage(usage: &Value) -> (usize, usize) {
+    let cache_read = token_count(&usage["cache_read_input_tokens"]);
+    let prompt = token_count(&usage["input_tokens"])
+        + token_count(&usage["cache_creation_input_tokens"])
+        + cache_read;
+    (prompt, cache_read)
+}
+
+/// `{"type": "error", "error": {"type": ..., "message": ...}}`
+fn error_message(json: &Value) -> Option<String> {
+    if json["type"] != "error" {
+        return None;
+    }
+    let error = &json["error"];
+    Some(format!(
+        "Anthropic {}: {}",
+        error["type"].as_str().unwrap_or("error"),
+        error["message"].as_str().unwrap_or("unknown error")
+    ))
+}
+
+#[derive(Debug, Default)]
+struct ToolUseBlock {
+    id: String,
+    name: String,
+    input_json: String,
+}
+
+/// 流式响应解析器，`message_stop` 事件返回 [`StreamStep::Stop`]
+#[derive(Debug, Default)]
+pub struct AnthropicStream {
+    content: String,
+    /// 按内容块 index 记录的工具调用
+    tool_uses: Vec<(usize, ToolUseBlock)>,
+    input_tokens: usize,
+    output_tokens: usize,
+    cached_tokens: usize,
+}
+
+impl AnthropicStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一个事件（SSE `data:` 中的 JSON），`error` 事件返回 Err
+    pub fn push(&mut self, event: &Value) -> Result<StreamStep, String> {
+        if let Some(error) = error_message(event) {
+            return Err(error);
+        }
+
+        match event["type"].as_str().unwrap_or("") {
+            "message_start" => {
+                let usage = &event["message"]["usage"];
+                (self.input_tokens, self.cached_tokens) = prompt_usage(usage);
+                self.output_tokens = token_count(&usage["output_tokens"]);
+            }
+            "content_block_start" => {
+                let block = &event["content_block"];
+                if block["type"] == "tool_use" {
+                    let index = event["index"].as_u64().unwrap_or(0) as usize;
+                    self.tool_uses.push((index, ToolUseBlock {
+                        id: block["id"].as_str().unwrap_or("").to_string(),
+                        name: block["name"].as_str().unwrap_or("").to_string(),
+                        input_json: String::new(),
+                    }));
+                }
+            }
+            "content_block_delta" => {
+                let delta = &event["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => {
+                        let text = delta["text"].as_str().unwrap_or("");
+                        if !text.is_empty() {
+                            self.content.push_str(text);
+                            return Ok(StreamStep::Text(text.to_string()));
+                        }
+                    }
+                    Some("input_json_delta") => {
+                        let index = event["index"].as_u64().unwrap_or(0) as usize;
+                        if let Some((_, tool_use)) = self.tool_uses.iter_mut().find(|(i, _)| *i == index) {
+                            tool_use.input_json.push_str(delta["partial_json"].as_str().unwrap_or(""));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                // output_tokens 为累计值
+                if let Some(output) = event["usage"]["output_tokens"].as_u64() {
+                    self.output_tokens = output as usize;
+                }
+            }
+            "message_stop" => return Ok(StreamStep::Stop),
+            _ => {}
+        }
+        Ok(StreamStep::Continue)
+    }
+
+    /// 已接收的文本
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl StreamDecoder for AnthropicStream {
+    /// SSE 的事件类型与事件 JSON 中的 type 重复，只解析 data
+    fn push_data(&mut self, data: &str) -> Result<StreamStep, String> {
+        match serde_json::from_str::<Value>(data) {
+            Ok(event) => self.push(&event),
+            Err(_) => Ok(StreamStep::Continue),
+        }
+    }
+
+    fn response(&self) -> LlmResponse {
+        let tool_calls: Vec<ToolCall> = self.tool_uses.iter()
+            .map(|(_, t)| ToolCall {
+                id: t.id.clone(),
+                name: t.name.clone(),
+                params: serde_json::from_str(&t.input_json).unwrap_or_default(),
+            })
+            .collect();
+        LlmResponse {
+            content: self.content.clone(),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            prompt_tokens: self.input_tokens,
+            completion_tokens: self.output_tokens,
+            total_tokens: self.input_tokens + self.output_tokens,
+            cached_tokens: self.cached_tokens,
+        }
+    }
+
+    /// 无参数的工具调用返回 `{}`
+    fn tool_calls(&self) -> Vec<(String, String)> {
+        self.tool_uses.iter()
+            .map(|(_, t)| {
+                let args = if t.input_json.trim().is_empty() { "{}" } else { &t.input_json };
+                (t.name.clone(), args.to_string())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::sse_frames;
+
+    const TEXT_RESPONSE: &str = include_str!("../../../tests/fixtures/anthropic/messages_text.json");
+    const TOOL_USE_RESPONSE: &str = include_str!("../../../tests/fixtures/anthropic/messages_tool_use.json");
+    const TOOL_USE_STREAM: &str = include_str!("../../../tests/fixtures/anthropic/stream_tool_use.sse");
+
+    fn message(role: MessageRole, content: &str, tool_call_id: Option<&str>) -> Message {
+        Message {
+            tool_call_id: tool_call_id.map(String::from),
+            ..Message::new(role, content)
+        }
+    }
+
+    #[test]
+    fn test_build_request() {
+        let messages = vec![
+            message(MessageRole::System, "你是笔记助手", None),
+            message(MessageRole::User, "读取 react.md", None),
+            message(MessageRole::Assistant, "好的", None),
+            message(MessageRole::Assistant, "", None),
+            message(MessageRole::Tool, "# React", Some("toolu_1")),
+            message(MessageRole::User, "总结一下", None),
+        ];
+        let tools = vec![json!({
+            "type": "function",
+            "function": {
+                "name": "read_note",
+                "description": "读取笔记",
+                "parameters": { "type": "object", "properties": { "path": { "type": "string" } } },
+            },
+        })];
+
+        let body = Anthropic.build_request(&ChatParams {
+            model: "claude-sonnet-4-20250514",
+            messages: &messages,
+            tools: Some(&tools),
+            temperature: 1.5,
+            max_tokens: 4096,
+            stream: true,
+        });
+
+        assert_eq!(body["system"], "你是笔记助手");
+        assert_eq!(body["max_tokens"], 4096);
+        assert_eq!(body["temperature"], 1.0);
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["tools"][0]["name"], "read_note");
+        assert_eq!(body["tools"][0]["input_schema"]["properties"]["path"]["type"], "string");
+        assert!(body["tools"][0].get("function").is_none());
+
+        // 空的 assistant 消息被跳过，tool 结果与后续 user 消息合并
+        let messages = body["messages"].as_array().unwrap();
+        let roles: Vec<_> = messages.iter().map(|m| m["role"].as_str().unwrap()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user"]);
+        assert_eq!(messages[1]["content"][0]["text"], "好的");
+        let blocks = messages[2]["content"].as_array().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["type"], "tool_result");
+        assert_eq!(blocks[0]["tool_use_id"], "toolu_1");
+        assert_eq!(blocks[1]["text"], "总结一下");
+    }
+
+    #[test]
+    fn test_tool_use_round_trip() {
+        let response = Anthropic.parse_response(&serde_json::from_str(TOOL_USE_RESPONSE).unwrap()).unwrap();
+        let calls = response.tool_calls.unwrap();
+        let messages = vec![
+            Message::user("读取 react.md"),
+            Message::assistant_with_tool_calls(response.content, calls.clone()),
+            Message::tool_result(&calls[0], "# React"),
+            Message::user("总结").with_part(ContentPart::Text { text: "一句话".into() }),
+        ];
+        let body = Anthropic.build_request(&ChatParams {
+            model: "claude-sonnet-4-20250514",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let assistant = &body["messages"][1]["content"];
+        assert_eq!(assistant[0]["text"], "我先读取这篇笔记。");
+        assert_eq!(assistant[1]["type"], "tool_use");
+        assert_eq!(assistant[1]["id"], "toolu_01A09q90qw90lq917835lq9");
+        assert_eq!(assistant[1]["input"]["path"], "notes/react.md");
+        let user = body["messages"][2]["content"].as_array().unwrap();
+        assert_eq!(user[0]["type"], "tool_result");
+        assert_eq!(user[0]["tool_use_id"], "toolu_01A09q90qw90lq917835lq9");
+        assert_eq!(user[0]["content"], "# React");
+        let texts: Vec<_> = user[1..].iter().map(|b| b["text"].as_str().unwrap()).collect();
+        assert_eq!(texts, vec!["总结", "一句话"]);
+    }
+
+    #[test]
+    fn test_images() {
+        let image = ContentPart::Image { media_type: "image/png".into(), path: "a.png".into(), data: Some("iVBORw==".into()) };
+        let call = ToolCall { id: "toolu_1".into(), name: "read_image".into(), params: Default::default() };
+        let messages = vec![
+            Message::user("这张图是什么？").with_part(image.clone()),
+            Message::assistant_with_tool_calls("", vec![call.clone()]),
+            Message::tool_result(&call, "已读取图片").with_part(image),
+        ];
+        let body = Anthropic.build_request(&ChatParams {
+            model: "claude-sonnet-4-20250514",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let image = &body["messages"][0]["content"][1];
+        assert_eq!(image["type"], "image");
+        assert_eq!(image["source"], json!({ "type": "base64", "media_type": "image/png", "data": "iVBORw==" }));
+        // tool_result 内可直接携带图片块
+        let result = &body["messages"][2]["content"][0]["content"];
+        assert_eq!(result[0]["text"], "已读取图片");
+        assert_eq!(result[1]["type"], "image");
+    }
+
+    #[test]
+    fn test_cache_breakpoints() {
+        let messages = vec![
+            Message::system("你是笔记助手").with_cache_breakpoint(),
+            Message::user("笔记库结构"),
+            Message::assistant("好的").with_cache_breakpoint(),
+            Message::user("总结一下"),
+        ];
+        let body = Anthropic.build_request(&ChatParams {
+            model: "claude-sonnet-4-20250514",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let ephemeral = json!({ "type": "ephemeral" });
+        assert_eq!(body["system"][0]["text"], "你是笔记助手");
+        assert_eq!(body["system"][0]["cache_control"], ephemeral);
+        assert_eq!(body["messages"][1]["content"][0]["cache_control"], ephemeral);
+        assert!(body["messages"][0]["content"][0].get("cache_control").is_none());
+        assert!(body["messages"][2]["content"][0].get("cache_control").is_none());
+
+        let response = Anthropic.parse_response(&json!({
+            "content": [{ "type": "text", "text": "好" }],
+            "usage": {
+                "input_tokens": 12,
+                "cache_creation_input_tokens": 100,
+                "cache_read_input_tokens": 2000,
+                "output_tokens": 3,
+            },
+        })).unwrap();
+        assert_eq!(response.prompt_tokens, 2112);
+        assert_eq!(response.cached_tokens, 2000);
+    }
+
+    #[test]
+    fn test_parse_text_response() {
+        let response = Anthropic.parse_response(&serde_json::from_str(TEXT_RESPONSE).unwrap()).unwrap();
+        assert_eq!(response.content, "你好！有什么可以帮你整理的笔记吗？");
+        assert!(response.tool_calls.is_none());
+        assert_eq!(response.prompt_tokens, 42);
+        assert_eq!(response.completion_tokens, 18);
+        assert_eq!(response.total_tokens, 60);
+    }
+
+    #[test]
+    fn test_parse_tool_use_response() {
+        let response = Anthropic.parse_response(&serde_json::from_str(TOOL_USE_RESPONSE).unwrap()).unwrap();
+        assert_eq!(response.content, "我先读取这篇笔记。");
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "toolu_01A09q90qw90lq917835lq9");
+        assert_eq!(calls[0].name, "read_note");
+        assert_eq!(calls[0].params["path"], "notes/react.md");
+        assert_eq!(calls[0].params["start_line"], 1);
+
+        let error = json!({
+            "type": "error",
+            "error": { "type": "overloaded_error", "message": "Overloaded" },
+        });
+        assert_eq!(Anthropic.parse_response(&error).unwrap_err(), "Anthropic overloaded_error: Overloaded");
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        let mut decoder = AnthropicStream::new();
+        let mut chunks = Vec::new();
+        let mut stopped = false;
+        for data in sse_frames(TOOL_USE_STREAM) {
+            match decoder.push_data(&data).unwrap() {
+                StreamStep::Text(text) => chunks.push(text),
+                StreamStep::Stop => stopped = true,
+                StreamStep::Continue => {}
+            }
+        }
+
+        assert!(stopped);
+        assert_eq!(chunks, vec!["好的，", "我来搜索相关笔记。"]);
+        assert_eq!(decoder.content(), "好的，我来搜索相关笔记。");
+        assert_eq!(
+            decoder.tool_calls(),
+            vec![("search_notes".to_string(), r#"{"query": "React hooks"}"#.to_string())]
+        );
+
+        let response = decoder.response();
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls[0].id, "toolu_01T1x1fJ34qAmk2tNTrN7Up6");
+        assert_eq!(calls[0].params["query"], "React hooks");
+        assert_eq!(response.prompt_tokens, 472);
+        assert_eq!(response.completion_tokens, 89);
+
+        let error = json!({ "type": "error", "error": { "type": "api_error", "message": "boom" } });
+        assert!(AnthropicStream::new().push(&error).is_err());
+    }
+}
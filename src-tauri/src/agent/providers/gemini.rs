@@ -0,0 +1,395 @@
+//! Google Gemini 原生 API（`generateContent`）
+//!
+//! - 角色只有 `user` / `model`，system 提示放在 `systemInstruction`
+//! - 工具定义为 `tools[].functionDeclarations`，调用与结果为 `functionCall` / `functionResponse` 部分
+//! - 流式使用 `streamGenerateContent?alt=sse`，每个事件都是一个完整的响应片段
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{
+    object_params, token_count, ChatParams, LlmProvider, ProviderCapabilities, StreamDecoder,
+    StreamStep,
+};
+use crate::agent::llm_client::LlmResponse;
+use crate::agent::types::{ContentPart, Message, MessageRole, ToolCall};
+
+/// Gemini API
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gemini;
+
+impl LlmProvider for Gemini {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "https://generativelanguage.googleapis.com/v1beta"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            function_calling: true,
+            requires_api_key: true,
+        }
+    }
+
+    /// 兼容填写了 OpenAI 兼容地址（`.../v1beta/openai`）的配置
+    fn endpoint(&self, base_url: &str, model: &str, stream: bool) -> String {
+        let base = base_url.trim_end_matches("/openai");
+        if stream {
+            format!("{}/models/{}:streamGenerateContent?alt=sse", base, model)
+        } else {
+            format!("{}/models/{}:generateContent", base, model)
+        }
+    }
+
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("x-goog-api-key".to_string(), api_key.to_string());
+        headers
+    }
+
+    fn build_request(&self, params: &ChatParams) -> Value {
+        let (system, contents) = convert_messages(params.messages);
+
+        let mut body = json!({
+            "contents": contents,
+            "generationConfig": {
+                "temperature": params.temperature,
+                "maxOutputTokens": params.max_tokens,
+            },
+        });
+        if let Some(system) = system {
+            body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+        }
+        if let Some(tools) = params.tools.filter(|t| !t.is_empty()) {
+            let declarations: Vec<Value> = tools.iter().map(convert_tool).collect();
+            body["tools"] = json!([{ "functionDeclarations": declarations }]);
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<LlmResponse, String> {
+        if let Some(error) = error_message(json) {
+            return Err(error);
+        }
+        let mut decoded = GeminiStream::default();
+        decoded.push_chunk(json);
+        Ok(decoded.response())
+    }
+
+    fn stream_decoder(&self) -> Box<dyn StreamDecoder> {
+        Box::new(GeminiStream::default())
+    }
+}
+
+/// 转换消息：返回 (system 提示, contents)，连续的同角色消息合并为一条
+fn convert_messages(messages: &[Message]) -> (Option<String>, Vec<Value>) {
+    let mut system: Vec<String> = Vec::new();
+    let mut contents: Vec<(&str, Vec<Value>)> = Vec::new();
+    // functionResponse 需要工具名，未设置 name 时按调用 ID 查找
+    let mut call_names: HashMap<&str, &str> = HashMap::new();
+
+    for message in messages {
+        let (role, parts) = match message.role {
+            MessageRole::System => {
+                system.push(message.text());
+                continue;
+            }
+            MessageRole::Assistant => {
+                let mut parts = message_parts(message);
+                for call in &message.tool_calls {
+                    call_names.insert(&call.id, &call.name);
+                    parts.push(json!({ "functionCall": { "name": call.name, "args": call.params } }));
+                }
+                ("model", parts)
+            }
+            MessageRole::Tool => {
+                let name = message.name.as_deref().or_else(|| {
+                    message.tool_call_id.as_deref().and_then(|id| call_names.get(id).copied())
+                });
+                match name {
+                    Some(name) => {
+                        let mut parts = vec![json!({
+                            "functionResponse": {
+                                "name": name,
+                                "response": { "content": message.text() },
+                            },
+                        })];
+                        // functionResponse 只携带文本，图片作为同一轮的 inlineData
+                        parts.extend(message_parts(message).into_iter().filter(|part| part.get("inlineData").is_some()));
+                        ("user", parts)
+                    }
+                    None => ("user", message_parts(message)),
+                }
+            }
+            MessageRole::User => ("user", message_parts(message)),
+        };
+        if parts.is_empty() {
+            continue;
+        }
+
+        match contents.last_mut() {
+            Some((last_role, last)) if *last_role == role => last.extend(parts),
+            _ => contents.push((role, parts)),
+        }
+    }
+
+    let system = (!system.is_empty()).then(|| system.join("\n\n"));
+    let contents = contents.into_iter()
+        .map(|(role, parts)| json!({ "role": role, "parts": parts }))
+        .collect();
+    (system, contents)
+}
+
+/// content 与内容块转换为 parts（跳过空文本），图片使用 inlineData
+fn message_parts(message: &Message) -> Vec<Value> {
+    message.content_parts().into_iter()
+        .filter_map(|part| match part {
+            ContentPart::Text { text } if text.is_empty() => None,
+            ContentPart::Text { text } => Some(json!({ "text": text })),
+            ContentPart::Image { media_type, data, .. } => Some(json!({
+                "inlineData": { "mimeType": media_type, "data": data.unwrap_or_default() },
+            })),
+        })
+        .collect()
+}
+
+/// 将 OpenAI 格式的工具定义转换为 functionDeclaration
+fn convert_tool(tool: &Value) -> Value {
+    let Some(function) = tool.get("function") else {
+        return tool.clone();
+    };
+    let mut declaration = json!({
+        "name": function["name"],
+        "description": function["description"].as_str().unwrap_or(""),
+    });
+    if let Some(parameters) = function.get("parameters") {
+        declaration["parameters"] = parameters.clone();
+    }
+    declaration
+}
+
+/// `{"error": {"code": ..., "message": ..., "status": ...}}`
+fn error_message(json: &Value) -> Option<String> {
+    let error = json.get("error")?;
+    Some(format!(
+        "Gemini {}: {}",
+        error["status"].as_str().unwrap_or("error"),
+        error["message"].as_str().unwrap_or("unknown error")
+    ))
+}
+
+/// 流式响应解析器；Gemini 没有结束事件，以连接关闭为准
+#[derive(Debug, Default)]
+pub struct GeminiStream {
+    content: String,
+    function_calls: Vec<(String, Value)>,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+    /// 隐式缓存命中的 tokens
+    cached_tokens: usize,
+}
+
+impl GeminiStream {
+    /// 合并一个响应片段，返回其中的文本
+    fn push_chunk(&mut self, json: &Value) -> String {
+        let mut text = String::new();
+        for part in json["candidates"][0]["content"]["parts"].as_array().into_iter().flatten() {
+            if let Some(t) = part["text"].as_str() {
+                text.push_str(t);
+            }
+            if let Some(call) = part.get("functionCall") {
+                let name = call["name"].as_str().unwrap_or("").to_string();
+                self.function_calls.push((name, call["args"].clone()));
+            }
+        }
+        self.content.push_str(&text);
+
+        // usageMetadata 为累计值
+        let usage = &json["usageMetadata"];
+        if usage.is_object() {
+            self.prompt_tokens = token_count(&usage["promptTokenCount"]);
+            self.completion_tokens = token_count(&usage["candidatesTokenCount"]);
+            self.cached_tokens = token_count(&usage["cachedContentTokenCount"]);
+        }
+        text
+    }
+}
+
+impl StreamDecoder for GeminiStream {
+    fn push_data(&mut self, data: &str) -> Result<StreamStep, String> {
+        let Ok(json) = serde_json::from_str::<Value>(data) else {
+            return Ok(StreamStep::Continue);
+        };
+        if let Some(error) = error_message(&json) {
+            return Err(error);
+        }
+        let text = self.push_chunk(&json);
+        Ok(if text.is_empty() { StreamStep::Continue } else { StreamStep::Text(text) })
+    }
+
+    fn response(&self) -> LlmResponse {
+        let tool_calls: Vec<ToolCall> = self.function_calls.iter().enumerate()
+            .map(|(idx, (name, args))| ToolCall {
+                id: format!("call_{}", idx),
+                name: name.clone(),
+                params: object_params(args),
+            })
+            .collect();
+        LlmResponse {
+            content: self.content.clone(),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.prompt_tokens + self.completion_tokens,
+            cached_tokens: self.cached_tokens,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::sse_frames;
+
+    const FUNCTION_CALL_STREAM: &str = include_str!("../../../tests/fixtures/gemini/stream_function_call.sse");
+
+    #[test]
+    fn test_build_request() {
+        let messages = vec![
+            Message::system("你是笔记助手"),
+            Message::user("搜索 React"),
+            Message::assistant("好的"),
+            Message {
+                name: Some("search_notes".into()),
+                tool_call_id: Some("call_0".into()),
+                ..Message::new(MessageRole::Tool, "找到 2 篇")
+            },
+        ];
+        let tools = vec![json!({
+            "type": "function",
+            "function": { "name": "search_notes", "description": "搜索", "parameters": { "type": "object" } },
+        })];
+        let body = Gemini.build_request(&ChatParams {
+            model: "gemini-2.5-flash",
+            messages: &messages,
+            tools: Some(&tools),
+            temperature: 0.7,
+            max_tokens: 2048,
+            stream: false,
+        });
+
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "你是笔记助手");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 2048);
+        assert_eq!(body["tools"][0]["functionDeclarations"][0]["name"], "search_notes");
+        let roles: Vec<_> = body["contents"].as_array().unwrap().iter()
+            .map(|c| c["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["user", "model", "user"]);
+        assert_eq!(body["contents"][2]["parts"][0]["functionResponse"]["name"], "search_notes");
+    }
+
+    #[test]
+    fn test_function_call_round_trip() {
+        let response = Gemini.parse_response(&json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [
+                    { "functionCall": { "name": "read_note", "args": { "path": "a.md" } } },
+                ] },
+            }],
+        })).unwrap();
+        let calls = response.tool_calls.unwrap();
+        let mut result = Message::tool_result(&calls[0], "# A");
+        result.name = None;
+        let messages = vec![
+            Message::user("读取 a.md"),
+            Message::assistant_with_tool_calls(response.content, calls),
+            result,
+        ];
+        let body = Gemini.build_request(&ChatParams {
+            model: "gemini-2.5-flash",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let model = &body["contents"][1];
+        assert_eq!(model["role"], "model");
+        assert_eq!(model["parts"].as_array().unwrap().len(), 1);
+        assert_eq!(model["parts"][0]["functionCall"]["args"]["path"], "a.md");
+        // 按调用 ID 找回工具名
+        let response = &body["contents"][2]["parts"][0]["functionResponse"];
+        assert_eq!(response["name"], "read_note");
+        assert_eq!(response["response"]["content"], "# A");
+    }
+
+    #[test]
+    fn test_images() {
+        let image = ContentPart::Image { media_type: "image/jpeg".into(), path: "a.jpg".into(), data: Some("/9j/4A==".into()) };
+        let call = ToolCall { id: "call_0".into(), name: "read_image".into(), params: Default::default() };
+        let messages = vec![
+            Message::user("这张图是什么？").with_part(image.clone()),
+            Message::assistant_with_tool_calls("", vec![call.clone()]),
+            Message::tool_result(&call, "已读取图片").with_part(image),
+        ];
+        let body = Gemini.build_request(&ChatParams {
+            model: "gemini-2.5-flash",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let inline = json!({ "inlineData": { "mimeType": "image/jpeg", "data": "/9j/4A==" } });
+        assert_eq!(body["contents"][0]["parts"][1], inline);
+        let parts = body["contents"][2]["parts"].as_array().unwrap();
+        assert_eq!(parts[0]["functionResponse"]["response"]["content"], "已读取图片");
+        assert_eq!(parts[1], inline);
+        assert_eq!(parts.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let json = json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [
+                    { "functionCall": { "name": "read_note", "args": { "path": "a.md" } } },
+                ] },
+                "finishReason": "STOP",
+            }],
+            "usageMetadata": { "promptTokenCount": 30, "candidatesTokenCount": 8, "totalTokenCount": 38 },
+        });
+        let response = Gemini.parse_response(&json).unwrap();
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls[0].name, "read_note");
+        assert_eq!(calls[0].params["path"], "a.md");
+        assert_eq!(response.total_tokens, 38);
+
+        let error = json!({ "error": { "code": 429, "message": "Resource exhausted", "status": "RESOURCE_EXHAUSTED" } });
+        assert_eq!(Gemini.parse_response(&error).unwrap_err(), "Gemini RESOURCE_EXHAUSTED: Resource exhausted");
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        let mut decoder = GeminiStream::default();
+        let chunks: Vec<_> = sse_frames(FUNCTION_CALL_STREAM).iter()
+            .filter_map(|data| match decoder.push_data(data).unwrap() {
+                StreamStep::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chunks, vec!["我来搜索", "相关笔记。"]);
+        assert_eq!(decoder.tool_calls(), vec![("search_notes".to_string(), r#"{"query":"React hooks"}"#.to_string())]);
+
+        let response = decoder.response();
+        assert_eq!(response.content, "我来搜索相关笔记。");
+        assert_eq!(response.prompt_tokens, 120);
+        assert_eq!(response.completion_tokens, 24);
+    }
+}
@@ -0,0 +1,284 @@
+//! LLM 提供商适配
+//!
+//! 每个提供商实现 [`LlmProvider`]：请求地址与鉴权、请求体构建、响应与流式解析、能力声明。
+//! 新增提供商只需添加一个模块并在 [`resolve`] 中注册
+
+pub mod anthropic;
+pub mod gemini;
+pub mod ollama;
+pub mod openai;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::agent::llm_client::LlmResponse;
+use crate::agent::types::{Message, MessageRole};
+use crate::sse::{LineDecoder, SseDecoder};
+
+pub use anthropic::Anthropic;
+pub use gemini::Gemini;
+pub use ollama::Ollama;
+pub use openai::OpenAiCompatible;
+
+/// 提供商能力
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderCapabilities {
+    /// 原生 Function Calling（否则工具调用以 XML 文本形式交给 Agent）
+    pub function_calling: bool,
+    /// 是否需要 API Key
+    pub requires_api_key: bool,
+}
+
+/// 一次对话请求的参数
+#[derive(Debug, Clone, Copy)]
+pub struct ChatParams<'a> {
+    pub model: &'a str,
+    pub messages: &'a [Message],
+    /// OpenAI 格式的工具定义，由各提供商转换
+    pub tools: Option<&'a [Value]>,
+    pub temperature: f32,
+    pub max_tokens: usize,
+    pub stream: bool,
+}
+
+/// LLM 提供商
+pub trait LlmProvider: Send + Sync {
+    /// 提供商标识（与 `AgentConfig::provider` 一致）
+    fn name(&self) -> &str;
+
+    /// 未配置 `base_url` 时使用的地址
+    fn default_base_url(&self) -> &str;
+
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// 模型是否接受图片输入，默认按模型名判断
+    fn supports_vision(&self, model: &str) -> bool {
+        vision_model(model)
+    }
+
+    /// 模型是否支持原生 Function Calling，默认取提供商能力
+    fn supports_function_calling(&self, _model: &str) -> bool {
+        self.capabilities().function_calling
+    }
+
+    /// 请求地址，`base_url` 已去除尾部斜杠
+    fn endpoint(&self, base_url: &str, model: &str, stream: bool) -> String;
+
+    /// 请求头（含鉴权）
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("Authorization".to_string(), format!("Bearer {}", api_key));
+        headers
+    }
+
+    /// 构建请求体
+    fn build_request(&self, params: &ChatParams) -> Value;
+
+    /// 解析非流式响应
+    fn parse_response(&self, json: &Value) -> Result<LlmResponse, String>;
+
+    /// 流式响应的分帧格式
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Sse
+    }
+
+    /// 创建流式响应解析器
+    fn stream_decoder(&self) -> Box<dyn StreamDecoder>;
+}
+
+/// 流式响应的分帧格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Server-Sent Events
+    Sse,
+    /// 每行一个 JSON
+    Ndjson,
+}
+
+/// 将响应字节流切分为解析器的输入：SSE 事件的 data 或 NDJSON 的一行
+#[derive(Debug)]
+pub enum StreamFramer {
+    Sse(SseDecoder),
+    Ndjson(LineDecoder),
+}
+
+impl StreamFramer {
+    pub fn new(format: StreamFormat) -> Self {
+        match format {
+            StreamFormat::Sse => Self::Sse(SseDecoder::new()),
+            StreamFormat::Ndjson => Self::Ndjson(LineDecoder::new()),
+        }
+    }
+
+    /// 输入一个 chunk，返回其中完整的数据帧
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        match self {
+            Self::Sse(decoder) => decoder.push(bytes).into_iter().map(|event| event.data).collect(),
+            Self::Ndjson(decoder) => decoder.push(bytes).into_iter().filter(|line| !line.trim().is_empty()).collect(),
+        }
+    }
+
+    /// 流结束，返回剩余的数据帧
+    pub fn finish(&mut self) -> Vec<String> {
+        match self {
+            Self::Sse(decoder) => decoder.finish().into_iter().map(|event| event.data).collect(),
+            Self::Ndjson(decoder) => decoder.finish().into_iter().filter(|line| !line.trim().is_empty()).collect(),
+        }
+    }
+}
+
+/// 流式事件的处理结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamStep {
+    /// 新的文本片段
+    Text(String),
+    /// 无需处理的行（心跳、块开始/结束、工具参数片段等）
+    Continue,
+    /// 响应结束
+    Stop,
+}
+
+/// 流式响应解析器，逐帧输入由 [`StreamFramer`] 切分的数据
+pub trait StreamDecoder: Send {
+    /// 处理一帧（SSE 事件的 data 或 NDJSON 的一行），API 返回错误时为 Err
+    fn push_data(&mut self, data: &str) -> Result<StreamStep, String>;
+
+    /// 汇总已接收的内容
+    fn response(&self) -> LlmResponse;
+
+    /// 工具调用的 (名称, 参数 JSON)
+    fn tool_calls(&self) -> Vec<(String, String)> {
+        self.response().tool_calls.unwrap_or_default().into_iter()
+            .map(|call| {
+                let args = serde_json::to_string(&call.params).unwrap_or_else(|_| "{}".to_string());
+                (call.name, args)
+            })
+            .collect()
+    }
+}
+
+/// 根据 `AgentConfig::provider` 选择提供商，未知名称按 OpenAI 兼容处理
+pub fn resolve(name: &str) -> Arc<dyn LlmProvider> {
+    match name {
+        "anthropic" => Arc::new(Anthropic),
+        "gemini" => Arc::new(Gemini),
+        "ollama" => Arc::new(Ollama),
+        _ => Arc::new(OpenAiCompatible::new(name)),
+    }
+}
+
+/// 按模型名判断是否为视觉模型（OpenAI 兼容服务与本地模型没有能力查询接口）
+pub fn vision_model(model: &str) -> bool {
+    const VISION_MARKERS: &[&str] = &[
+        "gpt-4o", "gpt-4.1", "gpt-4-turbo", "gpt-5", "o1", "o3", "o4",
+        "claude", "gemini", "vision", "-vl", "llava", "pixtral", "gemma3", "minicpm-v", "moondream",
+    ];
+    let model = model.to_lowercase();
+    // OpenRouter 等聚合服务的模型名带有厂商前缀，如 `openai/gpt-4o`
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    VISION_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+fn role_name(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "system",
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::Tool => "tool",
+    }
+}
+
+/// JSON 对象形式的工具参数
+fn object_params(value: &Value) -> HashMap<String, Value> {
+    value.as_object()
+        .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+/// 字符串形式的工具参数（OpenAI 的 `arguments`）
+fn string_params(arguments: &str) -> HashMap<String, Value> {
+    serde_json::from_str(arguments).unwrap_or_default()
+}
+
+fn token_count(value: &Value) -> usize {
+    value.as_u64().unwrap_or(0) as usize
+}
+
+/// 按 SSE 切分测试数据
+#[cfg(test)]
+fn sse_frames(text: &str) -> Vec<String> {
+    let mut framer = StreamFramer::new(StreamFormat::Sse);
+    let mut frames = framer.push(text.as_bytes());
+    frames.extend(framer.finish());
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_providers() {
+        let cases = [
+            ("openai", "https://api.openai.com/v1", true),
+            ("deepseek", "https://api.deepseek.com/v1", true),
+            ("openrouter", "https://openrouter.ai/api/v1", true),
+            ("anthropic", "https://api.anthropic.com/v1", true),
+            ("gemini", "https://generativelanguage.googleapis.com/v1beta", true),
+            ("ollama", "http://localhost:11434", false),
+            ("llamacpp", "http://127.0.0.1:8080/v1", true),
+            ("lmstudio", "http://localhost:1234/v1", true),
+            ("my-proxy", "https://api.openai.com/v1", false),
+        ];
+        for (name, base_url, function_calling) in cases {
+            let provider = resolve(name);
+            assert_eq!(provider.name(), name);
+            assert_eq!(provider.default_base_url(), base_url, "{}", name);
+            assert_eq!(provider.capabilities().function_calling, function_calling, "{}", name);
+        }
+        for name in ["ollama", "llamacpp", "lmstudio"] {
+            assert!(!resolve(name).capabilities().requires_api_key, "{}", name);
+        }
+        assert!(resolve("my-proxy").capabilities().requires_api_key);
+    }
+
+    #[test]
+    fn test_vision_models() {
+        for model in ["gpt-4o-mini", "openai/gpt-4.1", "qwen2.5-vl-72b-instruct", "llava:13b", "gemma3:4b"] {
+            assert!(vision_model(model), "{}", model);
+        }
+        for model in ["deepseek-chat", "gpt-3.5-turbo", "qwen3:8b", "moonshot-v1-8k"] {
+            assert!(!vision_model(model), "{}", model);
+        }
+        assert!(resolve("anthropic").supports_vision("claude-sonnet-4-5"));
+        assert!(resolve("gemini").supports_vision("gemini-2.5-flash"));
+    }
+
+    #[test]
+    fn test_endpoints() {
+        assert_eq!(
+            resolve("openai").endpoint("https://api.openai.com/v1", "gpt-4o", true),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            resolve("anthropic").endpoint("https://api.anthropic.com/v1", "claude", false),
+            "https://api.anthropic.com/v1/messages"
+        );
+        let gemini = resolve("gemini");
+        assert_eq!(
+            gemini.endpoint("https://generativelanguage.googleapis.com/v1beta", "gemini-2.5-flash", false),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent"
+        );
+        // OpenAI 兼容地址也走原生接口
+        assert_eq!(
+            gemini.endpoint("https://generativelanguage.googleapis.com/v1beta/openai", "gemini-2.5-flash", true),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse"
+        );
+        assert_eq!(
+            resolve("ollama").endpoint("http://localhost:11434/v1", "qwen3", true),
+            "http://localhost:11434/api/chat"
+        );
+    }
+}
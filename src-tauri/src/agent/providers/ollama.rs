@@ -0,0 +1,304 @@
+//! Ollama 原生 API（`/api/chat`）
+//!
+//! 流式响应为 NDJSON（每行一个 JSON，最后一行 `done: true` 携带 token 统计），
+//! 工具调用参数为 JSON 对象而非字符串。
+//! 是否使用原生工具调用按模型判断：优先使用本地检测到的模型能力，否则按模型系列判断
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{
+    object_params, role_name, token_count, ChatParams, LlmProvider, ProviderCapabilities, StreamDecoder,
+    StreamFormat, StreamStep,
+};
+use crate::agent::llm_client::LlmResponse;
+use crate::agent::local::ollama_capability;
+use crate::agent::types::{ContentPart, MessageRole, ToolCall};
+
+/// Ollama 本地服务
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ollama;
+
+impl LlmProvider for Ollama {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn default_base_url(&self) -> &str {
+        "http://localhost:11434"
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            // 未知模型使用 XML 模式，支持工具调用的模型见 `supports_function_calling`
+            function_calling: false,
+            requires_api_key: false,
+        }
+    }
+
+    /// 检测过的模型以 `/api/show` 返回的 `capabilities` 为准
+    fn supports_function_calling(&self, model: &str) -> bool {
+        ollama_capability(model, "tools").unwrap_or_else(|| tool_model(model))
+    }
+
+    /// 兼容填写了 OpenAI 兼容地址（`.../v1`）的配置
+    fn endpoint(&self, base_url: &str, _model: &str, _stream: bool) -> String {
+        format!("{}/api/chat", base_url.trim_end_matches("/v1"))
+    }
+
+    /// 本地服务无需鉴权，配置了 Key 时（如经过反向代理）才携带
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        if !api_key.is_empty() {
+            headers.insert("Authorization".to_string(), format!("Bearer {}", api_key));
+        }
+        headers
+    }
+
+    fn build_request(&self, params: &ChatParams) -> Value {
+        let messages: Vec<Value> = params.messages.iter()
+            .map(|m| {
+                let mut message = json!({ "role": role_name(&m.role), "content": m.text() });
+                let images: Vec<&str> = m.parts.iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Image { data, .. } => Some(data.as_deref().unwrap_or_default()),
+                        ContentPart::Text { .. } => None,
+                    })
+                    .collect();
+                if !images.is_empty() {
+                    message["images"] = json!(images);
+                }
+                if !m.tool_calls.is_empty() {
+                    let calls: Vec<Value> = m.tool_calls.iter()
+                        .map(|call| json!({ "function": { "name": call.name, "arguments": call.params } }))
+                        .collect();
+                    message["tool_calls"] = json!(calls);
+                }
+                if let (Some(name), MessageRole::Tool) = (&m.name, &m.role) {
+                    message["tool_name"] = json!(name);
+                }
+                message
+            })
+            .collect();
+
+        let mut body = json!({
+            "model": params.model,
+            "messages": messages,
+            "stream": params.stream,
+            "options": {
+                "temperature": params.temperature,
+                "num_predict": params.max_tokens,
+            },
+        });
+        // 不支持工具的模型收到 tools 会返回 400
+        if let Some(tools) = params.tools.filter(|t| !t.is_empty()) {
+            if self.supports_function_calling(params.model) {
+                body["tools"] = json!(tools);
+            }
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<LlmResponse, String> {
+        let mut decoded = OllamaStream::default();
+        decoded.push_chunk(json)?;
+        Ok(decoded.response())
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Ndjson
+    }
+
+    fn stream_decoder(&self) -> Box<dyn StreamDecoder> {
+        Box::new(OllamaStream::default())
+    }
+}
+
+/// 按模型系列判断是否支持工具调用（Ollama 模型库中带 `tools` 标签的系列）
+pub fn tool_model(model: &str) -> bool {
+    const TOOL_FAMILIES: &[&str] = &[
+        "llama3.1", "llama3.2", "llama3.3", "llama4", "qwen2.5", "qwen3", "qwq",
+        "mistral", "mixtral", "devstral", "magistral", "command-r", "firefunction",
+        "hermes3", "granite3", "granite4", "smollm2", "nemotron", "gpt-oss", "phi4-mini", "cogito",
+    ];
+    let name = model.to_lowercase();
+    // `hf.co/org/model:tag`、`library/model` 只看模型名
+    let name = name.rsplit('/').next().unwrap_or(&name);
+    TOOL_FAMILIES.iter().any(|family| name.starts_with(family))
+}
+
+/// NDJSON 流式响应解析器
+#[derive(Debug, Default)]
+pub struct OllamaStream {
+    content: String,
+    tool_calls: Vec<(String, Value)>,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+}
+
+impl OllamaStream {
+    /// 合并一行响应，返回 (文本片段, 是否结束)
+    fn push_chunk(&mut self, json: &Value) -> Result<(String, bool), String> {
+        if let Some(error) = json["error"].as_str() {
+            return Err(format!("Ollama: {}", error));
+        }
+
+        let message = &json["message"];
+        let text = message["content"].as_str().unwrap_or("").to_string();
+        self.content.push_str(&text);
+        for call in message["tool_calls"].as_array().into_iter().flatten() {
+            let function = &call["function"];
+            let name = function["name"].as_str().unwrap_or("").to_string();
+            self.tool_calls.push((name, function["arguments"].clone()));
+        }
+
+        let done = json["done"].as_bool().unwrap_or(false);
+        if done {
+            self.prompt_tokens = token_count(&json["prompt_eval_count"]);
+            self.completion_tokens = token_count(&json["eval_count"]);
+        }
+        Ok((text, done))
+    }
+}
+
+impl StreamDecoder for OllamaStream {
+    fn push_data(&mut self, data: &str) -> Result<StreamStep, String> {
+        let Ok(json) = serde_json::from_str::<Value>(data) else {
+            return Ok(StreamStep::Continue);
+        };
+        Ok(match self.push_chunk(&json)? {
+            (text, _) if !text.is_empty() => StreamStep::Text(text),
+            (_, true) => StreamStep::Stop,
+            _ => StreamStep::Continue,
+        })
+    }
+
+    fn response(&self) -> LlmResponse {
+        let tool_calls: Vec<ToolCall> = self.tool_calls.iter().enumerate()
+            .map(|(idx, (name, args))| ToolCall {
+                id: format!("call_{}", idx),
+                name: name.clone(),
+                params: object_params(args),
+            })
+            .collect();
+        LlmResponse {
+            content: self.content.clone(),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.prompt_tokens + self.completion_tokens,
+            cached_tokens: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::Message;
+
+    const CHAT_STREAM: &str = include_str!("../../../tests/fixtures/ollama/chat_stream.ndjson");
+
+    #[test]
+    fn test_build_request() {
+        let messages = vec![Message::user("hi")];
+        let body = Ollama.build_request(&ChatParams {
+            model: "qwen3:8b",
+            messages: &messages,
+            tools: None,
+            temperature: 0.2,
+            max_tokens: 512,
+            stream: true,
+        });
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["options"]["num_predict"], 512);
+        assert!(body.get("tools").is_none());
+        assert!(!Ollama.headers("").contains_key("Authorization"));
+    }
+
+    #[test]
+    fn test_tool_call_round_trip() {
+        let response = Ollama.parse_response(&json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "function": { "name": "read_note", "arguments": { "path": "a.md" } } }],
+            },
+            "done": true,
+        })).unwrap();
+        let calls = response.tool_calls.unwrap();
+        let messages = vec![
+            Message::assistant_with_tool_calls(response.content, calls.clone()),
+            Message::tool_result(&calls[0], "# A"),
+        ];
+        let body = Ollama.build_request(&ChatParams {
+            model: "qwen3:8b",
+            messages: &messages,
+            tools: None,
+            temperature: 0.2,
+            max_tokens: 512,
+            stream: false,
+        });
+        assert_eq!(body["messages"][0]["tool_calls"][0]["function"]["arguments"]["path"], "a.md");
+        assert_eq!(body["messages"][1]["role"], "tool");
+        assert_eq!(body["messages"][1]["tool_name"], "read_note");
+    }
+
+    #[test]
+    fn test_tool_support() {
+        for model in ["qwen3:8b", "llama3.1", "hf.co/bartowski/Qwen2.5-7B-Instruct-GGUF:Q4_K_M", "gpt-oss:20b"] {
+            assert!(Ollama.supports_function_calling(model), "{}", model);
+        }
+        for model in ["gemma2:9b", "llama3:8b", "deepseek-coder:6.7b"] {
+            assert!(!Ollama.supports_function_calling(model), "{}", model);
+        }
+
+        let tools = vec![json!({ "type": "function", "function": { "name": "read_note", "parameters": {} } })];
+        let messages = vec![Message::user("hi")];
+        let request = |model: &str| Ollama.build_request(&ChatParams {
+            model,
+            messages: &messages,
+            tools: Some(tools.as_slice()),
+            temperature: 0.2,
+            max_tokens: 512,
+            stream: false,
+        });
+        assert_eq!(request("qwen3:8b")["tools"][0]["function"]["name"], "read_note");
+        assert!(request("gemma2:9b").get("tools").is_none());
+    }
+
+    #[test]
+    fn test_images() {
+        let messages = vec![
+            Message::user("描述图片").with_part(ContentPart::Image { media_type: "image/png".into(), path: "a.png".into(), data: Some("iVBORw==".into()) }),
+        ];
+        let body = Ollama.build_request(&ChatParams {
+            model: "llava:13b",
+            messages: &messages,
+            tools: None,
+            temperature: 0.2,
+            max_tokens: 512,
+            stream: false,
+        });
+        assert_eq!(body["messages"][0]["content"], "描述图片");
+        assert_eq!(body["messages"][0]["images"], json!(["iVBORw=="]));
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        let mut decoder = OllamaStream::default();
+        let steps: Vec<_> = CHAT_STREAM.lines().map(|line| decoder.push_data(line).unwrap()).collect();
+        assert_eq!(steps[0], StreamStep::Text("好的".to_string()));
+        assert_eq!(steps[3], StreamStep::Stop);
+
+        let response = decoder.response();
+        assert_eq!(response.content, "好的，我来读取。");
+        assert_eq!(response.tool_calls.unwrap()[0].params["path"], "notes/react.md");
+        assert_eq!(response.prompt_tokens, 88);
+        assert_eq!(response.completion_tokens, 31);
+
+        let error = Ollama.parse_response(&json!({ "error": "model \"llama9\" not found" }));
+        assert_eq!(error.unwrap_err(), "Ollama: model \"llama9\" not found");
+    }
+}
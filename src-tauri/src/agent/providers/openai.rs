@@ -0,0 +1,407 @@
+//! OpenAI 兼容的 Chat Completions API
+//!
+//! OpenAI、DeepSeek、Moonshot、Groq、OpenRouter、本地的 llama.cpp server 与 LM Studio
+//! 以及未知提供商（自定义代理）均使用此格式
+
+use serde_json::{json, Value};
+
+use super::{
+    role_name, string_params, token_count, ChatParams, LlmProvider, ProviderCapabilities,
+    StreamDecoder, StreamStep,
+};
+use crate::agent::llm_client::LlmResponse;
+use crate::agent::types::{ContentPart, Message, MessageRole, ToolCall};
+
+/// OpenAI 兼容提供商
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatible {
+    name: String,
+    default_base_url: &'static str,
+    function_calling: bool,
+    requires_api_key: bool,
+}
+
+impl OpenAiCompatible {
+    pub fn new(name: &str) -> Self {
+        let (default_base_url, function_calling, requires_api_key) = match name {
+            "openai" => ("https://api.openai.com/v1", true, true),
+            "deepseek" => ("https://api.deepseek.com/v1", true, true),
+            "moonshot" => ("https://api.moonshot.cn/v1", true, true),
+            "groq" => ("https://api.groq.com/openai/v1", true, true),
+            "openrouter" => ("https://openrouter.ai/api/v1", true, true),
+            // 本地服务：llama-server 需以 `--jinja` 启动才支持工具调用
+            "llamacpp" => ("http://127.0.0.1:8080/v1", true, false),
+            "lmstudio" => ("http://localhost:1234/v1", true, false),
+            // 未知 provider 默认不支持 FC
+            _ => ("https://api.openai.com/v1", false, true),
+        };
+        Self {
+            name: name.to_string(),
+            default_base_url,
+            function_calling,
+            requires_api_key,
+        }
+    }
+}
+
+impl LlmProvider for OpenAiCompatible {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn default_base_url(&self) -> &str {
+        self.default_base_url
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            function_calling: self.function_calling,
+            requires_api_key: self.requires_api_key,
+        }
+    }
+
+    fn endpoint(&self, base_url: &str, _model: &str, _stream: bool) -> String {
+        format!("{}/chat/completions", base_url)
+    }
+
+    fn build_request(&self, params: &ChatParams) -> Value {
+        let mut messages: Vec<Value> = Vec::with_capacity(params.messages.len());
+        // tool 消息只接受文本：其中的图片在这一组 tool 消息之后以 user 消息补充
+        let mut tool_images: Vec<Value> = Vec::new();
+        for message in params.messages {
+            if message.role == MessageRole::Tool {
+                let images: Vec<Value> = message.parts.iter()
+                    .filter(|part| matches!(part, ContentPart::Image { .. }))
+                    .map(convert_part)
+                    .collect();
+                if !images.is_empty() {
+                    let name = message.name.as_deref().unwrap_or("tool");
+                    tool_images.push(json!({ "type": "text", "text": format!("工具 {} 返回的图片：", name) }));
+                    tool_images.extend(images);
+                }
+            } else if !tool_images.is_empty() {
+                messages.push(json!({ "role": "user", "content": std::mem::take(&mut tool_images) }));
+            }
+            messages.push(convert_message(message));
+        }
+        if !tool_images.is_empty() {
+            messages.push(json!({ "role": "user", "content": tool_images }));
+        }
+
+        let mut body = json!({
+            "model": params.model,
+            "messages": messages,
+            "temperature": params.temperature,
+            "max_tokens": params.max_tokens,
+            "stream": params.stream,
+        });
+        if let Some(tools) = params.tools {
+            body["tools"] = json!(tools);
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> Result<LlmResponse, String> {
+        let usage = &json["usage"];
+        let prompt_tokens = token_count(&usage["prompt_tokens"]);
+        let completion_tokens = token_count(&usage["completion_tokens"]);
+        let total_tokens = usage["total_tokens"].as_u64()
+            .map(|t| t as usize)
+            .unwrap_or(prompt_tokens + completion_tokens);
+        let cached_tokens = cached_tokens(usage);
+
+        let message = &json["choices"][0]["message"];
+        let tool_calls = message.get("tool_calls").and_then(|v| v.as_array()).map(|calls| {
+            calls.iter().enumerate()
+                .map(|(idx, tc)| ToolCall {
+                    id: tc["id"].as_str().map(String::from).unwrap_or_else(|| format!("call_{}", idx)),
+                    name: tc["function"]["name"].as_str().unwrap_or("").to_string(),
+                    params: string_params(tc["function"]["arguments"].as_str().unwrap_or("{}")),
+                })
+                .collect()
+        });
+
+        Ok(LlmResponse {
+            content: message["content"].as_str().unwrap_or("").to_string(),
+            tool_calls,
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+            cached_tokens,
+        })
+    }
+
+    fn stream_decoder(&self) -> Box<dyn StreamDecoder> {
+        Box::new(OpenAiStream::default())
+    }
+}
+
+fn convert_message(message: &Message) -> Value {
+    let content = if message.role == MessageRole::Tool {
+        json!(message.text())
+    } else if message.parts.is_empty() {
+        json!(message.content)
+    } else {
+        let parts: Vec<Value> = message.content_parts().iter().map(convert_part).collect();
+        json!(parts)
+    };
+    let mut converted = json!({ "role": role_name(&message.role), "content": content });
+
+    if !message.tool_calls.is_empty() {
+        // 仅有工具调用时 content 为 null
+        if message.content.is_empty() && message.parts.is_empty() {
+            converted["content"] = Value::Null;
+        }
+        let calls: Vec<Value> = message.tool_calls.iter()
+            .map(|call| json!({
+                "id": call.id,
+                "type": "function",
+                "function": {
+                    "name": call.name,
+                    "arguments": serde_json::to_string(&call.params).unwrap_or_else(|_| "{}".to_string()),
+                },
+            }))
+            .collect();
+        converted["tool_calls"] = json!(calls);
+    }
+    if let Some(id) = &message.tool_call_id {
+        converted["tool_call_id"] = json!(id);
+    } else if let Some(name) = &message.name {
+        // tool 消息通过 tool_call_id 关联，不携带 name
+        converted["name"] = json!(name);
+    }
+    converted
+}
+
+/// 命中前缀缓存的 prompt tokens（OpenAI 为自动缓存，DeepSeek 使用独立字段）
+fn cached_tokens(usage: &Value) -> usize {
+    usage["prompt_tokens_details"]["cached_tokens"].as_u64()
+        .or_else(|| usage["prompt_cache_hit_tokens"].as_u64())
+        .unwrap_or(0) as usize
+}
+
+/// 图片以 data URL 形式放入 `image_url`
+fn convert_part(part: &ContentPart) -> Value {
+    match part {
+        ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+        ContentPart::Image { media_type, data, .. } => json!({
+            "type": "image_url",
+            "image_url": { "url": format!("data:{};base64,{}", media_type, data.as_deref().unwrap_or_default()) },
+        }),
+    }
+}
+
+#[derive(Debug, Default)]
+struct StreamToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// `choices[0].delta` 形式的 SSE 流，以 `data: [DONE]` 结束
+#[derive(Debug, Default)]
+pub struct OpenAiStream {
+    content: String,
+    /// 按 `index` 累积的工具调用
+    tool_calls: Vec<StreamToolCall>,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+    cached_tokens: usize,
+}
+
+impl StreamDecoder for OpenAiStream {
+    fn push_data(&mut self, data: &str) -> Result<StreamStep, String> {
+        if data == "[DONE]" {
+            return Ok(StreamStep::Stop);
+        }
+        let Ok(json) = serde_json::from_str::<Value>(data) else {
+            return Ok(StreamStep::Continue);
+        };
+        if let Some(error) = json.get("error") {
+            return Err(format!("API error: {}", error));
+        }
+
+        // 部分服务在最后一个 chunk 中返回 usage
+        if json["usage"].is_object() {
+            self.prompt_tokens = token_count(&json["usage"]["prompt_tokens"]);
+            self.completion_tokens = token_count(&json["usage"]["completion_tokens"]);
+            self.cached_tokens = cached_tokens(&json["usage"]);
+        }
+
+        let delta = &json["choices"][0]["delta"];
+        for tc in delta["tool_calls"].as_array().into_iter().flatten() {
+            let idx = tc["index"].as_u64().unwrap_or(0) as usize;
+            while self.tool_calls.len() <= idx {
+                self.tool_calls.push(StreamToolCall::default());
+            }
+            let call = &mut self.tool_calls[idx];
+            if let Some(id) = tc["id"].as_str() {
+                call.id = id.to_string();
+            }
+            if let Some(name) = tc["function"]["name"].as_str() {
+                call.name.push_str(name);
+            }
+            if let Some(args) = tc["function"]["arguments"].as_str() {
+                call.arguments.push_str(args);
+            }
+        }
+
+        match delta["content"].as_str() {
+            Some(content) if !content.is_empty() => {
+                self.content.push_str(content);
+                Ok(StreamStep::Text(content.to_string()))
+            }
+            _ => Ok(StreamStep::Continue),
+        }
+    }
+
+    fn response(&self) -> LlmResponse {
+        let tool_calls: Vec<ToolCall> = self.tool_calls.iter().enumerate()
+            .map(|(idx, call)| ToolCall {
+                id: if call.id.is_empty() { format!("call_{}", idx) } else { call.id.clone() },
+                name: call.name.clone(),
+                params: string_params(&call.arguments),
+            })
+            .collect();
+        LlmResponse {
+            content: self.content.clone(),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.prompt_tokens + self.completion_tokens,
+            cached_tokens: self.cached_tokens,
+        }
+    }
+
+    fn tool_calls(&self) -> Vec<(String, String)> {
+        self.tool_calls.iter()
+            .map(|call| (call.name.clone(), call.arguments.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::providers::sse_frames;
+
+    #[test]
+    fn test_parse_tool_call_response() {
+        let json = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_abc",
+                        "type": "function",
+                        "function": { "name": "read_note", "arguments": "{\"path\":\"a.md\"}" },
+                    }],
+                },
+            }],
+            "usage": {
+                "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15,
+                "prompt_tokens_details": { "cached_tokens": 8 },
+            },
+        });
+        let response = OpenAiCompatible::new("openai").parse_response(&json).unwrap();
+        let calls = response.tool_calls.unwrap();
+        assert_eq!(calls[0].id, "call_abc");
+        assert_eq!(calls[0].name, "read_note");
+        assert_eq!(calls[0].params["path"], "a.md");
+        assert_eq!(response.content, "");
+        assert_eq!(response.total_tokens, 15);
+        assert_eq!(response.cached_tokens, 8);
+    }
+
+    #[test]
+    fn test_tool_call_round_trip() {
+        let provider = OpenAiCompatible::new("openai");
+        let response = provider.parse_response(&json!({
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_abc",
+                        "type": "function",
+                        "function": { "name": "read_note", "arguments": "{\"path\":\"a.md\"}" },
+                    }],
+                },
+            }],
+        })).unwrap();
+        let calls = response.tool_calls.unwrap();
+        let messages = vec![
+            Message::user("读取 a.md"),
+            Message::assistant_with_tool_calls(response.content, calls.clone()),
+            Message::tool_result(&calls[0], "# A"),
+            Message::user("总结").with_part(ContentPart::Text { text: "一句话".into() }),
+        ];
+        let body = provider.build_request(&ChatParams {
+            model: "gpt-4o",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let assistant = &body["messages"][1];
+        assert!(assistant["content"].is_null());
+        assert_eq!(assistant["tool_calls"][0]["id"], "call_abc");
+        assert_eq!(assistant["tool_calls"][0]["function"]["arguments"], r#"{"path":"a.md"}"#);
+        let tool = &body["messages"][2];
+        assert_eq!(tool["role"], "tool");
+        assert_eq!(tool["tool_call_id"], "call_abc");
+        assert_eq!(tool["content"], "# A");
+        assert_eq!(body["messages"][3]["content"][1]["text"], "一句话");
+    }
+
+    #[test]
+    fn test_image_parts() {
+        let image = ContentPart::Image { media_type: "image/png".into(), path: "a.png".into(), data: Some("iVBORw==".into()) };
+        let call = ToolCall { id: "call_1".into(), name: "read_image".into(), params: Default::default() };
+        let messages = vec![
+            Message::user("这张图是什么？").with_part(image.clone()),
+            Message::assistant_with_tool_calls("", vec![call.clone()]),
+            Message::tool_result(&call, "已读取图片").with_part(image),
+            Message::assistant("一张截图"),
+        ];
+        let body = OpenAiCompatible::new("openai").build_request(&ChatParams {
+            model: "gpt-4o",
+            messages: &messages,
+            tools: None,
+            temperature: 0.7,
+            max_tokens: 1024,
+            stream: false,
+        });
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["content"][1]["image_url"]["url"], "data:image/png;base64,iVBORw==");
+        // tool 消息只保留文本，图片紧随其后以 user 消息发送
+        assert_eq!(messages[2]["content"], "已读取图片");
+        assert_eq!(messages[3]["role"], "user");
+        assert_eq!(messages[3]["content"][1]["type"], "image_url");
+        assert_eq!(messages[4]["role"], "assistant");
+        assert_eq!(messages.len(), 5);
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        let stream = [
+            r#"data: {"choices":[{"index":0,"delta":{"role":"assistant","content":""}}]}"#,
+            r#": OPENROUTER PROCESSING"#,
+            r#"data: {"choices":[{"index":0,"delta":{"content":"Hi"}}]}"#,
+            r#"data: {"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"search_notes","arguments":""}}]}}]}"#,
+            r#"data: {"choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"query\":\"x\"}"}}]}}]}"#,
+            "data: [DONE]",
+        ].join("\n\n");
+        let mut decoder = OpenAiStream::default();
+        let steps: Vec<_> = sse_frames(&stream).iter().map(|data| decoder.push_data(data).unwrap()).collect();
+        assert_eq!(steps[1], StreamStep::Text("Hi".to_string()));
+        assert_eq!(steps[4], StreamStep::Stop);
+        assert_eq!(decoder.tool_calls(), vec![("search_notes".to_string(), r#"{"query":"x"}"#.to_string())]);
+        assert_eq!(decoder.response().tool_calls.unwrap()[0].id, "call_1");
+
+        assert!(decoder.push_data(r#"{"error":{"message":"quota"}}"#).is_err());
+    }
+}
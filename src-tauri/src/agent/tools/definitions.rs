@@ -12,6 +12,7 @@ pub fn get_all_tool_definitions() -> Vec<Value> {
         read_note_definition(),
         read_outline_definition(),
         read_section_definition(),
+        read_image_definition(),
         edit_note_definition(),
         create_note_definition(),
         list_notes_definition(),
@@ -60,6 +61,7 @@ pub fn get_tools_for_agent(agent: &str) -> Vec<Value> {
             read_note_definition(),
             read_outline_definition(),
             read_section_definition(),
+            read_image_definition(),
             edit_note_definition(),
             create_note_definition(),
             list_notes_definition(),
@@ -72,6 +74,7 @@ pub fn get_tools_for_agent(agent: &str) -> Vec<Value> {
             read_note_definition(),
             read_outline_definition(),
             read_section_definition(),
+            read_image_definition(),
             list_notes_definition(),
             fast_search_definition(), // 快速并行搜索 - 首选
             search_notes_definition(),
@@ -84,6 +87,7 @@ pub fn get_tools_for_agent(agent: &str) -> Vec<Value> {
         "writer" => vec![
             update_plan_definition(),
             read_note_definition(),
+            read_image_definition(),
             create_note_definition(),
             edit_note_definition(),
             list_notes_definition(),
@@ -507,6 +511,26 @@ fn add_database_row_definition() -> Value {
     })
 }
 
+fn read_image_definition() -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": "read_image",
+            "description": "读取笔记库中的图片（如笔记内嵌的截图），以附件形式提供给你查看。支持 png、jpg、gif、webp，单张不超过 5 MB。",
+            "parameters": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "图片路径，相对于笔记库根目录"
+                    }
+                },
+                "required": ["path"]
+            }
+        }
+    })
+}
+
 fn get_backlinks_definition() -> Value {
     json!({
         "type": "function",
@@ -7,11 +7,12 @@ use crate::agent::tools::fast_search::FastSearch;
 use crate::agent::commands::{ApprovalManager, ToolApprovalResponse};
 use crate::mcp::manager::McpManager;
 use crate::mcp::types::McpContentBlock;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 use regex::Regex;
 use std::collections::HashMap;
 use std::path::Path;
 use walkdir::WalkDir;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime, Wry};
 
 /// 需要用户审批的危险工具列表
 const DANGEROUS_TOOLS: &[&str] = &[
@@ -22,13 +23,13 @@ const DANGEROUS_TOOLS: &[&str] = &[
 ];
 
 /// 工具注册表
-pub struct ToolRegistry {
+pub struct ToolRegistry<R: Runtime = Wry> {
     workspace_path: String,
-    app: Option<AppHandle>,
+    app: Option<AppHandle<R>>,
     auto_approve: bool,
 }
 
-impl ToolRegistry {
+impl<R: Runtime> ToolRegistry<R> {
     pub fn new(workspace_path: String) -> Self {
         Self { 
             workspace_path,
@@ -38,7 +39,7 @@ impl ToolRegistry {
     }
     
     /// 设置 AppHandle（用于发送事件）
-    pub fn with_app(mut self, app: AppHandle) -> Self {
+    pub fn with_app(mut self, app: AppHandle<R>) -> Self {
         self.app = Some(app);
         self
     }
@@ -104,6 +105,7 @@ impl ToolRegistry {
                     success: false,
                     content: String::new(),
                     error: Some(format!("Invalid MCP tool name format: {}", tool_call.name)),
+                    attachments: Vec::new(),
                 };
             }
         };
@@ -133,6 +135,7 @@ impl ToolRegistry {
                     success: false,
                     content: String::new(),
                     error: Some(format!("MCP server '{}' not connected", server_name)),
+                    attachments: Vec::new(),
                 };
             }
         };
@@ -153,6 +156,15 @@ impl ToolRegistry {
                     })
                     .collect::<Vec<_>>()
                     .join("\n");
+                let mut attachments = Vec::new();
+                for block in &response.content {
+                    if let McpContentBlock::Image { data, mime_type } = block {
+                        match self.save_attachment(mime_type, data).await {
+                            Ok(image) => attachments.push(image),
+                            Err(e) => eprintln!("[MCP] 保存图片附件失败: {}", e),
+                        }
+                    }
+                }
 
                 // P2 修复：错误时保留实际错误内容
                 let error = if response.is_error {
@@ -174,6 +186,7 @@ impl ToolRegistry {
                         content
                     },
                     error,
+                    attachments,
                 }
             }
             Err(e) => ToolResult {
@@ -181,6 +194,7 @@ impl ToolRegistry {
                 success: false,
                 content: String::new(),
                 error: Some(e),
+                attachments: Vec::new(),
             },
         }
     }
@@ -239,6 +253,7 @@ impl ToolRegistry {
                         success: false,
                         content: String::new(),
                         error: Some("用户拒绝了此操作".to_string()),
+                        attachments: Vec::new(),
                     };
                 }
                 Err(e) => {
@@ -248,6 +263,7 @@ impl ToolRegistry {
                         success: false,
                         content: String::new(),
                         error: Some(format!("审批过程出错: {}", e)),
+                        attachments: Vec::new(),
                     };
                 }
             }
@@ -258,8 +274,13 @@ impl ToolRegistry {
             return self.execute_mcp_tool(tool_call).await;
         }
         
+        let mut attachments = Vec::new();
         let result = match tool_call.name.as_str() {
             "read_note" => self.read_note(&tool_call.params).await,
+            "read_image" => self.read_image(&tool_call.params).await.map(|(content, image)| {
+                attachments.push(image);
+                content
+            }),
             "read_outline" => self.read_outline(&tool_call.params).await,
             "read_section" => self.read_section(&tool_call.params).await,
             "edit_note" => self.edit_note(&tool_call.params).await,
@@ -287,12 +308,14 @@ impl ToolRegistry {
                 success: true,
                 content,
                 error: None,
+                attachments,
             },
             Err(e) => ToolResult {
                 tool_call_id: tool_call.id.clone(),
                 success: false,
                 content: String::new(),
                 error: Some(e),
+                attachments: Vec::new(),
             },
         }
     }
@@ -310,6 +333,45 @@ impl ToolRegistry {
         base.join(rel).to_string_lossy().to_string()
     }
 
+    /// 读取笔记库中的图片，作为附件返回给视觉模型
+    ///
+    /// 附件只引用图片文件，发送请求时才读取数据
+    async fn read_image(&self, params: &HashMap<String, serde_json::Value>) -> Result<(String, ContentPart), String> {
+        let path = params.get("path")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing 'path' parameter")?;
+        let media_type = image_media_type(path)
+            .ok_or_else(|| format!("Unsupported image format: {} (png, jpg, gif, webp)", path))?;
+
+        let full_path = self.get_full_path(path);
+        let size = tokio::fs::metadata(&full_path).await
+            .map_err(|e| format!("Failed to read image: {}", e))?
+            .len();
+        if size > MAX_IMAGE_BYTES {
+            return Err(format!(
+                "Image too large: {:.1} MB (max {} MB)",
+                size as f64 / 1024.0 / 1024.0,
+                MAX_IMAGE_BYTES / 1024 / 1024
+            ));
+        }
+
+        let image = ContentPart::image(media_type, full_path);
+        Ok((format!("已读取图片 {}（{}，{} KB），内容见附件", path, media_type, size.div_ceil(1024)), image))
+    }
+
+    /// 把 MCP 工具返回的 base64 图片写入 `.lumina/attachments/`，附件只引用该文件
+    async fn save_attachment(&self, media_type: &str, data: &str) -> Result<ContentPart, String> {
+        let bytes = STANDARD.decode(data).map_err(|e| format!("Invalid image data: {}", e))?;
+        let dir = Path::new(&self.workspace_path).join(".lumina").join("attachments");
+        tokio::fs::create_dir_all(&dir).await
+            .map_err(|e| format!("Failed to create attachment directory: {}", e))?;
+        let extension = media_type.rsplit('/').next().unwrap_or("bin");
+        let path = dir.join(format!("{}.{}", uuid::Uuid::new_v4().simple(), extension));
+        tokio::fs::write(&path, bytes).await
+            .map_err(|e| format!("Failed to save image: {}", e))?;
+        Ok(ContentPart::image(media_type, path.to_string_lossy()))
+    }
+
     /// 读取笔记（支持分段读取）
     /// 
     /// Windsurf 风格：
@@ -1153,3 +1215,54 @@ impl ToolRegistry {
         Some(map)
     }
 }
+
+/// 按扩展名判断图片 MIME 类型，仅支持视觉 API 通用的格式
+fn image_media_type(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, path: &str) -> ToolCall {
+        ToolCall {
+            id: "call_0".to_string(),
+            name: name.to_string(),
+            params: HashMap::from([("path".to_string(), serde_json::json!(path))]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_image() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("assets")).unwrap();
+        std::fs::write(dir.path().join("assets/shot.PNG"), [0x89, b'P', b'N', b'G']).unwrap();
+        std::fs::write(dir.path().join("notes.pdf"), b"%PDF").unwrap();
+        let registry: ToolRegistry = ToolRegistry::new(dir.path().to_string_lossy().to_string());
+
+        let result = registry.execute(&call("read_image", "assets/shot.PNG")).await;
+        assert!(result.success);
+        let image = dir.path().join("assets/shot.PNG").to_string_lossy().to_string();
+        assert_eq!(result.attachments, vec![ContentPart::image("image/png", image)]);
+
+        // 超过大小限制时不读取文件内容
+        let oversized = std::fs::File::create(dir.path().join("big.png")).unwrap();
+        oversized.set_len(MAX_IMAGE_BYTES + 1).unwrap();
+        let result = registry.execute(&call("read_image", "big.png")).await;
+        assert!(!result.success);
+        assert!(result.attachments.is_empty());
+
+        let result = registry.execute(&call("read_image", "notes.pdf")).await;
+        assert!(!result.success);
+        assert!(result.attachments.is_empty());
+        assert!(registry.execute(&call("read_image", "missing.png")).await.error.is_some());
+    }
+}
@@ -0,0 +1,390 @@
+//! 固件录制与回放
+//!
+//! 固件为 JSON 文件，按请求顺序保存请求（方法、URL、请求体）与响应（状态码、响应体）。
+//! 流式响应（SSE、NDJSON）按原文保存，回放时逐行分块返回，与实际流式读取一致。
+//!
+//! 请求按方法、URL 与请求体匹配，相同的请求按录制顺序依次返回；
+//! API Key 在录制与匹配前都会被替换，固件中不会保存密钥
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+
+use super::{body_value, HttpRequest, HttpResponse, HttpTransport, TransportError};
+
+/// 设置为 `1` 时测试以录制模式运行（需要网络与 API Key）
+pub const RECORD_ENV: &str = "LUMINA_RECORD_FIXTURES";
+
+/// 替换密钥后的占位符
+const REDACTED: &str = "REDACTED";
+
+/// URL 查询参数与 JSON 请求体中视为密钥的字段
+const SECRET_FIELDS: &[&str] = &["key", "api_key", "apikey", "access_token", "token"];
+
+/// 录制的响应头，其余响应头（如 Cookie）不保存
+const RECORDED_HEADERS: &[&str] = &["content-type", "retry-after"];
+
+/// 固件模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    Record,
+    Replay,
+}
+
+impl FixtureMode {
+    /// [`RECORD_ENV`] 为 `1` 时录制，否则回放
+    pub fn from_env() -> Self {
+        match std::env::var(RECORD_ENV).as_deref() {
+            Ok("1") => Self::Record,
+            _ => Self::Replay,
+        }
+    }
+}
+
+/// 固件文件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+/// 一次请求与响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    /// JSON 请求体，非 JSON 时为字符串
+    #[serde(default)]
+    pub body: Value,
+}
+
+impl RecordedRequest {
+    fn from_request(request: &HttpRequest) -> Self {
+        let mut body = body_value(request.body.as_deref());
+        redact_body(&mut body);
+        Self {
+            method: request.method.to_uppercase(),
+            url: redact_url(&request.url),
+            body,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl RecordedResponse {
+    fn to_response(&self) -> HttpResponse {
+        let headers = self.headers.iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let chunks = self.body.split_inclusive('\n')
+            .map(|line| line.as_bytes().to_vec())
+            .collect();
+        HttpResponse::from_chunks(self.status, headers, chunks)
+    }
+}
+
+#[derive(Debug, Default)]
+struct FixtureState {
+    cassette: Cassette,
+    /// 回放模式下已返回过的录制
+    used: Vec<bool>,
+}
+
+/// 录制或回放请求的传输
+pub struct FixtureTransport {
+    mode: FixtureMode,
+    path: PathBuf,
+    inner: Option<Arc<dyn HttpTransport>>,
+    state: Arc<Mutex<FixtureState>>,
+}
+
+impl FixtureTransport {
+    /// 通过 `inner` 发送请求并录制，每个响应读取完后写入固件（覆盖已有文件）
+    pub fn record(path: impl Into<PathBuf>, inner: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            mode: FixtureMode::Record,
+            path: path.into(),
+            inner: Some(inner),
+            state: Arc::new(Mutex::new(FixtureState::default())),
+        }
+    }
+
+    /// 回放固件，没有匹配的录制时请求失败
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read fixture {}: {}", path.display(), e))?;
+        let cassette: Cassette = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid fixture {}: {}", path.display(), e))?;
+        let used = vec![false; cassette.interactions.len()];
+        Ok(Self {
+            mode: FixtureMode::Replay,
+            path,
+            inner: None,
+            state: Arc::new(Mutex::new(FixtureState { cassette, used })),
+        })
+    }
+
+    /// 按 [`FixtureMode::from_env`] 录制（通过 `inner`）或回放
+    pub fn from_env(path: impl Into<PathBuf>, inner: Arc<dyn HttpTransport>) -> Result<Self, String> {
+        match FixtureMode::from_env() {
+            FixtureMode::Record => Ok(Self::record(path, inner)),
+            FixtureMode::Replay => Self::replay(path),
+        }
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// 回放模式下没有被请求过的录制（`方法 URL`）
+    pub fn unused(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.cassette.interactions.iter()
+            .zip(&state.used)
+            .filter(|(_, used)| !**used)
+            .map(|(interaction, _)| format!("{} {}", interaction.request.method, interaction.request.url))
+            .collect()
+    }
+
+    fn replay_request(&self, request: &HttpRequest) -> Result<HttpResponse, TransportError> {
+        let recorded = RecordedRequest::from_request(request);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let FixtureState { cassette, used } = &mut *state;
+        let index = cassette.interactions.iter()
+            .zip(used.iter())
+            .position(|(interaction, used)| !used && interaction.request == recorded)
+            .ok_or(TransportError::Unmatched { method: recorded.method, url: recorded.url })?;
+        used[index] = true;
+        Ok(cassette.interactions[index].response.to_response())
+    }
+
+    async fn record_request(&self, inner: &dyn HttpTransport, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let recorded = RecordedRequest::from_request(&request);
+        let response = inner.send(request).await?;
+        let headers: BTreeMap<String, String> = response.headers.iter()
+            .filter(|(key, _)| RECORDED_HEADERS.contains(&key.to_lowercase().as_str()))
+            .map(|(key, value)| (key.to_lowercase(), value.clone()))
+            .collect();
+        let status = response.status;
+        let response_headers = response.headers.clone();
+
+        // 响应体被读取完（或丢弃）时写入固件
+        let mut recorder = Recorder {
+            interaction: Some(Interaction {
+                request: recorded,
+                response: RecordedResponse { status, headers, body: String::new() },
+            }),
+            body: Vec::new(),
+            path: self.path.clone(),
+            state: self.state.clone(),
+        };
+        let body = response.bytes_stream()
+            .map(move |chunk| {
+                if let Ok(bytes) = &chunk {
+                    recorder.body.extend_from_slice(bytes);
+                }
+                chunk
+            })
+            .boxed();
+        Ok(HttpResponse::new(status, response_headers, body))
+    }
+}
+
+impl HttpTransport for FixtureTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            match (&self.mode, &self.inner) {
+                (FixtureMode::Record, Some(inner)) => self.record_request(inner.as_ref(), request).await,
+                _ => self.replay_request(&request),
+            }
+        })
+    }
+}
+
+/// 收集流经的响应体，释放时把这次请求写入固件
+struct Recorder {
+    interaction: Option<Interaction>,
+    body: Vec<u8>,
+    path: PathBuf,
+    state: Arc<Mutex<FixtureState>>,
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let Some(mut interaction) = self.interaction.take() else {
+            return;
+        };
+        interaction.response.body = String::from_utf8_lossy(&self.body).into_owned();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.cassette.interactions.push(interaction);
+        if let Err(e) = save_cassette(&self.path, &state.cassette) {
+            eprintln!("[Fixture] {}", e);
+        }
+    }
+}
+
+fn save_cassette(path: &Path, cassette: &Cassette) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(cassette).map_err(|e| e.to_string())?;
+    std::fs::write(path, json + "\n")
+        .map_err(|e| format!("Failed to write fixture {}: {}", path.display(), e))
+}
+
+fn is_secret(field: &str) -> bool {
+    SECRET_FIELDS.contains(&field.to_lowercase().as_str())
+}
+
+/// 替换 URL 查询参数中的密钥（如 Gemini 的 `?key=`）
+fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let query: Vec<String> = query.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_secret(name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, query.join("&"))
+}
+
+/// 替换 JSON 请求体中的密钥（如 Tavily 的 `api_key`）
+fn redact_body(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret(key) && value.is_string() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_body(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_body),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::transport::ReqwestTransport;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SSE_BODY: &str = "data: {\"n\":1}\n\ndata: {\"n\":2}\n\ndata: [DONE]\n\n";
+
+    fn search(server: &str, api_key: &str) -> HttpRequest {
+        HttpRequest::post(format!("{}/search?key={}&q=rust", server, api_key))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(&json!({ "api_key": api_key, "query": "rust" }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_json_body_error() {
+        let body = BTreeMap::from([((1, 2), "non-string key")]);
+        let error = HttpRequest::post("http://localhost").json(&body).unwrap_err();
+        assert!(matches!(error, TransportError::Request(message) if message.starts_with("Invalid JSON body")));
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/search"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("set-cookie", "session=secret")
+                .set_body_raw(SSE_BODY, "text/event-stream"))
+            .up_to_n_times(1)
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/search"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "3").set_body_string("slow down"))
+            .mount(&server).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = dir.path().join("search.json");
+        let recorder = FixtureTransport::record(&fixture, Arc::new(ReqwestTransport::default()));
+        let first = recorder.send(search(&server.uri(), "sk-live")).await.unwrap();
+        assert_eq!(first.text().await.unwrap(), SSE_BODY);
+        let second = recorder.send(search(&server.uri(), "sk-live")).await.unwrap();
+        assert_eq!(second.status, 429);
+        drop(second);
+
+        // 密钥与未列出的响应头不会写入固件
+        let saved = std::fs::read_to_string(&fixture).unwrap();
+        assert!(!saved.contains("sk-live") && !saved.contains("session"));
+        let cassette: Cassette = serde_json::from_str(&saved).unwrap();
+        assert_eq!(cassette.interactions.len(), 2);
+        assert_eq!(cassette.interactions[0].request.body["api_key"], REDACTED);
+        // 未读取响应体的录制响应体为空
+        assert_eq!(cassette.interactions[1].response.body, "");
+
+        // 回放时使用不同的密钥也能匹配，相同的请求按录制顺序返回
+        let replay = FixtureTransport::replay(&fixture).unwrap();
+        let response = replay.send(search(&server.uri(), "sk-ci")).await.unwrap();
+        assert_eq!(response.header("Content-Type"), Some("text/event-stream"));
+        let chunks: Vec<_> = response.bytes_stream().collect().await;
+        assert_eq!(chunks.len(), 6);
+        assert_eq!(replay.unused().len(), 1);
+        let limited = replay.send(search(&server.uri(), "sk-ci")).await.unwrap();
+        assert_eq!((limited.status, limited.header("retry-after")), (429, Some("3")));
+        assert!(replay.unused().is_empty());
+
+        let exhausted = replay.send(search(&server.uri(), "sk-ci")).await.err().unwrap();
+        assert!(matches!(exhausted, TransportError::Unmatched { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_replay_unmatched() {
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = dir.path().join("cassette.json");
+        std::fs::write(&fixture, serde_json::to_string(&json!({ "interactions": [{
+            "request": { "method": "GET", "url": "https://r.jina.ai/https://example.com" },
+            "response": { "status": 200, "body": "# Example" },
+        }] })).unwrap()).unwrap();
+
+        let replay = FixtureTransport::replay(&fixture).unwrap();
+        let other = replay.send(HttpRequest::get("https://r.jina.ai/https://example.org")).await.err().unwrap();
+        assert_eq!(other.to_string(), "No recorded response for GET https://r.jina.ai/https://example.org");
+        let post = replay.send(HttpRequest::post("https://r.jina.ai/https://example.com")).await;
+        assert!(post.is_err());
+        let page = replay.send(HttpRequest::get("https://r.jina.ai/https://example.com")).await.unwrap();
+        assert_eq!(page.text().await.unwrap(), "# Example");
+
+        assert!(FixtureTransport::replay(dir.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact_url("https://example.com/v1/models:generate?alt=sse&key=AIza123"),
+            "https://example.com/v1/models:generate?alt=sse&key=REDACTED",
+        );
+        let mut body = json!({ "api_key": "tvly-1", "messages": [{ "token": "t" }], "max_tokens": 10 });
+        redact_body(&mut body);
+        assert_eq!(body, json!({ "api_key": REDACTED, "messages": [{ "token": REDACTED }], "max_tokens": 10 }));
+    }
+}
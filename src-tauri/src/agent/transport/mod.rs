@@ -0,0 +1,187 @@
+//! HTTP 传输层
+//!
+//! `LlmClient`、`TavilyClient` 与 `JinaClient` 通过 [`HttpTransport`] 发送请求：
+//! - [`ReqwestTransport`]：实际发送请求（默认）
+//! - [`fixture::FixtureTransport`]：录制请求/响应到固件文件，或离线回放固件，
+//!   用于在没有网络和 API Key 的情况下测试 Agent 与 Deep Research 图
+
+pub mod fixture;
+
+use futures_util::future::BoxFuture;
+use futures_util::stream::{self, BoxStream};
+use futures_util::{StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// 响应体字节流
+pub type ByteStream = BoxStream<'static, Result<Vec<u8>, String>>;
+
+/// HTTP 请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    pub fn new(method: &str, url: impl Into<String>) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url)
+    }
+
+    pub fn post(url: impl Into<String>) -> Self {
+        Self::new("POST", url)
+    }
+
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn headers<K: Into<String>, V: Into<String>>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Self {
+        for (key, value) in headers {
+            self.headers.push((key.into(), value.into()));
+        }
+        self
+    }
+
+    /// JSON 请求体，同时设置 `Content-Type`；无法序列化时返回 [`TransportError::Request`]
+    pub fn json<T: serde::Serialize + ?Sized>(mut self, body: &T) -> Result<Self, TransportError> {
+        let body = serde_json::to_vec(body)
+            .map_err(|e| TransportError::Request(format!("Invalid JSON body: {}", e)))?;
+        if !self.headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("content-type")) {
+            self.headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+        self.body = Some(body);
+        Ok(self)
+    }
+}
+
+/// HTTP 响应，响应体以流的形式读取
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    body: ByteStream,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, headers: Vec<(String, String)>, body: ByteStream) -> Self {
+        Self { status, headers, body }
+    }
+
+    /// 按块返回给定的响应体
+    pub fn from_chunks(status: u16, headers: Vec<(String, String)>, chunks: Vec<Vec<u8>>) -> Self {
+        Self::new(status, headers, stream::iter(chunks.into_iter().map(Ok)).boxed())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 响应头（不区分大小写）
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn bytes_stream(self) -> ByteStream {
+        self.body
+    }
+
+    pub async fn bytes(self) -> Result<Vec<u8>, String> {
+        self.body.try_concat().await
+    }
+
+    pub async fn text(self) -> Result<String, String> {
+        self.bytes().await.map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T, String> {
+        let bytes = self.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// 传输错误
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TransportError {
+    /// 连接失败或请求未发送成功
+    #[error("{0}")]
+    Request(String),
+    /// 回放模式下没有录制过该请求
+    #[error("No recorded response for {method} {url}")]
+    Unmatched { method: String, url: String },
+    /// 固件文件读写失败
+    #[error("Fixture error: {0}")]
+    Fixture(String),
+}
+
+/// HTTP 传输
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>>;
+}
+
+/// 使用 reqwest 实际发送请求
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    pub fn shared(client: reqwest::Client) -> Arc<dyn HttpTransport> {
+        Arc::new(Self::new(client))
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            let method = reqwest::Method::from_bytes(request.method.as_bytes())
+                .map_err(|e| TransportError::Request(format!("Unsupported HTTP method {}: {}", request.method, e)))?;
+            let mut builder = self.client.request(method, &request.url);
+            for (key, value) in &request.headers {
+                builder = builder.header(key, value);
+            }
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+
+            let response = builder.send().await
+                .map_err(|e| TransportError::Request(e.to_string()))?;
+            let headers = response.headers().iter()
+                .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+            let status = response.status().as_u16();
+            let body = response.bytes_stream()
+                .map(|chunk| chunk.map(|bytes| bytes.to_vec()).map_err(|e| e.to_string()))
+                .boxed();
+            Ok(HttpResponse::new(status, headers, body))
+        })
+    }
+}
+
+/// 请求体按 JSON 解析，失败时作为文本（用于固件匹配与展示）
+pub(crate) fn body_value(body: Option<&[u8]>) -> Value {
+    match body {
+        None => Value::Null,
+        Some(bytes) => serde_json::from_slice(bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).into_owned())),
+    }
+}
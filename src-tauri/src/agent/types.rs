@@ -3,6 +3,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use crate::langgraph::state::GraphState as LangGraphState;
+use crate::agent::budget::{BudgetConfig, BudgetError};
+use crate::agent::providers;
+use crate::network::NetworkOptions;
 
 /// Agent 状态
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -75,10 +78,144 @@ pub enum MessageRole {
 pub struct Message {
     pub role: MessageRole,
     pub content: String,
+    /// 追加在 content 之后的内容块
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+    /// Tool 消息：工具名
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Tool 消息：对应的工具调用 ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    /// Assistant 消息：本轮发起的工具调用
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// 提示缓存断点：此消息及之前的内容在多次请求间保持不变
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache_breakpoint: bool,
+}
+
+impl Message {
+    pub fn new(role: MessageRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            parts: Vec::new(),
+            name: None,
+            tool_call_id: None,
+            tool_calls: Vec::new(),
+            cache_breakpoint: false,
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(MessageRole::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(MessageRole::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(MessageRole::Assistant, content)
+    }
+
+    /// 带工具调用的助手消息（FC 模式）
+    pub fn assistant_with_tool_calls(content: impl Into<String>, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls,
+            ..Self::assistant(content)
+        }
+    }
+
+    /// 工具调用的结果
+    pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> Self {
+        Self {
+            name: Some(call.name.clone()),
+            tool_call_id: Some(call.id.clone()),
+            ..Self::new(MessageRole::Tool, content)
+        }
+    }
+
+    /// 标记为提示缓存断点
+    pub fn with_cache_breakpoint(mut self) -> Self {
+        self.cache_breakpoint = true;
+        self
+    }
+
+    /// 追加内容块
+    pub fn with_part(mut self, part: ContentPart) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// content 与所有文本块拼接后的文本
+    pub fn text(&self) -> String {
+        let mut text = self.content.clone();
+        for part in &self.parts {
+            let ContentPart::Text { text: part } = part else {
+                continue;
+            };
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(part);
+        }
+        text
+    }
+
+    /// 全部内容块：非空的 content 作为第一个文本块
+    pub fn content_parts(&self) -> Vec<ContentPart> {
+        let mut parts = Vec::with_capacity(self.parts.len() + 1);
+        if !self.content.is_empty() {
+            parts.push(ContentPart::Text { text: self.content.clone() });
+        }
+        parts.extend(self.parts.iter().cloned());
+        parts
+    }
+
+    /// 是否包含图片块
+    pub fn has_images(&self) -> bool {
+        self.parts.iter().any(|part| matches!(part, ContentPart::Image { .. }))
+    }
+
+    /// 图片块替换为文本占位，用于不支持视觉输入的模型
+    pub fn without_images(&self) -> Message {
+        let mut message = self.clone();
+        for part in &mut message.parts {
+            if let ContentPart::Image { media_type, .. } = part {
+                *part = ContentPart::Text { text: format!("[图片: {}，当前模型不支持图片输入]", media_type) };
+            }
+        }
+        message
+    }
+}
+
+/// 单张图片的大小上限（主流视觉 API 的单图上限）
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// 消息内容块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    /// 图片文件
+    ///
+    /// 消息、检查点和事件中只保存路径；base64 数据在构建请求时由 `LlmClient` 读入，
+    /// 不参与序列化
+    Image {
+        media_type: String,
+        path: String,
+        #[serde(skip)]
+        data: Option<String>,
+    },
+}
+
+impl ContentPart {
+    /// 引用图片文件（数据在发送请求时读取）
+    pub fn image(media_type: impl Into<String>, path: impl Into<String>) -> Self {
+        Self::Image { media_type: media_type.into(), path: path.into(), data: None }
+    }
 }
 
 /// 工具调用
@@ -97,6 +234,9 @@ pub struct ToolResult {
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// 随结果返回的附件（如 read_image 读取的图片）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<ContentPart>,
 }
 
 /// 计划步骤状态 (Windsurf 风格)
@@ -233,6 +373,18 @@ pub struct AgentConfig {
     /// 语言
     #[serde(default = "default_locale")]
     pub locale: String,
+    /// 代理、超时与证书选项
+    #[serde(default)]
+    pub network: NetworkOptions,
+    /// 上下文窗口（tokens），未设置时按模型名推断
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<usize>,
+    /// 按节点选择模型与回退链
+    #[serde(default)]
+    pub routing: ModelRouting,
+    /// 限流与用量预算
+    #[serde(default)]
+    pub budget: BudgetConfig,
 }
 
 fn default_temperature() -> f32 { 0.7 }
@@ -254,10 +406,75 @@ impl Default for AgentConfig {
             max_steps: default_max_steps(),
             auto_approve: false,
             locale: default_locale(),
+            network: NetworkOptions::default(),
+            context_window: None,
+            routing: ModelRouting::default(),
+            budget: BudgetConfig::default(),
         }
     }
 }
 
+/// 路由目标模型，未设置的字段沿用 [`AgentConfig`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelRoute {
+    /// LLM 提供商
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// 模型名称
+    pub model: String,
+    /// API Key，切换提供商时不沿用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Base URL，切换提供商时不沿用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// 温度
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// 最大 tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+}
+
+impl ModelRoute {
+    /// 以 `base` 为基础生成该模型的配置
+    ///
+    /// 切换到需要 API Key 的提供商却未配置 Key 时返回 `None`，
+    /// 不会把 `base` 的 Key 发给其他提供商
+    pub fn apply(&self, base: &AgentConfig) -> Option<AgentConfig> {
+        let provider = self.provider.clone().unwrap_or_else(|| base.provider.clone());
+        let same_provider = provider == base.provider;
+        let api_key = match &self.api_key {
+            Some(key) => key.clone(),
+            None if same_provider => base.api_key.clone(),
+            None if providers::resolve(&provider).capabilities().requires_api_key => return None,
+            None => String::new(),
+        };
+        Some(AgentConfig {
+            model: self.model.clone(),
+            api_key,
+            base_url: self.base_url.clone().or_else(|| base.base_url.clone().filter(|_| same_provider)),
+            temperature: self.temperature.unwrap_or(base.temperature),
+            max_tokens: self.max_tokens.unwrap_or(base.max_tokens),
+            // 上下文窗口随模型变化，重新按模型名推断
+            context_window: None,
+            routing: ModelRouting::default(),
+            provider,
+            ..base.clone()
+        })
+    }
+}
+
+/// 模型路由：按图节点选择模型，限流或服务端错误时按顺序回退
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelRouting {
+    /// 节点名 → 模型（如 coordinator、planner、editor、reporter、write_report）
+    pub nodes: HashMap<String, ModelRoute>,
+    /// 回退链，对所有节点生效
+    pub fallbacks: Vec<ModelRoute>,
+}
+
 /// Agent 事件（发送给前端）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
@@ -265,6 +482,8 @@ impl Default for AgentConfig {
 pub enum AgentEvent {
     /// 状态变化
     StatusChange { status: AgentStatus },
+    /// 任务开始，检查点保存在该线程下（用于恢复、回放或分叉）
+    ThreadStarted { thread_id: String },
     /// 消息块（流式输出）
     MessageChunk { content: String, agent: AgentType },
     /// 意图分析结果
@@ -281,6 +500,10 @@ pub enum AgentEvent {
     Complete { result: String },
     /// 错误
     Error { message: String },
+    /// 超出用量预算，任务已中止
+    BudgetExceeded { error: BudgetError, message: String },
+    /// 用量预算提示（如模型没有内置价格，按保守价格计费）
+    BudgetWarning { message: String },
     /// 等待工具审批
     WaitingApproval { 
         tool: ToolCall,
@@ -24,6 +24,9 @@ pub enum AppError {
     #[error("WebDAV error: {0}")]
     WebDAV(String),
 
+    #[error("Git error: {0}")]
+    Git(String),
+
     #[error("Network error: {0}")]
     Network(String),
 }
@@ -0,0 +1,81 @@
+//! Git Tauri 命令
+//!
+//! 暴露给前端的命令接口，仓库操作在阻塞线程中执行
+
+use super::repo::GitVault;
+use super::types::*;
+use crate::error::AppError;
+use crate::webdav::types::SyncResult;
+
+async fn run_blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Git(format!("Git task failed: {}", e)))?
+}
+
+/// 在 vault 中初始化仓库
+#[tauri::command]
+pub async fn git_init(vault_path: String) -> Result<GitStatus, AppError> {
+    run_blocking(move || GitVault::init(&vault_path)?.status()).await
+}
+
+/// 创建本地 bare 远程仓库
+#[tauri::command]
+pub async fn git_init_remote(remote_path: String) -> Result<(), AppError> {
+    run_blocking(move || GitVault::init_bare_remote(&remote_path)).await
+}
+
+/// 获取工作区状态
+#[tauri::command]
+pub async fn git_status(vault_path: String) -> Result<GitStatus, AppError> {
+    run_blocking(move || GitVault::open(&vault_path)?.status()).await
+}
+
+/// 提交全部变更
+#[tauri::command]
+pub async fn git_commit_all(
+    vault_path: String,
+    message: Option<String>,
+) -> Result<Option<GitCommitInfo>, AppError> {
+    run_blocking(move || GitVault::open(&vault_path)?.commit_all(message)).await
+}
+
+/// 获取笔记的提交历史
+#[tauri::command]
+pub async fn git_log(
+    vault_path: String,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<GitCommitInfo>, AppError> {
+    run_blocking(move || GitVault::open(&vault_path)?.log(&path, limit)).await
+}
+
+/// 获取笔记的差异
+#[tauri::command]
+pub async fn git_diff(
+    vault_path: String,
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<String, AppError> {
+    run_blocking(move || {
+        GitVault::open(&vault_path)?.diff(&path, from.as_deref(), to.as_deref())
+    })
+    .await
+}
+
+/// 推送到本地 bare 仓库
+#[tauri::command]
+pub async fn git_push(vault_path: String, remote_path: String) -> Result<GitPushResult, AppError> {
+    run_blocking(move || GitVault::open(&vault_path)?.push(&remote_path)).await
+}
+
+/// 从本地 bare 仓库拉取
+#[tauri::command]
+pub async fn git_pull(vault_path: String, remote_path: String) -> Result<SyncResult, AppError> {
+    run_blocking(move || GitVault::open(&vault_path)?.pull(&remote_path)).await
+}
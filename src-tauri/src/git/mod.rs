@@ -0,0 +1,10 @@
+//! Git 版本管理模块
+//!
+//! 用 git 管理 vault 的历史，包括：
+//! - 仓库：状态、提交、笔记历史、差异
+//! - 远程：与本地 bare 仓库推送/拉取，冲突按 WebDAV 的方式处理
+//! - 命令：Tauri 命令接口
+
+pub mod types;
+pub mod repo;
+pub mod commands;
@@ -0,0 +1,1064 @@
+//! Git 仓库操作
+//!
+//! 基于 gix (纯 Rust 实现)，不依赖系统 git。
+//! 远程仅支持本地 bare 仓库，推送/拉取通过直接复制对象完成。
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use gix::bstr::ByteSlice;
+use gix::objs::tree::{Entry as TreeEntry, EntryKind, EntryMode};
+use gix::objs::Write as _;
+use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+use gix::refs::Target;
+use gix::ObjectId;
+use walkdir::WalkDir;
+
+use super::types::*;
+use crate::error::AppError;
+use crate::webdav::types::{SyncAction, SyncError, SyncResult};
+
+/// 不纳入版本管理的内部文件
+const INTERNAL_FILES: &[&str] = &[".lumina-sync-state.json", ".lumina-manifest.json"];
+
+/// 不纳入版本管理的内部目录（同步日志、设置等）
+const INTERNAL_DIR: &str = ".lumina";
+
+/// WebDAV 下载时的临时文件后缀
+const DOWNLOAD_TEMP_SUFFIX: &str = ".lumina-download";
+
+/// 未配置 git 身份时使用的提交者
+const FALLBACK_NAME: &str = "Lumina Note";
+const FALLBACK_EMAIL: &str = "lumina-note@localhost";
+
+/// 路径 -> (blob ID, 文件模式)
+type FileMap = BTreeMap<String, (ObjectId, EntryMode)>;
+
+fn git_err(e: impl std::fmt::Display) -> AppError {
+    AppError::Git(e.to_string())
+}
+
+/// 以 git 仓库管理的 vault
+pub struct GitVault {
+    repo: gix::Repository,
+    vault_path: String,
+}
+
+impl GitVault {
+    /// 打开 vault 中已有的仓库
+    pub fn open(vault_path: &str) -> Result<Self, AppError> {
+        let repo = gix::open(vault_path).map_err(git_err)?;
+        Ok(Self {
+            repo,
+            vault_path: vault_path.to_string(),
+        })
+    }
+
+    /// 在 vault 中初始化仓库（已存在时直接打开）
+    pub fn init(vault_path: &str) -> Result<Self, AppError> {
+        if Path::new(vault_path).join(".git").exists() {
+            return Self::open(vault_path);
+        }
+        let repo = gix::init(vault_path).map_err(git_err)?;
+        Ok(Self {
+            repo,
+            vault_path: vault_path.to_string(),
+        })
+    }
+
+    /// 创建本地 bare 仓库作为远程
+    pub fn init_bare_remote(remote_path: &str) -> Result<(), AppError> {
+        gix::init_bare(remote_path).map_err(git_err)?;
+        Ok(())
+    }
+
+    /// 当前分支名
+    pub fn branch_name(&self) -> Result<Option<String>, AppError> {
+        Ok(self
+            .repo
+            .head_name()
+            .map_err(git_err)?
+            .map(|name| name.shorten().to_str_lossy().into_owned()))
+    }
+
+    /// HEAD 指向的提交 (尚无提交时为 None)
+    pub fn head_id(&self) -> Result<Option<ObjectId>, AppError> {
+        let head = self.repo.head().map_err(git_err)?;
+        Ok(head.id().map(|id| id.detach()))
+    }
+
+    /// 工作区相对 HEAD 的状态
+    pub fn status(&self) -> Result<GitStatus, AppError> {
+        let head = self.head_id()?;
+        let base = self.head_files(head)?;
+        let (_, entries) = self.snapshot(&base, false)?;
+
+        Ok(GitStatus {
+            branch: self.branch_name()?,
+            head: head.map(|id| id.to_string()),
+            entries,
+        })
+    }
+
+    /// 提交工作区的全部变更，无变更时返回 None
+    ///
+    /// 未提供说明时根据变更自动生成
+    pub fn commit_all(&self, message: Option<String>) -> Result<Option<GitCommitInfo>, AppError> {
+        let head = self.head_id()?;
+        let base = self.head_files(head)?;
+        let (current, changes) = self.snapshot(&base, true)?;
+
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let tree = write_tree(&self.repo, &current)?;
+        let message = message
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or_else(|| generate_commit_message(&changes));
+
+        let id = self.commit(&message, tree, head.into_iter().collect())?;
+        self.write_index(tree)?;
+
+        eprintln!("[Git] Committed {} ({} changes)", id.to_hex_with_len(7), changes.len());
+        Ok(Some(self.commit_info(id)?))
+    }
+
+    /// 修改过某个笔记的提交（最新的在前）
+    pub fn log(&self, path: &str, limit: Option<usize>) -> Result<Vec<GitCommitInfo>, AppError> {
+        let Some(head) = self.head_id()? else {
+            return Ok(Vec::new());
+        };
+        let path = normalize_path(path);
+
+        let mut commits = Vec::new();
+        let walk = self.repo.rev_walk([head]).all().map_err(git_err)?;
+        for info in walk {
+            let info = info.map_err(git_err)?;
+            let commit = self.repo.find_commit(info.id).map_err(git_err)?;
+
+            let current = blob_at(&commit, &path)?;
+            let previous = match commit.parent_ids().next() {
+                Some(parent) => {
+                    let parent = self.repo.find_commit(parent).map_err(git_err)?;
+                    blob_at(&parent, &path)?
+                }
+                None => None,
+            };
+
+            if current != previous {
+                commits.push(self.commit_info(info.id)?);
+                if limit.is_some_and(|limit| commits.len() >= limit) {
+                    break;
+                }
+            }
+        }
+
+        Ok(commits)
+    }
+
+    /// 笔记的统一格式差异
+    ///
+    /// `from` 默认为 HEAD，`to` 默认为工作区
+    pub fn diff(&self, path: &str, from: Option<&str>, to: Option<&str>) -> Result<String, AppError> {
+        let path = normalize_path(path);
+
+        let old = match from {
+            Some(rev) => self.blob_at_rev(rev, &path)?,
+            None => match self.head_id()? {
+                Some(head) => {
+                    let commit = self.repo.find_commit(head).map_err(git_err)?;
+                    self.read_blob(blob_at(&commit, &path)?)?
+                }
+                None => None,
+            },
+        };
+
+        let new = match to {
+            Some(rev) => self.blob_at_rev(rev, &path)?,
+            None => fs::read(Path::new(&self.vault_path).join(&path)).ok(),
+        };
+
+        unified_diff(&path, &old.unwrap_or_default(), &new.unwrap_or_default())
+    }
+
+    /// 推送当前分支到本地 bare 仓库
+    ///
+    /// 只允许快进，远程有本地没有的提交时需先拉取
+    pub fn push(&self, remote_path: &str) -> Result<GitPushResult, AppError> {
+        let head = self
+            .head_id()?
+            .ok_or_else(|| AppError::Git("Nothing to push: no commits yet".to_string()))?;
+        let branch = self
+            .branch_name()?
+            .ok_or_else(|| AppError::Git("Cannot push a detached HEAD".to_string()))?;
+
+        let remote = gix::open(remote_path).map_err(git_err)?;
+        let ref_name = format!("refs/heads/{}", branch);
+        let old = find_ref(&remote, &ref_name)?;
+
+        if old == Some(head) {
+            return Ok(GitPushResult {
+                branch,
+                old_head: old.map(|id| id.to_string()),
+                new_head: head.to_string(),
+                objects_copied: 0,
+            });
+        }
+
+        if let Some(old) = old {
+            if !self.repo.has_object(old) || !is_ancestor(&self.repo, old, head) {
+                return Err(AppError::Git(
+                    "Remote has commits that are not in the vault, pull first".to_string(),
+                ));
+            }
+        }
+
+        let objects_copied = copy_objects(&self.repo, &remote, head)?;
+
+        let expected = match old {
+            Some(old) => PreviousValue::ExistingMustMatch(Target::Object(old)),
+            None => PreviousValue::MustNotExist,
+        };
+        update_ref(&remote, &ref_name, expected, head, "push: lumina note")?;
+
+        eprintln!("[Git] Pushed {} to {} ({} objects)", branch, remote_path, objects_copied);
+        Ok(GitPushResult {
+            branch,
+            old_head: old.map(|id| id.to_string()),
+            new_head: head.to_string(),
+            objects_copied,
+        })
+    }
+
+    /// 从本地 bare 仓库拉取当前分支
+    ///
+    /// 能快进时直接快进；分叉时按文件三方合并，双方都修改的文件保留本地版本，
+    /// 远程版本作为冲突副本（如 `note.conflict.md`）一起提交，推送后其他设备也能看到
+    pub fn pull(&self, remote_path: &str) -> Result<SyncResult, AppError> {
+        let start = Instant::now();
+        let branch = self
+            .branch_name()?
+            .ok_or_else(|| AppError::Git("Cannot pull into a detached HEAD".to_string()))?;
+        let head = self.head_id()?;
+        let ours_files = self.head_files(head)?;
+
+        let (_, changes) = self.snapshot(&ours_files, false)?;
+        if !changes.is_empty() {
+            return Err(AppError::Git(format!(
+                "{} uncommitted changes, commit before pulling",
+                changes.len()
+            )));
+        }
+
+        let mut result = SyncResult {
+            success: true,
+            uploaded: 0,
+            downloaded: 0,
+            deleted: 0,
+            conflicts: 0,
+            errors: Vec::new(),
+            duration_ms: 0,
+        };
+
+        let remote = gix::open(remote_path).map_err(git_err)?;
+        let Some(theirs) = find_ref(&remote, &format!("refs/heads/{}", branch))? else {
+            result.duration_ms = start.elapsed().as_millis() as u64;
+            return Ok(result);
+        };
+
+        copy_objects(&remote, &self.repo, theirs)?;
+        let theirs_files = commit_files(&self.repo, theirs)?;
+
+        match head {
+            Some(ours) if is_ancestor(&self.repo, theirs, ours) => {
+                // 已是最新
+            }
+            Some(ours) if !is_ancestor(&self.repo, ours, theirs) => {
+                let merged = self.merge(ours, theirs, &ours_files, &theirs_files, &mut result)?;
+                let tree = write_tree(&self.repo, &merged)?;
+                let message = format!("Merge {} from {}", branch, remote_path);
+                self.commit(&message, tree, vec![ours, theirs])?;
+                self.write_index(tree)?;
+            }
+            _ => {
+                // 快进（包括本地尚无提交的情况）
+                self.checkout(&ours_files, &theirs_files, &mut result);
+                self.set_head(head, theirs, "pull: fast-forward")?;
+                let tree = self
+                    .repo
+                    .find_commit(theirs)
+                    .map_err(git_err)?
+                    .tree_id()
+                    .map_err(git_err)?
+                    .detach();
+                self.write_index(tree)?;
+            }
+        }
+
+        result.success = result.errors.is_empty();
+        result.duration_ms = start.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+
+    /// 把三方合并的结果写入工作区，返回合并后的文件表（包含冲突副本）
+    fn merge(
+        &self,
+        ours: ObjectId,
+        theirs: ObjectId,
+        ours_files: &FileMap,
+        theirs_files: &FileMap,
+        result: &mut SyncResult,
+    ) -> Result<FileMap, AppError> {
+        let base = self.repo.merge_base(ours, theirs).map_err(git_err)?.detach();
+        let base_files = commit_files(&self.repo, base)?;
+        let mut merged = merge_files(&base_files, ours_files, theirs_files);
+
+        for (path, theirs_entry) in &merged.take_theirs {
+            let outcome = match theirs_entry {
+                Some((id, _)) => self.write_worktree_file(path, *id).map(|_| result.downloaded += 1),
+                None => self.remove_worktree_file(path).map(|_| result.deleted += 1),
+            };
+            if let Err(e) = outcome {
+                result.errors.push(SyncError {
+                    path: path.clone(),
+                    action: SyncAction::Download,
+                    message: e.to_string(),
+                });
+            }
+        }
+
+        for path in &merged.conflicts {
+            result.conflicts += 1;
+            let Some(&(id, mode)) = theirs_files.get(path) else {
+                // 远程删除、本地修改：本地优先，保留本地版本
+                continue;
+            };
+            let copy = conflict_copy_name(path);
+            match self.write_worktree_file(&copy, id) {
+                Ok(()) => {
+                    merged.files.insert(copy, (id, mode));
+                }
+                Err(e) => result.errors.push(SyncError {
+                    path: path.clone(),
+                    action: SyncAction::Conflict,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(merged.files)
+    }
+
+    /// 将工作区从 `from` 更新到 `to`
+    fn checkout(&self, from: &FileMap, to: &FileMap, result: &mut SyncResult) {
+        for (path, (id, _)) in to {
+            if from.get(path).map(|(old, _)| old) == Some(id) {
+                continue;
+            }
+            match self.write_worktree_file(path, *id) {
+                Ok(()) => result.downloaded += 1,
+                Err(e) => result.errors.push(SyncError {
+                    path: path.clone(),
+                    action: SyncAction::Download,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        for path in from.keys().filter(|p| !to.contains_key(*p)) {
+            match self.remove_worktree_file(path) {
+                Ok(()) => result.deleted += 1,
+                Err(e) => result.errors.push(SyncError {
+                    path: path.clone(),
+                    action: SyncAction::DeleteLocal,
+                    message: e.to_string(),
+                }),
+            }
+        }
+    }
+
+    fn write_worktree_file(&self, path: &str, id: ObjectId) -> Result<(), AppError> {
+        let data = self.read_blob(Some(id))?.unwrap_or_default();
+        let target = Path::new(&self.vault_path).join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, data)?;
+        Ok(())
+    }
+
+    fn remove_worktree_file(&self, path: &str) -> Result<(), AppError> {
+        match fs::remove_file(Path::new(&self.vault_path).join(path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// HEAD 中的文件
+    fn head_files(&self, head: Option<ObjectId>) -> Result<FileMap, AppError> {
+        match head {
+            Some(id) => commit_files(&self.repo, id),
+            None => Ok(FileMap::new()),
+        }
+    }
+
+    /// 扫描工作区，计算每个文件的 blob ID 以及相对 `base` 的变更
+    ///
+    /// `write` 为 true 时同时把内容写入对象库
+    fn snapshot(&self, base: &FileMap, write: bool) -> Result<(FileMap, Vec<GitStatusEntry>), AppError> {
+        let index = self.repo.index_or_empty().map_err(git_err)?;
+        let mut excludes = self
+            .repo
+            .excludes(
+                &index,
+                None,
+                gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
+            )
+            .map_err(git_err)?;
+
+        let vault = Path::new(&self.vault_path);
+        let mut current = FileMap::new();
+        let mut entries = Vec::new();
+
+        let mut walker = WalkDir::new(vault).min_depth(1).into_iter();
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            let Ok(relative) = entry.path().strip_prefix(vault) else {
+                continue;
+            };
+            let rel = relative.to_string_lossy().replace('\\', "/");
+            let is_dir = entry.file_type().is_dir();
+
+            let skip = if is_dir {
+                rel == ".git" || rel == INTERNAL_DIR || (is_excluded(&mut excludes, relative, true) && !has_tracked_under(base, &rel))
+            } else {
+                is_internal(&rel)
+                    || is_webdav_conflict_copy(vault, &rel)
+                    || (is_excluded(&mut excludes, relative, false) && !base.contains_key(&rel))
+            };
+            if skip {
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let data = fs::read(entry.path())?;
+            let id = if write {
+                self.repo.write_blob(&data).map_err(git_err)?.detach()
+            } else {
+                gix::objs::compute_hash(self.repo.object_hash(), gix::objs::Kind::Blob, &data)
+                    .map_err(git_err)?
+            };
+
+            let mode = match base.get(&rel) {
+                Some((old, mode)) => {
+                    if *old != id {
+                        entries.push(GitStatusEntry {
+                            path: rel.clone(),
+                            status: GitFileStatus::Modified,
+                        });
+                    }
+                    *mode
+                }
+                None => {
+                    entries.push(GitStatusEntry {
+                        path: rel.clone(),
+                        status: GitFileStatus::Added,
+                    });
+                    EntryKind::Blob.into()
+                }
+            };
+            current.insert(rel, (id, mode));
+        }
+
+        for path in base.keys().filter(|p| !current.contains_key(*p)) {
+            entries.push(GitStatusEntry {
+                path: path.clone(),
+                status: GitFileStatus::Deleted,
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok((current, entries))
+    }
+
+    fn commit(&self, message: &str, tree: ObjectId, parents: Vec<ObjectId>) -> Result<ObjectId, AppError> {
+        let signature = signature(&self.repo);
+        let mut committer_time = gix::date::parse::TimeBuf::default();
+        let mut author_time = gix::date::parse::TimeBuf::default();
+
+        let id = self
+            .repo
+            .commit_as(
+                signature.to_ref(&mut committer_time),
+                signature.to_ref(&mut author_time),
+                "HEAD",
+                message,
+                tree,
+                parents,
+            )
+            .map_err(git_err)?;
+        Ok(id.detach())
+    }
+
+    /// 移动 HEAD（及其指向的分支）
+    fn set_head(&self, old: Option<ObjectId>, new: ObjectId, message: &str) -> Result<(), AppError> {
+        let expected = match old {
+            Some(old) => PreviousValue::MustExistAndMatch(Target::Object(old)),
+            None => PreviousValue::Any,
+        };
+        update_ref(&self.repo, "HEAD", expected, new, message)
+    }
+
+    /// 用提交的树重写索引，使命令行 git 看到一致的状态
+    fn write_index(&self, tree: ObjectId) -> Result<(), AppError> {
+        let mut index = self.repo.index_from_tree(&tree).map_err(git_err)?;
+        index
+            .write(gix::index::write::Options::default())
+            .map_err(git_err)
+    }
+
+    fn commit_info(&self, id: ObjectId) -> Result<GitCommitInfo, AppError> {
+        let commit = self.repo.find_commit(id).map_err(git_err)?;
+        let author = commit.author().map_err(git_err)?;
+        let time = commit.time().map_err(git_err)?;
+
+        Ok(GitCommitInfo {
+            id: id.to_string(),
+            short_id: id.to_hex_with_len(7).to_string(),
+            message: commit.message_raw_sloppy().to_str_lossy().trim().to_string(),
+            author: author.name.to_str_lossy().into_owned(),
+            timestamp: time.seconds,
+        })
+    }
+
+    fn blob_at_rev(&self, rev: &str, path: &str) -> Result<Option<Vec<u8>>, AppError> {
+        let id = self.repo.rev_parse_single(rev).map_err(git_err)?;
+        let commit = id
+            .object()
+            .map_err(git_err)?
+            .peel_to_commit()
+            .map_err(git_err)?;
+        self.read_blob(blob_at(&commit, path)?)
+    }
+
+    fn read_blob(&self, id: Option<ObjectId>) -> Result<Option<Vec<u8>>, AppError> {
+        match id {
+            Some(id) => {
+                let blob = self.repo.find_blob(id).map_err(git_err)?;
+                Ok(Some(blob.data.clone()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// 三方合并的文件级结果
+struct MergedFiles {
+    /// 合并后的文件（冲突文件保留本地版本）
+    files: FileMap,
+    /// 需要采用远程版本的文件 (None 表示远程已删除)
+    take_theirs: Vec<(String, Option<(ObjectId, EntryMode)>)>,
+    /// 双方都修改的文件
+    conflicts: Vec<String>,
+}
+
+fn merge_files(base: &FileMap, ours: &FileMap, theirs: &FileMap) -> MergedFiles {
+    let mut merged = MergedFiles {
+        files: ours.clone(),
+        take_theirs: Vec::new(),
+        conflicts: Vec::new(),
+    };
+
+    let paths: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    for path in paths {
+        let b = base.get(path).map(|(id, _)| id);
+        let o = ours.get(path).map(|(id, _)| id);
+        let t = theirs.get(path).map(|(id, _)| id);
+
+        if o == t || t == b {
+            continue;
+        }
+
+        if o == b {
+            let entry = theirs.get(path).copied();
+            match entry {
+                Some(entry) => merged.files.insert(path.clone(), entry),
+                None => merged.files.remove(path),
+            };
+            merged.take_theirs.push((path.clone(), entry));
+        } else {
+            merged.conflicts.push(path.clone());
+        }
+    }
+
+    merged
+}
+
+fn commit_files(repo: &gix::Repository, commit: ObjectId) -> Result<FileMap, AppError> {
+    let tree = repo
+        .find_commit(commit)
+        .map_err(git_err)?
+        .tree_id()
+        .map_err(git_err)?
+        .detach();
+    let mut files = FileMap::new();
+    collect_tree(repo, tree, "", &mut files)?;
+    Ok(files)
+}
+
+fn collect_tree(repo: &gix::Repository, tree: ObjectId, prefix: &str, files: &mut FileMap) -> Result<(), AppError> {
+    for (name, id, mode) in tree_entries(repo, tree)? {
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if mode.is_tree() {
+            collect_tree(repo, id, &path, files)?;
+        } else if !mode.is_commit() {
+            files.insert(path, (id, mode));
+        }
+    }
+    Ok(())
+}
+
+fn tree_entries(repo: &gix::Repository, tree: ObjectId) -> Result<Vec<(String, ObjectId, EntryMode)>, AppError> {
+    let tree = repo.find_tree(tree).map_err(git_err)?;
+    let decoded = tree.decode().map_err(git_err)?;
+    Ok(decoded
+        .entries
+        .iter()
+        .map(|e| (e.filename.to_str_lossy().into_owned(), e.oid.to_owned(), e.mode))
+        .collect())
+}
+
+/// 由扁平的文件表逐层写入树对象，返回根树 ID
+fn write_tree(repo: &gix::Repository, files: &FileMap) -> Result<ObjectId, AppError> {
+    #[derive(Default)]
+    struct Dir {
+        files: Vec<(String, ObjectId, EntryMode)>,
+        dirs: BTreeMap<String, Dir>,
+    }
+
+    fn write_dir(repo: &gix::Repository, dir: &Dir) -> Result<ObjectId, AppError> {
+        let mut entries = Vec::with_capacity(dir.files.len() + dir.dirs.len());
+        for (name, sub) in &dir.dirs {
+            entries.push(TreeEntry {
+                mode: EntryKind::Tree.into(),
+                filename: name.as_str().into(),
+                oid: write_dir(repo, sub)?,
+            });
+        }
+        for (name, id, mode) in &dir.files {
+            entries.push(TreeEntry {
+                mode: *mode,
+                filename: name.as_str().into(),
+                oid: *id,
+            });
+        }
+        entries.sort();
+
+        let id = repo
+            .write_object(&gix::objs::Tree { entries })
+            .map_err(git_err)?;
+        Ok(id.detach())
+    }
+
+    let mut root = Dir::default();
+    for (path, (id, mode)) in files {
+        let mut parts: Vec<&str> = path.split('/').collect();
+        let name = parts.pop().unwrap_or_default();
+        let mut dir = &mut root;
+        for part in parts {
+            dir = dir.dirs.entry(part.to_string()).or_default();
+        }
+        dir.files.push((name.to_string(), *id, *mode));
+    }
+
+    write_dir(repo, &root)
+}
+
+/// 把 `tip` 可达但 `dst` 缺少的对象从 `src` 复制过去，返回复制的对象数
+fn copy_objects(src: &gix::Repository, dst: &gix::Repository, tip: ObjectId) -> Result<usize, AppError> {
+    let mut copied = 0;
+    let mut seen = HashSet::new();
+    let mut pending = vec![tip];
+
+    while let Some(id) = pending.pop() {
+        if dst.has_object(id) || !seen.insert(id) {
+            continue;
+        }
+        let commit = src.find_commit(id).map_err(git_err)?;
+        pending.extend(commit.parent_ids().map(|p| p.detach()));
+        copied += copy_tree(src, dst, commit.tree_id().map_err(git_err)?.detach())?;
+        copy_object(src, dst, id)?;
+        copied += 1;
+    }
+
+    Ok(copied)
+}
+
+fn copy_tree(src: &gix::Repository, dst: &gix::Repository, tree: ObjectId) -> Result<usize, AppError> {
+    if dst.has_object(tree) {
+        return Ok(0);
+    }
+
+    let mut copied = 0;
+    for (_, id, mode) in tree_entries(src, tree)? {
+        if mode.is_tree() {
+            copied += copy_tree(src, dst, id)?;
+        } else if !mode.is_commit() && !dst.has_object(id) {
+            copy_object(src, dst, id)?;
+            copied += 1;
+        }
+    }
+    copy_object(src, dst, tree)?;
+    Ok(copied + 1)
+}
+
+fn copy_object(src: &gix::Repository, dst: &gix::Repository, id: ObjectId) -> Result<(), AppError> {
+    let object = src.find_object(id).map_err(git_err)?;
+    dst.objects.write_buf(object.kind, &object.data).map_err(git_err)?;
+    Ok(())
+}
+
+/// 提交者身份，未配置时使用默认值
+fn signature(repo: &gix::Repository) -> gix::actor::Signature {
+    match repo.committer() {
+        Some(Ok(sig)) => sig.into(),
+        _ => gix::actor::Signature {
+            name: FALLBACK_NAME.into(),
+            email: FALLBACK_EMAIL.into(),
+            time: gix::date::Time::now_local_or_utc(),
+        },
+    }
+}
+
+/// 更新引用（符号引用会解引用到目标分支），reflog 使用 [`signature`]
+fn update_ref(
+    repo: &gix::Repository,
+    name: &str,
+    expected: PreviousValue,
+    new: ObjectId,
+    message: &str,
+) -> Result<(), AppError> {
+    let committer = signature(repo);
+    let mut time = gix::date::parse::TimeBuf::default();
+    repo.edit_references_as(
+        Some(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: message.into(),
+                },
+                expected,
+                new: Target::Object(new),
+            },
+            name: name.try_into().map_err(git_err)?,
+            deref: true,
+        }),
+        Some(committer.to_ref(&mut time)),
+    )
+    .map_err(git_err)?;
+    Ok(())
+}
+
+fn find_ref(repo: &gix::Repository, name: &str) -> Result<Option<ObjectId>, AppError> {
+    Ok(repo
+        .try_find_reference(name)
+        .map_err(git_err)?
+        .and_then(|r| r.target().try_id().map(|id| id.to_owned())))
+}
+
+fn is_ancestor(repo: &gix::Repository, ancestor: ObjectId, descendant: ObjectId) -> bool {
+    ancestor == descendant
+        || repo
+            .merge_base(ancestor, descendant)
+            .is_ok_and(|base| base.detach() == ancestor)
+}
+
+fn blob_at(commit: &gix::Commit<'_>, path: &str) -> Result<Option<ObjectId>, AppError> {
+    let tree = commit.tree().map_err(git_err)?;
+    Ok(tree
+        .lookup_entry_by_path(path)
+        .map_err(git_err)?
+        .filter(|e| !e.mode().is_tree())
+        .map(|e| e.object_id()))
+}
+
+fn is_excluded(excludes: &mut gix::AttributeStack<'_>, relative: &Path, is_dir: bool) -> bool {
+    let mode = if is_dir {
+        gix::index::entry::Mode::DIR
+    } else {
+        gix::index::entry::Mode::FILE
+    };
+    excludes
+        .at_path(relative, Some(mode))
+        .is_ok_and(|platform| platform.is_excluded())
+}
+
+fn has_tracked_under(files: &FileMap, dir: &str) -> bool {
+    let prefix = format!("{}/", dir);
+    files
+        .range(prefix.clone()..)
+        .next()
+        .is_some_and(|(path, _)| path.starts_with(&prefix))
+}
+
+fn is_internal(path: &str) -> bool {
+    INTERNAL_FILES.contains(&path)
+        || path.split('/').next() == Some(INTERNAL_DIR)
+        || path.ends_with(DOWNLOAD_TEMP_SUFFIX)
+}
+
+/// WebDAV 同步写入的冲突副本：`conflict_copy_path` 生成的 `{path}.conflict`，且原文件存在
+fn is_webdav_conflict_copy(vault: &Path, path: &str) -> bool {
+    path.strip_suffix(".conflict")
+        .is_some_and(|original| vault.join(original).is_file())
+}
+
+/// Git 合并时远程版本的冲突副本路径：`dir/note.md` → `dir/note.conflict.md`
+fn conflict_copy_name(path: &str) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), path),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}{}.conflict.{}", dir, stem, ext),
+        _ => format!("{}{}-conflict", dir, name),
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches('/').to_string()
+}
+
+/// 根据变更生成提交说明
+fn generate_commit_message(changes: &[GitStatusEntry]) -> String {
+    if let [change] = changes {
+        let verb = match change.status {
+            GitFileStatus::Added => "Add",
+            GitFileStatus::Modified => "Update",
+            GitFileStatus::Deleted => "Delete",
+        };
+        return format!("{} {}", verb, change.path);
+    }
+
+    let mut parts = Vec::new();
+    for (status, verb) in [
+        (GitFileStatus::Added, "add"),
+        (GitFileStatus::Modified, "update"),
+        (GitFileStatus::Deleted, "delete"),
+    ] {
+        let count = changes.iter().filter(|c| c.status == status).count();
+        if count > 0 {
+            parts.push(format!("{} {} {}", verb, count, if count == 1 { "note" } else { "notes" }));
+        }
+    }
+
+    let mut message = format!("Vault snapshot: {}\n\n", parts.join(", "));
+    for change in changes {
+        message.push_str(&format!("{} {}\n", change.status.marker(), change.path));
+    }
+    message
+}
+
+/// 生成统一格式差异，内容相同时返回空字符串
+fn unified_diff(path: &str, old: &[u8], new: &[u8]) -> Result<String, AppError> {
+    use gix::diff::blob::intern::InternedInput;
+    use gix::diff::blob::unified_diff::{ConsumeBinaryHunk, ContextSize};
+    use gix::diff::blob::{diff, Algorithm, UnifiedDiff};
+
+    if old == new {
+        return Ok(String::new());
+    }
+
+    let old = String::from_utf8_lossy(old);
+    let new = String::from_utf8_lossy(new);
+    let input = InternedInput::new(old.as_ref(), new.as_ref());
+    let hunks = diff(
+        Algorithm::Histogram,
+        &input,
+        UnifiedDiff::new(
+            &input,
+            ConsumeBinaryHunk::new(String::new(), "\n"),
+            ContextSize::symmetrical(3),
+        ),
+    )
+    .map_err(git_err)?;
+
+    Ok(format!("--- a/{}\n+++ b/{}\n{}", path, path, hunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(vault: &Path, path: &str, content: &str) {
+        let target = vault.join(path);
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(target, content).unwrap();
+    }
+
+    fn open(vault: &Path) -> GitVault {
+        GitVault::init(vault.to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_status_commit_and_log() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path();
+        write(vault, "notes/a.md", "# A\n");
+        write(vault, "b.md", "B\n");
+        write(vault, ".gitignore", "ignored/\n");
+        write(vault, "ignored/x.md", "x\n");
+        write(vault, ".lumina-sync-state.json", "{}");
+        write(vault, ".lumina-manifest.json", "{}");
+        write(vault, ".lumina/sync-journal.jsonl", "");
+        write(vault, "notes/.c.md.lumina-download", "partial");
+
+        let git = open(vault);
+        let status = git.status().unwrap();
+        let paths: Vec<&str> = status.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec![".gitignore", "b.md", "notes/a.md"]);
+        assert!(status.entries.iter().all(|e| e.status == GitFileStatus::Added));
+
+        let first = git.commit_all(None).unwrap().unwrap();
+        assert!(first.message.starts_with("Vault snapshot: add 3 notes"));
+        assert!(git.status().unwrap().is_clean());
+        assert!(git.commit_all(None).unwrap().is_none());
+
+        write(vault, "notes/a.md", "# A\n\nmore\n");
+        fs::remove_file(vault.join("b.md")).unwrap();
+        let status = git.status().unwrap();
+        assert_eq!(status.entries.len(), 2);
+        assert_eq!(status.entries[0].status, GitFileStatus::Deleted);
+        assert_eq!(status.entries[1].status, GitFileStatus::Modified);
+
+        let diff = git.diff("notes/a.md", None, None).unwrap();
+        assert!(diff.contains("+more"));
+
+        git.commit_all(Some("Expand A".to_string())).unwrap().unwrap();
+        let log = git.log("notes/a.md", None).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].message, "Expand A");
+        assert_eq!(git.log("b.md", Some(1)).unwrap()[0].message, "Expand A");
+    }
+
+    #[test]
+    fn test_push_and_fast_forward_pull() {
+        let dir = tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let remote = remote.to_str().unwrap();
+        GitVault::init_bare_remote(remote).unwrap();
+
+        let vault_a = dir.path().join("a");
+        write(&vault_a, "note.md", "hello\n");
+        let a = open(&vault_a);
+        a.commit_all(None).unwrap();
+        let pushed = a.push(remote).unwrap();
+        assert!(pushed.old_head.is_none());
+        assert!(pushed.objects_copied >= 3);
+
+        let vault_b = dir.path().join("b");
+        fs::create_dir_all(&vault_b).unwrap();
+        let b = open(&vault_b);
+        let result = b.pull(remote).unwrap();
+        assert_eq!(result.downloaded, 1);
+        assert_eq!(fs::read_to_string(vault_b.join("note.md")).unwrap(), "hello\n");
+        assert!(b.status().unwrap().is_clean());
+
+        write(&vault_b, "note.md", "hello\nfrom b\n");
+        b.commit_all(None).unwrap();
+        b.push(remote).unwrap();
+
+        let result = a.pull(remote).unwrap();
+        assert_eq!(result.downloaded, 1);
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(a.head_id().unwrap(), b.head_id().unwrap());
+    }
+
+    #[test]
+    fn test_diverged_pull_merges_and_reports_conflicts() {
+        let dir = tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let remote = remote.to_str().unwrap();
+        GitVault::init_bare_remote(remote).unwrap();
+
+        let vault_a = dir.path().join("a");
+        write(&vault_a, "shared.md", "base\n");
+        write(&vault_a, "other.md", "base\n");
+        let a = open(&vault_a);
+        a.commit_all(None).unwrap();
+        a.push(remote).unwrap();
+
+        let vault_b = dir.path().join("b");
+        fs::create_dir_all(&vault_b).unwrap();
+        let b = open(&vault_b);
+        b.pull(remote).unwrap();
+        write(&vault_b, "shared.md", "from b\n");
+        write(&vault_b, "other.md", "from b\n");
+        b.commit_all(None).unwrap();
+        b.push(remote).unwrap();
+
+        write(&vault_a, "shared.md", "from a\n");
+        a.commit_all(None).unwrap();
+        assert!(a.push(remote).is_err());
+
+        let result = a.pull(remote).unwrap();
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(result.downloaded, 1);
+        assert_eq!(fs::read_to_string(vault_a.join("shared.md")).unwrap(), "from a\n");
+        assert_eq!(fs::read_to_string(vault_a.join("shared.conflict.md")).unwrap(), "from b\n");
+        assert_eq!(fs::read_to_string(vault_a.join("other.md")).unwrap(), "from b\n");
+
+        // 冲突副本随合并提交推送，另一台设备拉取后能看到双方的版本
+        assert!(a.status().unwrap().is_clean());
+        assert!(a.push(remote).is_ok());
+        b.pull(remote).unwrap();
+        assert_eq!(fs::read_to_string(vault_b.join("shared.md")).unwrap(), "from a\n");
+        assert_eq!(fs::read_to_string(vault_b.join("shared.conflict.md")).unwrap(), "from b\n");
+    }
+
+    #[test]
+    fn test_only_webdav_conflict_copies_are_ignored() {
+        let dir = tempdir().unwrap();
+        let vault = dir.path().join("vault");
+        write(&vault, "note.md", "note\n");
+        write(&vault, "note.md.conflict", "remote\n");
+        write(&vault, "archive.conflict", "user file\n");
+        let repo = open(&vault);
+        repo.commit_all(None).unwrap();
+
+        let head = repo.head_files(repo.head_id().unwrap()).unwrap();
+        assert!(head.contains_key("note.md"));
+        assert!(head.contains_key("archive.conflict"));
+        assert!(!head.contains_key("note.md.conflict"));
+
+        assert_eq!(conflict_copy_name("a/b/note.md"), "a/b/note.conflict.md");
+        assert_eq!(conflict_copy_name("Makefile"), "Makefile-conflict");
+        assert_eq!(conflict_copy_name(".env"), ".env-conflict");
+    }
+
+    #[test]
+    fn test_generate_commit_message() {
+        let single = vec![GitStatusEntry {
+            path: "a.md".to_string(),
+            status: GitFileStatus::Modified,
+        }];
+        assert_eq!(generate_commit_message(&single), "Update a.md");
+    }
+}
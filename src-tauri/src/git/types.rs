@@ -0,0 +1,81 @@
+//! Git 版本管理类型定义
+
+use serde::{Deserialize, Serialize};
+
+/// 工作区文件状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitFileStatus {
+    /// 新文件
+    Added,
+    /// 已修改
+    Modified,
+    /// 已删除
+    Deleted,
+}
+
+impl GitFileStatus {
+    /// 单字母标记，与 `git status --short` 一致
+    pub fn marker(&self) -> char {
+        match self {
+            GitFileStatus::Added => 'A',
+            GitFileStatus::Modified => 'M',
+            GitFileStatus::Deleted => 'D',
+        }
+    }
+}
+
+/// 单个文件的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatusEntry {
+    /// 相对 vault 的路径
+    pub path: String,
+    /// 状态
+    pub status: GitFileStatus,
+}
+
+/// 仓库状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatus {
+    /// 当前分支 (detached 时为 None)
+    pub branch: Option<String>,
+    /// HEAD 提交 (尚无提交时为 None)
+    pub head: Option<String>,
+    /// 相对 HEAD 的变更
+    pub entries: Vec<GitStatusEntry>,
+}
+
+impl GitStatus {
+    /// 工作区是否干净
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// 提交信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitCommitInfo {
+    /// 完整提交 ID
+    pub id: String,
+    /// 短 ID
+    pub short_id: String,
+    /// 提交说明
+    pub message: String,
+    /// 作者
+    pub author: String,
+    /// 提交时间 (Unix 时间戳)
+    pub timestamp: i64,
+}
+
+/// 推送结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitPushResult {
+    /// 推送的分支
+    pub branch: String,
+    /// 远程原来的提交
+    pub old_head: Option<String>,
+    /// 远程新的提交
+    pub new_head: String,
+    /// 复制的对象数
+    pub objects_copied: usize,
+}
@@ -155,6 +155,9 @@ pub struct ConfigComparison {
     pub latency_delta_pct: f64,
     /// Change in token usage vs baseline (negative = fewer)
     pub token_delta_pct: f64,
+    /// Change in cost vs baseline (negative = cheaper)
+    #[serde(default)]
+    pub cost_delta_pct: f64,
     /// Change in success rate vs baseline
     pub success_rate_delta: f64,
     /// Change in quality score vs baseline
@@ -171,6 +174,9 @@ pub struct NodeContribution {
     pub latency_contribution_pct: f64,
     /// Percentage of total tokens attributed to this node
     pub token_contribution_pct: f64,
+    /// Percentage of total cost attributed to this node
+    #[serde(default)]
+    pub cost_contribution_pct: f64,
     /// Impact on success rate when removed
     pub success_rate_impact: f64,
     /// Impact on quality when removed
@@ -257,6 +263,12 @@ impl AblationReport {
             0.0
         };
 
+        let cost_delta_pct = if baseline.avg_cost_usd > 0.0 {
+            ((stats.avg_cost_usd - baseline.avg_cost_usd) / baseline.avg_cost_usd) * 100.0
+        } else {
+            0.0
+        };
+
         let success_rate_delta = stats.success_rate - baseline.success_rate;
 
         let quality_delta = match (stats.avg_quality_score, baseline.avg_quality_score) {
@@ -281,6 +293,7 @@ impl AblationReport {
             config_name: name.to_string(),
             latency_delta_pct,
             token_delta_pct,
+            cost_delta_pct,
             success_rate_delta,
             quality_delta,
             assessment,
@@ -322,6 +335,12 @@ impl AblationReport {
                 0.0
             };
 
+            let cost_contribution = if baseline.avg_cost_usd > 0.0 {
+                (node_stats.avg_cost_usd * node_stats.call_rate) / baseline.avg_cost_usd * 100.0
+            } else {
+                0.0
+            };
+
             // Calculate importance score
             // High importance = high impact on success/quality when removed
             // Low importance = low impact but high cost
@@ -340,6 +359,7 @@ impl AblationReport {
                 node: node_name.clone(),
                 latency_contribution_pct: latency_contribution,
                 token_contribution_pct: token_contribution,
+                cost_contribution_pct: cost_contribution,
                 success_rate_impact: success_impact,
                 quality_impact,
                 importance_score: importance,
@@ -450,15 +470,16 @@ impl AblationReport {
 
         // Summary table
         md.push_str("## Configuration Comparison\n\n");
-        md.push_str("| Configuration | Latency Δ | Token Δ | Success Rate Δ | Assessment |\n");
-        md.push_str("|---------------|-----------|---------|----------------|------------|\n");
+        md.push_str("| Configuration | Latency Δ | Token Δ | Cost Δ | Success Rate Δ | Assessment |\n");
+        md.push_str("|---------------|-----------|---------|--------|----------------|------------|\n");
 
         for comp in &self.comparisons {
             md.push_str(&format!(
-                "| {} | {:+.1}% | {:+.1}% | {:+.1}% | {} |\n",
+                "| {} | {:+.1}% | {:+.1}% | {:+.1}% | {:+.1}% | {} |\n",
                 comp.config_name,
                 comp.latency_delta_pct,
                 comp.token_delta_pct,
+                comp.cost_delta_pct,
                 comp.success_rate_delta * 100.0,
                 comp.assessment
             ));
@@ -466,8 +487,8 @@ impl AblationReport {
 
         // Node contributions
         md.push_str("\n## Node Contribution Analysis\n\n");
-        md.push_str("| Node | Latency % | Token % | Success Impact | Recommendation |\n");
-        md.push_str("|------|-----------|---------|----------------|----------------|\n");
+        md.push_str("| Node | Latency % | Token % | Cost % | Success Impact | Recommendation |\n");
+        md.push_str("|------|-----------|---------|--------|----------------|----------------|\n");
 
         for contrib in &self.node_contributions {
             let rec_str = match contrib.recommendation {
@@ -479,10 +500,11 @@ impl AblationReport {
             };
 
             md.push_str(&format!(
-                "| {} | {:.1}% | {:.1}% | {:+.1}% | {} |\n",
+                "| {} | {:.1}% | {:.1}% | {:.1}% | {:+.1}% | {} |\n",
                 contrib.node,
                 contrib.latency_contribution_pct,
                 contrib.token_contribution_pct,
+                contrib.cost_contribution_pct,
                 contrib.success_rate_impact * 100.0,
                 rec_str
             ));
@@ -617,6 +639,7 @@ mod tests {
             p50_latency_ms: 900,
             p95_latency_ms: 1500,
             avg_tokens: 500.0,
+            avg_cost_usd: 0.02,
             avg_quality_score: Some(0.85),
             node_stats: HashMap::new(),
         };
@@ -629,6 +652,7 @@ mod tests {
             p50_latency_ms: 650,
             p95_latency_ms: 1000,
             avg_tokens: 350.0,
+            avg_cost_usd: 0.015,
             avg_quality_score: Some(0.80),
             node_stats: HashMap::new(),
         };
@@ -637,6 +661,7 @@ mod tests {
         
         assert!(comparison.latency_delta_pct < 0.0); // Faster
         assert!(comparison.token_delta_pct < 0.0);   // Fewer tokens
+        assert!((comparison.cost_delta_pct + 25.0).abs() < 1e-9); // 25% cheaper
         assert!(comparison.success_rate_delta < 0.0); // Lower success
     }
 }
@@ -127,6 +127,13 @@ impl<S: GraphState> Clone for BranchSpec<S> {
     }
 }
 
+/// Build a path map for a router that returns node names directly
+/// 
+/// Declaring the destinations lets the graph validate and draw the branch.
+pub fn path_map<'a>(targets: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+    targets.into_iter().map(|t| (t.to_string(), t.to_string())).collect()
+}
+
 /// Common routing patterns
 pub mod patterns {
     use super::*;
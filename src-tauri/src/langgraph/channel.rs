@@ -2,8 +2,13 @@
 //! 
 //! Channels provide different strategies for aggregating state updates.
 //! This is an advanced feature for complex state management scenarios.
+//!
+//! A [`StateChannel`] binds a channel to one field of the graph state; the
+//! executor uses these bindings to apply partial updates and to merge the
+//! outputs of parallel branches.
 
 use std::any::Any;
+use std::sync::Arc;
 
 /// Channel trait for state field management
 pub trait Channel: Send + Sync {
@@ -157,6 +162,183 @@ where
     }
 }
 
+type ChannelFactory = Arc<dyn Fn() -> Box<dyn Channel> + Send + Sync>;
+type ReadFn<S> = Arc<dyn Fn(&S) -> Box<dyn Any + Send + Sync> + Send + Sync>;
+type WriteFn<S> = Arc<dyn Fn(&S, &S) -> Option<Box<dyn Any + Send + Sync>> + Send + Sync>;
+type StoreFn<S> = Arc<dyn Fn(&mut S, &dyn Any) + Send + Sync>;
+
+/// Binds a channel to one field of the state
+///
+/// A write from an update node is fed through a fresh channel seeded with the
+/// current value, and the reduced result is stored back. When several branches
+/// run in parallel, every branch's write goes through the same channel. Fields
+/// without a binding take the change of the one branch that made it (see
+/// `GraphState::merge_branch`; branches dispatched by a `SendPacket` only write
+/// through channels). Bind a channel to every field that more than one branch
+/// changes.
+pub struct StateChannel<S> {
+    key: String,
+    /// Type written to this field (for error messages)
+    value_type: &'static str,
+    /// Checks that a partial update has a type the channel accepts
+    accepts: fn(&dyn Any) -> bool,
+    /// Creates the channel used for one merge
+    factory: ChannelFactory,
+    /// Reads the current value of the field
+    read: ReadFn<S>,
+    /// Extracts what a branch wrote, given its input and output state
+    write: WriteFn<S>,
+    /// Stores the reduced value back into the state
+    store: StoreFn<S>,
+}
+
+impl<S: 'static> StateChannel<S> {
+    /// Last write wins (`LastValue`), in branch dispatch order
+    ///
+    /// Branches that left the field unchanged do not write.
+    pub fn last_value<T>(key: impl Into<String>, get: fn(&S) -> &T, get_mut: fn(&mut S) -> &mut T) -> Self
+    where
+        T: Clone + PartialEq + Send + Sync + 'static,
+    {
+        let key = key.into();
+        let name = key.clone();
+        Self {
+            key,
+            value_type: std::any::type_name::<T>(),
+            accepts: |value| value.is::<T>(),
+            factory: Arc::new(move || Box::new(LastValue::<T>::new(name.clone()))),
+            read: Arc::new(move |s: &S| Box::new(get(s).clone())),
+            write: Arc::new(move |input: &S, output: &S| {
+                let value = get(output);
+                (value != get(input)).then(|| Box::new(value.clone()) as Box<dyn Any + Send + Sync>)
+            }),
+            store: Self::store_fn(get_mut),
+        }
+    }
+
+    /// Append items to a list (`AppendChannel`)
+    ///
+    /// Updates may write a single `T` or a `Vec<T>`. A parallel branch's write
+    /// is the part of its list past the length of its input.
+    pub fn append<T>(key: impl Into<String>, get: fn(&S) -> &Vec<T>, get_mut: fn(&mut S) -> &mut Vec<T>) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let key = key.into();
+        let name = key.clone();
+        Self {
+            key,
+            value_type: std::any::type_name::<T>(),
+            accepts: |value| value.is::<T>() || value.is::<Vec<T>>(),
+            factory: Arc::new(move || Box::new(AppendChannel::<T>::new(name.clone()))),
+            read: Arc::new(move |s: &S| Box::new(get(s).clone())),
+            write: Arc::new(move |input: &S, output: &S| {
+                let added = get(output).get(get(input).len()..).unwrap_or_default();
+                (!added.is_empty()).then(|| Box::new(added.to_vec()) as Box<dyn Any + Send + Sync>)
+            }),
+            store: Self::store_fn(get_mut),
+        }
+    }
+
+    /// Fold written values into the current value with a reducer (`BinaryOpChannel`)
+    ///
+    /// Update writes are reduced as deltas (e.g. `reducers::add` increments a
+    /// counter). Full-state branches contribute their whole changed value, which
+    /// suits `max`/`min`; for additive reducers return updates or dispatch
+    /// branches with fresh state so they do not re-add the current value.
+    pub fn reduce<T, F>(
+        key: impl Into<String>,
+        get: fn(&S) -> &T,
+        get_mut: fn(&mut S) -> &mut T,
+        reducer: F,
+    ) -> Self
+    where
+        T: Clone + PartialEq + Send + Sync + 'static,
+        F: Fn(T, T) -> T + Clone + Send + Sync + 'static,
+    {
+        let key = key.into();
+        let name = key.clone();
+        Self {
+            key,
+            value_type: std::any::type_name::<T>(),
+            accepts: |value| value.is::<T>(),
+            factory: Arc::new(move || Box::new(BinaryOpChannel::new(name.clone(), reducer.clone()))),
+            read: Arc::new(move |s: &S| Box::new(get(s).clone())),
+            write: Arc::new(move |input: &S, output: &S| {
+                let value = get(output);
+                (value != get(input)).then(|| Box::new(value.clone()) as Box<dyn Any + Send + Sync>)
+            }),
+            store: Self::store_fn(get_mut),
+        }
+    }
+
+    fn store_fn<T: Clone + 'static>(get_mut: fn(&mut S) -> &mut T) -> StoreFn<S> {
+        Arc::new(move |s: &mut S, value: &dyn Any| {
+            if let Some(v) = value.downcast_ref::<T>() {
+                *get_mut(s) = v.clone();
+            }
+        })
+    }
+}
+
+impl<S> StateChannel<S> {
+    /// Get the state field this channel is bound to
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Apply a partial update to `state`
+    ///
+    /// Returns an error message when the value has a type the channel does not accept.
+    pub fn apply(&self, state: &mut S, value: Box<dyn Any + Send + Sync>) -> Result<(), String> {
+        if !(self.accepts)(&*value) {
+            return Err(format!("expected a value of type {}", self.value_type));
+        }
+        let mut channel = (self.factory)();
+        channel.update((self.read)(state));
+        channel.update(value);
+        if let Some(value) = channel.get() {
+            (self.store)(state, value);
+        }
+        Ok(())
+    }
+
+    /// Merge branch results into `state`
+    ///
+    /// `branches` holds the input and output state of every branch, in dispatch order.
+    pub fn merge(&self, state: &mut S, branches: &[(S, S)]) {
+        let mut channel = (self.factory)();
+        channel.update((self.read)(state));
+        for (input, output) in branches {
+            if let Some(value) = (self.write)(input, output) {
+                channel.update(value);
+            }
+        }
+        if let Some(value) = channel.get() {
+            (self.store)(state, value);
+        }
+    }
+
+    /// Copy the bound field from `from` into `to`
+    pub fn copy(&self, from: &S, to: &mut S) {
+        (self.store)(to, &*(self.read)(from));
+    }
+}
+
+impl<S> Clone for StateChannel<S> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            value_type: self.value_type,
+            accepts: self.accepts,
+            factory: self.factory.clone(),
+            read: self.read.clone(),
+            write: self.write.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
 /// Common reducer functions
 pub mod reducers {
     /// Add numbers
@@ -0,0 +1,688 @@
+//! Checkpoint persistence for graph executions
+//!
+//! A `Checkpointer` stores a snapshot of the state after every executed node,
+//! keyed by thread id and step. Any run can then be listed, resumed after an
+//! application restart, or replayed and forked from an earlier step into a
+//! new thread that records where it branched off.
+//!
+//! # Example
+//! ```rust
+//! let checkpointer = Arc::new(SqliteCheckpointer::open("checkpoints.db")?);
+//! let graph = graph.compile()?
+//!     .with_name("agent")
+//!     .with_checkpointer(checkpointer, "thread-1");
+//!
+//! graph.invoke(initial_state).await?;
+//!
+//! // Later (e.g. after a restart): continue from the latest snapshot
+//! if let Some(checkpoint) = graph.load_checkpoint(None)? {
+//!     graph.continue_from(checkpoint).await?;
+//! }
+//!
+//! // Time travel: re-run from step 2 with a different plan
+//! let fork = graph.fork("thread-1", 2, |state| state.plan = new_plan).await?;
+//! println!("forked into {}", fork.thread_id);
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::langgraph::constants::END;
+use crate::langgraph::error::{GraphError, GraphResult, Interrupt};
+use crate::langgraph::executor::Checkpoint;
+
+/// A persisted snapshot taken after a node has executed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckpointRecord {
+    /// Thread (run) identifier
+    pub thread_id: String,
+    /// Name of the graph that produced the snapshot
+    pub graph: String,
+    /// Step number (0 is the initial state before any node ran)
+    pub step: usize,
+    /// Node that produced this state (`START` for step 0)
+    pub node: String,
+    /// Node to execute next (`END` when the run finished)
+    pub next_node: String,
+    /// Serialized state
+    pub state: serde_json::Value,
+    /// Interrupts waiting for human input
+    #[serde(default)]
+    pub pending_interrupts: Vec<Interrupt>,
+    /// Resume values collected so far
+    #[serde(default)]
+    pub resume_values: HashMap<String, serde_json::Value>,
+    /// Checkpoints of interrupted subgraph runs, keyed by subgraph node
+    #[serde(default)]
+    pub subgraphs: HashMap<String, serde_json::Value>,
+    /// Thread this one was forked from
+    #[serde(default)]
+    pub parent_thread_id: Option<String>,
+    /// Step of the parent thread the fork started at
+    #[serde(default)]
+    pub parent_step: Option<usize>,
+    /// Creation time (Unix timestamp, milliseconds)
+    pub created_at: i64,
+}
+
+impl CheckpointRecord {
+    /// Whether the run finished at this snapshot
+    pub fn is_complete(&self) -> bool {
+        self.next_node == END
+    }
+
+    /// Whether the run is paused waiting for human input
+    pub fn is_interrupted(&self) -> bool {
+        !self.pending_interrupts.is_empty()
+    }
+
+    /// Decode into a typed checkpoint that the executor can continue from
+    pub fn to_checkpoint<S: DeserializeOwned>(&self) -> GraphResult<Checkpoint<S>> {
+        let state = serde_json::from_value(self.state.clone()).map_err(|e| {
+            GraphError::CheckpointError(format!(
+                "Failed to decode state of thread '{}' at step {}: {}",
+                self.thread_id, self.step, e
+            ))
+        })?;
+
+        Ok(Checkpoint {
+            state,
+            next_node: self.next_node.clone(),
+            pending_interrupts: self.pending_interrupts.clone(),
+            iterations: self.step,
+            resume_values: self.resume_values.clone(),
+            subgraphs: self.subgraphs.clone(),
+        })
+    }
+}
+
+/// Summary of one thread, built from its latest snapshot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThreadSummary {
+    /// Thread (run) identifier
+    pub thread_id: String,
+    /// Name of the graph
+    pub graph: String,
+    /// Latest step
+    pub latest_step: usize,
+    /// Node executed at the latest step
+    pub last_node: String,
+    /// Node to execute next
+    pub next_node: String,
+    /// Whether the run finished
+    pub complete: bool,
+    /// Whether the run is paused waiting for human input
+    pub interrupted: bool,
+    /// Thread this one was forked from
+    #[serde(default)]
+    pub parent_thread_id: Option<String>,
+    /// Step of the parent thread the fork started at
+    #[serde(default)]
+    pub parent_step: Option<usize>,
+    /// First snapshot time (Unix timestamp, milliseconds)
+    pub created_at: i64,
+    /// Latest snapshot time (Unix timestamp, milliseconds)
+    pub updated_at: i64,
+}
+
+/// Storage backend for checkpoints
+pub trait Checkpointer: Send + Sync {
+    /// Save a snapshot, replacing any existing one with the same thread and step
+    fn put(&self, record: &CheckpointRecord) -> GraphResult<()>;
+
+    /// Get a snapshot; `None` step means the latest one
+    fn get(&self, thread_id: &str, step: Option<usize>) -> GraphResult<Option<CheckpointRecord>>;
+
+    /// List all snapshots of a thread, oldest first
+    fn list(&self, thread_id: &str) -> GraphResult<Vec<CheckpointRecord>>;
+
+    /// List threads, most recently updated first, optionally filtered by graph name
+    fn list_threads(&self, graph: Option<&str>) -> GraphResult<Vec<ThreadSummary>>;
+
+    /// Delete all snapshots of a thread
+    fn delete_thread(&self, thread_id: &str) -> GraphResult<()>;
+}
+
+/// SQLite-backed checkpointer
+pub struct SqliteCheckpointer {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteCheckpointer {
+    /// Open (or create) a checkpoint database at `path`
+    pub fn open(path: impl AsRef<Path>) -> GraphResult<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                GraphError::CheckpointError(format!("Failed to create checkpoint directory: {}", e))
+            })?;
+        }
+        let conn = Connection::open(path).map_err(db_error("open checkpoint database"))?;
+        Self::from_connection(conn)
+    }
+
+    /// Create an in-memory checkpointer (useful for tests)
+    pub fn in_memory() -> GraphResult<Self> {
+        let conn = Connection::open_in_memory().map_err(db_error("open checkpoint database"))?;
+        Self::from_connection(conn)
+    }
+
+    fn from_connection(conn: Connection) -> GraphResult<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS checkpoints (
+                thread_id TEXT NOT NULL,
+                step INTEGER NOT NULL,
+                graph TEXT NOT NULL,
+                node TEXT NOT NULL,
+                next_node TEXT NOT NULL,
+                state TEXT NOT NULL,
+                pending_interrupts TEXT NOT NULL,
+                resume_values TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                parent_thread_id TEXT,
+                parent_step INTEGER,
+                subgraphs TEXT NOT NULL DEFAULT '{}',
+                PRIMARY KEY (thread_id, step)
+            );
+            CREATE INDEX IF NOT EXISTS idx_checkpoints_graph ON checkpoints(graph);",
+        )
+        .map_err(db_error("create checkpoints table"))?;
+
+        // Databases created before forking was supported lack the parent columns
+        let has_parent: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('checkpoints') WHERE name = 'parent_thread_id'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(db_error("inspect checkpoints table"))?;
+        if !has_parent {
+            conn.execute_batch(
+                "ALTER TABLE checkpoints ADD COLUMN parent_thread_id TEXT;
+                 ALTER TABLE checkpoints ADD COLUMN parent_step INTEGER;",
+            )
+            .map_err(db_error("migrate checkpoints table"))?;
+        }
+        let has_subgraphs: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('checkpoints') WHERE name = 'subgraphs'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(db_error("inspect checkpoints table"))?;
+        if !has_subgraphs {
+            conn.execute_batch("ALTER TABLE checkpoints ADD COLUMN subgraphs TEXT NOT NULL DEFAULT '{}';")
+                .map_err(db_error("migrate checkpoints table"))?;
+        }
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn lock(&self) -> GraphResult<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| GraphError::CheckpointError("Checkpoint database lock poisoned".to_string()))
+    }
+}
+
+const SELECT_COLUMNS: &str = "thread_id, graph, step, node, next_node, state, pending_interrupts, resume_values, \
+     created_at, parent_thread_id, parent_step, subgraphs";
+
+/// Raw row before JSON columns are decoded
+type CheckpointRow = (
+    String, String, i64, String, String, String, String, String, i64, Option<String>, Option<i64>, String,
+);
+
+fn read_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<CheckpointRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+        row.get(9)?,
+        row.get(10)?,
+        row.get(11)?,
+    ))
+}
+
+fn decode_row(row: CheckpointRow) -> GraphResult<CheckpointRecord> {
+    let (
+        thread_id, graph, step, node, next_node, state, interrupts, resume_values, created_at, parent_thread_id, parent_step,
+        subgraphs,
+    ) = row;
+    Ok(CheckpointRecord {
+        thread_id,
+        graph,
+        step: step as usize,
+        node,
+        next_node,
+        state: serde_json::from_str(&state).map_err(json_error)?,
+        pending_interrupts: serde_json::from_str(&interrupts).map_err(json_error)?,
+        resume_values: serde_json::from_str(&resume_values).map_err(json_error)?,
+        subgraphs: serde_json::from_str(&subgraphs).map_err(json_error)?,
+        parent_thread_id,
+        parent_step: parent_step.map(|s| s as usize),
+        created_at,
+    })
+}
+
+impl Checkpointer for SqliteCheckpointer {
+    fn put(&self, record: &CheckpointRecord) -> GraphResult<()> {
+        let state = serde_json::to_string(&record.state).map_err(json_error)?;
+        let interrupts = serde_json::to_string(&record.pending_interrupts).map_err(json_error)?;
+        let resume_values = serde_json::to_string(&record.resume_values).map_err(json_error)?;
+        let subgraphs = serde_json::to_string(&record.subgraphs).map_err(json_error)?;
+
+        self.lock()?
+            .execute(
+                "INSERT OR REPLACE INTO checkpoints
+                 (thread_id, step, graph, node, next_node, state, pending_interrupts, resume_values, created_at,
+                  parent_thread_id, parent_step, subgraphs)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    record.thread_id,
+                    record.step as i64,
+                    record.graph,
+                    record.node,
+                    record.next_node,
+                    state,
+                    interrupts,
+                    resume_values,
+                    record.created_at,
+                    record.parent_thread_id,
+                    record.parent_step.map(|s| s as i64),
+                    subgraphs,
+                ],
+            )
+            .map_err(db_error("save checkpoint"))?;
+        Ok(())
+    }
+
+    fn get(&self, thread_id: &str, step: Option<usize>) -> GraphResult<Option<CheckpointRecord>> {
+        let conn = self.lock()?;
+        let row = match step {
+            Some(step) => conn
+                .query_row(
+                    &format!("SELECT {} FROM checkpoints WHERE thread_id = ?1 AND step = ?2", SELECT_COLUMNS),
+                    params![thread_id, step as i64],
+                    read_row,
+                )
+                .optional(),
+            None => conn
+                .query_row(
+                    &format!(
+                        "SELECT {} FROM checkpoints WHERE thread_id = ?1 ORDER BY step DESC LIMIT 1",
+                        SELECT_COLUMNS
+                    ),
+                    params![thread_id],
+                    read_row,
+                )
+                .optional(),
+        }
+        .map_err(db_error("load checkpoint"))?;
+
+        row.map(decode_row).transpose()
+    }
+
+    fn list(&self, thread_id: &str) -> GraphResult<Vec<CheckpointRecord>> {
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM checkpoints WHERE thread_id = ?1 ORDER BY step ASC",
+                SELECT_COLUMNS
+            ))
+            .map_err(db_error("list checkpoints"))?;
+        let rows = stmt
+            .query_map(params![thread_id], read_row)
+            .map_err(db_error("list checkpoints"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error("list checkpoints"))?;
+
+        rows.into_iter().map(decode_row).collect()
+    }
+
+    fn list_threads(&self, graph: Option<&str>) -> GraphResult<Vec<ThreadSummary>> {
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.thread_id, c.graph, c.step, c.node, c.next_node, c.pending_interrupts,
+                        t.created_at, c.created_at, c.parent_thread_id, c.parent_step
+                 FROM checkpoints c
+                 JOIN (SELECT thread_id, MAX(step) AS step, MIN(created_at) AS created_at
+                       FROM checkpoints GROUP BY thread_id) t
+                   ON c.thread_id = t.thread_id AND c.step = t.step
+                 WHERE ?1 IS NULL OR c.graph = ?1
+                 ORDER BY c.created_at DESC",
+            )
+            .map_err(db_error("list threads"))?;
+
+        let rows = stmt
+            .query_map(params![graph], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, i64>(6)?,
+                    row.get::<_, i64>(7)?,
+                    row.get::<_, Option<String>>(8)?,
+                    row.get::<_, Option<i64>>(9)?,
+                ))
+            })
+            .map_err(db_error("list threads"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error("list threads"))?;
+
+        rows.into_iter()
+            .map(|(thread_id, graph, step, last_node, next_node, interrupts, created_at, updated_at, parent_thread_id, parent_step)| {
+                let interrupts: Vec<Interrupt> = serde_json::from_str(&interrupts).map_err(json_error)?;
+                Ok(ThreadSummary {
+                    thread_id,
+                    graph,
+                    latest_step: step as usize,
+                    last_node,
+                    complete: next_node == END,
+                    next_node,
+                    interrupted: !interrupts.is_empty(),
+                    parent_thread_id,
+                    parent_step: parent_step.map(|s| s as usize),
+                    created_at,
+                    updated_at,
+                })
+            })
+            .collect()
+    }
+
+    fn delete_thread(&self, thread_id: &str) -> GraphResult<()> {
+        self.lock()?
+            .execute("DELETE FROM checkpoints WHERE thread_id = ?1", params![thread_id])
+            .map_err(db_error("delete thread"))?;
+        Ok(())
+    }
+}
+
+fn db_error(action: &'static str) -> impl Fn(rusqlite::Error) -> GraphError {
+    move |e| GraphError::CheckpointError(format!("Failed to {}: {}", action, e))
+}
+
+fn json_error(e: serde_json::Error) -> GraphError {
+    GraphError::CheckpointError(format!("Invalid checkpoint data: {}", e))
+}
+
+/// State encoder captured when a checkpointer is attached to a typed graph
+pub(crate) type StateEncoder<S> = Arc<dyn Fn(&S) -> GraphResult<serde_json::Value> + Send + Sync>;
+
+/// A checkpointer bound to one thread of a compiled graph
+pub(crate) struct CheckpointBinding<S> {
+    pub(crate) checkpointer: Arc<dyn Checkpointer>,
+    pub(crate) thread_id: String,
+    /// Thread and step this thread was forked from
+    pub(crate) parent: Option<(String, usize)>,
+    pub(crate) encode: StateEncoder<S>,
+}
+
+impl<S> Clone for CheckpointBinding<S> {
+    fn clone(&self) -> Self {
+        Self {
+            checkpointer: Arc::clone(&self.checkpointer),
+            thread_id: self.thread_id.clone(),
+            parent: self.parent.clone(),
+            encode: Arc::clone(&self.encode),
+        }
+    }
+}
+
+/// Run a checkpointer call on the blocking thread pool
+///
+/// Store implementations such as [`SqliteCheckpointer`] do synchronous I/O,
+/// which must not stall the async executor between nodes.
+pub(crate) async fn run_blocking<T, F>(checkpointer: &Arc<dyn Checkpointer>, f: F) -> GraphResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn Checkpointer) -> GraphResult<T> + Send + 'static,
+{
+    let checkpointer = Arc::clone(checkpointer);
+    tokio::task::spawn_blocking(move || f(checkpointer.as_ref()))
+        .await
+        .map_err(|e| GraphError::CheckpointError(format!("Checkpoint task failed: {}", e)))?
+}
+
+/// Current time in milliseconds
+pub(crate) fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::langgraph::constants::START;
+    use crate::langgraph::graph::StateGraph;
+    use crate::langgraph::state::GraphState;
+    use crate::langgraph::executor::ExecutionResult;
+
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    struct CountState {
+        visited: Vec<String>,
+        approved: bool,
+    }
+
+    impl GraphState for CountState {}
+
+    fn record(thread_id: &str, step: usize, next_node: &str) -> CheckpointRecord {
+        CheckpointRecord {
+            thread_id: thread_id.to_string(),
+            graph: "test".to_string(),
+            step,
+            node: "a".to_string(),
+            next_node: next_node.to_string(),
+            state: serde_json::json!({ "visited": [], "approved": false }),
+            pending_interrupts: vec![],
+            resume_values: HashMap::new(),
+            subgraphs: HashMap::new(),
+            parent_thread_id: None,
+            parent_step: None,
+            created_at: step as i64,
+        }
+    }
+
+    #[test]
+    fn test_sqlite_put_get_list() {
+        let cp = SqliteCheckpointer::in_memory().unwrap();
+        cp.put(&record("t1", 0, "a")).unwrap();
+        cp.put(&record("t1", 1, "b")).unwrap();
+        cp.put(&record("t2", 0, END)).unwrap();
+
+        assert_eq!(cp.get("t1", None).unwrap().unwrap().step, 1);
+        assert_eq!(cp.get("t1", Some(0)).unwrap().unwrap().next_node, "a");
+        assert!(cp.get("t1", Some(5)).unwrap().is_none());
+        assert_eq!(cp.list("t1").unwrap().len(), 2);
+
+        let threads = cp.list_threads(None).unwrap();
+        assert_eq!(threads.len(), 2);
+        let t1 = threads.iter().find(|t| t.thread_id == "t1").unwrap();
+        assert_eq!(t1.latest_step, 1);
+        assert!(!t1.complete);
+        assert!(threads.iter().find(|t| t.thread_id == "t2").unwrap().complete);
+        assert!(cp.list_threads(Some("other")).unwrap().is_empty());
+
+        cp.delete_thread("t1").unwrap();
+        assert!(cp.list("t1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_old_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old.db");
+        Connection::open(&path).unwrap().execute_batch(
+            "CREATE TABLE checkpoints (
+                thread_id TEXT NOT NULL, step INTEGER NOT NULL, graph TEXT NOT NULL,
+                node TEXT NOT NULL, next_node TEXT NOT NULL, state TEXT NOT NULL,
+                pending_interrupts TEXT NOT NULL, resume_values TEXT NOT NULL,
+                created_at INTEGER NOT NULL, PRIMARY KEY (thread_id, step)
+            );
+            INSERT INTO checkpoints VALUES ('t1', 0, 'test', 'a', 'b', '{}', '[]', '{}', 0);",
+        ).unwrap();
+
+        let cp = SqliteCheckpointer::open(&path).unwrap();
+        let old = cp.get("t1", None).unwrap().unwrap();
+        assert!(old.parent_thread_id.is_none());
+        assert!(old.subgraphs.is_empty());
+        cp.put(&record("t1", 1, END)).unwrap();
+        assert_eq!(cp.list("t1").unwrap().len(), 2);
+    }
+
+    fn build_graph() -> StateGraph<CountState> {
+        let mut graph = StateGraph::<CountState>::new();
+        graph.add_node("a", |mut s: CountState| async move {
+            s.visited.push("a".to_string());
+            Ok(s)
+        });
+        graph.add_node("b", |mut s: CountState| async move {
+            if !s.approved {
+                return crate::langgraph::error::interrupt("approve?", "b");
+            }
+            s.visited.push("b".to_string());
+            Ok(s)
+        });
+        graph.add_edge(START, "a");
+        graph.add_edge("a", "b");
+        graph.add_edge("b", END);
+        graph
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoints.db");
+
+        // First "process": run until the interrupt at node b
+        {
+            let cp: Arc<dyn Checkpointer> = Arc::new(SqliteCheckpointer::open(&path).unwrap());
+            let graph = build_graph().compile().unwrap()
+                .with_name("test")
+                .with_checkpointer(cp.clone(), "thread-1");
+            let result = graph.invoke_resumable(CountState::default()).await.unwrap();
+            assert!(matches!(result, ExecutionResult::Interrupted { .. }));
+
+            let steps: Vec<String> = cp.list("thread-1").unwrap().into_iter().map(|r| r.node).collect();
+            assert_eq!(steps, vec![START.to_string(), "a".to_string(), "b".to_string()]);
+            assert!(cp.list_threads(Some("test")).unwrap()[0].interrupted);
+        }
+
+        // Second "process": reopen the database and continue
+        let cp: Arc<dyn Checkpointer> = Arc::new(SqliteCheckpointer::open(&path).unwrap());
+        let graph = build_graph().compile().unwrap()
+            .with_name("test")
+            .with_checkpointer(cp.clone(), "thread-1");
+
+        let mut checkpoint = graph.load_checkpoint(None).unwrap().unwrap();
+        assert_eq!(checkpoint.next_node, "b");
+        checkpoint.state.approved = true;
+
+        match graph.continue_from(checkpoint).await.unwrap() {
+            ExecutionResult::Complete(state) => assert_eq!(state.visited, vec!["a", "b"]),
+            ExecutionResult::Interrupted { .. } => panic!("expected completion"),
+        }
+        assert!(cp.get("thread-1", None).unwrap().unwrap().is_complete());
+
+        // Replay from the initial snapshot
+        let initial = graph.load_checkpoint(Some(0)).unwrap().unwrap();
+        assert!(initial.state.visited.is_empty());
+        assert_eq!(initial.next_node, "a");
+    }
+
+    /// Records the thread each write runs on
+    struct ThreadRecorder {
+        inner: SqliteCheckpointer,
+        threads: Mutex<Vec<std::thread::ThreadId>>,
+    }
+
+    impl Checkpointer for ThreadRecorder {
+        fn put(&self, record: &CheckpointRecord) -> GraphResult<()> {
+            self.threads.lock().unwrap().push(std::thread::current().id());
+            self.inner.put(record)
+        }
+
+        fn get(&self, thread_id: &str, step: Option<usize>) -> GraphResult<Option<CheckpointRecord>> {
+            self.inner.get(thread_id, step)
+        }
+
+        fn list(&self, thread_id: &str) -> GraphResult<Vec<CheckpointRecord>> {
+            self.inner.list(thread_id)
+        }
+
+        fn list_threads(&self, graph: Option<&str>) -> GraphResult<Vec<ThreadSummary>> {
+            self.inner.list_threads(graph)
+        }
+
+        fn delete_thread(&self, thread_id: &str) -> GraphResult<()> {
+            self.inner.delete_thread(thread_id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_writes_run_off_the_executor() {
+        let recorder = Arc::new(ThreadRecorder {
+            inner: SqliteCheckpointer::in_memory().unwrap(),
+            threads: Mutex::new(vec![]),
+        });
+        let graph = build_graph().compile().unwrap()
+            .with_checkpointer(recorder.clone(), "thread-1");
+        graph.invoke_resumable(CountState::default()).await.unwrap();
+
+        let executor = std::thread::current().id();
+        let threads = recorder.threads.lock().unwrap();
+        assert_eq!(threads.len(), 3);
+        assert!(threads.iter().all(|id| *id != executor));
+    }
+
+    #[tokio::test]
+    async fn test_replay_and_fork() {
+        let cp: Arc<dyn Checkpointer> = Arc::new(SqliteCheckpointer::in_memory().unwrap());
+        let graph = build_graph().compile().unwrap()
+            .with_name("test")
+            .with_checkpointer(cp.clone(), "thread-1");
+        graph.invoke_resumable(CountState::default()).await.unwrap();
+        assert_eq!(graph.history().unwrap().len(), 3);
+
+        // Fork after node a with an approval, so b runs to completion
+        let fork = graph.fork("thread-1", 1, |s| s.approved = true).await.unwrap();
+        match fork.result {
+            ExecutionResult::Complete(state) => assert_eq!(state.visited, vec!["a", "b"]),
+            ExecutionResult::Interrupted { .. } => panic!("expected completion"),
+        }
+        let history = cp.list(&fork.thread_id).unwrap();
+        let nodes: Vec<&str> = history.iter().map(|r| r.node.as_str()).collect();
+        assert_eq!(nodes, vec![START, "a", "b"]);
+        assert!(history.iter().all(|r| r.parent_thread_id.as_deref() == Some("thread-1") && r.parent_step == Some(1)));
+        assert_eq!(history[1].state["approved"], true);
+
+        // The source thread is untouched
+        assert!(cp.get("thread-1", None).unwrap().unwrap().is_interrupted());
+
+        // Replaying from the start reaches the same interrupt
+        let replay = graph.replay("thread-1", 0).await.unwrap();
+        assert!(matches!(replay.result, ExecutionResult::Interrupted { .. }));
+        let summary = cp.list_threads(None).unwrap().into_iter()
+            .find(|t| t.thread_id == replay.thread_id)
+            .unwrap();
+        assert_eq!(summary.parent_step, Some(0));
+
+        assert!(graph.replay("thread-1", 9).await.is_err());
+    }
+}
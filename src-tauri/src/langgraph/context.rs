@@ -0,0 +1,209 @@
+//! Node execution context
+//!
+//! While a node runs, the executor exposes a [`NodeContext`] through a tokio
+//! task-local, so code inside the node (such as a subgraph) can read resume
+//! values, write to the run's event stream and report nested metrics without
+//! changing the node signature.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::langgraph::events::{EventSink, GraphEvent};
+use crate::langgraph::metrics::{RunMetrics, TokenUsage};
+
+tokio::task_local! {
+    static NODE_CONTEXT: NodeContext;
+}
+
+/// Resume values of a run, keyed by interrupt id
+pub(crate) type ResumeValues = Arc<HashMap<String, serde_json::Value>>;
+
+/// Checkpoints of interrupted subgraph runs, keyed by subgraph node
+pub(crate) type SubgraphRuns = Arc<Mutex<HashMap<String, serde_json::Value>>>;
+
+/// Per-run data shared by the contexts of all nodes in the run
+#[derive(Clone, Default)]
+pub(crate) struct RunScope {
+    /// Resume values of the run, keyed by interrupt id
+    resume_values: ResumeValues,
+    /// Interrupted subgraph runs, saved in the run's checkpoint
+    subgraphs: SubgraphRuns,
+    /// Event stream of the run
+    events: Option<EventSink>,
+    /// Prefix of node paths (`parent/` for subgraph runs)
+    prefix: String,
+}
+
+impl RunScope {
+    /// Scope of a new run; runs nested in a node inherit its event stream
+    pub(crate) fn new(resume_values: HashMap<String, serde_json::Value>, events: Option<EventSink>) -> Self {
+        let parent = NodeContext::current();
+        Self {
+            resume_values: Arc::new(resume_values),
+            subgraphs: SubgraphRuns::default(),
+            events: events.or_else(|| parent.as_ref().and_then(|p| p.events.clone())),
+            prefix: parent.map(|p| format!("{}/", p.path)).unwrap_or_default(),
+        }
+    }
+
+    /// Restore the interrupted subgraph runs of a checkpoint
+    pub(crate) fn with_subgraphs(self, subgraphs: HashMap<String, serde_json::Value>) -> Self {
+        Self { subgraphs: Arc::new(Mutex::new(subgraphs)), ..self }
+    }
+
+    /// Resume values of the run
+    pub(crate) fn resume_values(&self) -> &HashMap<String, serde_json::Value> {
+        &self.resume_values
+    }
+
+    /// Interrupted subgraph runs not yet resumed
+    pub(crate) fn subgraphs(&self) -> HashMap<String, serde_json::Value> {
+        self.subgraphs.lock().map(|runs| runs.clone()).unwrap_or_default()
+    }
+}
+
+/// Context of the node currently being executed
+#[derive(Clone, Default)]
+pub struct NodeContext {
+    /// Node being executed
+    node: String,
+    /// Node path including parent subgraph nodes (`parent/child`)
+    path: String,
+    /// Resume values of the run, keyed by interrupt id
+    resume_values: ResumeValues,
+    /// Interrupted subgraph runs of the run
+    subgraphs: SubgraphRuns,
+    /// Event stream of the run
+    events: Option<EventSink>,
+    /// Whether the parent run collects metrics
+    collect_metrics: bool,
+    /// Metrics of graph runs started inside this node
+    child_metrics: Arc<Mutex<Vec<RunMetrics>>>,
+    /// Token usage reported by this node
+    usage: Arc<Mutex<TokenUsage>>,
+}
+
+impl NodeContext {
+    pub(crate) fn new(node: &str, scope: &RunScope, collect_metrics: bool) -> Self {
+        Self {
+            node: node.to_string(),
+            path: format!("{}{}", scope.prefix, node),
+            resume_values: scope.resume_values.clone(),
+            subgraphs: scope.subgraphs.clone(),
+            events: scope.events.clone(),
+            collect_metrics,
+            child_metrics: Arc::new(Mutex::new(Vec::new())),
+            usage: Arc::new(Mutex::new(TokenUsage::default())),
+        }
+    }
+
+    /// Context of the node running on the current task, if any
+    pub fn current() -> Option<NodeContext> {
+        NODE_CONTEXT.try_with(|ctx| ctx.clone()).ok()
+    }
+
+    /// Name of the node being executed
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    /// Node path including parent subgraph nodes
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Resume value provided for an interrupt
+    pub fn resume_value(&self, interrupt_id: &str) -> Option<&serde_json::Value> {
+        self.resume_values.get(interrupt_id)
+    }
+
+    /// Whether the parent run collects metrics
+    pub fn collect_metrics(&self) -> bool {
+        self.collect_metrics
+    }
+
+    /// Keep the checkpoint of an interrupted subgraph run of this node
+    ///
+    /// It is saved with the parent run's checkpoint, so a resume continues
+    /// the subgraph even after the graph was rebuilt or the app restarted.
+    pub(crate) fn save_subgraph(&self, checkpoint: serde_json::Value) {
+        if let Ok(mut runs) = self.subgraphs.lock() {
+            runs.insert(self.node.clone(), checkpoint);
+        }
+    }
+
+    /// Take the checkpoint of this node's interrupted subgraph run, if any
+    pub(crate) fn take_subgraph(&self) -> Option<serde_json::Value> {
+        self.subgraphs.lock().ok()?.remove(&self.node)
+    }
+
+    /// Write a custom event to the run's event stream
+    pub fn emit(&self, name: impl Into<String>, data: impl Serialize) {
+        self.send(GraphEvent::Custom {
+            node: self.path.clone(),
+            name: name.into(),
+            data: serde_json::to_value(data).unwrap_or(serde_json::Value::Null),
+        });
+    }
+
+    /// Write a chunk of streamed output to the run's event stream
+    pub fn emit_token(&self, chunk: impl Into<String>) {
+        self.send(GraphEvent::Token {
+            node: self.path.clone(),
+            chunk: chunk.into(),
+        });
+    }
+
+    /// Whether anyone is consuming the run's events
+    pub fn has_event_stream(&self) -> bool {
+        self.events.as_ref().is_some_and(|tx| !tx.is_closed())
+    }
+
+    pub(crate) fn send(&self, event: GraphEvent) {
+        if let Some(tx) = &self.events {
+            // The consumer may have dropped the stream; the run carries on
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Report tokens and cost used by this node (e.g. one LLM call)
+    ///
+    /// Usage accumulates across calls and retries and is recorded in the
+    /// node's metrics when it finishes.
+    pub fn record_usage(&self, usage: TokenUsage) {
+        if let Ok(mut total) = self.usage.lock() {
+            total.add(&usage);
+        }
+    }
+
+    /// Take the usage reported so far
+    pub(crate) fn take_usage(&self) -> TokenUsage {
+        self.usage
+            .lock()
+            .map(|mut usage| std::mem::take(&mut *usage))
+            .unwrap_or_default()
+    }
+
+    /// Report the metrics of a graph run nested in this node
+    pub(crate) fn report_metrics(&self, metrics: RunMetrics) {
+        if let Ok(mut children) = self.child_metrics.lock() {
+            children.push(metrics);
+        }
+    }
+
+    /// Take the metrics reported so far
+    pub(crate) fn take_child_metrics(&self) -> Vec<RunMetrics> {
+        self.child_metrics
+            .lock()
+            .map(|mut children| std::mem::take(&mut *children))
+            .unwrap_or_default()
+    }
+
+    /// Run a future with this context as the current node context
+    pub(crate) async fn scope<F: Future>(self, future: F) -> F::Output {
+        NODE_CONTEXT.scope(self, future).await
+    }
+}
@@ -104,6 +104,14 @@ pub enum GraphError {
     CompilationError(String),
     /// Graph interrupted - waiting for human input
     Interrupted(Vec<Interrupt>),
+    /// Checkpoint persistence error
+    CheckpointError(String),
+    /// Node exceeded its timeout
+    NodeTimeout { node: String, timeout_ms: u64 },
+    /// Transient node failure that is safe to retry (network, rate limit, ...)
+    Transient { node: String, message: String },
+    /// Partial update could not be applied to the state
+    InvalidUpdate { node: String, key: String, message: String },
     /// Generic error
     Other(String),
 }
@@ -131,6 +139,16 @@ impl fmt::Display for GraphError {
             Self::Interrupted(interrupts) => {
                 write!(f, "Graph interrupted with {} pending interrupt(s)", interrupts.len())
             }
+            Self::CheckpointError(msg) => write!(f, "Checkpoint error: {}", msg),
+            Self::NodeTimeout { node, timeout_ms } => {
+                write!(f, "Node '{}' timed out after {} ms", node, timeout_ms)
+            }
+            Self::Transient { node, message } => {
+                write!(f, "Transient error in node '{}': {}", node, message)
+            }
+            Self::InvalidUpdate { node, key, message } => {
+                write!(f, "Invalid update of '{}' from node '{}': {}", key, node, message)
+            }
             Self::Other(msg) => write!(f, "{}", msg),
         }
     }
@@ -138,6 +156,21 @@ impl fmt::Display for GraphError {
 
 impl std::error::Error for GraphError {}
 
+impl GraphError {
+    /// Create a transient (retryable) error
+    pub fn transient(node: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Transient { node: node.into(), message: message.into() }
+    }
+
+    /// Whether retrying the node may succeed
+    ///
+    /// Only timeouts and errors the node marked as [`GraphError::Transient`] are
+    /// retried; execution errors are never classified by their message.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::NodeTimeout { .. } | Self::Transient { .. })
+    }
+}
+
 /// Result type for graph operations
 pub type GraphResult<T> = Result<T, GraphError>;
 
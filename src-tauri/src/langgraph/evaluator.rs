@@ -77,6 +77,8 @@ pub struct EvalContext {
     pub test_name: String,
     /// Test case input
     pub input: Value,
+    /// Tools called during the run, in order (empty if not tracked)
+    pub tool_calls: Vec<String>,
 }
 
 /// Trait for evaluators that assess graph execution quality
@@ -283,8 +285,13 @@ impl ToolCallEvaluator {
 
 impl Evaluator for ToolCallEvaluator {
     fn evaluate(&self, ctx: &EvalContext) -> EvalResult {
-        // Extract tool calls from execution path
-        let executed_nodes: Vec<&str> = ctx.metrics.execution_path.iter()
+        // Use recorded tool calls, falling back to the execution path
+        let calls = if ctx.tool_calls.is_empty() {
+            &ctx.metrics.execution_path
+        } else {
+            &ctx.tool_calls
+        };
+        let executed_nodes: Vec<&str> = calls.iter()
             .map(|s| s.as_str())
             .collect();
 
@@ -623,6 +630,7 @@ mod tests {
             metrics,
             test_name: "test".to_string(),
             input: Value::Null,
+            tool_calls: Vec::new(),
         }
     }
 
@@ -639,6 +647,7 @@ mod tests {
             metrics,
             test_name: "test".to_string(),
             input: Value::Null,
+            tool_calls: Vec::new(),
         };
 
         let result = evaluator.evaluate(&ctx);
@@ -677,9 +686,24 @@ mod tests {
             metrics,
             test_name: "test".to_string(),
             input: Value::Null,
+            tool_calls: Vec::new(),
         };
 
         let result = evaluator.evaluate(&ctx);
         assert!(result.passed);
     }
+
+    #[test]
+    fn test_tool_call_evaluator_uses_recorded_tools() {
+        let evaluator = ToolCallEvaluator::new(vec!["read_note".to_string()])
+            .with_forbidden(vec!["delete_note".to_string()]);
+
+        let mut metrics = RunMetrics::new("r1", "c1");
+        metrics.execution_path = vec!["coordinator".to_string(), "researcher".to_string()];
+        let mut ctx = make_test_context(Value::Null, metrics);
+        assert!(!evaluator.evaluate(&ctx).passed);
+
+        ctx.tool_calls = vec!["list_notes".to_string(), "read_note".to_string()];
+        assert!(evaluator.evaluate(&ctx).passed);
+    }
 }
@@ -0,0 +1,199 @@
+//! Graph events - typed event stream of a running graph
+//!
+//! The executor reports node start/end, and nodes write custom events and
+//! token chunks through [`NodeContext`](crate::langgraph::context::NodeContext).
+//! Consumers read them as an async `Stream` without depending on Tauri.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::langgraph::error::{GraphError, GraphResult};
+use crate::langgraph::node::BoxFuture;
+
+/// Event emitted while a graph runs
+///
+/// Nodes of subgraphs are reported as `parent/child`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraphEvent {
+    /// A node started executing
+    NodeStart { node: String },
+    /// A node finished (after retries); `error` is set when it failed
+    NodeEnd {
+        node: String,
+        duration_ms: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Application-defined event written by a node
+    Custom {
+        node: String,
+        name: String,
+        data: serde_json::Value,
+    },
+    /// Chunk of streamed LLM output written by a node
+    Token { node: String, chunk: String },
+}
+
+/// Sender side of a run's event stream
+pub(crate) type EventSink = mpsc::UnboundedSender<GraphEvent>;
+
+/// Stream of events from a running graph
+///
+/// Polling the stream drives the graph. Once the stream ends, the final state
+/// is available through [`EventStream::into_result`].
+pub struct EventStream<'a, S> {
+    run: Option<BoxFuture<'a, GraphResult<S>>>,
+    events: mpsc::UnboundedReceiver<GraphEvent>,
+    result: Option<GraphResult<S>>,
+}
+
+impl<'a, S> EventStream<'a, S> {
+    pub(crate) fn new(run: BoxFuture<'a, GraphResult<S>>, events: mpsc::UnboundedReceiver<GraphEvent>) -> Self {
+        Self {
+            run: Some(run),
+            events,
+            result: None,
+        }
+    }
+
+    /// Final result of the run, available once the stream has ended
+    pub fn into_result(self) -> Option<GraphResult<S>> {
+        self.result
+    }
+
+    /// Drive the run to completion, discarding remaining events
+    pub async fn finish(mut self) -> GraphResult<S> {
+        while futures_util::StreamExt::next(&mut self).await.is_some() {}
+        self.result
+            .unwrap_or_else(|| Err(GraphError::Other("Event stream ended without a result".to_string())))
+    }
+}
+
+// The run future is boxed and the final state is never pinned
+impl<S> Unpin for EventStream<'_, S> {}
+
+impl<S> Stream for EventStream<'_, S> {
+    type Item = GraphEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<GraphEvent>> {
+        let this = self.get_mut();
+        loop {
+            if let Poll::Ready(Some(event)) = this.events.poll_recv(cx) {
+                return Poll::Ready(Some(event));
+            }
+
+            let Some(run) = this.run.as_mut() else {
+                // Run finished: hand out what is still buffered, then end
+                return Poll::Ready(this.events.try_recv().ok());
+            };
+
+            match run.as_mut().poll(cx) {
+                Poll::Ready(result) => {
+                    this.result = Some(result);
+                    this.run = None;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use crate::langgraph::constants::{START, END};
+    use crate::langgraph::context::NodeContext;
+    use crate::langgraph::graph::StateGraph;
+    use crate::langgraph::state::GraphState;
+
+    #[derive(Clone, Default, Serialize, Deserialize)]
+    struct Doc {
+        text: String,
+    }
+
+    impl GraphState for Doc {}
+
+    fn writer_graph() -> StateGraph<Doc> {
+        let mut graph = StateGraph::<Doc>::new();
+        graph.add_node("write", |mut s: Doc| async move {
+            let ctx = NodeContext::current().expect("node context");
+            ctx.emit("progress", serde_json::json!({ "step": 1 }));
+            for chunk in ["Hel", "lo"] {
+                ctx.emit_token(chunk);
+                s.text.push_str(chunk);
+            }
+            Ok(s)
+        });
+        graph.add_edge(START, "write");
+        graph.add_edge("write", END);
+        graph
+    }
+
+    #[tokio::test]
+    async fn test_stream_events() {
+        let graph = writer_graph().compile().unwrap();
+        let mut stream = graph.stream_events(Doc::default());
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event);
+        }
+
+        assert_eq!(events[0], GraphEvent::NodeStart { node: "write".to_string() });
+        assert_eq!(
+            events[1],
+            GraphEvent::Custom {
+                node: "write".to_string(),
+                name: "progress".to_string(),
+                data: serde_json::json!({ "step": 1 }),
+            }
+        );
+        let tokens: Vec<&str> = events.iter()
+            .filter_map(|e| match e {
+                GraphEvent::Token { chunk, .. } => Some(chunk.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tokens, vec!["Hel", "lo"]);
+        assert!(matches!(events.last(), Some(GraphEvent::NodeEnd { node, error: None, .. }) if node == "write"));
+
+        assert_eq!(stream.into_result().unwrap().unwrap().text, "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_events_are_nested() {
+        let mut graph = StateGraph::<Doc>::new();
+        graph.add_subgraph("draft", writer_graph().compile().unwrap(), |s: &Doc| s.clone(), |_, inner| inner);
+        graph.add_edge(START, "draft");
+        graph.add_edge("draft", END);
+        let graph = graph.compile().unwrap();
+
+        let events: Vec<GraphEvent> = graph.stream_events(Doc::default()).collect().await;
+        let starts: Vec<&str> = events.iter()
+            .filter_map(|e| match e {
+                GraphEvent::NodeStart { node } => Some(node.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(starts, vec!["draft", "draft/write"]);
+        assert!(events.contains(&GraphEvent::Token { node: "draft/write".to_string(), chunk: "lo".to_string() }));
+
+        // Without a consumer, emitting is a no-op
+        assert_eq!(graph.invoke(Doc::default()).await.unwrap().text, "Hello");
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = GraphEvent::NodeEnd { node: "a".to_string(), duration_ms: 3, error: None };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "node_end", "node": "a", "duration_ms": 3 })
+        );
+    }
+}
@@ -2,21 +2,32 @@
 //!
 //! Supports:
 //! - Interrupt/resume for human-in-the-loop workflows
+//! - Parallel fan-out and join (multiple edges or send packets)
+//! - Typed event streaming (node start/end, custom events, token chunks)
 //! - Node masking for ablation studies
 //! - Metrics collection for performance analysis
 
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use futures_util::future::join_all;
 use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
 
 use crate::langgraph::constants::{START, END, MAX_ITERATIONS};
 use crate::langgraph::error::{GraphError, GraphResult, Interrupt, ResumeCommand};
-use crate::langgraph::state::GraphState;
+use crate::langgraph::state::{GraphState, Updates, Write};
 use crate::langgraph::graph::{StateGraph, Edge};
-use crate::langgraph::node::{Node, NodeSpec};
+use crate::langgraph::node::{Node, NodeSpec, RetryBackoff};
 use crate::langgraph::branch::{Branch, BranchSpec};
+use crate::langgraph::channel::StateChannel;
+use crate::langgraph::send::SendFn;
 use crate::langgraph::metrics::{MetricsCollector, RunMetrics, RunMetricsBuilder};
 use crate::langgraph::ablation::NodeOverride;
+use crate::langgraph::checkpoint::{Checkpointer, CheckpointBinding, CheckpointRecord, now_millis, run_blocking};
+use crate::langgraph::context::{NodeContext, RunScope};
+use crate::langgraph::events::{EventSink, EventStream, GraphEvent};
+use crate::langgraph::visualize::GraphView;
 
 /// Configuration for graph execution
 #[derive(Clone, Debug, Default)]
@@ -109,6 +120,9 @@ pub struct Checkpoint<S> {
     /// Resume values (from user input)
     #[serde(default)]
     pub resume_values: HashMap<String, serde_json::Value>,
+    /// Checkpoints of interrupted subgraph runs, keyed by subgraph node
+    #[serde(default)]
+    pub subgraphs: HashMap<String, serde_json::Value>,
 }
 
 /// Execution result - may complete or be interrupted
@@ -123,6 +137,15 @@ pub enum ExecutionResult<S> {
     },
 }
 
+/// Run started from a past snapshot by `replay` or `fork`
+#[derive(Debug)]
+pub struct ForkedRun<S> {
+    /// Thread the new run is persisted under
+    pub thread_id: String,
+    /// Result of the new run
+    pub result: ExecutionResult<S>,
+}
+
 /// Result of execution with metrics
 #[derive(Debug)]
 pub struct ExecutionResultWithMetrics<S> {
@@ -132,32 +155,244 @@ pub struct ExecutionResultWithMetrics<S> {
     pub metrics: Option<RunMetrics>,
 }
 
+/// A node invocation chosen by the router
+struct Task<S> {
+    node: String,
+    /// Own input state (send packets); `None` runs on the current state
+    input: Option<S>,
+}
+
+impl<S> Task<S> {
+    fn shared(node: impl Into<String>) -> Self {
+        Self { node: node.into(), input: None }
+    }
+}
+
+/// Result of one parallel branch
+enum BranchOutput<S> {
+    /// Full output state of a regular node
+    State(S),
+    /// Partial updates of an update node
+    Update(Updates),
+}
+
 /// A compiled graph ready for execution
 pub struct CompiledGraph<S: GraphState> {
+    /// Graph name (used to group persisted checkpoints)
+    name: String,
     /// Node definitions
     pub(crate) nodes: HashMap<String, NodeSpec<S>>,
     /// Edge definitions
     pub(crate) edges: HashMap<String, Vec<Edge>>,
     /// Branch definitions
     pub(crate) branches: HashMap<String, BranchSpec<S>>,
+    /// Send edge definitions
+    pub(crate) senders: HashMap<String, SendFn<S>>,
+    /// Channels used to merge parallel branches
+    pub(crate) channels: Vec<StateChannel<S>>,
     /// Execution configuration
     config: ExecutionConfig,
     /// Metrics collector (shared across runs)
     metrics_collector: Option<Arc<MetricsCollector>>,
+    /// Checkpointer bound to a thread (saves state after every node)
+    checkpointer: Option<CheckpointBinding<S>>,
 }
 
 impl<S: GraphState> CompiledGraph<S> {
     /// Create from a StateGraph
     pub(crate) fn new(graph: StateGraph<S>) -> Self {
         Self {
+            name: "graph".to_string(),
             nodes: graph.nodes,
             edges: graph.edges,
             branches: graph.branches,
+            senders: graph.senders,
+            channels: graph.channels,
             config: ExecutionConfig::new(),
             metrics_collector: None,
+            checkpointer: None,
         }
     }
 
+    /// Set the graph name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Get the graph name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Persist a checkpoint after every node under the given thread id
+    pub fn with_checkpointer(mut self, checkpointer: Arc<dyn Checkpointer>, thread_id: impl Into<String>) -> Self
+    where
+        S: Serialize,
+    {
+        self.checkpointer = Some(CheckpointBinding {
+            checkpointer,
+            thread_id: thread_id.into(),
+            parent: None,
+            encode: Arc::new(|state: &S| {
+                serde_json::to_value(state)
+                    .map_err(|e| GraphError::CheckpointError(format!("Failed to encode state: {}", e)))
+            }),
+        });
+        self
+    }
+
+    /// Thread id of the bound checkpointer
+    pub fn thread_id(&self) -> Option<&str> {
+        self.checkpointer.as_ref().map(|b| b.thread_id.as_str())
+    }
+
+    /// Load a persisted checkpoint of the bound thread (`None` step means the latest)
+    ///
+    /// Continuing from an earlier step writes over the later snapshots of this
+    /// thread; use [`CompiledGraph::replay`] or [`CompiledGraph::fork`] instead.
+    pub fn load_checkpoint(&self, step: Option<usize>) -> GraphResult<Option<Checkpoint<S>>>
+    where
+        S: DeserializeOwned,
+    {
+        let binding = self.binding()?;
+
+        binding.checkpointer
+            .get(&binding.thread_id, step)?
+            .map(|record| record.to_checkpoint())
+            .transpose()
+    }
+
+    /// Snapshots of the bound thread, one per executed step, oldest first
+    pub fn history(&self) -> GraphResult<Vec<CheckpointRecord>> {
+        let binding = self.binding()?;
+        binding.checkpointer.list(&binding.thread_id)
+    }
+
+    /// Re-execute a past run from `step` in a new thread
+    ///
+    /// The source thread is left untouched; see [`CompiledGraph::fork`].
+    pub async fn replay(&self, thread_id: &str, step: usize) -> GraphResult<ForkedRun<S>>
+    where
+        S: Serialize + DeserializeOwned,
+    {
+        self.fork(thread_id, step, |_| {}).await
+    }
+
+    /// Edit the state of a past run at `step` and continue from there in a new thread
+    ///
+    /// The new thread starts with a copy of the source history up to `step`, and
+    /// all its snapshots record the thread and step they were forked from.
+    pub async fn fork<F>(&self, thread_id: &str, step: usize, edit: F) -> GraphResult<ForkedRun<S>>
+    where
+        S: Serialize + DeserializeOwned,
+        F: FnOnce(&mut S),
+    {
+        let binding = self.binding()?;
+        let history = binding.checkpointer.list(thread_id)?;
+        let base = history.iter()
+            .find(|r| r.step == step)
+            .ok_or_else(|| GraphError::CheckpointError(format!(
+                "No checkpoint for thread '{}' at step {}", thread_id, step
+            )))?;
+
+        let mut checkpoint: Checkpoint<S> = base.to_checkpoint()?;
+        edit(&mut checkpoint.state);
+
+        let fork_id = format!("{}~{}", thread_id, &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let mut forked = self.clone();
+        forked.checkpointer = Some(CheckpointBinding {
+            thread_id: fork_id.clone(),
+            parent: Some((thread_id.to_string(), step)),
+            ..binding.clone()
+        });
+
+        let copies: Vec<_> = history.iter()
+            .filter(|r| r.step < step)
+            .map(|record| CheckpointRecord {
+                thread_id: fork_id.clone(),
+                parent_thread_id: Some(thread_id.to_string()),
+                parent_step: Some(step),
+                ..record.clone()
+            })
+            .collect();
+        run_blocking(&binding.checkpointer, move |checkpointer| {
+            copies.iter().try_for_each(|record| checkpointer.put(record))
+        }).await?;
+        let scope = RunScope::new(checkpoint.resume_values.clone(), None)
+            .with_subgraphs(checkpoint.subgraphs.clone());
+        forked.save_checkpoint(
+            step,
+            &base.node,
+            &checkpoint.next_node,
+            &checkpoint.state,
+            &checkpoint.pending_interrupts,
+            &scope,
+        ).await?;
+
+        if self.config.debug {
+            println!("[LangGraph] Forked {} at step {} into {}", thread_id, step, fork_id);
+        }
+
+        let result = forked.continue_from(checkpoint).await?;
+        Ok(ForkedRun { thread_id: fork_id, result })
+    }
+
+    fn binding(&self) -> GraphResult<&CheckpointBinding<S>> {
+        self.checkpointer.as_ref()
+            .ok_or_else(|| GraphError::CheckpointError("No checkpointer attached".to_string()))
+    }
+
+    /// Continue execution from a checkpoint without providing a resume value
+    ///
+    /// Used to pick up a run after a restart or to replay from an earlier step.
+    pub async fn continue_from(&self, checkpoint: Checkpoint<S>) -> GraphResult<ExecutionResult<S>> {
+        if checkpoint.next_node == END {
+            return Ok(ExecutionResult::Complete(checkpoint.state));
+        }
+        self.run_with_checkpoint(
+            checkpoint.state,
+            checkpoint.next_node,
+            checkpoint.iterations,
+            checkpoint.resume_values,
+            checkpoint.subgraphs,
+        ).await
+    }
+
+    /// Save a checkpoint if a checkpointer is attached
+    ///
+    /// Resume values and interrupted subgraph runs are taken from `scope`.
+    /// The write runs on the blocking thread pool.
+    async fn save_checkpoint(
+        &self,
+        step: usize,
+        node: &str,
+        next_node: &str,
+        state: &S,
+        pending_interrupts: &[Interrupt],
+        scope: &RunScope,
+    ) -> GraphResult<()> {
+        let Some(binding) = &self.checkpointer else {
+            return Ok(());
+        };
+
+        let record = CheckpointRecord {
+            thread_id: binding.thread_id.clone(),
+            graph: self.name.clone(),
+            step,
+            node: node.to_string(),
+            next_node: next_node.to_string(),
+            state: (binding.encode)(state)?,
+            pending_interrupts: pending_interrupts.to_vec(),
+            resume_values: scope.resume_values().clone(),
+            subgraphs: scope.subgraphs(),
+            parent_thread_id: binding.parent.as_ref().map(|(thread, _)| thread.clone()),
+            parent_step: binding.parent.as_ref().map(|(_, step)| *step),
+            created_at: now_millis(),
+        };
+        run_blocking(&binding.checkpointer, move |checkpointer| checkpointer.put(&record)).await
+    }
+
     /// Set execution configuration
     pub fn with_config(mut self, config: ExecutionConfig) -> Self {
         self.config = config;
@@ -196,16 +431,40 @@ impl<S: GraphState> CompiledGraph<S> {
 
     /// Execute and return metrics
     pub async fn invoke_with_metrics(&self, initial_state: S) -> GraphResult<ExecutionResultWithMetrics<S>> {
-        let run_id = uuid::Uuid::new_v4().to_string();
-        let mut metrics_builder = if self.config.collect_metrics {
-            Some(RunMetricsBuilder::new(&run_id, &self.config.config_id))
-        } else {
-            None
+        self.invoke_inner(initial_state, None).await
+    }
+
+    /// Execute the graph, yielding typed events while it runs
+    ///
+    /// The stream reports node start/end plus custom events and token chunks
+    /// written through [`NodeContext`]. Polling it drives the run; the final
+    /// state is returned by [`EventStream::finish`] or
+    /// [`EventStream::into_result`] once the stream has ended.
+    pub fn stream_events(&self, initial_state: S) -> EventStream<'_, S> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let run = async move {
+            match self.invoke_inner(initial_state, Some(tx)).await?.result {
+                ExecutionResult::Complete(state) => Ok(state),
+                ExecutionResult::Interrupted { .. } => {
+                    Err(GraphError::Other("Unexpected interrupt".to_string()))
+                }
+            }
         };
+        EventStream::new(Box::pin(run), rx)
+    }
+
+    async fn invoke_inner(
+        &self,
+        initial_state: S,
+        events: Option<EventSink>,
+    ) -> GraphResult<ExecutionResultWithMetrics<S>> {
+        let mut metrics_builder = self.metrics_builder();
+        let resume = RunScope::new(HashMap::new(), events);
 
         let mut state = initial_state;
-        let mut current_node = self.get_next_node(START, &state)?;
+        let (mut current_node, _) = self.advance(START, &mut state, &resume, metrics_builder.as_mut()).await?;
         let mut iterations = 0;
+        self.save_checkpoint(0, START, &current_node, &state, &[], &resume).await?;
 
         while current_node != END && iterations < self.config.max_iterations {
             iterations += 1;
@@ -223,7 +482,7 @@ impl<S: GraphState> CompiledGraph<S> {
                     mb.skip_node(&current_node);
                 }
                 // Skip to next node without executing
-                current_node = self.get_next_node(&current_node, &state)?;
+                current_node = self.advance(&current_node, &mut state, &resume, metrics_builder.as_mut()).await?.0;
                 continue;
             }
 
@@ -236,12 +495,13 @@ impl<S: GraphState> CompiledGraph<S> {
             let node = self.nodes.get(&current_node)
                 .ok_or_else(|| GraphError::NodeNotFound(current_node.clone()))?;
 
-            match node.execute(state).await {
+            let ctx = self.node_context(&current_node, &resume);
+            match self.execute_node(node, state, &ctx, metrics_builder.as_mut()).await {
                 Ok(new_state) => {
                     state = new_state;
-                    // Record metrics (tokens would come from state if available)
+                    // Tokens reported by the node through its context
                     if let Some(ref mut mb) = metrics_builder {
-                        mb.end_node(0); // TODO: get tokens from state
+                        mb.end_node_usage(&ctx.take_usage());
                     }
                 }
                 Err(e) => {
@@ -253,7 +513,9 @@ impl<S: GraphState> CompiledGraph<S> {
             }
 
             // Determine next node
-            current_node = self.get_next_node(&current_node, &state)?;
+            let (next_node, _) = self.advance(&current_node, &mut state, &resume, metrics_builder.as_mut()).await?;
+            let executed = std::mem::replace(&mut current_node, next_node);
+            self.save_checkpoint(iterations, &executed, &current_node, &state, &[], &resume).await?;
         }
 
         if iterations >= self.config.max_iterations {
@@ -261,14 +523,7 @@ impl<S: GraphState> CompiledGraph<S> {
         }
 
         // Finalize metrics
-        let metrics = metrics_builder.map(|mb| {
-            let m = mb.build(true);
-            // Add to collector if present
-            if let Some(ref collector) = self.metrics_collector {
-                collector.add_run(m.clone());
-            }
-            m
-        });
+        let metrics = metrics_builder.map(|mb| self.finish_metrics(mb));
 
         Ok(ExecutionResultWithMetrics {
             result: ExecutionResult::Complete(state),
@@ -281,16 +536,21 @@ impl<S: GraphState> CompiledGraph<S> {
     where
         F: FnMut(&str, &S),
     {
+        let resume = RunScope::new(HashMap::new(), None);
         let mut state = initial_state;
-        let mut current_node = self.get_next_node(START, &state)?;
+        let (mut current_node, branches) = self.advance(START, &mut state, &resume, None).await?;
+        for branch in &branches {
+            callback(branch, &state);
+        }
         let mut iterations = 0;
+        self.save_checkpoint(0, START, &current_node, &state, &[], &resume).await?;
 
         while current_node != END && iterations < self.config.max_iterations {
             iterations += 1;
 
             // Check if masked
             if self.config.is_masked(&current_node) {
-                current_node = self.get_next_node(&current_node, &state)?;
+                current_node = self.advance(&current_node, &mut state, &resume, None).await?.0;
                 continue;
             }
 
@@ -298,13 +558,18 @@ impl<S: GraphState> CompiledGraph<S> {
             let node = self.nodes.get(&current_node)
                 .ok_or_else(|| GraphError::NodeNotFound(current_node.clone()))?;
 
-            state = node.execute(state).await?;
+            state = self.execute_node(node, state, &self.node_context(&current_node, &resume), None).await?;
 
             // Callback with current state
             callback(&current_node, &state);
 
-            // Determine next node
-            current_node = self.get_next_node(&current_node, &state)?;
+            // Determine next node (parallel branches report the merged state)
+            let (next_node, branches) = self.advance(&current_node, &mut state, &resume, None).await?;
+            for branch in &branches {
+                callback(branch, &state);
+            }
+            let executed = std::mem::replace(&mut current_node, next_node);
+            self.save_checkpoint(iterations, &executed, &current_node, &state, &[], &resume).await?;
         }
 
         if iterations >= self.config.max_iterations {
@@ -314,35 +579,447 @@ impl<S: GraphState> CompiledGraph<S> {
         Ok(state)
     }
 
-    /// Get the next node to execute
-    fn get_next_node(&self, current: &str, state: &S) -> GraphResult<String> {
+    /// Execute a node, honouring its timeout and retry metadata
+    ///
+    /// Update nodes run on a shared snapshot and their writes are applied in place.
+    async fn execute_node(
+        &self,
+        node: &NodeSpec<S>,
+        state: S,
+        ctx: &NodeContext,
+        metrics: Option<&mut RunMetricsBuilder>,
+    ) -> GraphResult<S> {
+        if node.is_update() {
+            let snapshot = Arc::new(state);
+            let updates = self.run_update(node, snapshot.clone(), ctx, metrics).await;
+            let mut state = Arc::unwrap_or_clone(snapshot);
+            self.apply_updates(&node.name, &mut state, updates?)?;
+            return Ok(state);
+        }
+
+        let mut state = Some(state);
+        self.with_retries(node, ctx, metrics, |last| {
+            // Keep a copy of the input only while retries remain
+            let input = if last { state.take() } else { state.clone() }
+                .expect("node input consumed before the last attempt");
+            node.execute(input)
+        })
+        .await
+    }
+
+    /// Run an update node on a snapshot, honouring its timeout and retry metadata
+    async fn run_update(
+        &self,
+        node: &NodeSpec<S>,
+        snapshot: Arc<S>,
+        ctx: &NodeContext,
+        metrics: Option<&mut RunMetricsBuilder>,
+    ) -> GraphResult<Updates> {
+        let update = node.update.as_ref()
+            .ok_or_else(|| GraphError::Other(format!("Node '{}' is not an update node", node.name)))?;
+        self.with_retries(node, ctx, metrics, |_| update(snapshot.clone())).await
+    }
+
+    /// Run attempts of a node until one succeeds, retrying retryable errors
+    ///
+    /// Each attempt runs with `ctx` as the current node context; `attempt_fn`
+    /// receives whether this is the last allowed attempt. Node start/end events
+    /// are reported once around all attempts.
+    async fn with_retries<T, F, Fut>(
+        &self,
+        node: &NodeSpec<S>,
+        ctx: &NodeContext,
+        mut metrics: Option<&mut RunMetricsBuilder>,
+        mut attempt_fn: F,
+    ) -> GraphResult<T>
+    where
+        F: FnMut(bool) -> Fut,
+        Fut: std::future::Future<Output = GraphResult<T>>,
+    {
+        let (retries, timeout_ms, backoff) = match &node.metadata {
+            Some(m) => (m.retry_count, m.timeout_ms, m.backoff.clone()),
+            None => (0, None, RetryBackoff::default()),
+        };
+
+        let started = Instant::now();
+        ctx.send(GraphEvent::NodeStart { node: ctx.path().to_string() });
+
+        let mut attempt = 0;
+        let result = loop {
+            let run = ctx.clone().scope(attempt_fn(attempt >= retries));
+            let result = match timeout_ms {
+                Some(ms) => tokio::time::timeout(Duration::from_millis(ms), run)
+                    .await
+                    .unwrap_or_else(|_| Err(GraphError::NodeTimeout { node: node.name.clone(), timeout_ms: ms })),
+                None => run.await,
+            };
+
+            // Nest metrics of subgraphs run by this attempt
+            if let Some(mb) = metrics.as_deref_mut() {
+                for child in ctx.take_child_metrics() {
+                    ctx.record_usage(child.usage());
+                    mb.subgraph(&node.name, child);
+                }
+            }
+
+            let error = match result {
+                Ok(output) => break Ok(output),
+                Err(e) => e,
+            };
+
+            if let (GraphError::NodeTimeout { .. }, Some(mb)) = (&error, metrics.as_deref_mut()) {
+                mb.timeout(&node.name);
+            }
+
+            if attempt >= retries || !error.is_retryable() {
+                break Err(error);
+            }
+
+            attempt += 1;
+            if let Some(mb) = metrics.as_deref_mut() {
+                mb.retry(&node.name);
+            }
+            let delay = backoff.delay(attempt);
+            if self.config.debug {
+                println!(
+                    "[LangGraph] Retrying node {} ({}/{}) in {:?}: {}",
+                    node.name, attempt, retries, delay, error
+                );
+            }
+            tokio::time::sleep(delay).await;
+        };
+
+        ctx.send(GraphEvent::NodeEnd {
+            node: ctx.path().to_string(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        result
+    }
+
+    /// Apply partial updates from `node`: writes go through the bound channels
+    /// (or `GraphState::set`), and the update's goto replaces the routing target
+    fn apply_updates(&self, node: &str, state: &mut S, updates: Updates) -> GraphResult<()> {
+        let (writes, goto) = updates.into_parts();
+        self.apply_writes(node, state, writes)?;
+        state.set_next(goto);
+        Ok(())
+    }
+
+    fn apply_writes(
+        &self,
+        node: &str,
+        state: &mut S,
+        writes: Vec<Write>,
+    ) -> GraphResult<()> {
+        for (key, value) in writes {
+            let applied = match self.channels.iter().find(|c| c.key() == key) {
+                Some(channel) => channel.apply(state, value),
+                None => state.set(&key, value),
+            };
+            applied.map_err(|message| GraphError::InvalidUpdate {
+                node: node.to_string(),
+                key,
+                message,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Get the targets of all outgoing edges of `current`
+    fn get_next_targets(&self, current: &str, state: &S) -> GraphResult<Vec<Task<S>>> {
         // Check if state has explicit next
         if let Some(next) = state.get_next() {
-            return Ok(next.to_string());
+            return Ok(vec![Task::shared(next)]);
         }
 
         // Check edges
-        let edges = self.edges.get(current);
-
-        match edges {
-            None => Ok(END.to_string()),
-            Some(edges) if edges.is_empty() => Ok(END.to_string()),
-            Some(edges) => {
-                match &edges[0] {
-                    Edge::Direct(to) => Ok(to.clone()),
-                    Edge::Conditional(branch_name) => {
-                        let branch = self.branches.get(branch_name)
-                            .ok_or_else(|| GraphError::BranchError {
-                                node: current.to_string(),
-                                message: format!("Branch '{}' not found", branch_name),
-                            })?;
-
-                        let result = branch.evaluate(state)?;
-                        branch.resolve(&result)
+        let edges = match self.edges.get(current) {
+            Some(edges) if !edges.is_empty() => edges,
+            _ => return Ok(vec![Task::shared(END)]),
+        };
+
+        let mut tasks: Vec<Task<S>> = Vec::new();
+        for edge in edges {
+            match edge {
+                Edge::Direct(to) => tasks.push(Task::shared(to.clone())),
+                Edge::Conditional(branch_name) => {
+                    let branch = self.branches.get(branch_name)
+                        .ok_or_else(|| GraphError::BranchError {
+                            node: current.to_string(),
+                            message: format!("Branch '{}' not found", branch_name),
+                        })?;
+
+                    let result = branch.evaluate(state)?;
+                    tasks.push(Task::shared(branch.resolve(&result)?));
+                }
+                Edge::Send(sender_name) => {
+                    let sender = self.senders.get(sender_name)
+                        .ok_or_else(|| GraphError::BranchError {
+                            node: current.to_string(),
+                            message: format!("Sender '{}' not found", sender_name),
+                        })?;
+
+                    tasks.extend(sender(state)?.into_iter().map(|packet| Task {
+                        node: packet.node,
+                        input: Some(packet.state),
+                    }));
+                }
+            }
+        }
+
+        // A node reached by several edges runs once; END only matters when nothing else runs
+        let mut seen = HashSet::new();
+        tasks.retain(|t| t.input.is_some() || seen.insert(t.node.clone()));
+        if tasks.len() > 1 {
+            tasks.retain(|t| t.node != END || t.input.is_some());
+        }
+        if tasks.is_empty() {
+            tasks.push(Task::shared(END));
+        }
+
+        Ok(tasks)
+    }
+
+    /// Get the next node to execute
+    fn get_next_node(&self, current: &str, state: &S) -> GraphResult<String> {
+        match self.get_next_targets(current, state)?.as_slice() {
+            [task] if task.input.is_none() => Ok(task.node.clone()),
+            tasks => Err(GraphError::BranchError {
+                node: current.to_string(),
+                message: format!(
+                    "Expected a single next node, found {:?}",
+                    tasks.iter().map(|t| t.node.as_str()).collect::<Vec<_>>()
+                ),
+            }),
+        }
+    }
+
+    /// Route from `current`, running a parallel fan-out first when it has several targets
+    ///
+    /// All branches run concurrently on their own copy of the state, their
+    /// outputs are merged through the graph's channels, and routing continues at
+    /// the node the branches join on. Returns that node and the executed branches.
+    async fn advance(
+        &self,
+        current: &str,
+        state: &mut S,
+        resume: &RunScope,
+        mut metrics: Option<&mut RunMetricsBuilder>,
+    ) -> GraphResult<(String, Vec<String>)> {
+        let mut tasks = self.get_next_targets(current, state)?;
+        if tasks.len() == 1 && tasks[0].input.is_none() {
+            return Ok((tasks.remove(0).node, Vec::new()));
+        }
+
+        if self.config.debug {
+            let names: Vec<&str> = tasks.iter().map(|t| t.node.as_str()).collect();
+            println!("[LangGraph] Fan-out from {}: {:?}", current, names);
+        }
+
+        let runs = tasks.into_iter().map(|task| {
+            let shared = task.input.is_none();
+            let input = task.input.unwrap_or_else(|| state.clone());
+            let ctx = self.node_context(&task.node, resume);
+            async move {
+                let started = Instant::now();
+                let (input, result) = match self.nodes.get(&task.node) {
+                    _ if self.config.is_masked(&task.node) => (input.clone(), Ok(BranchOutput::State(input))),
+                    Some(node) if node.is_update() => {
+                        let snapshot = Arc::new(input);
+                        let result = self.run_update(node, snapshot.clone(), &ctx, None).await;
+                        (Arc::unwrap_or_clone(snapshot), result.map(BranchOutput::Update))
                     }
+                    Some(node) => {
+                        let result = self.execute_node(node, input.clone(), &ctx, None).await;
+                        (input, result.map(BranchOutput::State))
+                    }
+                    None => (input, Err(GraphError::NodeNotFound(task.node.clone()))),
+                };
+                (task.node, shared, input, result, started.elapsed(), ctx)
+            }
+        });
+
+        let mut names = Vec::new();
+        let mut branches = Vec::new();
+        let mut first_error = None;
+        for (node, shared, input, result, elapsed, ctx) in join_all(runs).await {
+            if let Some(mb) = metrics.as_deref_mut() {
+                for child in ctx.take_child_metrics() {
+                    ctx.record_usage(child.usage());
+                    mb.subgraph(&node, child);
                 }
             }
+            match result {
+                Ok(output) => {
+                    if let Some(mb) = metrics.as_deref_mut() {
+                        if self.config.is_masked(&node) {
+                            mb.skip_node(&node);
+                        } else {
+                            mb.record_node_usage(&node, elapsed.as_millis() as u64, &ctx.take_usage());
+                        }
+                    }
+                    names.push(node);
+                    branches.push((input, output, shared));
+                }
+                Err(e) => {
+                    if let Some(mb) = metrics.as_deref_mut() {
+                        mb.error(&node, &e.to_string());
+                    }
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+
+        // Full-state branches route on their own output
+        let mut join: Option<String> = None;
+        let mut check_join = |next: String| match &join {
+            Some(existing) if *existing != next => Err(GraphError::BranchError {
+                node: current.to_string(),
+                message: format!(
+                    "Parallel branches must join on a single node, found '{}' and '{}'",
+                    existing, next
+                ),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                join = Some(next);
+                Ok(())
+            }
+        };
+        for (node, (_, output, _)) in names.iter().zip(&branches) {
+            if let BranchOutput::State(output) = output {
+                check_join(self.get_next_node(node, output)?)?;
+            }
+        }
+
+        // Merge full-state outputs through the channels, then apply updates in dispatch order
+        let mut pairs = Vec::new();
+        let mut updates = Vec::new();
+        let mut shared_branches = Vec::new();
+        for (node, (input, output, shared)) in names.iter().zip(branches) {
+            match output {
+                BranchOutput::State(output) => {
+                    if shared {
+                        shared_branches.push((node, pairs.len()));
+                    }
+                    pairs.push((input, output));
+                }
+                BranchOutput::Update(update) => updates.push((node, update)),
+            }
+        }
+        for channel in &self.channels {
+            channel.merge(state, &pairs);
+        }
+        if !shared_branches.is_empty() {
+            let mut merged = self.merge_unbound_fields(state, &pairs, &shared_branches)?;
+            for channel in &self.channels {
+                channel.copy(state, &mut merged);
+            }
+            merged.set_next(state.get_next().map(str::to_string));
+            *state = merged;
         }
+        let mut goto = None;
+        for (node, update) in &mut updates {
+            let (writes, next) = std::mem::take(update).into_parts();
+            self.apply_writes(node, state, writes)?;
+            goto = next.or(goto);
+        }
+        if goto.is_some() {
+            state.set_next(goto);
+        }
+
+        // Update branches route on the merged state
+        for (node, _) in &updates {
+            check_join(self.get_next_node(node, state)?)?;
+        }
+
+        Ok((join.unwrap_or_else(|| END.to_string()), names))
+    }
+
+    /// Take the changes the shared-state branches made to fields without a channel
+    ///
+    /// A single branch's output is taken as is. Several branches are merged
+    /// through [`GraphState::merge_branch`], and a field changed by two of them
+    /// is rejected.
+    fn merge_unbound_fields(&self, state: &S, pairs: &[(S, S)], shared: &[(&String, usize)]) -> GraphResult<S> {
+        if let [(_, index)] = shared {
+            return Ok(pairs[*index].1.clone());
+        }
+        let mut merged = state.clone();
+        let mut changed: HashMap<String, &str> = HashMap::new();
+        for &(node, index) in shared {
+            let (input, output) = &pairs[index];
+            let keys = merged.merge_branch(input, output).map_err(|message| GraphError::InvalidUpdate {
+                node: node.clone(),
+                key: "*".to_string(),
+                message,
+            })?;
+            for key in keys {
+                if self.channels.iter().any(|c| c.key() == key) {
+                    continue;
+                }
+                if let Some(other) = changed.insert(key.clone(), node) {
+                    return Err(GraphError::InvalidUpdate {
+                        node: node.clone(),
+                        key,
+                        message: format!("also changed by parallel branch '{}'; bind a channel to merge it", other),
+                    });
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Create a metrics builder if this run collects metrics
+    ///
+    /// Runs nested in a node (subgraphs) collect metrics when the parent run does.
+    fn metrics_builder(&self) -> Option<RunMetricsBuilder> {
+        self.collects_metrics().then(|| {
+            let run_id = uuid::Uuid::new_v4().to_string();
+            RunMetricsBuilder::new(&run_id, &self.config.config_id)
+        })
+    }
+
+    fn collects_metrics(&self) -> bool {
+        self.config.collect_metrics
+            || NodeContext::current().is_some_and(|ctx| ctx.collect_metrics())
+    }
+
+    /// Build the metrics of a completed run and hand them to the collector and the parent node
+    fn finish_metrics(&self, builder: RunMetricsBuilder) -> RunMetrics {
+        let metrics = builder.build(true);
+        // Add to collector if present
+        if let Some(ref collector) = self.metrics_collector {
+            collector.add_run(metrics.clone());
+        }
+        if let Some(parent) = NodeContext::current() {
+            parent.report_metrics(metrics.clone());
+        }
+        metrics
+    }
+
+    /// Context exposed to a node while it runs
+    fn node_context(&self, node: &str, scope: &RunScope) -> NodeContext {
+        NodeContext::new(node, scope, self.collects_metrics())
+    }
+
+    /// Static view of the nodes and edges, for drawing and structural checks
+    pub fn view(&self) -> GraphView {
+        GraphView::new(self.nodes.keys(), &self.edges, &self.branches)
+    }
+
+    /// Export the graph as a Mermaid flowchart
+    pub fn draw_mermaid(&self) -> String {
+        self.view().to_mermaid()
+    }
+
+    /// Export the graph as a Graphviz DOT digraph
+    pub fn draw_dot(&self) -> String {
+        self.view().to_dot()
     }
 
     /// Get all node names
@@ -357,7 +1034,7 @@ impl<S: GraphState> CompiledGraph<S> {
 
     /// Execute graph with interrupt/resume support
     pub async fn invoke_resumable(&self, initial_state: S) -> GraphResult<ExecutionResult<S>> {
-        self.run_with_checkpoint(initial_state, START.to_string(), 0, HashMap::new()).await
+        self.run_with_checkpoint(initial_state, START.to_string(), 0, HashMap::new(), HashMap::new()).await
     }
 
     /// Resume from checkpoint
@@ -376,6 +1053,7 @@ impl<S: GraphState> CompiledGraph<S> {
             checkpoint.next_node,
             checkpoint.iterations,
             resume_values,
+            checkpoint.subgraphs,
         ).await
     }
 
@@ -386,10 +1064,15 @@ impl<S: GraphState> CompiledGraph<S> {
         start_node: String,
         start_iterations: usize,
         resume_values: HashMap<String, serde_json::Value>,
+        subgraphs: HashMap<String, serde_json::Value>,
     ) -> GraphResult<ExecutionResult<S>> {
+        let mut metrics_builder = self.metrics_builder();
+        let resume = RunScope::new(resume_values, None).with_subgraphs(subgraphs);
         let mut state = initial_state;
         let mut current_node = if start_node == START {
-            self.get_next_node(START, &state)?
+            let (first, _) = self.advance(START, &mut state, &resume, metrics_builder.as_mut()).await?;
+            self.save_checkpoint(0, START, &first, &state, &[], &resume).await?;
+            first
         } else {
             start_node
         };
@@ -407,20 +1090,42 @@ impl<S: GraphState> CompiledGraph<S> {
                 if self.config.debug {
                     println!("[LangGraph] Skipping masked node: {}", current_node);
                 }
-                current_node = self.get_next_node(&current_node, &state)?;
+                if let Some(ref mut mb) = metrics_builder {
+                    mb.skip_node(&current_node);
+                }
+                current_node = self.advance(&current_node, &mut state, &resume, metrics_builder.as_mut()).await?.0;
                 continue;
             }
 
             // Check if we have a resume value for this node
-            let has_resume = resume_values.contains_key(&current_node);
+            let has_resume = resume.resume_values().contains_key(&current_node);
 
             // Execute the node
             let node = self.nodes.get(&current_node)
                 .ok_or_else(|| GraphError::NodeNotFound(current_node.clone()))?;
 
-            match node.execute(state.clone()).await {
-                Ok(new_state) => {
-                    state = new_state;
+            if let Some(ref mut mb) = metrics_builder {
+                mb.start_node(&current_node);
+            }
+
+            // Update nodes leave the state untouched on error, so only full-state nodes need a copy
+            let ctx = self.node_context(&current_node, &resume);
+            let outcome = if node.is_update() {
+                let snapshot = Arc::new(state);
+                let result = self.run_update(node, snapshot.clone(), &ctx, metrics_builder.as_mut()).await;
+                state = Arc::unwrap_or_clone(snapshot);
+                result.and_then(|updates| self.apply_updates(&node.name, &mut state, updates))
+            } else {
+                self.execute_node(node, state.clone(), &ctx, metrics_builder.as_mut())
+                    .await
+                    .map(|new_state| state = new_state)
+            };
+
+            match outcome {
+                Ok(()) => {
+                    if let Some(ref mut mb) = metrics_builder {
+                        mb.end_node_usage(&ctx.take_usage());
+                    }
                 }
                 Err(GraphError::Interrupted(interrupts)) => {
                     if has_resume {
@@ -429,29 +1134,44 @@ impl<S: GraphState> CompiledGraph<S> {
                         }
                     } else {
                         // No resume value, return interrupted state
+                        self.save_checkpoint(
+                            iterations, &current_node, &current_node, &state, &interrupts, &resume,
+                        ).await?;
                         return Ok(ExecutionResult::Interrupted {
                             checkpoint: Checkpoint {
                                 state,
                                 next_node: current_node,
                                 pending_interrupts: interrupts.clone(),
                                 iterations,
-                                resume_values,
+                                resume_values: resume.resume_values().clone(),
+                                subgraphs: resume.subgraphs(),
                             },
                             interrupts,
                         });
                     }
                 }
-                Err(e) => return Err(e),
+                Err(e) => {
+                    if let Some(ref mut mb) = metrics_builder {
+                        mb.error(&current_node, &e.to_string());
+                    }
+                    return Err(e);
+                }
             }
 
             // Determine next node
-            current_node = self.get_next_node(&current_node, &state)?;
+            let (next_node, _) = self.advance(&current_node, &mut state, &resume, metrics_builder.as_mut()).await?;
+            let executed = std::mem::replace(&mut current_node, next_node);
+            self.save_checkpoint(iterations, &executed, &current_node, &state, &[], &resume).await?;
         }
 
         if iterations >= self.config.max_iterations {
             return Err(GraphError::MaxIterationsExceeded);
         }
 
+        if let Some(mb) = metrics_builder {
+            self.finish_metrics(mb);
+        }
+
         Ok(ExecutionResult::Complete(state))
     }
 
@@ -477,11 +1197,15 @@ impl<S: GraphState> CompiledGraph<S> {
             for input in &test_inputs {
                 // Create a new graph with this config
                 let graph = CompiledGraph {
+                    name: self.name.clone(),
                     nodes: self.nodes.clone(),
                     edges: self.edges.clone(),
                     branches: self.branches.clone(),
+                    senders: self.senders.clone(),
+                    channels: self.channels.clone(),
                     config: config.clone(),
                     metrics_collector: Some(collector.clone()),
+                    checkpointer: None,
                 };
 
                 // Run and collect metrics
@@ -513,11 +1237,15 @@ impl<S: GraphState> CompiledGraph<S> {
 impl<S: GraphState> Clone for CompiledGraph<S> {
     fn clone(&self) -> Self {
         Self {
+            name: self.name.clone(),
             nodes: self.nodes.clone(),
             edges: self.edges.clone(),
             branches: self.branches.clone(),
+            senders: self.senders.clone(),
+            channels: self.channels.clone(),
             config: self.config.clone(),
             metrics_collector: self.metrics_collector.clone(),
+            checkpointer: self.checkpointer.clone(),
         }
     }
 }
@@ -558,6 +1286,9 @@ impl Default for ExecutionTrace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::langgraph::send::SendPacket;
+    use crate::langgraph::metrics::TokenUsage;
+    use crate::langgraph::state::Updates;
 
     #[test]
     fn test_execution_config() {
@@ -574,6 +1305,417 @@ mod tests {
         assert!(config.collect_metrics);
     }
 
+    #[derive(Clone, Default)]
+    struct TestState {
+        attempts: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl GraphState for TestState {}
+
+    fn flaky_graph(failures: usize, error: GraphError, retries: usize) -> CompiledGraph<TestState> {
+        let mut graph = StateGraph::<TestState>::new();
+        graph.add_node_spec(
+            NodeSpec::new("flaky", move |s: TestState| {
+                let error = error.clone();
+                async move {
+                    let attempt = s.attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    if attempt < failures {
+                        return Err(error);
+                    }
+                    Ok(s)
+                }
+            })
+            .with_retry(retries)
+            .with_backoff(RetryBackoff::none()),
+        );
+        graph.add_edge(START, "flaky");
+        graph.add_edge("flaky", END);
+        graph.compile().unwrap().with_config(ExecutionConfig::new().with_metrics())
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        let graph = flaky_graph(2, GraphError::transient("flaky", "HTTP 503"), 3);
+        let state = TestState::default();
+        let result = graph.invoke_with_metrics(state.clone()).await.unwrap();
+
+        assert_eq!(state.attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+        let metrics = result.metrics.unwrap();
+        assert_eq!(metrics.total_retries, 2);
+        assert_eq!(metrics.node_metrics["flaky"].retry_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_for_permanent_errors() {
+        let error = GraphError::ExecutionError { node: "flaky".into(), message: "invalid api key".into() };
+        let graph = flaky_graph(1, error, 3);
+        let state = TestState::default();
+
+        assert!(graph.invoke(state.clone()).await.is_err());
+        assert_eq!(state.attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    fn slow_graph(retries: usize) -> CompiledGraph<TestState> {
+        let mut graph = StateGraph::<TestState>::new();
+        graph.add_node_spec(
+            NodeSpec::new("slow", |s: TestState| async move {
+                if s.attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok(s)
+            })
+            .with_timeout(20)
+            .with_retry(retries)
+            .with_backoff(RetryBackoff::none()),
+        );
+        graph.add_edge(START, "slow");
+        graph.add_edge("slow", END);
+        graph.compile().unwrap().with_config(ExecutionConfig::new().with_metrics())
+    }
+
+    #[tokio::test]
+    async fn test_node_timeout() {
+        let result = slow_graph(1).invoke_with_metrics(TestState::default()).await.unwrap();
+        let metrics = result.metrics.unwrap();
+        assert_eq!(metrics.total_timeouts, 1);
+        assert_eq!(metrics.total_retries, 1);
+
+        match slow_graph(0).invoke(TestState::default()).await {
+            Err(GraphError::NodeTimeout { node, timeout_ms }) => {
+                assert_eq!(node, "slow");
+                assert_eq!(timeout_ms, 20);
+            }
+            other => panic!("expected timeout, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_retryable_classification() {
+        assert!(GraphError::NodeTimeout { node: "a".into(), timeout_ms: 1 }.is_retryable());
+        assert!(GraphError::transient("a", "HTTP 429 Too Many Requests").is_retryable());
+        assert!(!GraphError::ExecutionError { node: "a".into(), message: "connection reset by peer".into() }.is_retryable());
+        assert!(!GraphError::ExecutionError { node: "a".into(), message: "HTTP 401 unauthorized".into() }.is_retryable());
+        assert!(!GraphError::Interrupted(vec![]).is_retryable());
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = RetryBackoff { initial_interval_ms: 100, backoff_factor: 2.0, max_interval_ms: 300, jitter: false };
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(200));
+        assert_eq!(backoff.delay(3), Duration::from_millis(300));
+    }
+
+    #[derive(Clone, Default)]
+    struct FanState {
+        docs: Vec<String>,
+        item: u32,
+        best: u32,
+        total: u32,
+        barrier: Option<Arc<tokio::sync::Barrier>>,
+    }
+
+    impl GraphState for FanState {
+        fn merge_branch(&mut self, input: &Self, output: &Self) -> Result<Vec<String>, String> {
+            let mut changed = Vec::new();
+            if output.docs != input.docs {
+                self.docs = output.docs.clone();
+                changed.push("docs".to_string());
+            }
+            for (key, before, after, field) in [
+                ("item", input.item, output.item, &mut self.item),
+                ("best", input.best, output.best, &mut self.best),
+                ("total", input.total, output.total, &mut self.total),
+            ] {
+                if after != before {
+                    *field = after;
+                    changed.push(key.to_string());
+                }
+            }
+            Ok(changed)
+        }
+    }
+
+    fn push_node(name: &'static str) -> impl Fn(FanState) -> futures_util::future::BoxFuture<'static, GraphResult<FanState>> {
+        move |mut s: FanState| Box::pin(async move {
+            // Both branches must be running at the same time to pass the barrier
+            if let Some(barrier) = s.barrier.clone() {
+                barrier.wait().await;
+            }
+            s.docs.push(name.to_string());
+            Ok(s)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_parallel_edges_join() {
+        let mut graph = StateGraph::<FanState>::new();
+        graph.add_node("split", |mut s: FanState| async move {
+            s.docs.push("split".to_string());
+            Ok(s)
+        });
+        graph.add_node("left", push_node("left"));
+        graph.add_node("right", push_node("right"));
+        graph.add_node("join", |mut s: FanState| async move {
+            s.barrier = None;
+            s.docs.push("join".to_string());
+            Ok(s)
+        });
+        graph.add_channel(StateChannel::append("docs", |s: &FanState| &s.docs, |s| &mut s.docs));
+        graph.add_edge(START, "split");
+        graph.add_edge("split", "left");
+        graph.add_edge("split", "right");
+        graph.add_edge("left", "join");
+        graph.add_edge("right", "join");
+        graph.add_edge("join", END);
+        let graph = graph.compile().unwrap();
+
+        let state = FanState { barrier: Some(Arc::new(tokio::sync::Barrier::new(2))), ..Default::default() };
+        let mut streamed = Vec::new();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            graph.stream(state, |node, _| streamed.push(node.to_string())),
+        )
+        .await
+        .expect("branches did not run concurrently")
+        .unwrap();
+
+        assert_eq!(result.docs, vec!["split", "left", "right", "join"]);
+        assert_eq!(streamed, vec!["split", "left", "right", "join"]);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_unbound_fields_merged_per_branch() {
+        let build = |right_item: u32| {
+            let mut graph = StateGraph::<FanState>::new();
+            graph.add_node("split", |s: FanState| async move { Ok(s) });
+            graph.add_node("left", |mut s: FanState| async move {
+                s.docs.push("left".to_string());
+                s.item = 1;
+                Ok(s)
+            });
+            graph.add_node("right", move |mut s: FanState| async move {
+                s.docs.push("right".to_string());
+                s.best = 7;
+                s.item += right_item;
+                Ok(s)
+            });
+            graph.add_node("join", |s: FanState| async move { Ok(s) });
+            graph.add_channel(StateChannel::append("docs", |s: &FanState| &s.docs, |s| &mut s.docs));
+            graph.add_edge(START, "split");
+            graph.add_edge("split", "left");
+            graph.add_edge("split", "right");
+            graph.add_edge("left", "join");
+            graph.add_edge("right", "join");
+            graph.add_edge("join", END);
+            graph.compile().unwrap()
+        };
+        let initial = FanState { total: 3, ..Default::default() };
+
+        // Bound fields merge every branch; unbound fields take each branch's own changes
+        let result = build(0).invoke(initial.clone()).await.unwrap();
+        assert_eq!(result.docs, vec!["left", "right"]);
+        assert_eq!(result.best, 7);
+        assert_eq!(result.item, 1);
+        assert_eq!(result.total, 3);
+
+        // Two branches changing the same unbound field conflict
+        let error = build(2).invoke(initial).await.err().unwrap();
+        assert!(
+            matches!(&error, GraphError::InvalidUpdate { node, key, .. } if node == "right" && key == "item"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_map_reduce() {
+        let mut graph = StateGraph::<FanState>::new();
+        graph.add_node("plan", |s: FanState| async move { Ok(s) });
+        graph.add_node("work", |mut s: FanState| async move {
+            s.docs.push(format!("item-{}", s.item));
+            s.best = s.item * 10;
+            Ok(s)
+        });
+        graph.add_node("collect", |s: FanState| async move { Ok(s) });
+        graph.add_channel(StateChannel::append("docs", |s: &FanState| &s.docs, |s| &mut s.docs));
+        graph.add_channel(StateChannel::reduce(
+            "best",
+            |s: &FanState| &s.best,
+            |s| &mut s.best,
+            crate::langgraph::channel::reducers::max,
+        ));
+        graph.add_edge(START, "plan");
+        graph.add_send_edges("plan", |_: &FanState| {
+            (1..=3)
+                .map(|item| SendPacket::new("work", FanState { item, ..Default::default() }))
+                .collect()
+        });
+        graph.add_edge("work", "collect");
+        graph.add_edge("collect", END);
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let graph = graph.compile().unwrap().with_metrics_collector(metrics.clone());
+        let initial = FanState { docs: vec!["seed".to_string()], best: 5, ..Default::default() };
+        let result = graph.invoke(initial).await.unwrap();
+
+        assert_eq!(result.docs, vec!["seed", "item-1", "item-2", "item-3"]);
+        assert_eq!(result.best, 30);
+        assert_eq!(result.item, 0);
+        assert_eq!(metrics.get_runs()[0].node_metrics["work"].call_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_branches_must_join() {
+        let mut graph = StateGraph::<FanState>::new();
+        graph.add_node("split", push_node("split"));
+        graph.add_node("left", push_node("left"));
+        graph.add_node("right", push_node("right"));
+        graph.add_edge(START, "split");
+        graph.add_edge("split", "left");
+        graph.add_edge("split", "right");
+        graph.add_edge("left", END);
+        graph.add_edge("right", "split");
+
+        match graph.compile().unwrap().invoke(FanState::default()).await {
+            Err(GraphError::BranchError { node, .. }) => assert_eq!(node, "split"),
+            other => panic!("expected branch error, got {:?}", other.err()),
+        }
+    }
+
+    fn update_graph(work: Updates) -> StateGraph<FanState> {
+        let work = Arc::new(std::sync::Mutex::new(Some(work)));
+        let mut graph = StateGraph::<FanState>::new();
+        graph.add_update_node("first", |_: Arc<FanState>| async move {
+            Ok(Updates::new().set("total", 2u32).set("docs", "first".to_string()))
+        });
+        graph.add_update_node("second", move |_: Arc<FanState>| {
+            let work = work.lock().unwrap().take().unwrap_or_default();
+            async move { Ok(work) }
+        });
+        graph.add_channel(StateChannel::append("docs", |s: &FanState| &s.docs, |s| &mut s.docs));
+        graph.add_channel(StateChannel::reduce(
+            "total",
+            |s: &FanState| &s.total,
+            |s| &mut s.total,
+            crate::langgraph::channel::reducers::add,
+        ));
+        graph.add_channel(StateChannel::last_value("best", |s: &FanState| &s.best, |s| &mut s.best));
+        graph.add_edge(START, "first");
+        graph.add_edge("first", "second");
+        graph.add_edge("second", END);
+        graph
+    }
+
+    #[tokio::test]
+    async fn test_update_nodes_apply_through_channels() {
+        let updates = Updates::new()
+            .set("total", 3u32)
+            .set("docs", vec!["a".to_string(), "b".to_string()])
+            .set("best", 7u32);
+        let graph = update_graph(updates).compile().unwrap();
+        let initial = FanState { total: 10, docs: vec!["seed".to_string()], ..Default::default() };
+
+        let result = graph.invoke(initial).await.unwrap();
+        assert_eq!(result.total, 15);
+        assert_eq!(result.docs, vec!["seed", "first", "a", "b"]);
+        assert_eq!(result.best, 7);
+
+        // Resumable execution applies updates the same way
+        match update_graph(Updates::new().set("total", 1u32)).compile().unwrap()
+            .invoke_resumable(FanState::default()).await.unwrap()
+        {
+            ExecutionResult::Complete(state) => assert_eq!(state.total, 3),
+            ExecutionResult::Interrupted { .. } => panic!("unexpected interrupt"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_with_wrong_type_fails() {
+        let graph = update_graph(Updates::new().set("total", "three")).compile().unwrap();
+        match graph.invoke(FanState::default()).await {
+            Err(GraphError::InvalidUpdate { node, key, .. }) => {
+                assert_eq!(node, "second");
+                assert_eq!(key, "total");
+            }
+            other => panic!("expected invalid update, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_to_unbound_key_fails() {
+        let graph = update_graph(Updates::new().set("item", 1u32)).compile().unwrap();
+        match graph.invoke(FanState::default()).await {
+            Err(GraphError::InvalidUpdate { node, key, message }) => {
+                assert_eq!(node, "second");
+                assert_eq!(key, "item");
+                assert_eq!(message, "no channel bound");
+            }
+            other => panic!("expected invalid update, got {:?}", other.err()),
+        }
+
+        // Outside a graph the node's own fallback rejects it too
+        let node = NodeSpec::update("solo", |_: Arc<FanState>| async move { Ok(Updates::new().set("item", 1u32)) });
+        assert!(matches!(
+            node.execute(FanState::default()).await,
+            Err(GraphError::InvalidUpdate { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_branches_reduce_deltas() {
+        let mut graph = StateGraph::<FanState>::new();
+        graph.add_node("plan", |s: FanState| async move { Ok(s) });
+        graph.add_update_node("work", |s: Arc<FanState>| async move {
+            Ok(Updates::new().set("total", s.item).set("docs", format!("item-{}", s.item)))
+        });
+        graph.add_node("collect", |s: FanState| async move { Ok(s) });
+        graph.add_channel(StateChannel::append("docs", |s: &FanState| &s.docs, |s| &mut s.docs));
+        graph.add_channel(StateChannel::reduce(
+            "total",
+            |s: &FanState| &s.total,
+            |s| &mut s.total,
+            crate::langgraph::channel::reducers::add,
+        ));
+        graph.add_edge(START, "plan");
+        graph.add_send_edges("plan", |s: &FanState| {
+            (1..=3)
+                .map(|item| SendPacket::new("work", FanState { item, ..s.clone() }))
+                .collect()
+        });
+        graph.add_edge("work", "collect");
+        graph.add_edge("collect", END);
+
+        let initial = FanState { total: 100, docs: vec!["seed".to_string()], ..Default::default() };
+        let result = graph.compile().unwrap().invoke(initial).await.unwrap();
+        assert_eq!(result.total, 106);
+        assert_eq!(result.docs, vec!["seed", "item-1", "item-2", "item-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_node_usage_is_aggregated() {
+        let mut graph = StateGraph::<TestState>::new();
+        graph.add_node("llm", |s: TestState| async move {
+            let ctx = NodeContext::current().unwrap();
+            ctx.record_usage(TokenUsage::new(100, 20).with_cost(0.002));
+            ctx.record_usage(TokenUsage::new(50, 10).with_cost(0.001));
+            Ok(s)
+        });
+        graph.add_node("tool", |s: TestState| async move { Ok(s) });
+        graph.add_edge(START, "llm");
+        graph.add_edge("llm", "tool");
+        graph.add_edge("tool", END);
+        let graph = graph.compile().unwrap().with_config(ExecutionConfig::new().with_metrics());
+
+        let metrics = graph.invoke_with_metrics(TestState::default()).await.unwrap().metrics.unwrap();
+        let llm = &metrics.node_metrics["llm"];
+        assert_eq!((llm.prompt_tokens, llm.completion_tokens, llm.total_tokens), (150, 30, 180));
+        assert!((llm.cost_usd - 0.003).abs() < 1e-9);
+        assert_eq!(metrics.node_metrics["tool"].total_tokens, 0);
+        assert_eq!(metrics.total_tokens, 180);
+        assert!((metrics.total_cost_usd - 0.003).abs() < 1e-9);
+    }
+
     #[test]
     fn test_ablation_config() {
         let masked: HashSet<String> = vec!["planner".to_string()].into_iter().collect();
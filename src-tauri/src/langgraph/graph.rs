@@ -4,13 +4,21 @@
 
 use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 use crate::langgraph::constants::{START, END, is_reserved_name, has_reserved_chars};
 use crate::langgraph::error::{GraphError, GraphResult};
-use crate::langgraph::state::GraphState;
+use crate::langgraph::state::{GraphState, Updates};
 use crate::langgraph::node::NodeSpec;
 use crate::langgraph::branch::{Branch, BranchSpec};
+use crate::langgraph::channel::StateChannel;
+use crate::langgraph::send::{SendFn, SendPacket};
+use crate::langgraph::subgraph::subgraph_node;
 use crate::langgraph::executor::CompiledGraph;
+use crate::langgraph::visualize::GraphView;
 
 /// Edge type
 #[derive(Clone, Debug)]
@@ -19,6 +27,8 @@ pub enum Edge {
     Direct(String),
     /// Conditional edge with a branch
     Conditional(String), // branch name
+    /// Send edge dispatching packets in parallel
+    Send(String), // sender name
 }
 
 /// StateGraph builder
@@ -38,6 +48,10 @@ pub enum Edge {
 /// graph.add_conditional_edges("process", router_fn, None);
 /// graph.add_edge("validate", END);
 /// 
+/// // Several outgoing edges run their targets in parallel;
+/// // bound fields are merged through channels before the join
+/// graph.add_channel(StateChannel::append("docs", |s: &MyState| &s.docs, |s| &mut s.docs));
+/// 
 /// // Compile and use
 /// let compiled = graph.compile()?;
 /// let result = compiled.invoke(initial_state).await?;
@@ -49,6 +63,10 @@ pub struct StateGraph<S: GraphState> {
     pub(crate) edges: HashMap<String, Vec<Edge>>,
     /// Conditional branches
     pub(crate) branches: HashMap<String, BranchSpec<S>>,
+    /// Send edges (dynamic fan-out)
+    pub(crate) senders: HashMap<String, SendFn<S>>,
+    /// Channels used to merge parallel branches
+    pub(crate) channels: Vec<StateChannel<S>>,
     /// Whether the graph has been compiled
     compiled: bool,
 }
@@ -60,6 +78,8 @@ impl<S: GraphState> StateGraph<S> {
             nodes: HashMap::new(),
             edges: HashMap::new(),
             branches: HashMap::new(),
+            senders: HashMap::new(),
+            channels: Vec::new(),
             compiled: false,
         }
     }
@@ -98,6 +118,58 @@ impl<S: GraphState> StateGraph<S> {
         self
     }
     
+    /// Add an update node
+    /// 
+    /// The node reads a shared snapshot of the state and returns only the
+    /// fields it changed; writes go through the channels bound with `add_channel`.
+    /// 
+    /// # Example
+    /// ```rust
+    /// graph.add_update_node("count", |state: Arc<MyState>| async move {
+    ///     Ok(Updates::new().set("total", state.batch.len() as u32))
+    /// });
+    /// ```
+    pub fn add_update_node<F, Fut>(&mut self, name: impl Into<String>, func: F) -> &mut Self
+    where
+        F: Fn(Arc<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = GraphResult<Updates>> + Send + 'static,
+    {
+        let name = name.into();
+        
+        if has_reserved_chars(&name) {
+            panic!("Node name '{}' contains reserved characters", name);
+        }
+        
+        self.add_node_spec(NodeSpec::update(name, func))
+    }
+    
+    /// Add a compiled graph as a node
+    /// 
+    /// `map_in` builds the subgraph's input from the parent state and `map_out`
+    /// folds its final state back. Interrupts inside the subgraph propagate to
+    /// the parent run, and its metrics are nested under this node. The subgraph
+    /// state is serialized into the parent checkpoint while it is interrupted.
+    pub fn add_subgraph<C, I, O>(
+        &mut self,
+        name: impl Into<String>,
+        graph: CompiledGraph<C>,
+        map_in: I,
+        map_out: O,
+    ) -> &mut Self
+    where
+        C: GraphState + Serialize + DeserializeOwned,
+        I: Fn(&S) -> C + Send + Sync + 'static,
+        O: Fn(S, C) -> S + Send + Sync + 'static,
+    {
+        let name = name.into();
+        
+        if has_reserved_chars(&name) {
+            panic!("Node name '{}' contains reserved characters", name);
+        }
+        
+        self.add_node_spec(subgraph_node(name, graph, map_in, map_out))
+    }
+    
     /// Add a node with a NodeSpec
     pub fn add_node_spec(&mut self, spec: NodeSpec<S>) -> &mut Self {
         let name = spec.name.clone();
@@ -182,6 +254,40 @@ impl<S: GraphState> StateGraph<S> {
         self.add_conditional_edges(from, move |state: S| Ok(path(&state)), path_map)
     }
     
+    /// Add a send edge (LangGraph `Send`)
+    /// 
+    /// `dispatch` returns the packets to run after `from`; each packet invokes
+    /// its node with its own state and all of them run in parallel. The
+    /// dispatched nodes must lead to the same join node.
+    pub fn add_send_edges<F>(&mut self, from: impl Into<String>, dispatch: F) -> &mut Self
+    where
+        F: Fn(&S) -> Vec<SendPacket<S>> + Send + Sync + 'static,
+    {
+        let from = from.into();
+        let sender_name = format!("send_{}", self.senders.len());
+        
+        self.senders.insert(sender_name.clone(), Arc::new(move |state: &S| Ok(dispatch(state))));
+        self.edges
+            .entry(from)
+            .or_default()
+            .push(Edge::Send(sender_name));
+        self
+    }
+    
+    /// Bind a channel to a state field
+    /// 
+    /// Update node writes to the field are applied through the channel, and
+    /// parallel branch outputs are merged field by field through the bound
+    /// channels; fields without a channel take the change of the branch that
+    /// made it, and two branches changing the same one is an error.
+    pub fn add_channel(&mut self, channel: StateChannel<S>) -> &mut Self {
+        if self.channels.iter().any(|c| c.key() == channel.key()) {
+            panic!("Channel '{}' already exists", channel.key());
+        }
+        self.channels.push(channel);
+        self
+    }
+    
     /// Set the entry point of the graph
     /// 
     /// Equivalent to `add_edge(START, node)`
@@ -270,6 +376,8 @@ impl<S: GraphState> StateGraph<S> {
                             }
                         }
                     }
+                    // Send targets are only known at runtime
+                    Edge::Send(_) => {}
                 }
             }
         }
@@ -284,6 +392,21 @@ impl<S: GraphState> StateGraph<S> {
         Ok(())
     }
     
+    /// Static view of the nodes and edges, for drawing and structural checks
+    pub fn view(&self) -> GraphView {
+        GraphView::new(self.nodes.keys(), &self.edges, &self.branches)
+    }
+    
+    /// Export the graph as a Mermaid flowchart
+    pub fn draw_mermaid(&self) -> String {
+        self.view().to_mermaid()
+    }
+    
+    /// Export the graph as a Graphviz DOT digraph
+    pub fn draw_dot(&self) -> String {
+        self.view().to_dot()
+    }
+    
     /// Compile the graph for execution
     pub fn compile(mut self) -> GraphResult<CompiledGraph<S>> {
         self.validate()?;
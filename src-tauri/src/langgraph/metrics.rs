@@ -8,6 +8,50 @@ use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use serde::{Serialize, Deserialize};
 
+/// Token usage and cost reported by a node (e.g. from LLM responses)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    /// Cost in USD (0 when unknown)
+    #[serde(default)]
+    pub cost_usd: f64,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cost_usd: 0.0,
+        }
+    }
+
+    /// Usage where only the total is known
+    pub fn total(total_tokens: u32) -> Self {
+        Self { total_tokens, ..Default::default() }
+    }
+
+    pub fn with_cost(mut self, cost_usd: f64) -> Self {
+        self.cost_usd = cost_usd;
+        self
+    }
+
+    /// Add another usage to this one
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens == 0 && self.cost_usd == 0.0
+    }
+}
+
 /// Metrics for a single node execution
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NodeMetrics {
@@ -21,8 +65,23 @@ pub struct NodeMetrics {
     pub avg_latency_ms: f64,
     /// Total tokens used (if applicable)
     pub total_tokens: u32,
+    /// Prompt tokens used
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    /// Completion tokens used
+    #[serde(default)]
+    pub completion_tokens: u32,
+    /// Total cost in USD
+    #[serde(default)]
+    pub cost_usd: f64,
     /// Number of errors
     pub error_count: u32,
+    /// Number of retries
+    #[serde(default)]
+    pub retry_count: u32,
+    /// Number of attempts that timed out
+    #[serde(default)]
+    pub timeout_count: u32,
     /// Whether this node was skipped (masked)
     pub skipped: bool,
 }
@@ -35,22 +94,42 @@ impl NodeMetrics {
             total_latency_ms: 0,
             avg_latency_ms: 0.0,
             total_tokens: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost_usd: 0.0,
             error_count: 0,
+            retry_count: 0,
+            timeout_count: 0,
             skipped: false,
         }
     }
 
     pub fn record_execution(&mut self, latency_ms: u64, tokens: u32) {
+        self.record_execution_usage(latency_ms, &TokenUsage::total(tokens));
+    }
+
+    pub fn record_execution_usage(&mut self, latency_ms: u64, usage: &TokenUsage) {
         self.call_count += 1;
         self.total_latency_ms += latency_ms;
         self.avg_latency_ms = self.total_latency_ms as f64 / self.call_count as f64;
-        self.total_tokens += tokens;
+        self.total_tokens += usage.total_tokens;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        self.cost_usd += usage.cost_usd;
     }
 
     pub fn record_error(&mut self) {
         self.error_count += 1;
     }
 
+    pub fn record_retry(&mut self) {
+        self.retry_count += 1;
+    }
+
+    pub fn record_timeout(&mut self) {
+        self.timeout_count += 1;
+    }
+
     pub fn mark_skipped(&mut self) {
         self.skipped = true;
     }
@@ -67,6 +146,15 @@ pub struct RunMetrics {
     pub total_latency_ms: u64,
     /// Total tokens used across all nodes
     pub total_tokens: u32,
+    /// Prompt tokens used across all nodes
+    #[serde(default)]
+    pub prompt_tokens: u32,
+    /// Completion tokens used across all nodes
+    #[serde(default)]
+    pub completion_tokens: u32,
+    /// Total cost in USD across all nodes
+    #[serde(default)]
+    pub total_cost_usd: f64,
     /// Whether the run completed successfully
     pub success: bool,
     /// Optional quality score (from evaluator)
@@ -79,6 +167,15 @@ pub struct RunMetrics {
     pub execution_path: Vec<String>,
     /// Error message if failed
     pub error: Option<String>,
+    /// Total node retries
+    #[serde(default)]
+    pub total_retries: u32,
+    /// Total node attempts that timed out
+    #[serde(default)]
+    pub total_timeouts: u32,
+    /// Runs of subgraphs invoked by each node (their token usage is included in the node's)
+    #[serde(default)]
+    pub subgraphs: HashMap<String, Vec<RunMetrics>>,
     /// Timestamp when run started
     pub started_at: String,
 }
@@ -90,26 +187,50 @@ impl RunMetrics {
             config_id: config_id.into(),
             total_latency_ms: 0,
             total_tokens: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            total_cost_usd: 0.0,
             success: false,
             quality_score: None,
             node_metrics: HashMap::new(),
             masked_nodes: Vec::new(),
             execution_path: Vec::new(),
             error: None,
+            total_retries: 0,
+            total_timeouts: 0,
+            subgraphs: HashMap::new(),
             started_at: chrono::Utc::now().to_rfc3339(),
         }
     }
 
     /// Record a node execution
     pub fn record_node(&mut self, node: &str, latency_ms: u64, tokens: u32) {
+        self.record_node_usage(node, latency_ms, &TokenUsage::total(tokens));
+    }
+
+    /// Record a node execution with its token usage
+    pub fn record_node_usage(&mut self, node: &str, latency_ms: u64, usage: &TokenUsage) {
         self.execution_path.push(node.to_string());
         self.total_latency_ms += latency_ms;
-        self.total_tokens += tokens;
+        self.total_tokens += usage.total_tokens;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        self.total_cost_usd += usage.cost_usd;
 
         self.node_metrics
             .entry(node.to_string())
             .or_insert_with(|| NodeMetrics::new(node))
-            .record_execution(latency_ms, tokens);
+            .record_execution_usage(latency_ms, usage);
+    }
+
+    /// Token usage of the whole run
+    pub fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            total_tokens: self.total_tokens,
+            cost_usd: self.total_cost_usd,
+        }
     }
 
     /// Record a node being skipped
@@ -130,6 +251,29 @@ impl RunMetrics {
             .record_error();
     }
 
+    /// Record a node retry
+    pub fn record_retry(&mut self, node: &str) {
+        self.total_retries += 1;
+        self.node_metrics
+            .entry(node.to_string())
+            .or_insert_with(|| NodeMetrics::new(node))
+            .record_retry();
+    }
+
+    /// Record a node attempt that timed out
+    pub fn record_timeout(&mut self, node: &str) {
+        self.total_timeouts += 1;
+        self.node_metrics
+            .entry(node.to_string())
+            .or_insert_with(|| NodeMetrics::new(node))
+            .record_timeout();
+    }
+
+    /// Record a subgraph run started by a node
+    pub fn record_subgraph(&mut self, node: &str, metrics: RunMetrics) {
+        self.subgraphs.entry(node.to_string()).or_default().push(metrics);
+    }
+
     /// Mark the run as successful
     pub fn mark_success(&mut self) {
         self.success = true;
@@ -210,6 +354,9 @@ pub struct AggregateStats {
     pub p95_latency_ms: u64,
     /// Average tokens used
     pub avg_tokens: f64,
+    /// Average cost in USD
+    #[serde(default)]
+    pub avg_cost_usd: f64,
     /// Average quality score (if available)
     pub avg_quality_score: Option<f64>,
     /// Per-node aggregate stats
@@ -222,6 +369,8 @@ pub struct NodeAggregateStats {
     pub call_rate: f64,      // % of runs that called this node
     pub avg_latency_ms: f64,
     pub avg_tokens: f64,
+    #[serde(default)]
+    pub avg_cost_usd: f64,
     pub error_rate: f64,
     pub skip_rate: f64,      // % of runs that skipped this node
 }
@@ -246,6 +395,7 @@ impl AggregateStats {
 
         // Token stats
         let avg_tokens = runs.iter().map(|r| r.total_tokens as f64).sum::<f64>() / run_count as f64;
+        let avg_cost_usd = runs.iter().map(|r| r.total_cost_usd).sum::<f64>() / run_count as f64;
 
         // Quality score
         let quality_scores: Vec<f64> = runs.iter()
@@ -270,6 +420,7 @@ impl AggregateStats {
             let mut call_count = 0;
             let mut total_latency = 0u64;
             let mut total_tokens = 0u32;
+            let mut total_cost = 0.0;
             let mut error_count = 0;
             let mut skip_count = 0;
 
@@ -281,6 +432,7 @@ impl AggregateStats {
                         call_count += 1;
                         total_latency += nm.total_latency_ms;
                         total_tokens += nm.total_tokens;
+                        total_cost += nm.cost_usd;
                         error_count += nm.error_count as usize;
                     }
                 }
@@ -291,6 +443,7 @@ impl AggregateStats {
                 call_rate: call_count as f64 / run_count as f64,
                 avg_latency_ms: if call_count > 0 { total_latency as f64 / call_count as f64 } else { 0.0 },
                 avg_tokens: if call_count > 0 { total_tokens as f64 / call_count as f64 } else { 0.0 },
+                avg_cost_usd: if call_count > 0 { total_cost / call_count as f64 } else { 0.0 },
                 error_rate: if call_count > 0 { error_count as f64 / call_count as f64 } else { 0.0 },
                 skip_rate: skip_count as f64 / run_count as f64,
             });
@@ -304,6 +457,7 @@ impl AggregateStats {
             p50_latency_ms,
             p95_latency_ms,
             avg_tokens,
+            avg_cost_usd,
             avg_quality_score,
             node_stats,
         }
@@ -318,6 +472,7 @@ impl AggregateStats {
             p50_latency_ms: 0,
             p95_latency_ms: 0,
             avg_tokens: 0.0,
+            avg_cost_usd: 0.0,
             avg_quality_score: None,
             node_stats: HashMap::new(),
         }
@@ -350,12 +505,27 @@ impl RunMetricsBuilder {
 
     /// End timing and record node metrics
     pub fn end_node(&mut self, tokens: u32) {
+        self.end_node_usage(&TokenUsage::total(tokens));
+    }
+
+    /// End timing and record node metrics with the node's token usage
+    pub fn end_node_usage(&mut self, usage: &TokenUsage) {
         if let (Some(node), Some(start)) = (self.current_node.take(), self.current_node_start.take()) {
             let latency_ms = start.elapsed().as_millis() as u64;
-            self.metrics.record_node(&node, latency_ms, tokens);
+            self.metrics.record_node_usage(&node, latency_ms, usage);
         }
     }
 
+    /// Record a node timed by the caller (e.g. a parallel branch)
+    pub fn record_node(&mut self, node: &str, latency_ms: u64, tokens: u32) {
+        self.metrics.record_node(node, latency_ms, tokens);
+    }
+
+    /// Record a node timed by the caller, with its token usage
+    pub fn record_node_usage(&mut self, node: &str, latency_ms: u64, usage: &TokenUsage) {
+        self.metrics.record_node_usage(node, latency_ms, usage);
+    }
+
     /// Record that a node was skipped
     pub fn skip_node(&mut self, node: &str) {
         self.metrics.record_skip(node);
@@ -366,6 +536,21 @@ impl RunMetricsBuilder {
         self.metrics.record_error(node, error);
     }
 
+    /// Record a retry
+    pub fn retry(&mut self, node: &str) {
+        self.metrics.record_retry(node);
+    }
+
+    /// Record a timeout
+    pub fn timeout(&mut self, node: &str) {
+        self.metrics.record_timeout(node);
+    }
+
+    /// Record a subgraph run nested under a node
+    pub fn subgraph(&mut self, node: &str, metrics: RunMetrics) {
+        self.metrics.record_subgraph(node, metrics);
+    }
+
     /// Build the final metrics
     pub fn build(mut self, success: bool) -> RunMetrics {
         self.metrics.total_latency_ms = self.start_time.elapsed().as_millis() as u64;
@@ -403,6 +588,12 @@ mod tests {
         assert_eq!(rm.total_tokens, 150);
         assert!(rm.success);
         assert_eq!(rm.execution_path, vec!["node_a", "node_b"]);
+
+        rm.record_node_usage("node_a", 50, &TokenUsage::new(30, 20).with_cost(0.01));
+        assert_eq!(rm.total_tokens, 200);
+        assert_eq!(rm.prompt_tokens, 30);
+        assert_eq!(rm.node_metrics["node_a"].completion_tokens, 20);
+        assert_eq!(rm.usage().cost_usd, 0.01);
     }
 
     #[test]
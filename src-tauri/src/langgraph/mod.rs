@@ -6,7 +6,12 @@
 //!
 //! - **State Graph**: Define nodes and edges for agent workflows
 //! - **Conditional Routing**: Dynamic routing based on state
+//! - **Partial Updates**: Update nodes return only changed fields, applied through channels
+//! - **Parallel Fan-out**: Multiple edges or `SendPacket`s run concurrently, merged through channels
+//! - **Event Stream**: Typed node, custom and token events as an async `Stream`
 //! - **Interrupt/Resume**: Human-in-the-loop support
+//! - **Checkpointing**: Persist state after every node (SQLite), resume after restart
+//! - **Visualization**: Export the wiring as Mermaid/DOT and flag unreachable nodes
 //! - **Metrics Collection**: Track latency, tokens, and success rates
 //! - **Ablation Studies**: Analyze node contributions by masking
 //! - **Evaluators**: Assess output quality with built-in or custom evaluators
@@ -68,6 +73,12 @@ pub mod branch;
 pub mod graph;
 pub mod executor;
 pub mod channel;
+pub mod send;
+pub mod context;
+pub mod events;
+pub mod subgraph;
+pub mod checkpoint;
+pub mod visualize;
 
 // Evaluation modules
 pub mod metrics;
@@ -1,13 +1,15 @@
 //! Node definitions for LangGraph
 //! 
 //! A node is a function that takes state and returns updated state.
+//! Update nodes instead read a shared snapshot and return only the fields they changed.
 
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::langgraph::error::GraphResult;
-use crate::langgraph::state::GraphState;
+use crate::langgraph::error::{GraphError, GraphResult};
+use crate::langgraph::state::{GraphState, Updates};
 
 /// A boxed future type for async node execution
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
@@ -17,6 +19,12 @@ pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 /// A node function takes the current state and returns the updated state.
 pub type NodeFn<S> = Arc<dyn Fn(S) -> BoxFuture<'static, GraphResult<S>> + Send + Sync>;
 
+/// Update node function signature
+/// 
+/// Reads a shared snapshot of the state and returns partial updates, which the
+/// executor applies through the graph's channels without cloning the state.
+pub type UpdateFn<S> = Arc<dyn Fn(Arc<S>) -> BoxFuture<'static, GraphResult<Updates>> + Send + Sync>;
+
 /// Trait for node implementations
 pub trait Node<S: GraphState>: Send + Sync {
     /// Get the node's name
@@ -32,6 +40,8 @@ pub struct NodeSpec<S: GraphState> {
     pub name: String,
     /// Node function
     pub func: NodeFn<S>,
+    /// Update function (set for update nodes)
+    pub update: Option<UpdateFn<S>>,
     /// Optional metadata
     pub metadata: Option<NodeMetadata>,
 }
@@ -41,12 +51,61 @@ pub struct NodeSpec<S: GraphState> {
 pub struct NodeMetadata {
     /// Retry policy
     pub retry_count: usize,
+    /// Delay between retries
+    pub backoff: RetryBackoff,
     /// Timeout in milliseconds
     pub timeout_ms: Option<u64>,
     /// Tags for filtering/routing
     pub tags: Vec<String>,
 }
 
+/// Exponential backoff between node retries
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryBackoff {
+    /// Delay before the first retry in milliseconds
+    pub initial_interval_ms: u64,
+    /// Multiplier applied after each retry
+    pub backoff_factor: f64,
+    /// Upper bound for a single delay in milliseconds
+    pub max_interval_ms: u64,
+    /// Add up to 25% random jitter to each delay
+    pub jitter: bool,
+}
+
+impl Default for RetryBackoff {
+    fn default() -> Self {
+        Self {
+            initial_interval_ms: 500,
+            backoff_factor: 2.0,
+            max_interval_ms: 30_000,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryBackoff {
+    /// No delay between retries
+    pub fn none() -> Self {
+        Self {
+            initial_interval_ms: 0,
+            backoff_factor: 1.0,
+            max_interval_ms: 0,
+            jitter: false,
+        }
+    }
+
+    /// Delay before the given retry (1-based)
+    pub fn delay(&self, retry: usize) -> Duration {
+        let exponent = retry.saturating_sub(1).min(32) as i32;
+        let base = self.initial_interval_ms as f64 * self.backoff_factor.powi(exponent);
+        let mut ms = base.min(self.max_interval_ms as f64);
+        if self.jitter && ms > 0.0 {
+            ms += ms * 0.25 * rand::random::<f64>();
+        }
+        Duration::from_millis(ms as u64)
+    }
+}
+
 impl<S: GraphState> NodeSpec<S> {
     /// Create a new node spec from an async function
     pub fn new<F, Fut>(name: impl Into<String>, func: F) -> Self
@@ -58,10 +117,57 @@ impl<S: GraphState> NodeSpec<S> {
         Self {
             name,
             func: Arc::new(move |state| Box::pin(func(state))),
+            update: None,
+            metadata: None,
+        }
+    }
+    
+    /// Create an update node that returns partial updates
+    /// 
+    /// When run through `Node::execute` (outside a compiled graph, so without
+    /// channels) the writes are applied with `GraphState::set`, and a goto
+    /// replaces the state's routing target.
+    pub fn update<F, Fut>(name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(Arc<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = GraphResult<Updates>> + Send + 'static,
+    {
+        let name = name.into();
+        let update: UpdateFn<S> = Arc::new(move |state| Box::pin(func(state)));
+        let fallback = update.clone();
+        let node = name.clone();
+        Self {
+            name,
+            func: Arc::new(move |state: S| {
+                let update = fallback.clone();
+                let node = node.clone();
+                Box::pin(async move {
+                    let snapshot = Arc::new(state);
+                    let (writes, goto) = update(snapshot.clone()).await?.into_parts();
+                    let mut state = Arc::unwrap_or_clone(snapshot);
+                    for (key, value) in writes {
+                        state.set(&key, value).map_err(|message| GraphError::InvalidUpdate {
+                            node: node.clone(),
+                            key,
+                            message,
+                        })?;
+                    }
+                    if goto.is_some() {
+                        state.set_next(goto);
+                    }
+                    Ok(state)
+                })
+            }),
+            update: Some(update),
             metadata: None,
         }
     }
     
+    /// Whether this node returns partial updates
+    pub fn is_update(&self) -> bool {
+        self.update.is_some()
+    }
+    
     /// Add metadata to the node
     pub fn with_metadata(mut self, metadata: NodeMetadata) -> Self {
         self.metadata = Some(metadata);
@@ -75,6 +181,13 @@ impl<S: GraphState> NodeSpec<S> {
         self
     }
     
+    /// Set retry backoff
+    pub fn with_backoff(mut self, backoff: RetryBackoff) -> Self {
+        let metadata = self.metadata.get_or_insert_with(NodeMetadata::default);
+        metadata.backoff = backoff;
+        self
+    }
+    
     /// Set timeout
     pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
         let metadata = self.metadata.get_or_insert_with(NodeMetadata::default);
@@ -98,6 +211,7 @@ impl<S: GraphState> Clone for NodeSpec<S> {
         Self {
             name: self.name.clone(),
             func: Arc::clone(&self.func),
+            update: self.update.clone(),
             metadata: self.metadata.clone(),
         }
     }
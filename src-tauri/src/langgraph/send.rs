@@ -0,0 +1,30 @@
+//! Send - dynamic fan-out (map-reduce) for LangGraph
+//!
+//! A send edge returns a list of packets; every packet invokes its node with
+//! its own input state, and all packets run in parallel before the graph joins.
+
+use std::sync::Arc;
+
+use crate::langgraph::error::GraphResult;
+
+/// Send function signature - takes the current state and returns the packets to dispatch
+pub type SendFn<S> = Arc<dyn Fn(&S) -> GraphResult<Vec<SendPacket<S>>> + Send + Sync>;
+
+/// A single node invocation dispatched by a send edge
+#[derive(Clone, Debug)]
+pub struct SendPacket<S> {
+    /// Node to invoke
+    pub node: String,
+    /// Input state for this invocation
+    pub state: S,
+}
+
+impl<S> SendPacket<S> {
+    /// Create a packet that invokes `node` with `state`
+    pub fn new(node: impl Into<String>, state: S) -> Self {
+        Self {
+            node: node.into(),
+            state,
+        }
+    }
+}
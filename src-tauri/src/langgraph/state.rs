@@ -46,7 +46,24 @@ pub trait GraphState: Clone + Send + Sync + 'static {
     }
     
     /// Set a value by key (for channel-based state)
-    fn set(&mut self, _key: &str, _value: Box<dyn Any + Send + Sync>) {}
+    ///
+    /// Receives update writes to keys without a bound channel. The default
+    /// rejects them so a write to an unknown key is not silently dropped.
+    fn set(&mut self, _key: &str, _value: Box<dyn Any + Send + Sync>) -> Result<(), String> {
+        Err("no channel bound".to_string())
+    }
+
+    /// Take the fields a parallel branch changed
+    ///
+    /// Called when several branches ran on copies of the shared state: copy onto
+    /// `self` the fields that differ between `input` (the state the branch
+    /// started from) and `output` (the state it returned), and return their
+    /// names. A field without a channel changed by two branches is rejected.
+    /// The default has no field-level view and fails, so such fan-outs need this
+    /// method.
+    fn merge_branch(&mut self, _input: &Self, _output: &Self) -> Result<Vec<String>, String> {
+        Err("state cannot merge parallel branches; implement GraphState::merge_branch".to_string())
+    }
 }
 
 /// A simple state that stores values in a HashMap
@@ -139,3 +156,57 @@ impl<S: GraphState> StateUpdate<S> {
         self
     }
 }
+
+/// A single field write: (key, value)
+pub type Write = (String, Box<dyn Any + Send + Sync>);
+
+/// Partial state update returned by update nodes
+///
+/// Each write targets a state field by key and is applied through the channel
+/// bound to that field, or through `GraphState::set` when no channel is bound
+/// (which fails unless the state overrides it).
+#[derive(Default)]
+pub struct Updates {
+    writes: Vec<Write>,
+    goto: Option<String>,
+}
+
+impl Updates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a value to a state field
+    pub fn set<T: Send + Sync + 'static>(mut self, key: impl Into<String>, value: T) -> Self {
+        self.writes.push((key.into(), Box::new(value)));
+        self
+    }
+
+    /// Route to a node next
+    pub fn goto(mut self, node: impl Into<String>) -> Self {
+        self.goto = Some(node.into());
+        self
+    }
+
+    /// Keys written by this update, in order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.writes.iter().map(|(key, _)| key.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty() && self.goto.is_none()
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<Write>, Option<String>) {
+        (self.writes, self.goto)
+    }
+}
+
+impl std::fmt::Debug for Updates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Updates")
+            .field("keys", &self.keys().collect::<Vec<_>>())
+            .field("goto", &self.goto)
+            .finish()
+    }
+}
@@ -0,0 +1,229 @@
+//! Subgraph - run a compiled graph as a node of another graph
+//!
+//! The parent state is mapped into the subgraph's state type, the subgraph runs
+//! to completion, and its final state is folded back into the parent state.
+
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::langgraph::context::NodeContext;
+use crate::langgraph::error::{GraphError, GraphResult, ResumeCommand};
+use crate::langgraph::executor::{Checkpoint, CompiledGraph, ExecutionResult};
+use crate::langgraph::node::NodeSpec;
+use crate::langgraph::state::GraphState;
+
+/// Build a node that runs `graph` as a subgraph
+///
+/// Interrupts raised inside the subgraph propagate to the parent run, with the
+/// subgraph node prefixed to `Interrupt::node` (e.g. `research/clarify`). The
+/// interrupted run's checkpoint is stored in the parent run's checkpoint: when
+/// the parent resumes with a value for one of its interrupts, the subgraph
+/// continues from where it stopped instead of starting over, also after the
+/// graph was rebuilt or restored from a `Checkpointer`. Metrics of the subgraph
+/// run are nested under this node.
+pub fn subgraph_node<S, C, I, O>(
+    name: impl Into<String>,
+    graph: CompiledGraph<C>,
+    map_in: I,
+    map_out: O,
+) -> NodeSpec<S>
+where
+    S: GraphState,
+    C: GraphState + Serialize + DeserializeOwned,
+    I: Fn(&S) -> C + Send + Sync + 'static,
+    O: Fn(S, C) -> S + Send + Sync + 'static,
+{
+    let name = name.into();
+    let graph = Arc::new(graph);
+    let map_in = Arc::new(map_in);
+    let map_out = Arc::new(map_out);
+    let node_name = name.clone();
+
+    NodeSpec::new(name, move |state: S| {
+        let graph = graph.clone();
+        let map_in = map_in.clone();
+        let map_out = map_out.clone();
+        let node_name = node_name.clone();
+
+        async move {
+            let ctx = NodeContext::current();
+            let resumable = match &ctx {
+                Some(ctx) => take_resumable(ctx)?,
+                None => None,
+            };
+            let result = match resumable {
+                Some((checkpoint, command)) => graph.resume(checkpoint, command).await?,
+                None => graph.invoke_resumable(map_in(&state)).await?,
+            };
+
+            match result {
+                ExecutionResult::Complete(output) => Ok(map_out(state, output)),
+                ExecutionResult::Interrupted { checkpoint, interrupts } => {
+                    if let Some(ctx) = &ctx {
+                        let checkpoint = serde_json::to_value(&checkpoint).map_err(|e| {
+                            GraphError::CheckpointError(format!("Failed to encode subgraph '{}': {}", node_name, e))
+                        })?;
+                        ctx.save_subgraph(checkpoint);
+                    }
+                    let interrupts = interrupts
+                        .into_iter()
+                        .map(|mut interrupt| {
+                            interrupt.node = format!("{}/{}", node_name, interrupt.node);
+                            interrupt
+                        })
+                        .collect();
+                    Err(GraphError::Interrupted(interrupts))
+                }
+            }
+        }
+    })
+}
+
+/// Take this node's interrupted run if the parent has provided a resume value for it
+fn take_resumable<C: DeserializeOwned>(ctx: &NodeContext) -> GraphResult<Option<(Checkpoint<C>, ResumeCommand)>> {
+    let Some(saved) = ctx.take_subgraph() else {
+        return Ok(None);
+    };
+    let checkpoint: Checkpoint<C> = serde_json::from_value(saved).map_err(|e| {
+        GraphError::CheckpointError(format!("Failed to decode subgraph '{}': {}", ctx.node(), e))
+    })?;
+    // The run resumes with the first of its interrupts the parent has answered
+    let answered = checkpoint
+        .pending_interrupts
+        .iter()
+        .find_map(|interrupt| ctx.resume_value(&interrupt.id).map(|value| (interrupt.id.clone(), value.clone())));
+    Ok(answered.map(|(id, value)| (checkpoint, ResumeCommand::with_id(value, id))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::langgraph::constants::{START, END};
+    use crate::langgraph::error::{interrupt_all, Interrupt};
+    use crate::langgraph::checkpoint::SqliteCheckpointer;
+    use crate::langgraph::graph::StateGraph;
+    use crate::langgraph::metrics::MetricsCollector;
+    use serde::Deserialize;
+
+    #[derive(Clone, Default, Serialize, Deserialize)]
+    struct Outer {
+        topic: String,
+        answer: String,
+    }
+
+    impl GraphState for Outer {}
+
+    #[derive(Clone, Default, Serialize, Deserialize)]
+    struct Inner {
+        question: String,
+        answer: String,
+    }
+
+    impl GraphState for Inner {}
+
+    fn inner_graph(prepared: Arc<AtomicUsize>) -> CompiledGraph<Inner> {
+        let mut graph = StateGraph::<Inner>::new();
+        graph.add_node("prepare", move |s: Inner| {
+            prepared.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(s) }
+        });
+        graph.add_node("ask", |mut s: Inner| async move {
+            let ctx = NodeContext::current().expect("node context");
+            match ctx.resume_value("confirm") {
+                Some(value) => {
+                    s.answer = format!("{}: {}", s.question, value.as_str().unwrap_or_default());
+                    Ok(s)
+                }
+                None => interrupt_all(vec![Interrupt::with_id("Confirm?", "ask", "confirm")]),
+            }
+        });
+        graph.add_edge(START, "prepare");
+        graph.add_edge("prepare", "ask");
+        graph.add_edge("ask", END);
+        graph.compile().unwrap()
+    }
+
+    fn outer_graph(prepared: Arc<AtomicUsize>) -> StateGraph<Outer> {
+        let mut graph = StateGraph::<Outer>::new();
+        graph.add_subgraph(
+            "research",
+            inner_graph(prepared),
+            |s: &Outer| Inner { question: s.topic.clone(), ..Default::default() },
+            |mut s: Outer, inner: Inner| {
+                s.answer = inner.answer;
+                s
+            },
+        );
+        graph.add_edge(START, "research");
+        graph.add_edge("research", END);
+        graph
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_interrupt_and_resume() {
+        let prepared = Arc::new(AtomicUsize::new(0));
+        let graph = outer_graph(prepared.clone());
+
+        let collector = Arc::new(MetricsCollector::new());
+        let graph = graph.compile().unwrap().with_metrics_collector(collector.clone());
+        let initial = Outer { topic: "rust".to_string(), ..Default::default() };
+
+        let checkpoint = match graph.invoke_resumable(initial).await.unwrap() {
+            ExecutionResult::Interrupted { checkpoint, interrupts } => {
+                assert_eq!(interrupts[0].id, "confirm");
+                assert_eq!(interrupts[0].node, "research/ask");
+                checkpoint
+            }
+            ExecutionResult::Complete(_) => panic!("expected interrupt"),
+        };
+
+        match graph.resume(checkpoint, ResumeCommand::new("yes")).await.unwrap() {
+            ExecutionResult::Complete(state) => assert_eq!(state.answer, "rust: yes"),
+            ExecutionResult::Interrupted { .. } => panic!("subgraph interrupted again"),
+        }
+        // The subgraph continued from its checkpoint instead of starting over
+        assert_eq!(prepared.load(Ordering::SeqCst), 1);
+
+        let runs = collector.get_runs();
+        assert_eq!(runs.len(), 1);
+        let nested = &runs[0].subgraphs["research"];
+        assert_eq!(nested.len(), 1);
+        assert_eq!(nested[0].execution_path, vec!["ask"]);
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_resumes_after_rebuild() {
+        let prepared = Arc::new(AtomicUsize::new(0));
+        let checkpointer = Arc::new(SqliteCheckpointer::in_memory().unwrap());
+        let initial = Outer { topic: "rust".to_string(), ..Default::default() };
+
+        let first = outer_graph(prepared.clone()).compile().unwrap().with_checkpointer(checkpointer.clone(), "t1");
+        assert!(matches!(
+            first.invoke_resumable(initial.clone()).await.unwrap(),
+            ExecutionResult::Interrupted { .. }
+        ));
+        // Another thread of the same graph interrupts with the same id
+        let other = first.clone().with_checkpointer(checkpointer.clone(), "t2");
+        let other_checkpoint = match other.invoke_resumable(Outer { topic: "go".to_string(), ..initial }).await.unwrap() {
+            ExecutionResult::Interrupted { checkpoint, .. } => checkpoint,
+            ExecutionResult::Complete(_) => panic!("expected interrupt"),
+        };
+        assert_eq!(prepared.load(Ordering::SeqCst), 2);
+
+        // A freshly built graph continues the subgraph from the persisted checkpoint
+        let rebuilt = outer_graph(prepared.clone()).compile().unwrap().with_checkpointer(checkpointer, "t1");
+        let checkpoint = rebuilt.load_checkpoint(None).unwrap().unwrap();
+        match rebuilt.resume(checkpoint, ResumeCommand::new("yes")).await.unwrap() {
+            ExecutionResult::Complete(state) => assert_eq!(state.answer, "rust: yes"),
+            ExecutionResult::Interrupted { .. } => panic!("subgraph interrupted again"),
+        }
+        match other.resume(other_checkpoint, ResumeCommand::new("no")).await.unwrap() {
+            ExecutionResult::Complete(state) => assert_eq!(state.answer, "go: no"),
+            ExecutionResult::Interrupted { .. } => panic!("subgraph interrupted again"),
+        }
+        assert_eq!(prepared.load(Ordering::SeqCst), 2);
+    }
+}
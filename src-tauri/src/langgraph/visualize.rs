@@ -0,0 +1,310 @@
+//! Graph visualization - export the wiring as Mermaid or Graphviz DOT
+//!
+//! [`GraphView`] is built from the actual nodes and edges of a graph, so the
+//! drawings never drift from the code. It also runs a structural check that
+//! flags unreachable nodes and dead ends.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::langgraph::branch::{Branch, BranchSpec};
+use crate::langgraph::constants::{START, END};
+use crate::langgraph::graph::Edge;
+use crate::langgraph::state::GraphState;
+
+/// Kind of a drawn edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// Direct edge
+    Direct,
+    /// Conditional branch target
+    Conditional,
+    /// Send target, decided at runtime
+    Send,
+}
+
+/// An edge between two nodes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeView {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// Path map key, when it differs from the target
+    pub label: Option<String>,
+}
+
+/// Structural problem found by [`GraphView::validate`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraphIssue {
+    /// No path from START reaches the node
+    Unreachable { node: String },
+    /// The node has no outgoing edges and only finishes implicitly
+    DeadEnd { node: String },
+    /// Every path from the node loops forever without reaching END
+    NoPathToEnd { node: String },
+    /// A conditional branch has no path map, so its targets are unknown
+    UnmappedBranch { node: String },
+}
+
+/// Static view of a graph's nodes and edges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphView {
+    /// Node names, sorted
+    pub nodes: Vec<String>,
+    /// Edges in declaration order per source node
+    pub edges: Vec<EdgeView>,
+    /// Nodes with a conditional branch that has no path map
+    pub unmapped: Vec<String>,
+}
+
+impl GraphView {
+    pub(crate) fn new<'a, S: GraphState>(
+        nodes: impl Iterator<Item = &'a String>,
+        edges: &HashMap<String, Vec<Edge>>,
+        branches: &HashMap<String, BranchSpec<S>>,
+    ) -> Self {
+        let nodes: Vec<String> = nodes.cloned().collect::<BTreeSet<_>>().into_iter().collect();
+
+        // START first, then nodes in name order
+        let mut sources: Vec<&String> = edges.keys().collect();
+        sources.sort_by_key(|s| (s.as_str() != START, s.as_str()));
+
+        let mut view_edges = Vec::new();
+        let mut unmapped = Vec::new();
+        for from in sources {
+            for edge in &edges[from] {
+                match edge {
+                    Edge::Direct(to) => view_edges.push(EdgeView {
+                        from: from.clone(),
+                        to: to.clone(),
+                        kind: EdgeKind::Direct,
+                        label: None,
+                    }),
+                    Edge::Conditional(name) => match branches.get(name).and_then(|b| b.destinations()) {
+                        Some(map) => {
+                            let mut targets: Vec<(&String, &String)> = map.iter().collect();
+                            targets.sort();
+                            view_edges.extend(targets.into_iter().map(|(key, to)| EdgeView {
+                                from: from.clone(),
+                                to: to.clone(),
+                                kind: EdgeKind::Conditional,
+                                label: (key != to).then(|| key.clone()),
+                            }));
+                        }
+                        // Like LangGraph, an unmapped branch may go to any node
+                        None => {
+                            unmapped.push(from.clone());
+                            view_edges.extend(nodes.iter().chain([&END.to_string()]).map(|to| EdgeView {
+                                from: from.clone(),
+                                to: to.clone(),
+                                kind: EdgeKind::Conditional,
+                                label: None,
+                            }));
+                        }
+                    },
+                    Edge::Send(_) => view_edges.extend(nodes.iter().map(|to| EdgeView {
+                        from: from.clone(),
+                        to: to.clone(),
+                        kind: EdgeKind::Send,
+                        label: None,
+                    })),
+                }
+            }
+        }
+        view_edges.dedup();
+
+        Self { nodes, edges: view_edges, unmapped }
+    }
+
+    /// Render as a Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart TD\n");
+        out.push_str(&format!("    {}([\"{}\"])\n", mermaid_id(START), START));
+        for node in &self.nodes {
+            out.push_str(&format!("    {}[\"{}\"]\n", mermaid_id(node), escape_mermaid(node)));
+        }
+        out.push_str(&format!("    {}([\"{}\"])\n", mermaid_id(END), END));
+
+        for edge in &self.edges {
+            let arrow = match (edge.kind, &edge.label) {
+                (EdgeKind::Direct, _) => "-->".to_string(),
+                (EdgeKind::Conditional, None) => "-.->".to_string(),
+                (EdgeKind::Conditional, Some(label)) => format!("-.->|{}|", escape_mermaid(label)),
+                (EdgeKind::Send, _) => "==>|send|".to_string(),
+            };
+            out.push_str(&format!("    {} {} {}\n", mermaid_id(&edge.from), arrow, mermaid_id(&edge.to)));
+        }
+        out
+    }
+
+    /// Render as a Graphviz DOT digraph
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n    rankdir=TB;\n");
+        out.push_str(&format!("    {} [shape=oval];\n", dot_id(START)));
+        for node in &self.nodes {
+            out.push_str(&format!("    {} [shape=box];\n", dot_id(node)));
+        }
+        out.push_str(&format!("    {} [shape=oval];\n", dot_id(END)));
+
+        for edge in &self.edges {
+            let attrs = match (edge.kind, &edge.label) {
+                (EdgeKind::Direct, _) => String::new(),
+                (EdgeKind::Conditional, None) => " [style=dashed]".to_string(),
+                (EdgeKind::Conditional, Some(label)) => format!(" [style=dashed, label={}]", dot_id(label)),
+                (EdgeKind::Send, _) => " [style=bold, label=\"send\"]".to_string(),
+            };
+            out.push_str(&format!("    {} -> {}{};\n", dot_id(&edge.from), dot_id(&edge.to), attrs));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Check the wiring for unreachable nodes and dead ends
+    ///
+    /// Unmapped branches and send edges may reach any node, so they never
+    /// cause false reports; they are listed as `UnmappedBranch` instead.
+    pub fn validate(&self) -> Vec<GraphIssue> {
+        let mut issues: Vec<GraphIssue> = self.unmapped.iter()
+            .map(|node| GraphIssue::UnmappedBranch { node: node.clone() })
+            .collect();
+
+        let mut forward: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut backward: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &self.edges {
+            forward.entry(&edge.from).or_default().push(&edge.to);
+            backward.entry(&edge.to).or_default().push(&edge.from);
+        }
+
+        let reachable = reachable_from(START, &forward);
+        for node in &self.nodes {
+            if !reachable.contains(node.as_str()) {
+                issues.push(GraphIssue::Unreachable { node: node.clone() });
+            }
+        }
+
+        // Nodes without edges finish implicitly, so they count as exits
+        let dead_ends: Vec<&String> = self.nodes.iter()
+            .filter(|n| !forward.contains_key(n.as_str()))
+            .collect();
+        let mut finishing = reachable_from(END, &backward);
+        for node in &dead_ends {
+            finishing.extend(reachable_from(node, &backward));
+            issues.push(GraphIssue::DeadEnd { node: (*node).clone() });
+        }
+        for node in &self.nodes {
+            if reachable.contains(node.as_str()) && !finishing.contains(node.as_str()) {
+                issues.push(GraphIssue::NoPathToEnd { node: node.clone() });
+            }
+        }
+
+        issues
+    }
+}
+
+/// Nodes reachable from `start` (inclusive) following `adjacency`
+fn reachable_from<'a>(start: &'a str, adjacency: &HashMap<&'a str, Vec<&'a str>>) -> HashSet<&'a str> {
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in adjacency.get(node).into_iter().flatten() {
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Mermaid node ids allow only word characters
+fn mermaid_id(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;").replace('|', "#124;")
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::langgraph::branch::path_map;
+    use crate::langgraph::graph::StateGraph;
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Flow {
+        done: bool,
+    }
+
+    impl GraphState for Flow {}
+
+    fn graph() -> StateGraph<Flow> {
+        let mut graph = StateGraph::<Flow>::new();
+        for name in ["plan", "act", "report"] {
+            graph.add_node(name, |s: Flow| async move { Ok(s) });
+        }
+        graph.add_edge(START, "plan");
+        graph.add_edge("plan", "act");
+        graph.add_conditional_edges_sync(
+            "act",
+            |s: &Flow| if s.done { "finish".to_string() } else { "act".to_string() },
+            Some(HashMap::from([
+                ("finish".to_string(), "report".to_string()),
+                ("act".to_string(), "act".to_string()),
+            ])),
+        );
+        graph.set_finish_point("report");
+        graph
+    }
+
+    #[test]
+    fn test_export_mermaid_and_dot() {
+        let view = graph().view();
+        assert!(view.validate().is_empty());
+
+        let mermaid = view.to_mermaid();
+        assert!(mermaid.starts_with("flowchart TD\n"));
+        assert!(mermaid.contains("    __start__ --> plan\n"));
+        assert!(mermaid.contains("    act -.->|finish| report\n"));
+        assert!(mermaid.contains("    act -.-> act\n"));
+        assert!(mermaid.contains("    report --> __end__\n"));
+
+        let dot = graph().compile().unwrap().draw_dot();
+        assert!(dot.contains("    \"act\" -> \"report\" [style=dashed, label=\"finish\"];\n"));
+        assert!(dot.contains("    \"plan\" -> \"act\";\n"));
+    }
+
+    #[test]
+    fn test_validate_flags_issues() {
+        let mut graph = graph();
+        graph.add_node("orphan", |s: Flow| async move { Ok(s) });
+        graph.add_node("loop", |s: Flow| async move { Ok(s) });
+        graph.add_node("stuck", |s: Flow| async move { Ok(s) });
+        graph.add_conditional_edges_sync("plan", |_: &Flow| "loop".to_string(), Some(path_map(["loop", "stuck"])));
+        graph.add_edge("loop", "loop");
+
+        let issues = graph.view().validate();
+        assert!(issues.contains(&GraphIssue::Unreachable { node: "orphan".to_string() }));
+        assert!(issues.contains(&GraphIssue::DeadEnd { node: "stuck".to_string() }));
+        assert!(issues.contains(&GraphIssue::NoPathToEnd { node: "loop".to_string() }));
+        assert!(!issues.contains(&GraphIssue::NoPathToEnd { node: "plan".to_string() }));
+    }
+
+    #[test]
+    fn test_unmapped_branch_reaches_any_node() {
+        let mut graph = StateGraph::<Flow>::new();
+        graph.add_node("route", |s: Flow| async move { Ok(s) });
+        graph.add_node("work", |s: Flow| async move { Ok(s) });
+        graph.add_edge(START, "route");
+        graph.add_conditional_edges_sync("route", |_: &Flow| END.to_string(), None);
+        graph.add_edge("work", END);
+
+        assert_eq!(graph.view().validate(), vec![GraphIssue::UnmappedBranch { node: "route".to_string() }]);
+    }
+}
@@ -5,6 +5,8 @@ mod error;
 mod fs;
 mod vector_db;
 mod llm;
+mod sse;
+mod network;
 pub mod agent;
 pub mod langgraph;
 pub mod mcp;
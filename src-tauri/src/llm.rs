@@ -1,13 +1,19 @@
 /**
  * LLM HTTP Client
  * 使用 Rust reqwest 库发送 HTTP 请求，避免 WebView 的 HTTP/2 协议问题
- * 支持流式传输 (SSE)
+ * 支持流式传输 (SSE，由 crate::sse 解码)
  */
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::{AppHandle, Emitter, Manager};
 use futures_util::StreamExt;
+use serde_json::Value;
+
+use crate::agent::budget::{self, BudgetConfig};
+use crate::langgraph::metrics::TokenUsage;
+use crate::network::NetworkOptions;
+use crate::sse::SseDecoder;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LLMRequest {
@@ -16,6 +22,78 @@ pub struct LLMRequest {
     pub headers: HashMap<String, String>,
     pub body: Option<String>,  // JSON string
     pub timeout_secs: Option<u64>,
+    /// 代理、超时与证书选项
+    #[serde(default)]
+    pub network: NetworkOptions,
+    /// 限流与当日预算
+    #[serde(default)]
+    pub budget: BudgetConfig,
+}
+
+impl LLMRequest {
+    /// 请求的模型：请求体中的 `model`，或 Gemini URL 中的 `models/{model}:`
+    fn model(&self) -> String {
+        self.body.as_deref()
+            .and_then(|body| serde_json::from_str::<Value>(body).ok())
+            .and_then(|body| body["model"].as_str().map(str::to_string))
+            .or_else(|| {
+                let rest = self.url.split("/models/").nth(1)?;
+                Some(rest.split([':', '?', '/']).next()?.to_string())
+            })
+            .unwrap_or_default()
+    }
+
+    /// 请求前检查当日预算，并按主机限流
+    async fn before_request(&self, app: &AppHandle) -> Result<(), String> {
+        budget::check_daily(&self.budget).map_err(|e| e.to_string())?;
+        if let Some(message) = self.budget.unpriced_warning(&self.model()) {
+            eprintln!("[Budget] {}", message);
+            let _ = app.emit("llm-budget-warning", message);
+        }
+        if let Some(per_minute) = self.budget.requests_per_minute {
+            let host = reqwest::Url::parse(&self.url).ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_default();
+            budget::acquire_rate_limit(&host, per_minute).await;
+        }
+        Ok(())
+    }
+
+    /// 将响应中的 token 用量与估算费用计入当日用量
+    fn record_usage(&self, usage: TokenUsage, cached_tokens: u32) {
+        if usage.is_empty() {
+            return;
+        }
+        let cost = self.budget.estimate_cost(
+            &self.model(),
+            usage.prompt_tokens as usize,
+            cached_tokens as usize,
+            usage.completion_tokens as usize,
+        );
+        budget::record_daily(&usage.with_cost(cost));
+    }
+}
+
+/// 流式响应中的用量，各事件分别携带部分统计（如 Anthropic 的 message_start 与 message_delta）
+#[derive(Default)]
+struct StreamUsage {
+    usage: TokenUsage,
+    cached_tokens: u32,
+}
+
+impl StreamUsage {
+    fn push(&mut self, data: &str) {
+        let Ok(json) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+        if let Some(usage) = budget::parse_usage(&json) {
+            self.usage = TokenUsage::new(
+                self.usage.prompt_tokens.max(usage.prompt_tokens),
+                self.usage.completion_tokens.max(usage.completion_tokens),
+            );
+        }
+        self.cached_tokens = self.cached_tokens.max(budget::parse_cached_tokens(&json));
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,11 +105,13 @@ pub struct LLMResponse {
 
 /// 发送 LLM API 请求（带重试机制）
 #[tauri::command]
-pub async fn llm_fetch(request: LLMRequest) -> Result<LLMResponse, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(request.timeout_secs.unwrap_or(120)))
+pub async fn llm_fetch(app: AppHandle, request: LLMRequest) -> Result<LLMResponse, String> {
+    let builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(request.timeout_secs.unwrap_or(120)));
+    let client = request.network.apply(builder)?
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    request.before_request(&app).await?;
 
     let max_retries = 2;
     let mut last_error = String::new();
@@ -65,6 +145,11 @@ pub async fn llm_fetch(request: LLMRequest) -> Result<LLMResponse, String> {
                 let status = response.status().as_u16();
                 match response.text().await {
                     Ok(body) => {
+                        if let Ok(json) = serde_json::from_str::<Value>(&body) {
+                            if let Some(usage) = budget::parse_usage(&json) {
+                                request.record_usage(usage, budget::parse_cached_tokens(&json));
+                            }
+                        }
                         return Ok(LLMResponse {
                             status,
                             body,
@@ -108,11 +193,22 @@ pub async fn llm_fetch_stream(
     request_id: String,
     request: LLMRequest,
 ) -> Result<(), String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(request.timeout_secs.unwrap_or(300)))
+    let builder = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(request.timeout_secs.unwrap_or(300)));
+    let client = request.network.apply(builder)?
         .build()
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
+    if let Err(e) = request.before_request(&app).await {
+        let _ = app.emit("llm-stream-chunk", StreamChunk {
+            request_id,
+            chunk: String::new(),
+            done: true,
+            error: Some(e),
+        });
+        return Ok(());
+    }
+
     let mut req_builder = match request.method.to_uppercase().as_str() {
         "POST" => client.post(&request.url),
         "GET" => client.get(&request.url),
@@ -157,50 +253,14 @@ pub async fn llm_fetch_stream(
 
     // 流式读取响应体
     let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
-
-    while let Some(chunk_result) = stream.next().await {
-        match chunk_result {
-            Ok(bytes) => {
-                let text = String::from_utf8_lossy(&bytes);
-                buffer.push_str(&text);
-
-                // 按行处理 SSE 数据
-                while let Some(newline_pos) = buffer.find('\n') {
-                    let line = buffer[..newline_pos].trim().to_string();
-                    buffer = buffer[newline_pos + 1..].to_string();
-
-                    // 跳过空行和 keep-alive 注释
-                    if line.is_empty() || line.starts_with(": keep-alive") || line == ":" {
-                        continue;
-                    }
+    let mut decoder = SseDecoder::new();
+    let mut usage = StreamUsage::default();
 
-                    // 解析 SSE data 行
-                    if line.starts_with("data: ") {
-                        let data = &line[6..];
-                        
-                        // [DONE] 表示流结束
-                        if data == "[DONE]" {
-                            let _ = app.emit("llm-stream-chunk", StreamChunk {
-                                request_id: request_id.clone(),
-                                chunk: String::new(),
-                                done: true,
-                                error: None,
-                            });
-                            return Ok(());
-                        }
-
-                        // 发送数据块
-                        let _ = app.emit("llm-stream-chunk", StreamChunk {
-                            request_id: request_id.clone(),
-                            chunk: data.to_string(),
-                            done: false,
-                            error: None,
-                        });
-                    }
-                }
-            }
-            Err(e) => {
+    loop {
+        let (events, finished) = match stream.next().await {
+            Some(Ok(bytes)) => (decoder.push(&bytes), false),
+            Some(Err(e)) => {
+                request.record_usage(usage.usage, usage.cached_tokens);
                 let _ = app.emit("llm-stream-chunk", StreamChunk {
                     request_id,
                     chunk: String::new(),
@@ -209,10 +269,41 @@ pub async fn llm_fetch_stream(
                 });
                 return Ok(());
             }
+            // 最后一个事件之后可能没有空行
+            None => (decoder.finish(), true),
+        };
+
+        for event in events {
+            // [DONE] 表示流结束
+            if event.data == "[DONE]" {
+                request.record_usage(usage.usage, usage.cached_tokens);
+                let _ = app.emit("llm-stream-chunk", StreamChunk {
+                    request_id: request_id.clone(),
+                    chunk: String::new(),
+                    done: true,
+                    error: None,
+                });
+                return Ok(());
+            }
+
+            usage.push(&event.data);
+
+            // 发送数据块（多行 data 已按规范以换行连接）
+            let _ = app.emit("llm-stream-chunk", StreamChunk {
+                request_id: request_id.clone(),
+                chunk: event.data,
+                done: false,
+                error: None,
+            });
+        }
+
+        if finished {
+            break;
         }
     }
 
     // 流正常结束
+    request.record_usage(usage.usage, usage.cached_tokens);
     let _ = app.emit("llm-stream-chunk", StreamChunk {
         request_id,
         chunk: String::new(),
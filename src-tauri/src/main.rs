@@ -9,8 +9,11 @@ mod fs;
 mod error;
 mod vector_db;
 mod llm;
+mod sse;
+mod network;
 mod cef;
 mod webdav;
+mod git;
 mod langgraph;
 mod agent;
 mod mcp;
@@ -112,12 +115,30 @@ fn main() {
             webdav::commands::webdav_execute_sync,
             webdav::commands::webdav_quick_sync,
             webdav::commands::webdav_scan_local,
+            webdav::commands::webdav_dry_run,
+            webdav::commands::webdav_get_file_history,
+            webdav::commands::webdav_get_sync_history,
+            webdav::commands::webdav_get_device_info,
+            // Git commands
+            git::commands::git_init,
+            git::commands::git_init_remote,
+            git::commands::git_status,
+            git::commands::git_commit_all,
+            git::commands::git_log,
+            git::commands::git_diff,
+            git::commands::git_push,
+            git::commands::git_pull,
             // Agent commands
             agent::agent_start_task,
             agent::agent_abort,
             agent::agent_approve_tool,
             agent::agent_get_status,
             agent::agent_continue_with_answer,
+            agent::agent_resume_thread,
+            agent::agent_fork_thread,
+            agent::agent_list_threads,
+            agent::agent_get_checkpoints,
+            agent::agent_delete_thread,
             // Agent debug commands
             agent::agent_enable_debug,
             agent::agent_disable_debug,
@@ -128,6 +149,11 @@ fn main() {
             agent::deep_research_resume,
             agent::deep_research_abort,
             agent::deep_research_is_running,
+            // Local LLM commands
+            agent::local::local_llm_detect,
+            agent::local::local_runtime_start,
+            agent::local::local_runtime_stop,
+            agent::local::local_runtime_status,
             // MCP commands
             mcp::mcp_init,
             mcp::mcp_list_servers,
@@ -155,6 +181,11 @@ fn main() {
         .manage(agent::DeepResearchStateManager::new())
         .manage(codex_vscode_host::CodexVscodeHostState::default())
         .setup(|app| {
+            // 当日 LLM 用量按日期保存，重启后继续累计
+            if let Ok(dir) = app.path().app_data_dir() {
+                agent::budget::load_daily_ledger(dir.join("llm_usage.json"));
+            }
+
             let window = app.get_webview_window("main").unwrap();
             
             // Mac 上启用 decorations 并使用透明标题栏，避免无边框窗口的兼容性问题
@@ -172,6 +203,12 @@ fn main() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            // 内置 llama-server 不随应用退出，需主动结束
+            if let tauri::RunEvent::Exit = event {
+                let _ = tauri::async_runtime::block_on(agent::local::stop_managed_runtime());
+            }
+        });
 }
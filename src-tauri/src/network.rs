@@ -0,0 +1,127 @@
+//! 网络连接选项
+//!
+//! WebDAV 与 LLM 的 HTTP 客户端共用：代理、超时与自定义证书
+
+use reqwest::{Certificate, ClientBuilder, Proxy};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// HTTP 连接选项
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkOptions {
+    /// 代理 URL (如 http://proxy.corp:8080、socks5://127.0.0.1:1080)，为空时使用系统代理
+    pub proxy_url: Option<String>,
+    /// 连接超时 (秒)
+    pub connect_timeout_secs: Option<u64>,
+    /// 读取超时 (秒)：两次收到数据之间的最长间隔
+    pub read_timeout_secs: Option<u64>,
+    /// 额外信任的 CA 证书文件 (PEM，可包含多个证书)
+    pub ca_bundle_path: Option<String>,
+    /// 是否接受无效证书 (自签名、过期、主机名不匹配)，仅用于受信任的内网服务器
+    pub accept_invalid_certs: bool,
+}
+
+impl NetworkOptions {
+    /// 连接超时
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout_secs.map(Duration::from_secs)
+    }
+
+    /// 读取超时
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout_secs.map(Duration::from_secs)
+    }
+
+    /// 将选项应用到 reqwest 客户端构建器
+    ///
+    /// 未设置的选项保持构建器原有的值，调用方可以先设置自己的默认超时
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, String> {
+        if let Some(url) = self.proxy_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+            let proxy = Proxy::all(url)
+                .map_err(|e| format!("Invalid proxy URL '{}': {}", url, e))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(timeout) = self.connect_timeout() {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout() {
+            builder = builder.read_timeout(timeout);
+        }
+
+        if let Some(path) = self.ca_bundle_path.as_deref().filter(|p| !p.is_empty()) {
+            for cert in load_ca_bundle(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if self.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// 读取 PEM 格式的 CA 证书文件
+fn load_ca_bundle(path: &str) -> Result<Vec<Certificate>, String> {
+    let pem = std::fs::read(path)
+        .map_err(|e| format!("Failed to read CA bundle '{}': {}", path, e))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid CA bundle '{}': {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in CA bundle '{}'", path));
+    }
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_apply_options() {
+        let options = NetworkOptions {
+            proxy_url: Some("http://127.0.0.1:3128".to_string()),
+            connect_timeout_secs: Some(5),
+            read_timeout_secs: Some(60),
+            accept_invalid_certs: true,
+            ..Default::default()
+        };
+        assert!(options.apply(reqwest::Client::builder()).unwrap().build().is_ok());
+
+        let invalid = NetworkOptions {
+            proxy_url: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.apply(reqwest::Client::builder()).is_err());
+    }
+
+    #[test]
+    fn test_ca_bundle_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate").unwrap();
+
+        let options = NetworkOptions {
+            ca_bundle_path: Some(path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        assert!(options.apply(reqwest::Client::builder()).is_err());
+
+        let missing = NetworkOptions {
+            ca_bundle_path: Some(dir.path().join("missing.pem").to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        assert!(missing.apply(reqwest::Client::builder()).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_partial() {
+        let options: NetworkOptions = serde_json::from_str(r#"{"proxy_url":"http://p:1"}"#).unwrap();
+        assert_eq!(options.proxy_url.as_deref(), Some("http://p:1"));
+        assert!(!options.accept_invalid_certs);
+    }
+}
@@ -0,0 +1,263 @@
+//! 流式响应解码
+//!
+//! LLM 流式接口（`llm_fetch_stream` 与 Agent 的 LlmClient）共用：
+//! - [`LineDecoder`]：按行切分字节流（NDJSON 等），跨 chunk 的不完整 UTF-8 字符留到下次解码
+//! - [`SseDecoder`]：按 [SSE 规范](https://html.spec.whatwg.org/multipage/server-sent-events.html)
+//!   解析事件，支持多行 `data:`、`event:`、`id:`、`retry:` 与注释行
+
+use std::time::Duration;
+
+/// 按行切分字节流，支持 `\n`、`\r\n` 与 `\r` 换行
+#[derive(Debug, Default)]
+pub struct LineDecoder {
+    buffer: Vec<u8>,
+    /// 上一个 chunk 以 `\r` 结尾，下一个 chunk 开头的 `\n` 属于同一个换行
+    pending_cr: bool,
+}
+
+impl LineDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一个 chunk，返回其中完整的行（不含换行符）
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut bytes = bytes;
+        if self.pending_cr {
+            self.pending_cr = false;
+            if let Some(rest) = bytes.strip_prefix(b"\n") {
+                bytes = rest;
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' => {
+                    lines.push(self.take_line(&bytes[start..i]));
+                    start = i + 1;
+                }
+                b'\r' => {
+                    lines.push(self.take_line(&bytes[start..i]));
+                    if i + 1 == bytes.len() {
+                        self.pending_cr = true;
+                    } else if bytes[i + 1] == b'\n' {
+                        i += 1;
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.buffer.extend_from_slice(&bytes[start..]);
+        lines
+    }
+
+    /// 流结束，返回没有换行符结尾的最后一行
+    pub fn finish(&mut self) -> Option<String> {
+        self.pending_cr = false;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        Some(self.take_line(&[]))
+    }
+
+    /// 换行符为 ASCII，完整的一行不会截断 UTF-8 字符
+    fn take_line(&mut self, tail: &[u8]) -> String {
+        self.buffer.extend_from_slice(tail);
+        let line = String::from_utf8_lossy(&self.buffer).into_owned();
+        self.buffer.clear();
+        line
+    }
+}
+
+/// SSE 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// 事件类型，未指定时为 `message`
+    pub event: String,
+    /// 多行 `data:` 以 `\n` 连接
+    pub data: String,
+    /// 最近一次 `id:` 的值
+    pub id: Option<String>,
+}
+
+/// SSE 解码器
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    lines: LineDecoder,
+    /// 是否已处理流开头（去除 BOM）
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一个 chunk，返回其中完整的事件
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let bytes = if self.started || bytes.is_empty() {
+            bytes
+        } else {
+            self.started = true;
+            bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes)
+        };
+        let lines = self.lines.push(bytes);
+        lines.iter().filter_map(|line| self.process_line(line)).collect()
+    }
+
+    /// 流结束
+    ///
+    /// 规范要求丢弃没有以空行结束的事件，但部分服务（如 Gemini）最后一个事件后没有空行，
+    /// 这里仍然派发
+    pub fn finish(&mut self) -> Vec<SseEvent> {
+        let mut events: Vec<SseEvent> = self.lines.finish()
+            .and_then(|line| self.process_line(&line))
+            .into_iter()
+            .collect();
+        events.extend(self.dispatch());
+        events
+    }
+
+    /// 服务端通过 `retry:` 建议的重连间隔
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// 最近一次的事件 ID，重连时作为 `Last-Event-ID` 发送
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            // 注释（心跳）
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok().map(Duration::from_millis);
+            }
+            // 未知字段忽略
+            _ => {}
+        }
+        None
+    }
+
+    /// 空行派发当前事件，没有 data 时只重置事件类型
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        if !self.has_data {
+            return None;
+        }
+        self.has_data = false;
+        Some(SseEvent {
+            event: if event.is_empty() { "message".to_string() } else { event },
+            data: std::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_chunks(chunks: &[&[u8]]) -> (Vec<SseEvent>, SseDecoder) {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            events.extend(decoder.push(chunk));
+        }
+        events.extend(decoder.finish());
+        (events, decoder)
+    }
+
+    #[test]
+    fn test_sse_fields() {
+        let stream = b"\xEF\xBB\xBF: keep-alive\r\n\
+            retry: 3000\r\n\
+            event: message_start\r\n\
+            id: 42\r\n\
+            data: {\"a\":1}\r\n\r\n\
+            data:first\n\
+            data: second\n\
+            data\n\n\
+            event: ping\n\n\
+            retry: soon\n\
+            unknown: field\n\
+            data: [DONE]\n\n";
+        let (events, decoder) = decode_chunks(&[stream]);
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], SseEvent {
+            event: "message_start".to_string(),
+            data: "{\"a\":1}".to_string(),
+            id: Some("42".to_string()),
+        });
+        // 多行 data 以换行连接，没有值的 data 字段为空行
+        assert_eq!(events[1].data, "first\nsecond\n");
+        assert_eq!(events[1].event, "message");
+        assert_eq!(events[1].id.as_deref(), Some("42"));
+        // 没有 data 的事件不派发，其事件类型也不会带到下一个事件
+        assert_eq!(events[2].event, "message");
+        assert_eq!(events[2].data, "[DONE]");
+        // 无效的 retry 被忽略
+        assert_eq!(decoder.retry(), Some(Duration::from_millis(3000)));
+        assert_eq!(decoder.last_event_id(), Some("42"));
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        let text = "data: 你好，世界\r\n\r\ndata: 第二条\n\n";
+        let bytes = text.as_bytes();
+        // 在每个字节处切分：跨 chunk 的 UTF-8 字符与 `\r\n` 都能正确处理
+        for split in 1..bytes.len() {
+            let (events, _) = decode_chunks(&[&bytes[..split], &bytes[split..]]);
+            let data: Vec<&str> = events.iter().map(|e| e.data.as_str()).collect();
+            assert_eq!(data, vec!["你好，世界", "第二条"], "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_last_event() {
+        let (events, _) = decode_chunks(&[b"data: a\n\ndata: b"]);
+        let data: Vec<&str> = events.iter().map(|e| e.data.as_str()).collect();
+        assert_eq!(data, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_line_decoder() {
+        let mut decoder = LineDecoder::new();
+        let mut lines = decoder.push(b"{\"a\":1}\r");
+        lines.extend(decoder.push(b"\n{\"b\":\xE4\xBD"));
+        lines.extend(decoder.push(b"\xA0}\n{\"c\":3}"));
+        lines.extend(decoder.finish());
+        assert_eq!(lines, vec!["{\"a\":1}", "{\"b\":你}", "{\"c\":3}"]);
+    }
+}
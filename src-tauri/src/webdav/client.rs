@@ -2,30 +2,63 @@
 //! 
 //! 封装 WebDAV 协议的 HTTP 请求，提供高层 API
 
-use reqwest::{Client, Method, StatusCode};
-use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Body, Client, Method, StatusCode};
+use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
-use std::time::Duration;
+use futures_util::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 use super::types::{WebDAVConfig, RemoteEntry};
 use crate::error::AppError;
 
+/// 分块上传的默认分块大小 (Nextcloud 要求除最后一块外不小于 5 MB)
+const DEFAULT_CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 大文件传输的单次请求超时
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// 普通请求的默认超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 默认连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 流式读取文件时的缓冲区大小
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
 /// WebDAV 客户端
 pub struct WebDAVClient {
     client: Client,
     config: WebDAVConfig,
+    /// 超过该大小的文件使用分块上传
+    chunk_size: u64,
 }
 
 impl WebDAVClient {
     /// 创建新的 WebDAV 客户端
     pub fn new(config: WebDAVConfig) -> Result<Self, AppError> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .connect_timeout(Duration::from_secs(10))
+        let network = &config.network;
+        // 读取超时超过默认请求超时时，放宽整体超时，避免慢速代理下请求被提前中断
+        let timeout = network.read_timeout().map_or(REQUEST_TIMEOUT, |t| t.max(REQUEST_TIMEOUT));
+        let builder = Client::builder()
+            .timeout(timeout)
+            .connect_timeout(CONNECT_TIMEOUT);
+        let client = network.apply(builder)
+            .map_err(AppError::WebDAV)?
             .build()
             .map_err(|e| AppError::WebDAV(format!("Failed to create HTTP client: {}", e)))?;
         
-        Ok(Self { client, config })
+        Ok(Self { client, config, chunk_size: DEFAULT_CHUNK_SIZE })
+    }
+
+    /// 设置分块上传的分块大小
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
     }
 
     /// 构建认证头
@@ -106,6 +139,30 @@ impl WebDAVClient {
         self.parse_propfind_response(&body, path)
     }
 
+    /// 查询单个文件的属性 (PROPFIND Depth 0)，远程不存在时返回 None
+    pub async fn stat(&self, path: &str) -> Result<Option<RemoteEntry>, AppError> {
+        let url = self.build_url(path);
+
+        let response = self.client
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
+            .header(AUTHORIZATION, self.auth_header())
+            .header("Depth", "0")
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("PROPFIND failed: {}", e)))?;
+
+        match response.status() {
+            StatusCode::MULTI_STATUS => {}
+            StatusCode::NOT_FOUND => return Ok(None),
+            status => return Err(AppError::WebDAV(format!("PROPFIND failed with status: {}", status))),
+        }
+
+        let body = response.text().await
+            .map_err(|e| AppError::WebDAV(format!("Failed to read response: {}", e)))?;
+
+        Ok(self.parse_propfind_response(&body, "")?.into_iter().next())
+    }
+
     /// 递归列出所有文件
     pub async fn list_all_recursive(&self, path: &str) -> Result<Vec<RemoteEntry>, AppError> {
         let mut all_entries = Vec::new();
@@ -134,10 +191,10 @@ impl WebDAVClient {
         
         let base_path_normalized = base_path.trim_matches('/');
         
-        // 按 <D:response> 分割
-        for response_block in xml.split("<D:response>").skip(1) {
-            let href = self.extract_xml_value(response_block, "D:href")
-                .or_else(|| self.extract_xml_value(response_block, "d:href"));
+        // 命名空间前缀大小写因服务器而异（如 Nextcloud 使用 d:），统一后按 <d:response> 分割
+        let xml = xml.replace("<D:", "<d:").replace("</D:", "</d:");
+        for response_block in xml.split("<d:response>").skip(1) {
+            let href = self.extract_xml_value(response_block, "d:href");
             
             if let Some(href) = href {
                 // URL 解码
@@ -149,26 +206,19 @@ impl WebDAVClient {
                     continue;
                 }
                 
-                let is_dir = response_block.contains("<D:collection")
-                    || response_block.contains("<d:collection")
-                    || response_block.contains("resourcetype><D:collection")
-                    || response_block.contains("resourcetype><d:collection");
+                let is_dir = response_block.contains("<d:collection");
                 
-                let size = self.extract_xml_value(response_block, "D:getcontentlength")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getcontentlength"))
+                let size = self.extract_xml_value(response_block, "d:getcontentlength")
                     .and_then(|s| s.parse::<u64>().ok())
                     .unwrap_or(0);
                 
-                let modified = self.extract_xml_value(response_block, "D:getlastmodified")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getlastmodified"))
+                let modified = self.extract_xml_value(response_block, "d:getlastmodified")
                     .and_then(|s| parse_http_date(&s))
                     .unwrap_or(0);
                 
-                let etag = self.extract_xml_value(response_block, "D:getetag")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getetag"));
+                let etag = self.extract_xml_value(response_block, "d:getetag");
                 
-                let content_type = self.extract_xml_value(response_block, "D:getcontenttype")
-                    .or_else(|| self.extract_xml_value(response_block, "d:getcontenttype"));
+                let content_type = self.extract_xml_value(response_block, "d:getcontenttype");
                 
                 let name = path.split('/').last().unwrap_or("").to_string();
                 
@@ -255,6 +305,42 @@ impl WebDAVClient {
             .map_err(|e| AppError::WebDAV(format!("Failed to read download: {}", e)))
     }
 
+    /// 下载文件，远程不存在时返回 None
+    pub async fn download_optional(&self, path: &str) -> Result<Option<Vec<u8>>, AppError> {
+        Ok(self.download_with_etag(path).await?.map(|(content, _)| content))
+    }
+
+    /// 下载文件及其 ETag，远程不存在时返回 None
+    pub async fn download_with_etag(&self, path: &str) -> Result<Option<(Vec<u8>, Option<String>)>, AppError> {
+        let url = self.build_url(path);
+
+        let response = self.client
+            .get(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("Download failed: {}", e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(AppError::WebDAV(format!(
+                "Download failed with status: {}",
+                response.status()
+            )));
+        }
+
+        let etag = response.headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        response.bytes().await
+            .map(|b| Some((b.to_vec(), etag)))
+            .map_err(|e| AppError::WebDAV(format!("Failed to read download: {}", e)))
+    }
+
     /// 下载文件为文本
     pub async fn download_text(&self, path: &str) -> Result<String, AppError> {
         let bytes = self.download(path).await?;
@@ -280,6 +366,251 @@ impl WebDAVClient {
         }
     }
 
+    /// 条件上传 (PUT + If-Match)
+    ///
+    /// `etag` 为 None 时仅在远程不存在时创建 (If-None-Match: *)。
+    /// 远程已被其他客户端改写时返回 `Ok(false)`
+    pub async fn upload_if_match(&self, path: &str, content: &[u8], etag: Option<&str>) -> Result<bool, AppError> {
+        let url = self.build_url(path);
+
+        let request = self.client
+            .put(&url)
+            .header(AUTHORIZATION, self.auth_header());
+        let request = match etag {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+
+        let response = request
+            .body(content.to_vec())
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("Upload failed: {}", e)))?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(true),
+            StatusCode::PRECONDITION_FAILED => Ok(false),
+            status => Err(AppError::WebDAV(format!("Upload failed with status: {}", status))),
+        }
+    }
+
+    /// 流式下载到本地文件
+    ///
+    /// 先写入同目录下的临时文件，完成后再重命名，避免中断时留下不完整的文件
+    pub async fn download_to_file(&self, path: &str, dest: &Path) -> Result<u64, AppError> {
+        let url = self.build_url(path);
+
+        let response = self.client
+            .get(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .timeout(TRANSFER_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("Download failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::WebDAV(format!(
+                "Download failed with status: {}",
+                response.status()
+            )));
+        }
+
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let temp_path = download_temp_path(dest);
+        let result = write_stream_to_file(response.bytes_stream(), &temp_path).await;
+        let written = match result {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(e);
+            }
+        };
+
+        tokio::fs::rename(&temp_path, dest).await
+            .map_err(|e| AppError::WebDAV(format!("Failed to move downloaded file: {}", e)))?;
+
+        Ok(written)
+    }
+
+    /// 流式上传本地文件
+    ///
+    /// 大文件在支持的服务器 (Nextcloud chunking v2) 上使用可续传的分块上传，
+    /// 其余情况使用单个流式 PUT
+    pub async fn upload_file(&self, path: &str, src: &Path) -> Result<u64, AppError> {
+        let metadata = tokio::fs::metadata(src).await
+            .map_err(|e| AppError::WebDAV(format!("Failed to read local file: {}", e)))?;
+        let size = metadata.len();
+
+        if size > self.chunk_size {
+            if let Some(upload_root) = self.chunked_upload_root() {
+                let mtime = metadata.modified().ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let transfer = transfer_id(path, size, mtime);
+                if self.upload_chunked(path, src, size, &upload_root, &transfer).await? {
+                    return Ok(size);
+                }
+                eprintln!("[WebDAV] Chunked upload not supported, falling back to PUT for {}", path);
+            }
+        }
+
+        let url = self.build_url(path);
+        let response = self.client
+            .put(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .header(CONTENT_LENGTH, size)
+            .timeout(TRANSFER_TIMEOUT)
+            .body(file_body(src, 0, size).await?)
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("Upload failed: {}", e)))?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(size),
+            status => Err(AppError::WebDAV(format!("Upload failed with status: {}", status))),
+        }
+    }
+
+    /// Nextcloud chunking v2 上传目录的根 URL
+    ///
+    /// 仅当服务器地址形如 `.../remote.php/...` 时认为支持
+    fn chunked_upload_root(&self) -> Option<String> {
+        let base = self.config.server_url.trim_end_matches('/');
+        let idx = base.find("/remote.php/")?;
+        let server = &base[..idx];
+
+        let user = base
+            .split("/remote.php/dav/files/")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .filter(|u| !u.is_empty())
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| urlencoding::encode(&self.config.username).into_owned());
+
+        Some(format!("{}/remote.php/dav/uploads/{}", server, user))
+    }
+
+    /// 分块上传 (Nextcloud chunking v2)
+    ///
+    /// 上传目录名由文件路径、大小和修改时间决定，中断后重试会跳过服务器上已有的分块。
+    /// 服务器不支持时返回 `Ok(false)`
+    async fn upload_chunked(
+        &self,
+        path: &str,
+        src: &Path,
+        size: u64,
+        upload_root: &str,
+        transfer: &str,
+    ) -> Result<bool, AppError> {
+        let destination = self.build_url(path);
+        let upload_url = format!("{}/{}", upload_root, transfer);
+
+        let existing = match self.list_uploaded_chunks(&upload_url).await? {
+            Some(chunks) => chunks,
+            None => {
+                let response = self.client
+                    .request(Method::from_bytes(b"MKCOL").unwrap(), &upload_url)
+                    .header(AUTHORIZATION, self.auth_header())
+                    .header("Destination", &destination)
+                    .send()
+                    .await
+                    .map_err(|e| AppError::WebDAV(format!("MKCOL failed: {}", e)))?;
+
+                match response.status() {
+                    StatusCode::CREATED | StatusCode::OK => HashMap::new(),
+                    _ => return Ok(false),
+                }
+            }
+        };
+
+        let chunk_count = size.div_ceil(self.chunk_size);
+        for index in 0..chunk_count {
+            let offset = index * self.chunk_size;
+            let len = self.chunk_size.min(size - offset);
+            let name = format!("{:05}", index + 1);
+
+            if existing.get(&name) == Some(&len) {
+                continue;
+            }
+
+            let response = self.client
+                .put(format!("{}/{}", upload_url, name))
+                .header(AUTHORIZATION, self.auth_header())
+                .header("Destination", &destination)
+                .header("OC-Total-Length", size)
+                .header(CONTENT_LENGTH, len)
+                .timeout(TRANSFER_TIMEOUT)
+                .body(file_body(src, offset, len).await?)
+                .send()
+                .await
+                .map_err(|e| AppError::WebDAV(format!("Chunk upload failed: {}", e)))?;
+
+            match response.status() {
+                StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => {}
+                status => {
+                    return Err(AppError::WebDAV(format!(
+                        "Chunk {} upload failed with status: {}",
+                        name, status
+                    )))
+                }
+            }
+        }
+
+        let response = self.client
+            .request(Method::from_bytes(b"MOVE").unwrap(), format!("{}/.file", upload_url))
+            .header(AUTHORIZATION, self.auth_header())
+            .header("Destination", &destination)
+            .header("OC-Total-Length", size)
+            .header("Overwrite", "T")
+            .timeout(TRANSFER_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("Chunk assembly failed: {}", e)))?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(true),
+            status => Err(AppError::WebDAV(format!("Chunk assembly failed with status: {}", status))),
+        }
+    }
+
+    /// 列出上传目录中已有的分块 (名称 -> 大小)，目录不存在时返回 None
+    async fn list_uploaded_chunks(&self, upload_url: &str) -> Result<Option<HashMap<String, u64>>, AppError> {
+        let response = self.client
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), upload_url)
+            .header(AUTHORIZATION, self.auth_header())
+            .header("Depth", "1")
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("PROPFIND failed: {}", e)))?;
+
+        if response.status() != StatusCode::MULTI_STATUS {
+            return Ok(None);
+        }
+
+        let body = response.text().await
+            .map_err(|e| AppError::WebDAV(format!("Failed to read response: {}", e)))?;
+        let body = body.replace("<D:", "<d:").replace("</D:", "</d:");
+
+        let mut chunks = HashMap::new();
+        for block in body.split("<d:response>").skip(1) {
+            let Some(href) = self.extract_xml_value(block, "d:href") else {
+                continue;
+            };
+            let name = href.trim_end_matches('/').rsplit('/').next().unwrap_or("").to_string();
+            let size = self.extract_xml_value(block, "d:getcontentlength")
+                .and_then(|s| s.parse::<u64>().ok());
+            if let Some(size) = size {
+                chunks.insert(name, size);
+            }
+        }
+
+        Ok(Some(chunks))
+    }
+
     /// 上传文本文件
     pub async fn upload_text(&self, path: &str, content: &str) -> Result<(), AppError> {
         self.upload(path, content.as_bytes()).await
@@ -339,6 +670,66 @@ impl WebDAVClient {
     }
 }
 
+/// 下载时使用的临时文件路径（以 `.` 开头，不会被同步扫描到）
+fn download_temp_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    dest.with_file_name(format!(".{}.lumina-download", name))
+}
+
+/// 将响应流写入文件，返回写入的字节数
+async fn write_stream_to_file<S, B>(mut stream: S, path: &Path) -> Result<u64, AppError>
+where
+    S: Stream<Item = Result<B, reqwest::Error>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut written = 0u64;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| AppError::WebDAV(format!("Failed to read download: {}", e)))?;
+        file.write_all(chunk.as_ref()).await?;
+        written += chunk.as_ref().len() as u64;
+    }
+
+    file.flush().await?;
+    file.sync_all().await?;
+    Ok(written)
+}
+
+/// 以流的形式读取文件的 `[offset, offset + len)` 部分作为请求体
+async fn file_body(path: &Path, offset: u64, len: u64) -> Result<Body, AppError> {
+    let mut file = tokio::fs::File::open(path).await
+        .map_err(|e| AppError::WebDAV(format!("Failed to read local file: {}", e)))?;
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset)).await?;
+    }
+
+    let stream = futures_util::stream::try_unfold(file.take(len), |mut reader| async move {
+        let mut buf = vec![0u8; STREAM_BUFFER_SIZE];
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok::<_, std::io::Error>(None);
+        }
+        buf.truncate(n);
+        Ok(Some((buf, reader)))
+    });
+
+    Ok(Body::wrap_stream(stream))
+}
+
+/// 分块上传的传输 ID
+///
+/// 同一文件（路径、大小、修改时间都不变）重试时得到相同的 ID，以便续传
+fn transfer_id(path: &str, size: u64, mtime: u64) -> String {
+    // FNV-1a，保证跨版本稳定
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in format!("{}:{}:{}", path, size, mtime).bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("lumina-{:016x}", hash)
+}
+
 /// 简单的 URL 解码
 fn urlencoding_decode(s: &str) -> String {
     let mut result = String::new();
@@ -396,6 +787,274 @@ fn parse_http_date(s: &str) -> Option<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::NetworkOptions;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+    use wiremock::matchers::any;
+    use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+    /// 内存中的 WebDAV 服务器，支持 Nextcloud chunking v2 的 MKCOL/PUT/MOVE
+    #[derive(Clone, Default)]
+    struct FakeDav {
+        files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+        dirs: Arc<Mutex<Vec<String>>>,
+        requests: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl FakeDav {
+        fn put(&self, path: &str, content: &[u8]) {
+            self.files.lock().unwrap().insert(path.to_string(), content.to_vec());
+        }
+
+        fn get(&self, path: &str) -> Option<Vec<u8>> {
+            self.files.lock().unwrap().get(path).cloned()
+        }
+
+        fn requests(&self, method: &str) -> Vec<String> {
+            self.requests.lock().unwrap().iter()
+                .filter(|(m, _)| m == method)
+                .map(|(_, p)| p.clone())
+                .collect()
+        }
+    }
+
+    impl Respond for FakeDav {
+        fn respond(&self, request: &Request) -> ResponseTemplate {
+            let path = request.url.path().to_string();
+            let method = request.method.to_string();
+            self.requests.lock().unwrap().push((method.clone(), path.clone()));
+
+            match method.as_str() {
+                "GET" => match self.get(&path) {
+                    Some(body) => ResponseTemplate::new(200)
+                        .insert_header("ETag", fake_etag(&body).as_str())
+                        .set_body_bytes(body),
+                    None => ResponseTemplate::new(404),
+                },
+                "PUT" => {
+                    let current = self.get(&path).map(|c| fake_etag(&c));
+                    let header = |name: &str| request.headers.get(name).and_then(|v| v.to_str().ok());
+                    let precondition = match (header("If-Match"), header("If-None-Match")) {
+                        (Some(expected), _) => current.as_deref() == Some(expected),
+                        (None, Some("*")) => current.is_none(),
+                        _ => true,
+                    };
+                    if !precondition {
+                        return ResponseTemplate::new(412);
+                    }
+                    self.put(&path, &request.body);
+                    ResponseTemplate::new(201)
+                }
+                "MKCOL" => {
+                    self.dirs.lock().unwrap().push(path);
+                    ResponseTemplate::new(201)
+                }
+                "PROPFIND" => {
+                    if let Some(content) = self.get(&path) {
+                        // Nextcloud 风格的小写 d: 前缀
+                        return ResponseTemplate::new(207).set_body_string(format!(
+                            "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\"><d:response><d:href>{}</d:href><d:propstat><d:prop><d:getcontentlength>{}</d:getcontentlength><d:getetag>{}</d:getetag><d:resourcetype/></d:prop></d:propstat></d:response></d:multistatus>",
+                            path,
+                            content.len(),
+                            fake_etag(&content)
+                        ));
+                    }
+                    if !self.dirs.lock().unwrap().contains(&path) {
+                        return ResponseTemplate::new(404);
+                    }
+                    let prefix = format!("{}/", path);
+                    let mut body = String::from("<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\">");
+                    for (name, content) in self.files.lock().unwrap().iter() {
+                        if name.starts_with(&prefix) {
+                            body.push_str(&format!(
+                                "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:getcontentlength>{}</d:getcontentlength></d:prop></d:propstat></d:response>",
+                                name,
+                                content.len()
+                            ));
+                        }
+                    }
+                    body.push_str("</d:multistatus>");
+                    ResponseTemplate::new(207).set_body_string(body)
+                }
+                "MOVE" => {
+                    let dir = path.trim_end_matches("/.file").to_string();
+                    let destination = request.headers.get("Destination").unwrap().to_str().unwrap();
+                    let destination = reqwest::Url::parse(destination).unwrap().path().to_string();
+
+                    let mut files = self.files.lock().unwrap();
+                    let mut chunks: Vec<String> = files.keys()
+                        .filter(|k| k.starts_with(&format!("{}/", dir)))
+                        .cloned()
+                        .collect();
+                    chunks.sort();
+                    let mut assembled = Vec::new();
+                    for chunk in chunks {
+                        assembled.extend(files.remove(&chunk).unwrap());
+                    }
+                    files.insert(destination, assembled);
+                    ResponseTemplate::new(201)
+                }
+                _ => ResponseTemplate::new(405),
+            }
+        }
+    }
+
+    fn fake_etag(content: &[u8]) -> String {
+        format!("\"{:x}\"", content.iter().fold(content.len() as u64, |h, b| h.wrapping_mul(31).wrapping_add(*b as u64)))
+    }
+
+    async fn start_server(dav: &FakeDav) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(any()).respond_with(dav.clone()).mount(&server).await;
+        server
+    }
+
+    fn client_for(server_url: String) -> WebDAVClient {
+        WebDAVClient::new(WebDAVConfig {
+            server_url,
+            username: "alice".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_streams_into_place() {
+        let dav = FakeDav::default();
+        let server = start_server(&dav).await;
+        let content = sample(200_000);
+        dav.put("/dav/videos/lecture.mp4", &content);
+
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("videos/lecture.mp4");
+        let client = client_for(format!("{}/dav", server.uri()));
+
+        let written = client.download_to_file("videos/lecture.mp4", &dest).await.unwrap();
+        assert_eq!(written, content.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), content);
+        assert!(!download_temp_path(&dest).exists());
+
+        assert!(client.download_to_file("missing.mp4", &dir.path().join("missing.mp4")).await.is_err());
+        assert!(!dir.path().join("missing.mp4").exists());
+    }
+
+    #[tokio::test]
+    async fn test_stat_parses_lowercase_namespace() {
+        let dav = FakeDav::default();
+        let server = start_server(&dav).await;
+        dav.put("/dav/notes/a.md", b"# A");
+        let client = client_for(format!("{}/dav", server.uri()));
+
+        let entry = client.stat("notes/a.md").await.unwrap().unwrap();
+        assert_eq!(entry.name, "a.md");
+        assert_eq!(entry.size, 3);
+        assert!(!entry.is_dir);
+        assert_eq!(entry.etag, Some(fake_etag(b"# A")));
+        assert!(client.stat("notes/missing.md").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_configured_proxy() {
+        let dav = FakeDav::default();
+        let proxy = start_server(&dav).await;
+        dav.put("/dav/note.md", b"# via proxy");
+
+        let client = WebDAVClient::new(WebDAVConfig {
+            server_url: "http://dav.internal.invalid/dav".to_string(),
+            network: NetworkOptions {
+                proxy_url: Some(proxy.uri()),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(client.download_text("note.md").await.unwrap(), "# via proxy");
+        assert_eq!(dav.requests("GET"), vec!["/dav/note.md".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_file_uses_single_put_without_chunking_support() {
+        let dav = FakeDav::default();
+        let server = start_server(&dav).await;
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("big.bin");
+        let content = sample(5000);
+        std::fs::write(&src, &content).unwrap();
+
+        let client = client_for(format!("{}/dav", server.uri())).with_chunk_size(1024);
+        client.upload_file("big.bin", &src).await.unwrap();
+
+        assert_eq!(dav.get("/dav/big.bin").unwrap(), content);
+        assert_eq!(dav.requests("PUT"), vec!["/dav/big.bin".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_chunked_upload_and_resume() {
+        let dav = FakeDav::default();
+        let server = start_server(&dav).await;
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("lecture.mp4");
+        let content = sample(3000);
+        std::fs::write(&src, &content).unwrap();
+
+        let server_url = format!("{}/remote.php/dav/files/alice", server.uri());
+        let client = client_for(server_url).with_chunk_size(1024);
+
+        // 模拟上次中断：上传目录与第一个分块已存在
+        let mtime = std::fs::metadata(&src).unwrap().modified().unwrap()
+            .duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let upload_dir = format!("/remote.php/dav/uploads/alice/{}", transfer_id("lecture.mp4", 3000, mtime));
+        dav.dirs.lock().unwrap().push(upload_dir.clone());
+        dav.put(&format!("{}/00001", upload_dir), &content[..1024]);
+
+        client.upload_file("lecture.mp4", &src).await.unwrap();
+
+        assert_eq!(dav.get("/remote.php/dav/files/alice/lecture.mp4").unwrap(), content);
+        assert_eq!(
+            dav.requests("PUT"),
+            vec![format!("{}/00002", upload_dir), format!("{}/00003", upload_dir)]
+        );
+        assert!(dav.requests("MKCOL").is_empty());
+        assert_eq!(dav.requests("MOVE"), vec![format!("{}/.file", upload_dir)]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_if_match_rejects_stale_etag() {
+        let dav = FakeDav::default();
+        let server = start_server(&dav).await;
+        let client = client_for(format!("{}/dav", server.uri()));
+
+        // 远程不存在时只允许创建
+        assert!(client.upload_if_match("m.json", b"v1", None).await.unwrap());
+        assert!(!client.upload_if_match("m.json", b"v1-again", None).await.unwrap());
+
+        let (content, etag) = client.download_with_etag("m.json").await.unwrap().unwrap();
+        assert_eq!(content, b"v1");
+        let etag = etag.unwrap();
+
+        // 另一台设备先写入，旧 ETag 失效
+        assert!(client.upload_if_match("m.json", b"v2", Some(&etag)).await.unwrap());
+        assert!(!client.upload_if_match("m.json", b"v2-stale", Some(&etag)).await.unwrap());
+        assert_eq!(dav.get("/dav/m.json").unwrap(), b"v2");
+    }
+
+    #[test]
+    fn test_chunked_upload_root() {
+        let client = client_for("https://cloud.example.com/remote.php/dav/files/bob/".to_string());
+        assert_eq!(
+            client.chunked_upload_root().as_deref(),
+            Some("https://cloud.example.com/remote.php/dav/uploads/bob")
+        );
+
+        let client = client_for("https://dav.example.com/webdav".to_string());
+        assert!(client.chunked_upload_root().is_none());
+    }
 
     #[test]
     fn test_url_decode() {
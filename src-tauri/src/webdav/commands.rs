@@ -3,11 +3,12 @@
 //! 暴露给前端的命令接口
 
 use std::sync::Mutex;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 use super::types::*;
 use super::client::WebDAVClient;
 use super::sync::SyncEngine;
+use super::history::{self, SyncJournal};
 use crate::error::AppError;
 
 /// WebDAV 状态管理
@@ -120,34 +121,55 @@ pub async fn webdav_delete(
     client.delete(&remote_path).await
 }
 
+/// 加载本机设备标识
+fn load_device(app: &AppHandle) -> Result<DeviceInfo, AppError> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| AppError::WebDAV(format!("Failed to resolve app data dir: {}", e)))?;
+    DeviceInfo::load_or_create(&dir)
+}
+
+/// 创建带本机设备标识的同步引擎
+fn device_engine(app: &AppHandle, config: WebDAVConfig, vault_path: String) -> Result<SyncEngine, AppError> {
+    Ok(SyncEngine::new(config, vault_path)?.with_device(load_device(app)?))
+}
+
+/// 获取本机设备信息
+#[tauri::command]
+pub async fn webdav_get_device_info(app: AppHandle) -> Result<DeviceInfo, AppError> {
+    load_device(&app)
+}
+
 /// 计算同步计划
 #[tauri::command]
 pub async fn webdav_compute_sync_plan(
+    app: AppHandle,
     config: WebDAVConfig,
     vault_path: String,
 ) -> Result<SyncPlan, AppError> {
-    let mut engine = SyncEngine::new(config, vault_path)?;
+    let mut engine = device_engine(&app, config, vault_path)?;
     engine.compute_sync_plan().await
 }
 
 /// 执行同步
 #[tauri::command]
 pub async fn webdav_execute_sync(
+    app: AppHandle,
     config: WebDAVConfig,
     vault_path: String,
     plan: SyncPlan,
 ) -> Result<SyncResult, AppError> {
-    let mut engine = SyncEngine::new(config, vault_path)?;
+    let mut engine = device_engine(&app, config, vault_path)?;
     engine.execute_sync(&plan).await
 }
 
 /// 快速同步（跳过冲突）
 #[tauri::command]
 pub async fn webdav_quick_sync(
+    app: AppHandle,
     config: WebDAVConfig,
     vault_path: String,
 ) -> Result<SyncResult, AppError> {
-    let mut engine = SyncEngine::new(config, vault_path)?;
+    let mut engine = device_engine(&app, config, vault_path)?;
     engine.quick_sync().await
 }
 
@@ -160,3 +182,34 @@ pub async fn webdav_scan_local(
     let engine = SyncEngine::new(config, vault_path)?;
     engine.scan_local_files()
 }
+
+/// 演练同步：只计算计划并生成可读报告，不做任何修改
+#[tauri::command]
+pub async fn webdav_dry_run(
+    app: AppHandle,
+    config: WebDAVConfig,
+    vault_path: String,
+) -> Result<String, AppError> {
+    let mut engine = device_engine(&app, config, vault_path)?;
+    let plan = engine.compute_sync_plan().await?;
+    Ok(history::render_dry_run_report(&plan))
+}
+
+/// 查询单个文件的同步历史
+#[tauri::command]
+pub async fn webdav_get_file_history(
+    vault_path: String,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<SyncHistoryEntry>, AppError> {
+    SyncJournal::new(&vault_path).file_history(&path, limit)
+}
+
+/// 查询最近的同步历史
+#[tauri::command]
+pub async fn webdav_get_sync_history(
+    vault_path: String,
+    limit: Option<usize>,
+) -> Result<Vec<SyncHistoryEntry>, AppError> {
+    SyncJournal::new(&vault_path).recent(limit.unwrap_or(100))
+}
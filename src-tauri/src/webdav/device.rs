@@ -0,0 +1,83 @@
+//! 设备标识
+//!
+//! 每个安装生成一个固定的设备 ID，保存在应用数据目录中，
+//! 用于在远程清单里标记文件由哪台设备写入
+
+use std::fs;
+use std::path::Path;
+
+use super::types::DeviceInfo;
+use crate::error::AppError;
+
+/// 设备信息文件名
+const DEVICE_FILE: &str = "webdav-device.json";
+
+impl DeviceInfo {
+    /// 读取 `dir` 下的设备信息，不存在时生成新的设备 ID
+    ///
+    /// 主机名变化时只更新名称，设备 ID 保持不变
+    pub fn load_or_create(dir: &Path) -> Result<Self, AppError> {
+        let path = dir.join(DEVICE_FILE);
+        let name = device_name();
+
+        if let Ok(content) = fs::read_to_string(&path) {
+            if let Ok(mut device) = serde_json::from_str::<DeviceInfo>(&content) {
+                if device.name == name {
+                    return Ok(device);
+                }
+                device.name = name;
+                device.save(&path)?;
+                return Ok(device);
+            }
+            eprintln!("[WebDAV] Invalid device file, generating a new device id");
+        }
+
+        let device = DeviceInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+        };
+        device.save(&path)?;
+        Ok(device)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), AppError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize device info: {}", e)))?;
+        fs::write(path, content)
+            .map_err(|e| AppError::WebDAV(format!("Failed to write device info: {}", e)))
+    }
+}
+
+/// 当前设备名称
+pub fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_device_id_is_stable() {
+        let dir = tempdir().unwrap();
+        let first = DeviceInfo::load_or_create(dir.path()).unwrap();
+        let second = DeviceInfo::load_or_create(dir.path()).unwrap();
+        assert_eq!(first, second);
+        assert!(!first.id.is_empty());
+
+        fs::write(
+            dir.path().join(DEVICE_FILE),
+            format!(r#"{{"id":"{}","name":"old-name"}}"#, first.id),
+        )
+        .unwrap();
+        let renamed = DeviceInfo::load_or_create(dir.path()).unwrap();
+        assert_eq!(renamed.id, first.id);
+        assert_eq!(renamed.name, device_name());
+    }
+}
@@ -0,0 +1,285 @@
+//! 同步历史日志
+//!
+//! 以 JSON Lines 格式追加记录每次同步的逐文件操作，
+//! 并提供同步计划的演练 (dry-run) 报告渲染
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use super::types::*;
+use crate::error::AppError;
+
+/// 日志文件相对 vault 的路径
+const JOURNAL_PATH: &str = ".lumina/sync-journal.jsonl";
+
+/// 同步日志（仅追加）
+pub struct SyncJournal {
+    path: PathBuf,
+}
+
+impl SyncJournal {
+    /// 打开 vault 下的同步日志
+    pub fn new(vault_path: &str) -> Self {
+        Self {
+            path: Path::new(vault_path).join(JOURNAL_PATH),
+        }
+    }
+
+    /// 日志文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一批记录
+    pub fn append(&self, entries: &[SyncHistoryEntry]) -> Result<(), AppError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::WebDAV(format!("Failed to create journal directory: {}", e)))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| AppError::WebDAV(format!("Failed to open sync journal: {}", e)))?;
+
+        let mut buf = String::new();
+        for entry in entries {
+            let line = serde_json::to_string(entry)
+                .map_err(|e| AppError::WebDAV(format!("Failed to serialize journal entry: {}", e)))?;
+            buf.push_str(&line);
+            buf.push('\n');
+        }
+
+        file.write_all(buf.as_bytes())
+            .map_err(|e| AppError::WebDAV(format!("Failed to write sync journal: {}", e)))
+    }
+
+    /// 读取全部记录（按写入顺序）
+    ///
+    /// 无法解析的行会被跳过，避免单行损坏导致整个历史不可读
+    pub fn read_all(&self) -> Result<Vec<SyncHistoryEntry>, AppError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = fs::File::open(&self.path)
+            .map_err(|e| AppError::WebDAV(format!("Failed to open sync journal: {}", e)))?;
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line
+                .map_err(|e| AppError::WebDAV(format!("Failed to read sync journal: {}", e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(entry) = serde_json::from_str::<SyncHistoryEntry>(&line) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// 查询单个文件的历史（最新的在前）
+    pub fn file_history(&self, path: &str, limit: Option<usize>) -> Result<Vec<SyncHistoryEntry>, AppError> {
+        let path = normalize_path(path);
+        let mut entries: Vec<SyncHistoryEntry> = self.read_all()?
+            .into_iter()
+            .filter(|e| e.path == path)
+            .collect();
+        entries.reverse();
+        if let Some(limit) = limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    /// 查询最近的记录（最新的在前）
+    pub fn recent(&self, limit: usize) -> Result<Vec<SyncHistoryEntry>, AppError> {
+        let mut entries = self.read_all()?;
+        entries.reverse();
+        entries.truncate(limit);
+        Ok(entries)
+    }
+}
+
+/// 统一路径分隔符，与同步计划中的相对路径保持一致
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches('/').to_string()
+}
+
+/// 渲染同步计划为可读的演练报告 (Markdown)
+pub fn render_dry_run_report(plan: &SyncPlan) -> String {
+    let mut out = String::new();
+
+    out.push_str("# WebDAV Sync Dry Run\n\n");
+
+    let delete_count = plan.items.iter()
+        .filter(|i| matches!(i.action, SyncAction::DeleteRemote | SyncAction::DeleteLocal))
+        .count();
+    let upload_bytes: u64 = plan.items.iter()
+        .filter(|i| i.action == SyncAction::Upload)
+        .filter_map(|i| i.local.as_ref().map(|l| l.size))
+        .sum();
+    let download_bytes: u64 = plan.items.iter()
+        .filter(|i| i.action == SyncAction::Download)
+        .filter_map(|i| i.remote.as_ref().map(|r| r.size))
+        .sum();
+
+    out.push_str(&format!(
+        "- Upload: {} ({})\n- Download: {} ({})\n- Delete: {}\n- Conflict: {}\n\n",
+        plan.upload_count,
+        format_bytes(upload_bytes),
+        plan.download_count,
+        format_bytes(download_bytes),
+        delete_count,
+        plan.conflict_count,
+    ));
+
+    if plan.items.is_empty() {
+        out.push_str("Everything is up to date, nothing to sync.\n");
+        return out;
+    }
+
+    let sections = [
+        (SyncAction::Upload, "Upload"),
+        (SyncAction::Download, "Download"),
+        (SyncAction::DeleteRemote, "Delete on remote"),
+        (SyncAction::DeleteLocal, "Delete locally (skipped, local-first)"),
+        (SyncAction::Conflict, "Conflicts (remote copy saved as .conflict)"),
+    ];
+
+    for (action, title) in sections.iter() {
+        let items: Vec<&SyncPlanItem> = plan.items.iter()
+            .filter(|i| &i.action == action)
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+
+        out.push_str(&format!("## {} ({})\n\n", title, items.len()));
+        for item in items {
+            let size = match action {
+                SyncAction::Upload => item.local.as_ref().map(|l| l.size),
+                _ => item.remote.as_ref().map(|r| r.size),
+            };
+            match size {
+                Some(size) if size > 0 => out.push_str(&format!(
+                    "- `{}` ({}) — {}\n",
+                    item.path,
+                    format_bytes(size),
+                    item.reason
+                )),
+                _ => out.push_str(&format!("- `{}` — {}\n", item.path, item.reason)),
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// 格式化字节数
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(sync_id: &str, path: &str, action: SyncAction) -> SyncHistoryEntry {
+        SyncHistoryEntry {
+            sync_id: sync_id.to_string(),
+            timestamp: 0,
+            device: "test".to_string(),
+            path: path.to_string(),
+            action,
+            bytes: 10,
+            success: true,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_journal_append_and_query() {
+        let dir = tempdir().unwrap();
+        let journal = SyncJournal::new(dir.path().to_str().unwrap());
+
+        journal.append(&[
+            entry("s1", "notes/a.md", SyncAction::Upload),
+            entry("s1", "notes/b.md", SyncAction::Download),
+        ]).unwrap();
+        journal.append(&[entry("s2", "notes/a.md", SyncAction::Download)]).unwrap();
+
+        assert_eq!(journal.read_all().unwrap().len(), 3);
+
+        let history = journal.file_history("notes\\a.md", None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].sync_id, "s2");
+        assert_eq!(history[1].action, SyncAction::Upload);
+
+        let limited = journal.file_history("notes/a.md", Some(1)).unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn test_journal_skips_corrupt_lines() {
+        let dir = tempdir().unwrap();
+        let journal = SyncJournal::new(dir.path().to_str().unwrap());
+        journal.append(&[entry("s1", "a.md", SyncAction::Upload)]).unwrap();
+
+        let mut file = OpenOptions::new().append(true).open(journal.path()).unwrap();
+        file.write_all(b"{not json\n").unwrap();
+
+        assert_eq!(journal.read_all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_render_dry_run_report() {
+        let plan = SyncPlan {
+            items: vec![SyncPlanItem {
+                path: "notes/a.md".to_string(),
+                action: SyncAction::Upload,
+                local: Some(LocalFileInfo {
+                    relative_path: "notes/a.md".to_string(),
+                    absolute_path: "/vault/notes/a.md".to_string(),
+                    is_dir: false,
+                    size: 2048,
+                    modified: 0,
+                }),
+                remote: None,
+                reason: "New local file".to_string(),
+            }],
+            upload_count: 1,
+            download_count: 0,
+            conflict_count: 0,
+        };
+
+        let report = render_dry_run_report(&plan);
+        assert!(report.contains("## Upload (1)"));
+        assert!(report.contains("`notes/a.md` (2.0 KB) — New local file"));
+        assert!(!report.contains("## Download"));
+
+        let empty = SyncPlan { items: vec![], upload_count: 0, download_count: 0, conflict_count: 0 };
+        assert!(render_dry_run_report(&empty).contains("nothing to sync"));
+    }
+}
@@ -3,11 +3,15 @@
 //! 提供 WebDAV 同步功能，包括：
 //! - 客户端：HTTP 请求封装
 //! - 同步：本地优先的双向同步逻辑
+//! - 历史：同步日志与演练报告
+//! - 设备：设备标识与远程清单，区分本机与其他设备的修改
 //! - 命令：Tauri 命令接口
 
 pub mod types;
 pub mod client;
 pub mod sync;
+pub mod history;
+pub mod device;
 pub mod commands;
 
 // Re-exports for internal use
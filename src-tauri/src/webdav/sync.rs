@@ -4,19 +4,58 @@
 
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH, Instant};
 use walkdir::WalkDir;
 
 use super::client::WebDAVClient;
+use super::device::device_name;
+use super::history::SyncJournal;
 use super::types::*;
 use crate::error::AppError;
 
+/// 将远程版本写入 `{path}.conflict`，本地文件保持不变
+///
+/// WebDAV 与 Git 同步共用此冲突处理方式
+pub fn write_conflict_copy(vault_path: &str, path: &str, content: &[u8]) -> Result<String, AppError> {
+    let conflict_path = conflict_copy_path(vault_path, path);
+
+    if let Some(parent) = conflict_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&conflict_path, content)?;
+    Ok(format!("{}.conflict", path))
+}
+
+/// 冲突副本 `{path}.conflict` 的绝对路径
+pub fn conflict_copy_path(vault_path: &str, path: &str) -> PathBuf {
+    Path::new(vault_path).join(format!("{}.conflict", path))
+}
+
+/// 远程同步清单路径 (以 `.` 开头，不参与文件同步)
+const MANIFEST_PATH: &str = ".lumina-manifest.json";
+
+/// 清单条件写入因并发修改失败时的最大重试次数
+const MANIFEST_WRITE_ATTEMPTS: usize = 5;
+
+/// 读取到的远程清单及其 ETag，用于条件写入
+struct ManifestSnapshot {
+    manifest: SyncManifest,
+    /// 远程清单是否已存在
+    exists: bool,
+    etag: Option<String>,
+}
+
 /// 同步引擎
 pub struct SyncEngine {
     client: WebDAVClient,
     vault_path: String,
     state: Option<SyncState>,
+    /// 当前设备，未设置时不读写远程清单
+    device: Option<DeviceInfo>,
+    /// 计划时读取的远程清单
+    manifest: Option<SyncManifest>,
 }
 
 impl SyncEngine {
@@ -27,21 +66,74 @@ impl SyncEngine {
             client,
             vault_path,
             state: None,
+            device: None,
+            manifest: None,
         })
     }
 
+    /// 设置当前设备，启用远程清单
+    pub fn with_device(mut self, device: DeviceInfo) -> Self {
+        self.device = Some(device);
+        self
+    }
+
     /// 加载同步状态
     pub fn load_state(&mut self) -> Result<(), AppError> {
         let state_path = self.state_file_path();
         if Path::new(&state_path).exists() {
             let content = fs::read_to_string(&state_path)
                 .map_err(|e| AppError::WebDAV(format!("Failed to read sync state: {}", e)))?;
-            self.state = serde_json::from_str(&content)
+            let state: SyncState = serde_json::from_str(&content)
                 .map_err(|e| AppError::WebDAV(format!("Failed to parse sync state: {}", e)))?;
+
+            // 状态文件随 vault 被复制到其他设备时，其中的记录不再可信
+            let foreign = match (&self.device, &state.device_id) {
+                (Some(device), Some(owner)) => &device.id != owner,
+                _ => false,
+            };
+            if foreign {
+                eprintln!("[WebDAV] Sync state belongs to another device, ignoring its records");
+                self.state = None;
+            } else {
+                self.state = Some(state);
+            }
         }
         Ok(())
     }
 
+    /// 读取远程清单，不存在或无法解析时返回空清单
+    pub async fn load_manifest(&self) -> Result<SyncManifest, AppError> {
+        Ok(self.load_manifest_snapshot().await?.manifest)
+    }
+
+    /// 读取远程清单及其 ETag
+    async fn load_manifest_snapshot(&self) -> Result<ManifestSnapshot, AppError> {
+        let Some((content, etag)) = self.client.download_with_etag(MANIFEST_PATH).await? else {
+            return Ok(ManifestSnapshot { manifest: SyncManifest::default(), exists: false, etag: None });
+        };
+        let manifest = serde_json::from_slice(&content).unwrap_or_else(|e| {
+            eprintln!("[WebDAV] Invalid sync manifest, starting a new one: {}", e);
+            SyncManifest::default()
+        });
+        Ok(ManifestSnapshot { manifest, exists: true, etag })
+    }
+
+    /// 基于读取时的 ETag 条件写入远程清单，期间被其他设备改写时返回 `Ok(false)`
+    async fn save_manifest(&self, snapshot: &ManifestSnapshot) -> Result<bool, AppError> {
+        let content = serde_json::to_vec_pretty(&snapshot.manifest)
+            .map_err(|e| AppError::WebDAV(format!("Failed to serialize sync manifest: {}", e)))?;
+
+        match (snapshot.exists, snapshot.etag.as_deref()) {
+            (false, _) => self.client.upload_if_match(MANIFEST_PATH, &content, None).await,
+            (true, Some(etag)) => self.client.upload_if_match(MANIFEST_PATH, &content, Some(etag)).await,
+            (true, None) => {
+                // 服务器未返回 ETag，无法条件写入
+                eprintln!("[WebDAV] Server returned no ETag for the sync manifest, writing unconditionally");
+                self.client.upload(MANIFEST_PATH, &content).await.map(|_| true)
+            }
+        }
+    }
+
     /// 保存同步状态
     pub fn save_state(&self) -> Result<(), AppError> {
         if let Some(ref state) = self.state {
@@ -120,16 +212,27 @@ impl SyncEngine {
 
     /// 扫描远程文件
     pub async fn scan_remote_files(&self) -> Result<Vec<RemoteEntry>, AppError> {
-        self.client.list_all_recursive("").await
+        let mut entries = self.client.list_all_recursive("").await?;
+        entries.retain(|e| e.path.trim_start_matches('/') != MANIFEST_PATH);
+        Ok(entries)
     }
 
     /// 计算同步计划
     pub async fn compute_sync_plan(&mut self) -> Result<SyncPlan, AppError> {
         self.load_state()?;
+        self.manifest = match self.device {
+            Some(_) => Some(self.load_manifest().await?),
+            None => None,
+        };
         
         let local_files = self.scan_local_files()?;
         let remote_files = self.scan_remote_files().await?;
 
+        Ok(self.build_plan(local_files, remote_files))
+    }
+
+    /// 根据本地/远程文件列表与上次同步记录生成同步计划
+    fn build_plan(&self, local_files: Vec<LocalFileInfo>, remote_files: Vec<RemoteEntry>) -> SyncPlan {
         // 构建映射表
         let local_map: HashMap<String, &LocalFileInfo> = local_files
             .iter()
@@ -154,8 +257,9 @@ impl SyncEngine {
             let path = &local.relative_path;
             let remote = remote_map.get(path).copied();
             let last_record = last_sync_map.get(path).copied();
+            let writer = self.manifest_entry(path);
 
-            let (action, reason) = self.determine_action(Some(local), remote, last_record);
+            let (action, reason) = self.determine_action(Some(local), remote, last_record, writer);
 
             if action != SyncAction::Skip {
                 items.push(SyncPlanItem {
@@ -173,13 +277,21 @@ impl SyncEngine {
             let path = &remote.path;
             if !local_map.contains_key(path) {
                 let last_record = last_sync_map.get(path).copied();
+                let writer = self.manifest_entry(path);
                 
-                let (action, reason) = if last_record.is_some() {
+                let (action, reason) = match last_record {
+                    // 本地删除后，远程文件又被修改 -> 保留远程修改
+                    Some(record) if self.remote_changed(record, remote, writer) => (
+                        SyncAction::Download,
+                        format!("Deleted locally but modified on {}", self.remote_writer(record, writer)),
+                    ),
                     // 之前同步过，现在本地没有了 -> 本地删除了
-                    (SyncAction::DeleteRemote, "Local file was deleted".to_string())
-                } else {
+                    Some(_) => (SyncAction::DeleteRemote, "Local file was deleted".to_string()),
                     // 从未同步过，远程新增 -> 下载
-                    (SyncAction::Download, "New file on remote".to_string())
+                    None => match writer {
+                        Some(w) => (SyncAction::Download, format!("New file from {}", w.device_name)),
+                        None => (SyncAction::Download, "New file on remote".to_string()),
+                    },
                 };
 
                 items.push(SyncPlanItem {
@@ -197,12 +309,42 @@ impl SyncEngine {
         let download_count = items.iter().filter(|i| i.action == SyncAction::Download).count();
         let conflict_count = items.iter().filter(|i| i.action == SyncAction::Conflict).count();
 
-        Ok(SyncPlan {
+        SyncPlan {
             items,
             upload_count,
             download_count,
             conflict_count,
-        })
+        }
+    }
+
+    /// 远程清单中某个文件的写入记录
+    fn manifest_entry(&self, path: &str) -> Option<&ManifestEntry> {
+        self.manifest.as_ref().and_then(|m| m.files.get(path))
+    }
+
+    /// 根据清单版本判断远程文件自上次同步后是否被其他设备改写
+    ///
+    /// 上次同步或清单缺少版本信息时返回 None
+    fn changed_by_other(&self, record: &FileRecord, writer: Option<&ManifestEntry>) -> Option<bool> {
+        let seen = record.manifest_version?;
+        let writer = writer?;
+        Some(writer.version != seen)
+    }
+
+    /// 远程文件自上次同步后是否被改写
+    ///
+    /// 清单只作为补充信息：不写清单的客户端 (网页端、其他 WebDAV 应用、旧版本) 修改文件时
+    /// 清单版本不变，仍需通过 ETag/mtime 发现
+    fn remote_changed(&self, record: &FileRecord, remote: &RemoteEntry, writer: Option<&ManifestEntry>) -> bool {
+        self.changed_by_other(record, writer).unwrap_or(false) || remote_differs(record, remote)
+    }
+
+    /// 改写远程文件的设备名称，清单版本未变时无法确定
+    fn remote_writer<'a>(&self, record: &FileRecord, writer: Option<&'a ManifestEntry>) -> &'a str {
+        match self.changed_by_other(record, writer) {
+            Some(true) => writer_name(writer),
+            _ => "remote",
+        }
     }
 
     /// 确定单个文件的同步动作
@@ -211,6 +353,7 @@ impl SyncEngine {
         local: Option<&LocalFileInfo>,
         remote: Option<&RemoteEntry>,
         last_record: Option<&FileRecord>,
+        writer: Option<&ManifestEntry>,
     ) -> (SyncAction, String) {
         match (local, remote, last_record) {
             // 本地存在，远程不存在
@@ -235,14 +378,21 @@ impl SyncEngine {
                     .map(|lr| l.modified > lr.local_mtime)
                     .unwrap_or(true);
                 
+                let by_manifest = last_record.and_then(|lr| self.changed_by_other(lr, writer));
                 let remote_changed = last_record
-                    .map(|lr| r.modified > lr.remote_mtime)
+                    .map(|lr| self.remote_changed(lr, r, writer))
                     .unwrap_or(true);
 
                 match (local_changed, remote_changed) {
                     (true, true) => {
                         // 双方都有修改 -> 冲突
-                        (SyncAction::Conflict, "Both sides modified".to_string())
+                        match (by_manifest, writer) {
+                            (Some(true), Some(w)) => (
+                                SyncAction::Conflict,
+                                format!("Modified here and on {}", w.device_name),
+                            ),
+                            _ => (SyncAction::Conflict, "Both sides modified".to_string()),
+                        }
                     }
                     (true, false) => {
                         // 只有本地修改 -> 上传
@@ -250,7 +400,13 @@ impl SyncEngine {
                     }
                     (false, true) => {
                         // 只有远程修改 -> 下载
-                        (SyncAction::Download, "Remote file modified".to_string())
+                        match (by_manifest, writer) {
+                            (Some(true), Some(w)) => (
+                                SyncAction::Download,
+                                format!("Modified on {}", w.device_name),
+                            ),
+                            _ => (SyncAction::Download, "Remote file modified".to_string()),
+                        }
                     }
                     (false, false) => {
                         // 都没修改
@@ -274,12 +430,30 @@ impl SyncEngine {
     /// 执行同步
     pub async fn execute_sync(&mut self, plan: &SyncPlan) -> Result<SyncResult, AppError> {
         let start = Instant::now();
+        if self.state.is_none() {
+            self.load_state()?;
+        }
+        if self.device.is_some() && self.manifest.is_none() {
+            self.manifest = Some(self.load_manifest().await?);
+        }
+
         let mut uploaded = 0;
         let mut downloaded = 0;
         let mut deleted = 0;
         let mut conflicts = 0;
         let mut errors = Vec::new();
         let mut new_records = Vec::new();
+        let mut journal_entries = Vec::new();
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let sync_id = uuid::Uuid::new_v4().to_string();
+        let device = self.device
+            .as_ref()
+            .map(|d| d.name.clone())
+            .unwrap_or_else(device_name);
 
         for item in &plan.items {
             let result = match item.action {
@@ -303,6 +477,24 @@ impl SyncEngine {
                 SyncAction::Skip => continue,
             };
 
+            let bytes = match item.action {
+                SyncAction::Upload => item.local.as_ref().map(|l| l.size).unwrap_or(0),
+                SyncAction::Download | SyncAction::Conflict => {
+                    item.remote.as_ref().map(|r| r.size).unwrap_or(0)
+                }
+                _ => 0,
+            };
+            journal_entries.push(SyncHistoryEntry {
+                sync_id: sync_id.clone(),
+                timestamp: now,
+                device: device.clone(),
+                path: item.path.clone(),
+                action: item.action.clone(),
+                bytes: if result.is_ok() { bytes } else { 0 },
+                success: result.is_ok(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+
             match result {
                 Ok(record) => {
                     match item.action {
@@ -312,7 +504,9 @@ impl SyncEngine {
                         SyncAction::Conflict => conflicts += 1,
                         _ => {}
                     }
-                    if let Some(r) = record {
+                    if let Some(mut r) = record {
+                        // 下载/冲突记录看到的远程版本，上传的版本在写清单时确定
+                        r.manifest_version = self.manifest_entry(&item.path).map(|w| w.version);
                         new_records.push(r);
                     }
                 }
@@ -326,12 +520,31 @@ impl SyncEngine {
             }
         }
 
-        // 更新同步状态 - 合并记录而非替换
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        // 更新远程清单：重新读取最新版本再合并本次写入，减少与其他设备的覆盖
+        if let Some(device) = self.device.clone() {
+            let written: Vec<&SyncPlanItem> = plan.items.iter()
+                .filter(|i| matches!(i.action, SyncAction::Upload | SyncAction::DeleteRemote))
+                .filter(|i| !errors.iter().any(|e: &SyncError| e.path == i.path))
+                .collect();
+
+            if !written.is_empty() {
+                match self.update_manifest(&device, &written, &mut new_records, now).await {
+                    Ok(()) => {}
+                    Err(e) => errors.push(SyncError {
+                        path: MANIFEST_PATH.to_string(),
+                        action: SyncAction::Upload,
+                        message: e.to_string(),
+                    }),
+                }
+            }
+        }
 
+        // 写入同步日志，失败不影响同步结果
+        if let Err(e) = SyncJournal::new(&self.vault_path).append(&journal_entries) {
+            eprintln!("[WebDAV] Failed to write sync journal: {}", e);
+        }
+
+        // 更新同步状态 - 合并记录而非替换
         // 保留之前的记录，只更新/添加本次处理的文件
         let mut merged_records: HashMap<String, FileRecord> = self.state
             .as_ref()
@@ -351,6 +564,7 @@ impl SyncEngine {
         }
 
         self.state = Some(SyncState {
+            device_id: self.device.as_ref().map(|d| d.id.clone()),
             last_sync: now,
             file_records: merged_records.into_values().collect(),
         });
@@ -367,6 +581,52 @@ impl SyncEngine {
         })
     }
 
+    /// 将本次上传/删除写入远程清单，并回填上传文件的版本号
+    async fn update_manifest(
+        &mut self,
+        device: &DeviceInfo,
+        written: &[&SyncPlanItem],
+        records: &mut [FileRecord],
+        now: u64,
+    ) -> Result<(), AppError> {
+        for _ in 0..MANIFEST_WRITE_ATTEMPTS {
+            let mut snapshot = self.load_manifest_snapshot().await?;
+            let mut versions = Vec::new();
+
+            for item in written {
+                if item.action == SyncAction::DeleteRemote {
+                    snapshot.manifest.files.remove(&item.path);
+                    continue;
+                }
+
+                let version = snapshot.manifest.files.get(&item.path).map(|e| e.version).unwrap_or(0) + 1;
+                snapshot.manifest.files.insert(item.path.clone(), ManifestEntry {
+                    device_id: device.id.clone(),
+                    device_name: device.name.clone(),
+                    version,
+                    written_at: now,
+                    size: item.local.as_ref().map(|l| l.size).unwrap_or(0),
+                });
+                versions.push((item.path.as_str(), version));
+            }
+
+            // 读取后清单被其他设备改写 -> 重新读取并合并
+            if !self.save_manifest(&snapshot).await? {
+                continue;
+            }
+
+            for (path, version) in versions {
+                if let Some(record) = records.iter_mut().find(|r| r.path == path) {
+                    record.manifest_version = Some(version);
+                }
+            }
+            self.manifest = Some(snapshot.manifest);
+            return Ok(());
+        }
+
+        Err(AppError::WebDAV("Sync manifest kept changing on the server, giving up".to_string()))
+    }
+
     /// 执行上传
     async fn execute_upload(&self, item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
         let local = item.local.as_ref().ok_or_else(|| {
@@ -384,19 +644,28 @@ impl SyncEngine {
                 }
             }
 
-            let content = fs::read(&local.absolute_path)
-                .map_err(|e| AppError::WebDAV(format!("Failed to read local file: {}", e)))?;
-            self.client.upload(&item.path, &content).await?;
+            self.client.upload_file(&item.path, Path::new(&local.absolute_path)).await?;
         }
 
-        // 重新获取远程信息
-        let remote_mtime = item.remote.as_ref().map(|r| r.modified).unwrap_or(local.modified);
-        
+        // 重新获取远程信息，记录本次写入后的 ETag/mtime，避免下次把自己的上传当作远程修改
+        let uploaded = match self.client.stat(&item.path).await {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("[WebDAV] Failed to stat {} after upload: {}", item.path, e);
+                None
+            }
+        };
+        let (remote_mtime, etag) = match uploaded {
+            Some(entry) => (entry.modified, entry.etag),
+            None => (unix_now(), None),
+        };
+
         Ok(Some(FileRecord {
             path: item.path.clone(),
             local_mtime: local.modified,
             remote_mtime,
-            etag: item.remote.as_ref().and_then(|r| r.etag.clone()),
+            etag,
+            manifest_version: None,
         }))
     }
 
@@ -413,15 +682,7 @@ impl SyncEngine {
             fs::create_dir_all(local_path)
                 .map_err(|e| AppError::WebDAV(format!("Failed to create directory: {}", e)))?;
         } else {
-            // 确保父目录存在
-            if let Some(parent) = local_path.parent() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| AppError::WebDAV(format!("Failed to create parent directory: {}", e)))?;
-            }
-
-            let content = self.client.download(&item.path).await?;
-            fs::write(local_path, &content)
-                .map_err(|e| AppError::WebDAV(format!("Failed to write local file: {}", e)))?;
+            self.client.download_to_file(&item.path, local_path).await?;
         }
 
         let local_mtime = local_path.metadata()
@@ -436,6 +697,7 @@ impl SyncEngine {
             local_mtime,
             remote_mtime: remote.modified,
             etag: remote.etag.clone(),
+            manifest_version: None,
         }))
     }
 
@@ -461,15 +723,8 @@ impl SyncEngine {
         })?;
 
         // 下载远程版本为 .conflict 文件
-        let conflict_path = format!("{}/{}.conflict", self.vault_path, item.path);
-        let conflict_path = Path::new(&conflict_path);
-
-        if let Some(parent) = conflict_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let content = self.client.download(&item.path).await?;
-        fs::write(conflict_path, &content)?;
+        let conflict_path = conflict_copy_path(&self.vault_path, &item.path);
+        self.client.download_to_file(&item.path, &conflict_path).await?;
 
         // 记录本地版本的信息
         let local = item.local.as_ref();
@@ -480,6 +735,7 @@ impl SyncEngine {
             local_mtime,
             remote_mtime: remote.modified,
             etag: remote.etag.clone(),
+            manifest_version: None,
         }))
     }
 
@@ -494,3 +750,157 @@ impl SyncEngine {
         self.execute_sync(&plan).await
     }
 }
+
+/// 清单中记录的写入设备名称
+fn writer_name(writer: Option<&ManifestEntry>) -> &str {
+    writer.map(|w| w.device_name.as_str()).unwrap_or("another device")
+}
+
+/// 远程 ETag/mtime 是否与上次同步时不同，两侧都有 ETag 时以 ETag 为准
+fn remote_differs(record: &FileRecord, remote: &RemoteEntry) -> bool {
+    match (&record.etag, &remote.etag) {
+        (Some(seen), Some(current)) => seen != current,
+        _ => remote.modified > record.remote_mtime,
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(manifest: Option<SyncManifest>) -> SyncEngine {
+        let config = WebDAVConfig {
+            server_url: "http://localhost".to_string(),
+            ..Default::default()
+        };
+        let mut engine = SyncEngine::new(config, "/vault".to_string())
+            .unwrap()
+            .with_device(DeviceInfo { id: "me".to_string(), name: "laptop".to_string() });
+        engine.manifest = manifest;
+        engine
+    }
+
+    fn local(modified: u64) -> LocalFileInfo {
+        LocalFileInfo {
+            relative_path: "a.md".to_string(),
+            absolute_path: "/vault/a.md".to_string(),
+            is_dir: false,
+            size: 1,
+            modified,
+        }
+    }
+
+    fn remote(modified: u64) -> RemoteEntry {
+        RemoteEntry {
+            path: "a.md".to_string(),
+            name: "a.md".to_string(),
+            is_dir: false,
+            size: 1,
+            modified,
+            etag: None,
+            content_type: None,
+        }
+    }
+
+    fn record(version: Option<u64>) -> FileRecord {
+        FileRecord {
+            path: "a.md".to_string(),
+            local_mtime: 100,
+            remote_mtime: 100,
+            etag: None,
+            manifest_version: version,
+        }
+    }
+
+    fn writer(version: u64) -> ManifestEntry {
+        ManifestEntry {
+            device_id: "other".to_string(),
+            device_name: "desktop".to_string(),
+            version,
+            written_at: 0,
+            size: 1,
+        }
+    }
+
+    #[test]
+    fn test_manifest_version_supplements_mtime() {
+        let engine = engine(None);
+        let last = record(Some(3));
+
+        // 版本与 mtime 都未变 -> 无需同步
+        let (action, _) = engine.determine_action(
+            Some(&local(100)), Some(&remote(100)), Some(&last), Some(&writer(3)),
+        );
+        assert_eq!(action, SyncAction::Skip);
+
+        // 远程 mtime 未变，但其他设备写入了新版本 -> 下载
+        let (action, reason) = engine.determine_action(
+            Some(&local(100)), Some(&remote(100)), Some(&last), Some(&writer(4)),
+        );
+        assert_eq!(action, SyncAction::Download);
+        assert_eq!(reason, "Modified on desktop");
+
+        // 不写清单的客户端修改了文件：版本未变但 mtime 变新 -> 仍然下载，不覆盖远程修改
+        let (action, reason) = engine.determine_action(
+            Some(&local(100)), Some(&remote(500)), Some(&last), Some(&writer(3)),
+        );
+        assert_eq!(action, SyncAction::Download);
+        assert_eq!(reason, "Remote file modified");
+
+        // 同时本地也有修改 -> 冲突而不是上传
+        let (action, _) = engine.determine_action(
+            Some(&local(200)), Some(&remote(500)), Some(&last), Some(&writer(3)),
+        );
+        assert_eq!(action, SyncAction::Conflict);
+
+        // 两侧都有 ETag 时以 ETag 为准
+        let mut seen = record(Some(3));
+        seen.etag = Some("\"a\"".to_string());
+        let mut same = remote(500);
+        same.etag = Some("\"a\"".to_string());
+        let (action, _) = engine.determine_action(
+            Some(&local(100)), Some(&same), Some(&seen), Some(&writer(3)),
+        );
+        assert_eq!(action, SyncAction::Skip);
+
+        let mut edited = remote(100);
+        edited.etag = Some("\"b\"".to_string());
+        let (action, _) = engine.determine_action(
+            Some(&local(100)), Some(&edited), Some(&seen), Some(&writer(3)),
+        );
+        assert_eq!(action, SyncAction::Download);
+    }
+
+    #[test]
+    fn test_local_delete_keeps_remote_edit_from_other_device() {
+        let mut manifest = SyncManifest::default();
+        manifest.files.insert("a.md".to_string(), writer(2));
+        let mut engine = engine(Some(manifest));
+        engine.state = Some(SyncState {
+            device_id: Some("me".to_string()),
+            last_sync: 0,
+            file_records: vec![record(Some(1))],
+        });
+
+        let plan = engine.build_plan(vec![], vec![remote(100)]);
+        assert_eq!(plan.items.len(), 1);
+        assert_eq!(plan.items[0].action, SyncAction::Download);
+        assert!(plan.items[0].reason.contains("desktop"));
+
+        // 未写清单的客户端修改后本地删除 -> 同样保留远程修改
+        engine.manifest = Some(SyncManifest::default());
+        let plan = engine.build_plan(vec![], vec![remote(500)]);
+        assert_eq!(plan.items[0].action, SyncAction::Download);
+
+        // 远程未变 -> 传播本地删除
+        let plan = engine.build_plan(vec![], vec![remote(100)]);
+        assert_eq!(plan.items[0].action, SyncAction::DeleteRemote);
+    }
+}
@@ -3,6 +3,9 @@
 //! 提供 WebDAV 操作所需的所有数据结构，与具体实现解耦
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::network::NetworkOptions;
 
 /// WebDAV 连接配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +22,9 @@ pub struct WebDAVConfig {
     pub auto_sync: bool,
     /// 自动同步间隔 (秒)
     pub sync_interval_secs: u64,
+    /// 代理、超时与证书选项
+    #[serde(default)]
+    pub network: NetworkOptions,
 }
 
 impl Default for WebDAVConfig {
@@ -30,6 +36,7 @@ impl Default for WebDAVConfig {
             remote_base_path: "/".to_string(),
             auto_sync: false,
             sync_interval_secs: 300, // 5 分钟
+            network: NetworkOptions::default(),
         }
     }
 }
@@ -179,9 +186,33 @@ pub struct SyncError {
     pub message: String,
 }
 
+/// 同步历史记录 (sync journal 中的一行)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncHistoryEntry {
+    /// 所属同步批次 ID
+    pub sync_id: String,
+    /// 记录时间 (Unix 时间戳)
+    pub timestamp: u64,
+    /// 执行同步的设备
+    pub device: String,
+    /// 文件路径
+    pub path: String,
+    /// 操作类型
+    pub action: SyncAction,
+    /// 传输字节数
+    pub bytes: u64,
+    /// 是否成功
+    pub success: bool,
+    /// 错误信息
+    pub error: Option<String>,
+}
+
 /// 同步状态记录 (用于增量同步)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncState {
+    /// 写入此状态的设备 ID
+    #[serde(default)]
+    pub device_id: Option<String>,
     /// 上次同步时间 (Unix 时间戳)
     pub last_sync: u64,
     /// 文件同步记录
@@ -199,4 +230,38 @@ pub struct FileRecord {
     pub remote_mtime: u64,
     /// 上次同步时的 ETag
     pub etag: Option<String>,
+    /// 上次同步时远程清单中的版本号
+    #[serde(default)]
+    pub manifest_version: Option<u64>,
+}
+
+/// 设备信息 (每个安装一个)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeviceInfo {
+    /// 设备 ID (安装时生成，不随主机名变化)
+    pub id: String,
+    /// 设备名称 (用于展示)
+    pub name: String,
+}
+
+/// 远程同步清单，记录每个文件最后由哪台设备写入
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncManifest {
+    /// 文件路径 -> 写入记录
+    pub files: HashMap<String, ManifestEntry>,
+}
+
+/// 清单中单个文件的写入记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestEntry {
+    /// 最后写入的设备 ID
+    pub device_id: String,
+    /// 最后写入的设备名称
+    pub device_name: String,
+    /// 版本号，每次写入加一
+    pub version: u64,
+    /// 写入时间 (写入设备的 Unix 时间戳，仅供展示)
+    pub written_at: u64,
+    /// 文件大小
+    pub size: u64,
 }
@@ -0,0 +1,40 @@
+//! 离线 Agent 评估
+//!
+//! 在固件笔记库上运行 `tests/agent-eval/cases` 中带 `mock` 脚本的用例，
+//! LLM 由本地 mock 服务按脚本应答，无需网络和 API Key。
+//!
+//! - `AGENT_EVAL_CATEGORY=basic` 只运行指定类别
+//! - `AGENT_EVAL_REPORTS=<dir>` 指定报告目录（默认写入 cargo 的临时目录）
+
+use std::path::PathBuf;
+
+use lumina_note_lib::agent::eval::case::load_cases;
+use lumina_note_lib::agent::eval::runner::EvalRunner;
+
+fn eval_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../tests/agent-eval")
+}
+
+#[tokio::test]
+async fn test_offline_agent_eval() {
+    let dir = eval_dir();
+    let mut cases = load_cases(&dir.join("cases")).expect("failed to load cases");
+    if let Ok(category) = std::env::var("AGENT_EVAL_CATEGORY") {
+        cases.retain(|c| c.category == category);
+    }
+
+    let app = tauri::test::mock_app();
+    let report = EvalRunner::new(dir.join("fixtures/test-vault"))
+        .run(app.handle(), &cases)
+        .await;
+
+    let out = std::env::var("AGENT_EVAL_REPORTS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("agent-eval"));
+    let (md_path, _) = report.write(&out).expect("failed to write report");
+    println!("{}", report.to_markdown());
+
+    assert!(!report.results.is_empty(), "no cases with a `mock` script");
+    let failed: Vec<_> = report.failures().map(|r| r.case_id.as_str()).collect();
+    assert!(failed.is_empty(), "failed cases {:?}, see {}", failed, md_path.display());
+}
@@ -193,6 +193,16 @@ describe('useRustAgentStore', () => {
       expect(useRustAgentStore.getState().status).toBe('running');
     });
 
+    it('should handle thread_started event', () => {
+      const store = useRustAgentStore.getState();
+
+      act(() => {
+        store._handleEvent({ type: 'thread_started', data: { thread_id: 'thread-1' } });
+      });
+
+      expect(useRustAgentStore.getState().threadId).toBe('thread-1');
+    });
+
     it('should handle message_chunk event', () => {
       const store = useRustAgentStore.getState();
       
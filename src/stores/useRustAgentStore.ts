@@ -151,6 +151,9 @@ interface RustAgentState {
   lastHeartbeat: number | null;
   connectionStatus: "connected" | "disconnected" | "unknown";
   
+  // 当前任务的检查点线程（用于恢复、回放或分叉）
+  threadId: string | null;
+  
   // 操作
   startTask: (task: string, context: TaskContext) => Promise<void>;
   abort: () => Promise<void>;
@@ -234,6 +237,9 @@ export const useRustAgentStore = create<RustAgentState>()(
       // 心跳监控初始状态（新增）
       lastHeartbeat: null,
       connectionStatus: "unknown",
+      
+      // 检查点线程初始状态
+      threadId: null,
 
       // 启动任务
       startTask: async (task: string, context: TaskContext) => {
@@ -769,6 +775,13 @@ export const useRustAgentStore = create<RustAgentState>()(
             break;
           }
           
+          // 任务开始，记录检查点线程
+          case "thread_started": {
+            const { thread_id } = event.data as { thread_id: string };
+            set({ threadId: thread_id });
+            break;
+          }
+          
           // 新增：心跳事件（用于连接状态监控）
           case "heartbeat": {
             const { timestamp } = event.data as { timestamp: number };